@@ -0,0 +1,26 @@
+use std::error::Error;
+
+#[cfg(not(feature = "blocking"))]
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    use public_ip_address::lookup::{LookupProvider, LookupService};
+
+    env_logger::init();
+    let service = LookupService::new(LookupProvider::IpWhoIs, None);
+    let target = "8.8.8.8".parse().ok();
+    // the lookups are futures, so they run concurrently on the runtime
+    let (own, target, v4) = tokio::join!(
+        public_ip_address::perform_lookup(None),
+        service.lookup(target),
+        public_ip_address::public_ip_v4(),
+    );
+    println!("{}", own?);
+    println!("{}", target?);
+    println!("IPv4: {}", v4?);
+    Ok(())
+}
+
+#[cfg(feature = "blocking")]
+fn main() -> Result<(), Box<dyn Error>> {
+    Err("this example uses the async API, build it without the blocking feature".into())
+}
@@ -0,0 +1,24 @@
+use public_ip_address::{beacon, lookup::LookupProvider};
+use std::{error::Error, time::Instant};
+
+#[cfg_attr(not(feature = "blocking"), tokio::main)]
+#[maybe_async::maybe_async]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+    let start = Instant::now();
+    let report = beacon::probe_beacons().await;
+    println!("Probed beacons in {:?}", start.elapsed());
+    for beacon in &report.latencies {
+        println!("{:>16}: {:?}", beacon.name, beacon.latency);
+    }
+    println!("Region: {:?}, quality: {:?}", report.region, report.quality);
+    // prefer providers hosted close to the estimated region
+    let providers = report.order_providers(vec![
+        (LookupProvider::IpInfo, None),
+        (LookupProvider::IpApiCo, None),
+        (LookupProvider::Mullvad, None),
+    ]);
+    let result = public_ip_address::perform_lookup_with(providers, None).await?;
+    println!("{}", result);
+    Ok(())
+}
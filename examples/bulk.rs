@@ -0,0 +1,34 @@
+use public_ip_address::lookup::{LookupProvider, LookupService};
+use std::{env, error::Error, io, net::IpAddr, time::Instant};
+
+#[cfg_attr(not(feature = "blocking"), tokio::main)]
+#[maybe_async::maybe_async]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+    // addresses from the arguments, or one per line from the standard input
+    let mut lines: Vec<String> = env::args().skip(1).collect();
+    if lines.is_empty() {
+        lines = io::stdin().lines().collect::<Result<_, _>>()?;
+    }
+    let ips: Vec<IpAddr> = lines
+        .iter()
+        .filter_map(|line| line.trim().parse().ok())
+        .collect();
+    let service = LookupService::new(LookupProvider::IpWhoIs, None);
+    let start = Instant::now();
+    // at most four requests in flight
+    let responses = service.lookup_many(&ips, 4).await;
+    for (ip, response) in ips.iter().zip(responses) {
+        match response {
+            Ok(response) => println!(
+                "{}: {}, {}",
+                ip,
+                response.city.unwrap_or("unknown".to_string()),
+                response.country.unwrap_or("unknown".to_string())
+            ),
+            Err(e) => println!("{}: {}", ip, e),
+        }
+    }
+    println!("Enriched {} addresses in {:?}", ips.len(), start.elapsed());
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use public_ip_address::lookup::{LookupProvider, LookupService};
+use std::{collections::BTreeMap, error::Error, net::IpAddr};
+
+#[cfg_attr(not(feature = "blocking"), tokio::main)]
+#[maybe_async::maybe_async]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+    let providers = vec![
+        LookupProvider::Ipify,
+        LookupProvider::IpWhoIs,
+        LookupProvider::IpInfo,
+        LookupProvider::FreeIpApi,
+        LookupProvider::MyIp,
+    ];
+    // ask every provider and group them by the address they returned
+    let mut votes: BTreeMap<IpAddr, Vec<LookupProvider>> = BTreeMap::new();
+    for provider in providers {
+        match LookupService::new(provider.clone(), None)
+            .lookup(None)
+            .await
+        {
+            Ok(response) => votes.entry(response.ip).or_default().push(provider),
+            Err(e) => eprintln!("{} failed: {}", provider, e),
+        }
+    }
+    let answered: usize = votes.values().map(Vec::len).sum();
+    let Some((ip, agreeing)) = votes.iter().max_by_key(|(_, providers)| providers.len()) else {
+        return Err("No provider answered".into());
+    };
+    // the address is trusted when a strict majority of the answers agree
+    if agreeing.len() * 2 > answered {
+        println!(
+            "Consensus on {} ({} of {} answers)",
+            ip,
+            agreeing.len(),
+            answered
+        );
+    } else {
+        println!("No consensus among {} answers", answered);
+    }
+    for (ip, providers) in &votes {
+        println!("{:>39}: {:?}", ip, providers);
+    }
+    Ok(())
+}
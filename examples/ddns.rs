@@ -0,0 +1,51 @@
+use public_ip_address::ddns::{duckdns::DuckDnsUpdater, verify::verify_dns, DdnsUpdater};
+use public_ip_address::{lookup::LookupProvider, monitor::Monitor};
+use std::{env, error::Error, time::Duration};
+
+#[cfg_attr(not(feature = "blocking"), tokio::main)]
+#[maybe_async::maybe_async]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+    // read the DuckDNS subdomain and token from the environment variables
+    let domain = env::var("DUCKDNS_DOMAIN")?;
+    let token = env::var("DUCKDNS_TOKEN")?;
+    let hostname = format!("{}.duckdns.org", domain.trim_end_matches(".duckdns.org"));
+    // check the record before updating it
+    let report = verify_dns(&hostname).await?;
+    if report.is_match() {
+        println!("{} points at the public addresses", hostname);
+    }
+    for mismatch in &report.mismatches {
+        println!("{}: {:?}", hostname, mismatch);
+    }
+    // update the record at every change of the public address
+    let mut monitor = Monitor::new(
+        vec![(LookupProvider::Ipify, None)],
+        Duration::from_secs(300),
+    );
+    DuckDnsUpdater::new(&domain, &token).attach(&mut monitor);
+    let changes = monitor.subscribe();
+    monitor.start();
+    wait_changes(changes, &hostname).await;
+    Ok(())
+}
+
+#[cfg(not(feature = "blocking"))]
+async fn wait_changes(
+    mut changes: tokio::sync::mpsc::UnboundedReceiver<public_ip_address::monitor::IpChange>,
+    hostname: &str,
+) {
+    while let Some(change) = changes.recv().await {
+        println!("Updating {} to {}", hostname, change.new_ip);
+    }
+}
+
+#[cfg(feature = "blocking")]
+fn wait_changes(
+    changes: std::sync::mpsc::Receiver<public_ip_address::monitor::IpChange>,
+    hostname: &str,
+) {
+    for change in changes {
+        println!("Updating {} to {}", hostname, change.new_ip);
+    }
+}
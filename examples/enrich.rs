@@ -0,0 +1,20 @@
+use public_ip_address::lookup::LookupProvider;
+use std::{error::Error, net::IpAddr, time::Instant};
+
+#[cfg_attr(not(feature = "blocking"), tokio::main)]
+#[maybe_async::maybe_async]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+    // providers in order of priority, missing fields are filled by the next ones
+    let providers = vec![
+        (LookupProvider::IpWhoIs, None),
+        (LookupProvider::IpInfo, None),
+        (LookupProvider::IpApiCo, None),
+    ];
+    let target = "8.8.8.8".parse::<IpAddr>().ok();
+    let start = Instant::now();
+    let result = public_ip_address::perform_enriched_lookup_with(providers, target).await?;
+    println!("{}", result);
+    println!("Enriched response in {:?}", start.elapsed());
+    Ok(())
+}
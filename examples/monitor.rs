@@ -0,0 +1,74 @@
+use public_ip_address::{lookup::LookupProvider, monitor::Monitor};
+use std::{env, error::Error, time::Duration};
+
+#[cfg_attr(not(feature = "blocking"), tokio::main)]
+#[maybe_async::maybe_async]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+    // read the webhook receiving the changes from the environment variables
+    let url = env::var("WEBHOOK_URL")?;
+    let mut monitor = Monitor::new(
+        vec![
+            (LookupProvider::Ipify, None),
+            (LookupProvider::IpWhoIs, None),
+        ],
+        Duration::from_secs(60),
+    );
+    monitor.on_lookup(|response| println!("Looked up {} with {}", response.ip, response.provider));
+    monitor.on_change(move |change| post(&url, serde_json::to_string(change).unwrap_or_default()));
+    let changes = monitor.stream();
+    monitor.start();
+    print_changes(changes).await;
+    Ok(())
+}
+
+/// Posts the change to the webhook without blocking the monitor
+#[cfg(not(feature = "blocking"))]
+fn post(url: &str, body: String) {
+    let request = reqwest::Client::new()
+        .post(url)
+        .header("Content-Type", "application/json")
+        .body(body);
+    tokio::spawn(async move {
+        if let Err(e) = request.send().await.and_then(|r| r.error_for_status()) {
+            eprintln!("Webhook failed: {}", e);
+        }
+    });
+}
+
+/// Posts the change to the webhook without blocking the monitor
+#[cfg(feature = "blocking")]
+fn post(url: &str, body: String) {
+    let request = reqwest::blocking::Client::new()
+        .post(url)
+        .header("Content-Type", "application/json")
+        .body(body);
+    std::thread::spawn(move || {
+        if let Err(e) = request.send().and_then(|r| r.error_for_status()) {
+            eprintln!("Webhook failed: {}", e);
+        }
+    });
+}
+
+#[cfg(not(feature = "blocking"))]
+async fn print_changes(changes: impl futures::Stream<Item = public_ip_address::monitor::IpChange>) {
+    use futures::StreamExt;
+
+    let mut changes = Box::pin(changes);
+    while let Some(change) = changes.next().await {
+        println!(
+            "{:?} -> {} via {}",
+            change.old_ip, change.new_ip, change.provider
+        );
+    }
+}
+
+#[cfg(feature = "blocking")]
+fn print_changes(changes: impl Iterator<Item = public_ip_address::monitor::IpChange>) {
+    for change in changes {
+        println!(
+            "{:?} -> {} via {}",
+            change.old_ip, change.new_ip, change.provider
+        );
+    }
+}
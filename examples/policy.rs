@@ -0,0 +1,29 @@
+use public_ip_address::{cache::RefreshPolicy, lookup::LookupProvider, options::LookupOptions};
+use std::{error::Error, time::Instant};
+
+#[cfg_attr(not(feature = "blocking"), tokio::main)]
+#[maybe_async::maybe_async]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+    // verify the IP every 10 seconds, reuse the geolocation data for an hour
+    let options = LookupOptions::new().refresh_policy(RefreshPolicy::new(Some(10), Some(60 * 60)));
+    for _ in 0..3 {
+        let start = Instant::now();
+        let result = public_ip_address::perform_cached_lookup_with_options(
+            vec![
+                (LookupProvider::IpWhoIs, None),
+                (LookupProvider::IpInfo, None),
+            ],
+            None,
+            &options,
+        )
+        .await?;
+        println!(
+            "{} from {} in {:?}",
+            result.ip,
+            result.provider,
+            start.elapsed()
+        );
+    }
+    Ok(())
+}
@@ -1,5 +1,4 @@
 use public_ip_address::lookup::{
-    handle_response,
     ipwhois::{IpWhoIs, IpWhoIsResponse},
     Provider, ProviderResponse,
 };
@@ -12,8 +11,9 @@ use std::error::Error;
 async fn main() -> Result<(), Box<dyn Error>> {
     env_logger::init();
     let provider = IpWhoIs;
-    let response = provider.get_client(None, None).send().await;
-    let response = handle_response(response).await?;
+    let response = provider
+        .make_request(&Default::default(), None, None)
+        .await?;
     let result = IpWhoIsResponse::parse(response)?;
     println!("{:#?}", result);
     Ok(())
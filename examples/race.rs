@@ -0,0 +1,20 @@
+use public_ip_address::lookup::LookupProvider;
+use std::{error::Error, time::Instant};
+
+#[cfg_attr(not(feature = "blocking"), tokio::main)]
+#[maybe_async::maybe_async]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+    let providers = vec![
+        (LookupProvider::IpInfo, None),
+        (LookupProvider::IpWhoIs, None),
+        (LookupProvider::FreeIpApi, None),
+        (LookupProvider::MyIp, None),
+    ];
+    let start = Instant::now();
+    // race the providers, the first successful response wins
+    let result = public_ip_address::perform_race_lookup_with(providers, None).await?;
+    println!("{}", result);
+    println!("Fastest response in {:?}", start.elapsed());
+    Ok(())
+}
@@ -0,0 +1,116 @@
+//! # 💾 Cache storage backends
+//!
+//! This module provides the storage of the [`ResponseCache`](crate::cache::ResponseCache). By default
+//! the cache is stored in a file of the system cache directory, with [`FileBackend`]. A global backend
+//! can be set to store it somewhere else, for example in an embedded database with the `sled` or
+//! `sqlite` features, or in a Redis server shared by several hosts with the `redis` feature.
+//!
+//! The backend stores the serialized (and encrypted with the `encryption` feature) cache under its
+//! name, the cache file name or `lookup.cache` by default. Backends keeping a history are also given
+//! every cached response when the cache is saved.
+//!
+//! ## Example
+//! ```rust
+//! use public_ip_address::backend::{self, FileBackend};
+//!
+//! backend::set_backend(FileBackend);
+//! ```
+
+use crate::cache::{get_cache_path, ResponseRecord, Result};
+use crate::logging::trace;
+use std::{
+    fs,
+    io::{Read, Write},
+    net::IpAddr,
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+
+#[cfg(feature = "redis")]
+pub mod redis;
+#[cfg(feature = "sled")]
+pub mod sled;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+/// Storage of the serialized cache
+pub trait CacheBackend: Send + Sync {
+    /// Returns the data stored under `name`, or an `IOError` of kind `NotFound` if there is none
+    fn load(&self, name: &str) -> Result<Vec<u8>>;
+
+    /// Stores the data under `name`, replacing the previous data
+    ///
+    /// The data is not needed anymore after `expires`, `None` if it never expires.
+    fn save(&self, name: &str, data: &[u8], expires: Option<SystemTime>) -> Result<()>;
+
+    /// Deletes the data stored under `name`
+    fn delete(&self, name: &str) -> Result<()>;
+
+    /// Records a cached response in the history of the backend, if it keeps one
+    ///
+    /// `target` is the looked up address, `None` for the current public address. Called for every
+    /// cached response each time the cache is saved, so recording the same response again must not
+    /// add it twice.
+    fn record(&self, _target: Option<IpAddr>, _record: &ResponseRecord) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Stores the cache in a file of the system cache directory, see [`get_cache_path`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileBackend;
+
+impl CacheBackend for FileBackend {
+    fn load(&self, name: &str) -> Result<Vec<u8>> {
+        let mut file = fs::File::open(get_cache_path(&Some(name.to_string())))?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    fn save(&self, name: &str, data: &[u8], _expires: Option<SystemTime>) -> Result<()> {
+        let mut file = fs::File::create(get_cache_path(&Some(name.to_string())))?;
+        file.write_all(data)?;
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        fs::remove_file(get_cache_path(&Some(name.to_string())))?;
+        Ok(())
+    }
+}
+
+static BACKEND: RwLock<Option<Arc<dyn CacheBackend>>> = RwLock::new(None);
+
+/// Sets the global cache backend, replacing the previous one.
+pub fn set_backend<B>(backend: B)
+where
+    B: CacheBackend + 'static,
+{
+    trace!("Setting cache backend");
+    *BACKEND.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(backend));
+}
+
+/// Removes the global cache backend, storing the cache in a file again.
+pub fn clear_backend() {
+    *BACKEND.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Returns the global cache backend, [`FileBackend`] if none is set.
+pub(crate) fn backend() -> Arc<dyn CacheBackend> {
+    BACKEND
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| Arc::new(FileBackend))
+}
+
+/// Returns the error of a backend with nothing stored under the name
+#[cfg(any(feature = "redis", feature = "sled", feature = "sqlite"))]
+fn not_found(name: &str) -> crate::error::CacheError {
+    std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("No cache named {}", name),
+    )
+    .into()
+}
@@ -0,0 +1,127 @@
+//! [Redis](https://docs.rs/redis) backend for distributed deployments
+//!
+//! Stores the cache in a Redis server, so all the instances of a service share the cached public
+//! address and the rate limits of the providers instead of each instance requesting them. The key
+//! expires when all the cached responses and rate limits of the cache have expired.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use public_ip_address::backend::{self, redis::RedisBackend};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let redis = RedisBackend::open("redis://cache.internal/")?.with_prefix("my-service:");
+//! backend::set_backend(redis);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{not_found, CacheBackend};
+use crate::cache::Result;
+use redis::{Client, Commands, IntoConnectionInfo};
+use std::time::SystemTime;
+
+/// Prefix of the keys when none is given
+pub const DEFAULT_PREFIX: &str = "public-ip-address:";
+
+/// Stores the cache in a Redis server, under the cache name prefixed with the key prefix
+///
+/// A connection is made for every operation, so the backend recovers from server restarts.
+#[derive(Debug, Clone)]
+pub struct RedisBackend {
+    client: Client,
+    prefix: String,
+}
+
+impl RedisBackend {
+    /// Creates a backend for the Redis server at the URL, such as `redis://127.0.0.1/`.
+    ///
+    /// No connection is made until the cache is loaded or saved.
+    pub fn open<T: IntoConnectionInfo>(url: T) -> Result<RedisBackend> {
+        Ok(RedisBackend::from_client(Client::open(url)?))
+    }
+
+    /// Creates a backend for the Redis server of the client.
+    pub fn from_client(client: Client) -> RedisBackend {
+        RedisBackend {
+            client,
+            prefix: DEFAULT_PREFIX.to_string(),
+        }
+    }
+
+    /// Sets the prefix of the keys, to share a server between several caches.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Returns the key of the cache name
+    fn key(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+}
+
+impl CacheBackend for RedisBackend {
+    fn load(&self, name: &str) -> Result<Vec<u8>> {
+        let mut connection = self.client.get_connection()?;
+        let data: Option<Vec<u8>> = connection.get(self.key(name))?;
+        data.ok_or_else(|| not_found(name))
+    }
+
+    fn save(&self, name: &str, data: &[u8], expires: Option<SystemTime>) -> Result<()> {
+        let mut connection = self.client.get_connection()?;
+        match expires.map(expires_in) {
+            None => connection.set::<_, _, ()>(self.key(name), data)?,
+            Some(0) => connection.del::<_, ()>(self.key(name))?,
+            Some(milliseconds) => {
+                connection.pset_ex::<_, _, ()>(self.key(name), data, milliseconds)?
+            }
+        }
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        let mut connection = self.client.get_connection()?;
+        connection.del::<_, ()>(self.key(name))?;
+        Ok(())
+    }
+}
+
+/// Returns the milliseconds until `expires`, `0` if it's in the past
+fn expires_in(expires: SystemTime) -> u64 {
+    expires
+        .duration_since(SystemTime::now())
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_expires_in() {
+        let expires = SystemTime::now() + Duration::from_secs(60);
+        assert!((59_000..=60_000).contains(&expires_in(expires)));
+        assert_eq!(expires_in(SystemTime::now() - Duration::from_secs(1)), 0);
+    }
+
+    #[ignore]
+    #[test]
+    fn test_redis_backend() {
+        let backend = RedisBackend::open("redis://127.0.0.1/")
+            .unwrap()
+            .with_prefix("public-ip-address-test:");
+        backend.save("lookup.cache", b"cache", None).unwrap();
+        assert_eq!(backend.load("lookup.cache").unwrap(), b"cache");
+        let expires = SystemTime::now() + Duration::from_millis(100);
+        backend
+            .save("lookup.cache", b"cache", Some(expires))
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(backend.load("lookup.cache").is_err(), "Key not expired");
+        backend.save("lookup.cache", b"cache", None).unwrap();
+        backend.delete("lookup.cache").unwrap();
+        assert!(backend.load("lookup.cache").is_err(), "Key not deleted");
+    }
+}
@@ -0,0 +1,130 @@
+//! [sled](https://docs.rs/sled) embedded database backend
+//!
+//! Stores the cache in a sled database, along with the history of all the cached responses, so
+//! long-running daemons can look back at the addresses they had.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use public_ip_address::backend::{self, sled::SledBackend};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let sled = SledBackend::open("/var/lib/public-ip-address")?;
+//! backend::set_backend(sled.clone());
+//! for record in sled.history()? {
+//!     println!("{:?}: {}", record.response_time(), record.ip());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{not_found, CacheBackend};
+use crate::cache::{ResponseRecord, Result};
+use std::{net::IpAddr, path::Path, time::SystemTime};
+
+/// Name of the tree holding the history of the cached responses
+const HISTORY_TREE: &str = "history";
+
+/// Stores the cache and the history of the cached responses in a sled database
+#[derive(Debug, Clone)]
+pub struct SledBackend {
+    db: sled::Db,
+    history: sled::Tree,
+}
+
+impl SledBackend {
+    /// Opens the sled database at `path`, creating it if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<SledBackend> {
+        SledBackend::from_db(sled::open(path)?)
+    }
+
+    /// Creates a backend storing the cache in an open sled database.
+    pub fn from_db(db: sled::Db) -> Result<SledBackend> {
+        let history = db.open_tree(HISTORY_TREE)?;
+        Ok(SledBackend { db, history })
+    }
+
+    /// Returns all the recorded responses, oldest first.
+    pub fn history(&self) -> Result<Vec<ResponseRecord>> {
+        self.history_since(SystemTime::UNIX_EPOCH)
+    }
+
+    /// Returns the responses recorded since `time`, oldest first.
+    pub fn history_since(&self, time: SystemTime) -> Result<Vec<ResponseRecord>> {
+        self.history
+            .range(timestamp(time).to_be_bytes()..)
+            .values()
+            .map(|value| Ok(serde_json::from_slice(&value?)?))
+            .collect()
+    }
+}
+
+impl CacheBackend for SledBackend {
+    fn load(&self, name: &str) -> Result<Vec<u8>> {
+        match self.db.get(name)? {
+            Some(data) => Ok(data.to_vec()),
+            None => Err(not_found(name)),
+        }
+    }
+
+    fn save(&self, name: &str, data: &[u8], _expires: Option<SystemTime>) -> Result<()> {
+        self.db.insert(name, data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        self.db.remove(name)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    fn record(&self, _target: Option<IpAddr>, record: &ResponseRecord) -> Result<()> {
+        // keyed by time then address, so the history is sorted and recording again is a no-op
+        let mut key = timestamp(record.response_time()).to_be_bytes().to_vec();
+        key.extend_from_slice(record.ip().to_string().as_bytes());
+        if !self.history.contains_key(&key)? {
+            self.history.insert(key, serde_json::to_vec(record)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the nanoseconds since the Unix epoch
+fn timestamp(time: SystemTime) -> u128 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lookup::LookupProvider, LookupResponse};
+
+    #[test]
+    fn test_sled_backend() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let backend = SledBackend::from_db(db).unwrap();
+        assert!(backend.load("lookup.cache").is_err(), "Empty database");
+        backend.save("lookup.cache", b"cache", None).unwrap();
+        assert_eq!(backend.load("lookup.cache").unwrap(), b"cache");
+        backend.delete("lookup.cache").unwrap();
+        assert!(backend.load("lookup.cache").is_err(), "Cache not deleted");
+
+        let start = SystemTime::now();
+        for ip in ["1.1.1.1", "2.2.2.2"] {
+            let response = LookupResponse::new(ip.parse().unwrap(), LookupProvider::Ipify);
+            let record = ResponseRecord::new(response, None);
+            backend.record(None, &record).unwrap();
+            backend.record(None, &record).unwrap();
+        }
+        let history = backend.history().unwrap();
+        let addresses: Vec<_> = history
+            .iter()
+            .map(|record| record.ip().to_string())
+            .collect();
+        assert_eq!(addresses, ["1.1.1.1", "2.2.2.2"], "History not in order");
+        assert_eq!(backend.history_since(start).unwrap().len(), 2);
+        assert!(backend.history_since(SystemTime::now()).unwrap().is_empty());
+    }
+}
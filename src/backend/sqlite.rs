@@ -0,0 +1,208 @@
+//! [SQLite](https://docs.rs/rusqlite) database backend
+//!
+//! Stores the cache in a SQLite database, along with an append-only `history` table of all the
+//! cached responses, so the address the host had at a given time can be queried.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use public_ip_address::backend::{self, sqlite::SqliteBackend};
+//! use std::time::{Duration, SystemTime};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let sqlite = SqliteBackend::open("lookup.sqlite")?;
+//! let last_week = SystemTime::now() - Duration::from_secs(7 * 24 * 60 * 60);
+//! if let Some(record) = sqlite.address_at(last_week)? {
+//!     println!("Public address a week ago: {}", record.ip());
+//! }
+//! backend::set_backend(sqlite);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{not_found, CacheBackend};
+use crate::cache::{ResponseRecord, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{
+    net::IpAddr,
+    path::Path,
+    sync::{Mutex, MutexGuard},
+    time::SystemTime,
+};
+
+/// Tables of the database, the history is keyed by time then address so recording again is a no-op
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS cache (
+        name TEXT PRIMARY KEY,
+        data BLOB NOT NULL,
+        expires INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS history (
+        time INTEGER NOT NULL,
+        ip TEXT NOT NULL,
+        current INTEGER NOT NULL,
+        record TEXT NOT NULL,
+        PRIMARY KEY (time, ip, current)
+    );
+";
+
+/// Stores the cache and the history of the cached responses in a SQLite database
+///
+/// The `time` of the history rows and the `expires` time of the cache are in nanoseconds since the
+/// Unix epoch, `current` is `1` for the responses of the current public address and `0` for the
+/// responses of target lookups.
+#[derive(Debug)]
+pub struct SqliteBackend {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteBackend {
+    /// Opens the SQLite database at `path`, creating it if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<SqliteBackend> {
+        SqliteBackend::from_connection(Connection::open(path)?)
+    }
+
+    /// Creates a backend storing the cache in an open SQLite database, creating the tables if needed.
+    pub fn from_connection(connection: Connection) -> Result<SqliteBackend> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(SqliteBackend {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Returns the responses recorded for the current public address since `time`, oldest first.
+    pub fn history_since(&self, time: SystemTime) -> Result<Vec<ResponseRecord>> {
+        let connection = self.connection();
+        let mut statement = connection
+            .prepare("SELECT record FROM history WHERE current = 1 AND time >= ?1 ORDER BY time")?;
+        let records = statement
+            .query_map(params![timestamp(time)], |row| row.get::<_, String>(0))?
+            .map(|record| Ok(serde_json::from_str(&record?)?))
+            .collect();
+        records
+    }
+
+    /// Returns the response of the current public address cached last at `time`, if any.
+    pub fn address_at(&self, time: SystemTime) -> Result<Option<ResponseRecord>> {
+        let record: Option<String> = self
+            .connection()
+            .query_row(
+                "SELECT record FROM history WHERE current = 1 AND time <= ?1 \
+                 ORDER BY time DESC LIMIT 1",
+                params![timestamp(time)],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(record
+            .map(|record| serde_json::from_str(&record))
+            .transpose()?)
+    }
+
+    /// Returns the database connection
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl CacheBackend for SqliteBackend {
+    fn load(&self, name: &str) -> Result<Vec<u8>> {
+        self.connection()
+            .query_row(
+                "SELECT data FROM cache WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| not_found(name))
+    }
+
+    fn save(&self, name: &str, data: &[u8], expires: Option<SystemTime>) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO cache (name, data, expires) VALUES (?1, ?2, ?3)",
+            params![name, data, expires.map(timestamp)],
+        )?;
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        self.connection()
+            .execute("DELETE FROM cache WHERE name = ?1", params![name])?;
+        Ok(())
+    }
+
+    fn record(&self, target: Option<IpAddr>, record: &ResponseRecord) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR IGNORE INTO history (time, ip, current, record) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                timestamp(record.response_time()),
+                record.ip().to_string(),
+                target.is_none(),
+                serde_json::to_string(record)?
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// Returns the nanoseconds since the Unix epoch
+fn timestamp(time: SystemTime) -> i64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lookup::LookupProvider, LookupResponse};
+
+    #[test]
+    fn test_sqlite_backend() {
+        let backend =
+            SqliteBackend::from_connection(Connection::open_in_memory().unwrap()).unwrap();
+        assert!(backend.load("lookup.cache").is_err(), "Empty database");
+        backend.save("lookup.cache", b"cache", None).unwrap();
+        backend.save("lookup.cache", b"updated", None).unwrap();
+        assert_eq!(backend.load("lookup.cache").unwrap(), b"updated");
+        backend.delete("lookup.cache").unwrap();
+        assert!(backend.load("lookup.cache").is_err(), "Cache not deleted");
+
+        let start = SystemTime::now();
+        assert!(backend.address_at(start).unwrap().is_none());
+        let first = ResponseRecord::new(
+            LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::Ipify),
+            None,
+        );
+        backend.record(None, &first).unwrap();
+        backend.record(None, &first).unwrap();
+        let between = SystemTime::now();
+        let target = ResponseRecord::new(
+            LookupResponse::new("8.8.8.8".parse().unwrap(), LookupProvider::IpWhoIs),
+            None,
+        );
+        backend.record(Some(target.ip()), &target).unwrap();
+        let second = ResponseRecord::new(
+            LookupResponse::new("2.2.2.2".parse().unwrap(), LookupProvider::Ipify),
+            None,
+        );
+        backend.record(None, &second).unwrap();
+
+        let history = backend.history_since(start).unwrap();
+        assert_eq!(history, [first, second], "Wrong history");
+        assert_eq!(
+            backend
+                .address_at(between)
+                .unwrap()
+                .map(|record| record.ip()),
+            Some("1.1.1.1".parse().unwrap()),
+            "Wrong address at the time"
+        );
+        assert_eq!(
+            backend
+                .address_at(SystemTime::now())
+                .unwrap()
+                .map(|record| record.ip()),
+            Some("2.2.2.2".parse().unwrap()),
+            "Latest address not returned"
+        );
+    }
+}
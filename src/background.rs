@@ -0,0 +1,255 @@
+//! # ⏹️ Background tasks
+//!
+//! This module provides the building blocks for components running in the background, like cache
+//! refreshers and monitors. Every background component is controlled through a `BackgroundHandle`,
+//! and its `shutdown()` method stops the component cleanly: the component finishes its pending work,
+//! like flushing cache writes and notifications, before `shutdown()` returns.
+//!
+//! Without the `blocking` feature the components run as `tokio` tasks, otherwise they run on their own thread.
+//!
+//! ## Example
+//! ```rust
+//! use public_ip_address::background::BackgroundHandle;
+//! use std::time::Duration;
+//!
+//! #[cfg(not(feature = "blocking"))]
+//! #[tokio::main]
+//! async fn main() {
+//!     let handle = BackgroundHandle::spawn(|signal| async move {
+//!         while !signal.wait(Duration::from_secs(1)).await {
+//!             // periodic work
+//!         }
+//!         // flush pending work before exiting
+//!     });
+//!     handle.shutdown().await;
+//! }
+//!
+//! #[cfg(feature = "blocking")]
+//! fn main() {
+//!     let handle = BackgroundHandle::spawn(|signal| {
+//!         while !signal.wait(Duration::from_secs(1)) {
+//!             // periodic work
+//!         }
+//!         // flush pending work before exiting
+//!     });
+//!     handle.shutdown();
+//! }
+//! ```
+
+use crate::logging::trace;
+use std::{sync::Arc, time::Duration};
+
+#[cfg(feature = "blocking")]
+use std::sync::{Condvar, Mutex};
+
+/// Signal telling a background component to shut down
+///
+/// The signal is cheap to clone, all clones observe the same state.
+#[derive(Clone, Debug)]
+pub struct ShutdownSignal {
+    #[cfg(not(feature = "blocking"))]
+    inner: Arc<tokio::sync::watch::Sender<bool>>,
+    #[cfg(feature = "blocking")]
+    inner: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl Default for ShutdownSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownSignal {
+    /// Creates a new signal that has not been triggered.
+    pub fn new() -> Self {
+        ShutdownSignal {
+            #[cfg(not(feature = "blocking"))]
+            inner: Arc::new(tokio::sync::watch::channel(false).0),
+            #[cfg(feature = "blocking")]
+            inner: Arc::new((Mutex::new(false), Condvar::new())),
+        }
+    }
+
+    /// Triggers the shutdown, waking up every component waiting on the signal.
+    #[maybe_async::async_impl]
+    pub fn trigger(&self) {
+        self.inner.send_replace(true);
+    }
+
+    /// Triggers the shutdown, waking up every component waiting on the signal.
+    #[maybe_async::sync_impl]
+    pub fn trigger(&self) {
+        let (stopped, condvar) = &*self.inner;
+        *stopped.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        condvar.notify_all();
+    }
+
+    /// Checks if the shutdown was triggered.
+    #[maybe_async::async_impl]
+    pub fn is_triggered(&self) -> bool {
+        *self.inner.borrow()
+    }
+
+    /// Checks if the shutdown was triggered.
+    #[maybe_async::sync_impl]
+    pub fn is_triggered(&self) -> bool {
+        *self.inner.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Waits for the given duration, returning early if the shutdown is triggered.
+    ///
+    /// Returns `true` if the shutdown was triggered.
+    #[maybe_async::async_impl]
+    pub async fn wait(&self, duration: Duration) -> bool {
+        let mut receiver = self.inner.subscribe();
+        let _ = tokio::time::timeout(duration, receiver.wait_for(|stopped| *stopped)).await;
+        self.is_triggered()
+    }
+
+    /// Waits for the given duration, returning early if the shutdown is triggered.
+    ///
+    /// Returns `true` if the shutdown was triggered.
+    #[maybe_async::sync_impl]
+    pub fn wait(&self, duration: Duration) -> bool {
+        let (stopped, condvar) = &*self.inner;
+        let stopped = stopped.lock().unwrap_or_else(|e| e.into_inner());
+        let (stopped, _) = condvar
+            .wait_timeout_while(stopped, duration, |stopped| !*stopped)
+            .unwrap_or_else(|e| e.into_inner());
+        *stopped
+    }
+}
+
+/// Handle controlling a component running in the background
+///
+/// Dropping the handle triggers the shutdown without waiting for the component to finish,
+/// use [`BackgroundHandle::shutdown`] to stop it cleanly.
+#[derive(Debug)]
+pub struct BackgroundHandle {
+    signal: ShutdownSignal,
+    #[cfg(not(feature = "blocking"))]
+    task: Option<tokio::task::JoinHandle<()>>,
+    #[cfg(feature = "blocking")]
+    task: Option<std::thread::JoinHandle<()>>,
+}
+
+impl BackgroundHandle {
+    /// Spawns a background component as a `tokio` task.
+    ///
+    /// The component receives a `ShutdownSignal` and is expected to return once it is triggered,
+    /// after finishing its pending work. Must be called from within a `tokio` runtime.
+    #[maybe_async::async_impl]
+    pub fn spawn<F, Fut>(component: F) -> Self
+    where
+        F: FnOnce(ShutdownSignal) -> Fut,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        let signal = ShutdownSignal::new();
+        let task = tokio::spawn(component(signal.clone()));
+        BackgroundHandle {
+            signal,
+            task: Some(task),
+        }
+    }
+
+    /// Spawns a background component on its own thread.
+    ///
+    /// The component receives a `ShutdownSignal` and is expected to return once it is triggered,
+    /// after finishing its pending work.
+    #[maybe_async::sync_impl]
+    pub fn spawn<F>(component: F) -> Self
+    where
+        F: FnOnce(ShutdownSignal) + Send + 'static,
+    {
+        let signal = ShutdownSignal::new();
+        let component_signal = signal.clone();
+        let task = std::thread::spawn(move || component(component_signal));
+        BackgroundHandle {
+            signal,
+            task: Some(task),
+        }
+    }
+
+    /// Returns the signal used to stop the component.
+    pub fn signal(&self) -> ShutdownSignal {
+        self.signal.clone()
+    }
+
+    /// Checks if the component has stopped.
+    pub fn is_finished(&self) -> bool {
+        self.task.as_ref().is_none_or(|task| task.is_finished())
+    }
+
+    /// Stops the component and waits until it finished its pending work.
+    #[maybe_async::async_impl]
+    pub async fn shutdown(mut self) {
+        trace!("Shutting down background component");
+        self.signal.trigger();
+        if let Some(task) = self.task.take() {
+            _ = task.await;
+        }
+        trace!("Background component stopped");
+    }
+
+    /// Stops the component and waits until it finished its pending work.
+    #[maybe_async::sync_impl]
+    pub fn shutdown(mut self) {
+        trace!("Shutting down background component");
+        self.signal.trigger();
+        if let Some(task) = self.task.take() {
+            _ = task.join();
+        }
+        trace!("Background component stopped");
+    }
+}
+
+impl Drop for BackgroundHandle {
+    fn drop(&mut self) {
+        self.signal.trigger();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_shutdown() {
+        let flushed = Arc::new(AtomicBool::new(false));
+        let component_flushed = flushed.clone();
+        let handle = BackgroundHandle::spawn(|signal| async move {
+            while !signal.wait(Duration::from_secs(60)).await {}
+            component_flushed.store(true, Ordering::SeqCst);
+        });
+        assert!(!handle.is_finished(), "Component should be running");
+        handle.shutdown().await;
+        assert!(flushed.load(Ordering::SeqCst), "Pending work not flushed");
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_shutdown() {
+        let flushed = Arc::new(AtomicBool::new(false));
+        let component_flushed = flushed.clone();
+        let handle = BackgroundHandle::spawn(move |signal| {
+            while !signal.wait(Duration::from_secs(60)) {}
+            component_flushed.store(true, Ordering::SeqCst);
+        });
+        assert!(!handle.is_finished(), "Component should be running");
+        handle.shutdown();
+        assert!(flushed.load(Ordering::SeqCst), "Pending work not flushed");
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_signal_wait() {
+        let signal = ShutdownSignal::new();
+        let triggered = signal.wait(Duration::from_millis(10)).await;
+        assert!(!triggered, "Signal should not be triggered");
+        signal.clone().trigger();
+        assert!(signal.is_triggered(), "Clones should share the signal");
+        let triggered = signal.wait(Duration::from_secs(60)).await;
+        assert!(triggered, "Triggered signal should not wait");
+    }
+}
@@ -0,0 +1,323 @@
+//! # 📡 Latency beacons
+//!
+//! This module probes a few well known regional endpoints with lightweight `HEAD` requests to estimate
+//! the coarse region of the host and the quality of its network connection, without sending any
+//! request to a lookup provider.
+//!
+//! The resulting `BeaconReport` can be used to order a list of providers, so that a first-time lookup
+//! prefers providers hosted close to the user.
+//!
+//! This module is only available with the `beacons` feature enabled.
+//!
+//! ## Example
+//! ```rust
+//! use public_ip_address::{beacon, lookup::LookupProvider};
+//! use std::error::Error;
+//!
+//! #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! #[maybe_async::maybe_async]
+//! async fn main() -> Result<(), Box<dyn Error>> {
+//!     let report = beacon::probe_beacons().await;
+//!     println!("Region: {:?}, quality: {:?}", report.region, report.quality);
+//!     let providers = report.order_providers(vec![
+//!         (LookupProvider::IpInfo, None),
+//!         (LookupProvider::Mullvad, None),
+//!     ]);
+//!     println!("{:?}", providers);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::logging::{debug, trace};
+use crate::lookup::{client::Client, LookupProvider, Parameters};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Timeout for a single beacon probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Coarse geographic region
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Region {
+    /// North America
+    NorthAmerica,
+    /// South America
+    SouthAmerica,
+    /// Europe
+    Europe,
+    /// Asia
+    Asia,
+    /// Oceania
+    Oceania,
+    /// Africa
+    Africa,
+}
+
+/// Coarse quality of the network connection, based on the latency to the closest beacon
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NetworkQuality {
+    /// Closest beacon answered within 100 ms
+    Good,
+    /// Closest beacon answered within 300 ms
+    Fair,
+    /// Closest beacon answered after 300 ms
+    Poor,
+    /// No beacon answered
+    Offline,
+}
+
+impl NetworkQuality {
+    /// Classifies the latency to the closest beacon.
+    pub fn from_latency(latency: Option<Duration>) -> NetworkQuality {
+        match latency {
+            Some(latency) if latency <= Duration::from_millis(100) => NetworkQuality::Good,
+            Some(latency) if latency <= Duration::from_millis(300) => NetworkQuality::Fair,
+            Some(_) => NetworkQuality::Poor,
+            None => NetworkQuality::Offline,
+        }
+    }
+}
+
+/// Well known endpoint located in a specific region
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Beacon {
+    /// Name of the beacon
+    pub name: &'static str,
+    /// URL probed with a `HEAD` request
+    pub url: &'static str,
+    /// Region the beacon is located in
+    pub region: Region,
+}
+
+/// Default beacons, one regional endpoint per region
+pub const BEACONS: &[Beacon] = &[
+    Beacon {
+        name: "us-east-1",
+        url: "https://dynamodb.us-east-1.amazonaws.com",
+        region: Region::NorthAmerica,
+    },
+    Beacon {
+        name: "sa-east-1",
+        url: "https://dynamodb.sa-east-1.amazonaws.com",
+        region: Region::SouthAmerica,
+    },
+    Beacon {
+        name: "eu-central-1",
+        url: "https://dynamodb.eu-central-1.amazonaws.com",
+        region: Region::Europe,
+    },
+    Beacon {
+        name: "ap-northeast-1",
+        url: "https://dynamodb.ap-northeast-1.amazonaws.com",
+        region: Region::Asia,
+    },
+    Beacon {
+        name: "ap-southeast-2",
+        url: "https://dynamodb.ap-southeast-2.amazonaws.com",
+        region: Region::Oceania,
+    },
+    Beacon {
+        name: "af-south-1",
+        url: "https://dynamodb.af-south-1.amazonaws.com",
+        region: Region::Africa,
+    },
+];
+
+/// Latency measured to a single beacon
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct BeaconLatency {
+    /// Name of the beacon
+    pub name: String,
+    /// Region of the beacon
+    pub region: Region,
+    /// Round-trip time of the probe, `None` if the beacon did not answer
+    pub latency: Option<Duration>,
+}
+
+impl BeaconLatency {
+    /// Creates a new `BeaconLatency` for a beacon.
+    pub fn new(beacon: &Beacon, latency: Option<Duration>) -> BeaconLatency {
+        BeaconLatency {
+            name: beacon.name.to_string(),
+            region: beacon.region,
+            latency,
+        }
+    }
+}
+
+/// Result of probing the beacons
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct BeaconReport {
+    /// Estimated region of the host, the region of the closest beacon
+    pub region: Option<Region>,
+    /// Estimated network quality
+    pub quality: NetworkQuality,
+    /// Latencies measured to every beacon
+    pub latencies: Vec<BeaconLatency>,
+}
+
+impl BeaconReport {
+    /// Creates a report out of the latencies measured to the beacons.
+    pub fn from_latencies(latencies: Vec<BeaconLatency>) -> BeaconReport {
+        let closest = latencies
+            .iter()
+            .filter_map(|beacon| beacon.latency.map(|latency| (beacon.region, latency)))
+            .min_by_key(|(_, latency)| *latency);
+        BeaconReport {
+            region: closest.map(|(region, _)| region),
+            quality: NetworkQuality::from_latency(closest.map(|(_, latency)| latency)),
+            latencies,
+        }
+    }
+
+    /// Orders a list of providers so the ones hosted in the estimated region come first.
+    ///
+    /// The relative order of the providers is otherwise preserved.
+    pub fn order_providers(
+        &self,
+        mut providers: Vec<(LookupProvider, Option<Parameters>)>,
+    ) -> Vec<(LookupProvider, Option<Parameters>)> {
+        if let Some(region) = self.region {
+            providers.sort_by_key(|(provider, _)| provider_region(provider) != Some(region));
+        }
+        providers
+    }
+}
+
+/// Returns the region a provider is known to be hosted in.
+///
+/// Providers served from anycast or multi-region infrastructure return `None`.
+pub fn provider_region(provider: &LookupProvider) -> Option<Region> {
+    match provider {
+        LookupProvider::Mullvad | LookupProvider::IpLeak | LookupProvider::IfConfig => {
+            Some(Region::Europe)
+        }
+        LookupProvider::IpApiCo | LookupProvider::AbstractApi => Some(Region::NorthAmerica),
+        _ => None,
+    }
+}
+
+/// Probes the default beacons concurrently and estimates the region and network quality.
+#[maybe_async::maybe_async]
+pub async fn probe_beacons() -> BeaconReport {
+    probe(BEACONS).await
+}
+
+/// Probes the given beacons concurrently and estimates the region and network quality.
+#[maybe_async::async_impl]
+pub async fn probe(beacons: &[Beacon]) -> BeaconReport {
+    let client = Client::builder().timeout(PROBE_TIMEOUT).build();
+    let latencies = match client {
+        Ok(client) => {
+            futures::future::join_all(beacons.iter().map(|beacon| probe_beacon(&client, beacon)))
+                .await
+        }
+        Err(_) => beacons
+            .iter()
+            .map(|beacon| BeaconLatency::new(beacon, None))
+            .collect(),
+    };
+    BeaconReport::from_latencies(latencies)
+}
+
+/// Probes the given beacons concurrently and estimates the region and network quality.
+#[maybe_async::sync_impl]
+pub fn probe(beacons: &[Beacon]) -> BeaconReport {
+    let client = Client::builder().timeout(PROBE_TIMEOUT).build();
+    let latencies = match client {
+        Ok(client) => std::thread::scope(|scope| {
+            let handles: Vec<_> = beacons
+                .iter()
+                .map(|beacon| {
+                    let client = &client;
+                    scope.spawn(move || probe_beacon(client, beacon))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .zip(beacons)
+                .map(|(handle, beacon)| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|_| BeaconLatency::new(beacon, None))
+                })
+                .collect()
+        }),
+        Err(_) => beacons
+            .iter()
+            .map(|beacon| BeaconLatency::new(beacon, None))
+            .collect(),
+    };
+    BeaconReport::from_latencies(latencies)
+}
+
+/// Measures the round-trip time of a `HEAD` request to a beacon.
+#[maybe_async::maybe_async]
+async fn probe_beacon(client: &Client, beacon: &Beacon) -> BeaconLatency {
+    trace!("Probing beacon {}", beacon.name);
+    let start = Instant::now();
+    let latency = match client.head(beacon.url).send().await {
+        // any HTTP answer means the beacon is reachable
+        Ok(_) => Some(start.elapsed()),
+        Err(e) => {
+            debug!("Beacon {} unreachable: {}", beacon.name, e);
+            None
+        }
+    };
+    BeaconLatency::new(beacon, latency)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latency(region: Region, millis: Option<u64>) -> BeaconLatency {
+        BeaconLatency {
+            name: format!("{:?}", region),
+            region,
+            latency: millis.map(Duration::from_millis),
+        }
+    }
+
+    #[test]
+    fn test_report_from_latencies() {
+        let report = BeaconReport::from_latencies(vec![
+            latency(Region::NorthAmerica, Some(120)),
+            latency(Region::Europe, Some(20)),
+            latency(Region::Asia, None),
+        ]);
+        assert_eq!(report.region, Some(Region::Europe), "Wrong region");
+        assert_eq!(report.quality, NetworkQuality::Good, "Wrong quality");
+
+        let report = BeaconReport::from_latencies(vec![latency(Region::Asia, None)]);
+        assert_eq!(report.region, None, "Unreachable beacons have no region");
+        assert_eq!(report.quality, NetworkQuality::Offline, "Wrong quality");
+    }
+
+    #[test]
+    fn test_order_providers() {
+        let report = BeaconReport::from_latencies(vec![latency(Region::Europe, Some(400))]);
+        assert_eq!(report.quality, NetworkQuality::Poor, "Wrong quality");
+        let providers = report.order_providers(vec![
+            (LookupProvider::IpInfo, None),
+            (LookupProvider::IpApiCo, None),
+            (LookupProvider::Mullvad, None),
+            (LookupProvider::IpWhoIs, None),
+        ]);
+        let providers: Vec<LookupProvider> = providers.into_iter().map(|(p, _)| p).collect();
+        assert_eq!(
+            providers,
+            vec![
+                LookupProvider::Mullvad,
+                LookupProvider::IpInfo,
+                LookupProvider::IpApiCo,
+                LookupProvider::IpWhoIs
+            ],
+            "Providers in the region should come first"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! # ⏱️ Provider benchmarking
+//!
+//! This module measures the latency and reliability of lookup providers from the current host,
+//! so fallback chains can be chosen empirically.
+//!
+//! ## Example
+//! ```rust
+//! use public_ip_address::benchmark::benchmark_providers;
+//! use public_ip_address::lookup::LookupProvider;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() {
+//! let providers = vec![
+//!     (LookupProvider::IpInfo, None),
+//!     (LookupProvider::IpWhoIs, None),
+//! ];
+//! let report = benchmark_providers(providers, 3).await;
+//! println!("{}", report);
+//! # }
+//! ```
+
+use crate::logging::debug;
+use crate::lookup::{LookupProvider, LookupService, Parameters};
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Benchmark results of a single provider
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ProviderBenchmark {
+    /// Benchmarked provider
+    pub provider: LookupProvider,
+    /// Number of successful lookups
+    pub successes: usize,
+    /// Number of failed lookups
+    pub errors: usize,
+    /// Latencies of the successful lookups, sorted in ascending order
+    pub latencies: Vec<Duration>,
+}
+
+impl ProviderBenchmark {
+    /// Creates the results of a provider from the latencies of its successful lookups.
+    pub fn new(provider: LookupProvider, mut latencies: Vec<Duration>, errors: usize) -> Self {
+        latencies.sort();
+        ProviderBenchmark {
+            provider,
+            successes: latencies.len(),
+            errors,
+            latencies,
+        }
+    }
+
+    /// Returns the latency percentile of the successful lookups, `None` if all lookups failed.
+    ///
+    /// Uses the nearest-rank method, `percentile` is clamped between 0 and 100.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * self.latencies.len() as f64).ceil();
+        let index = (rank as usize).saturating_sub(1);
+        self.latencies.get(index).copied()
+    }
+
+    /// Returns the median latency of the successful lookups.
+    pub fn median(&self) -> Option<Duration> {
+        self.percentile(50.0)
+    }
+
+    /// Returns the share of successful lookups, between 0 and 1.
+    pub fn success_rate(&self) -> f64 {
+        let total = self.successes + self.errors;
+        if total == 0 {
+            0.0
+        } else {
+            self.successes as f64 / total as f64
+        }
+    }
+}
+
+/// Benchmark results of a list of providers
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct BenchmarkReport {
+    /// Results of each provider, in the order they were benchmarked
+    pub results: Vec<ProviderBenchmark>,
+}
+
+impl BenchmarkReport {
+    /// Returns the results ranked by success rate, then by median latency.
+    pub fn ranked(&self) -> Vec<&ProviderBenchmark> {
+        let mut ranked: Vec<&ProviderBenchmark> = self.results.iter().collect();
+        ranked.sort_by(|a, b| {
+            b.success_rate().total_cmp(&a.success_rate()).then(
+                a.median()
+                    .unwrap_or(Duration::MAX)
+                    .cmp(&b.median().unwrap_or(Duration::MAX)),
+            )
+        });
+        ranked
+    }
+}
+
+impl fmt::Display for BenchmarkReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn ms(latency: Option<Duration>) -> String {
+            latency.map_or("-".to_string(), |latency| latency.as_millis().to_string())
+        }
+        write!(
+            f,
+            "{:<16} {:>4} {:>6} {:>8} {:>8} {:>8}",
+            "Provider", "OK", "Errors", "p50 ms", "p90 ms", "p99 ms"
+        )?;
+        for result in &self.results {
+            write!(
+                f,
+                "\n{:<16} {:>4} {:>6} {:>8} {:>8} {:>8}",
+                result.provider.to_string(),
+                result.successes,
+                result.errors,
+                ms(result.percentile(50.0)),
+                ms(result.percentile(90.0)),
+                ms(result.percentile(99.0)),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Benchmarks a list of providers by performing a number of lookups with each one.
+///
+/// The lookups are made one after the other, so the measured latencies are not affected
+/// by concurrent requests. The cache is not used.
+///
+/// # Arguments
+///
+/// * `providers` - A vector of `LookupProvider`s and their `Parameters` to benchmark.
+/// * `iterations` - Number of lookups to perform with each provider.
+///
+/// # Returns
+///
+/// * A `BenchmarkReport` with the latency percentiles and error counts of each provider.
+#[maybe_async::maybe_async]
+pub async fn benchmark_providers(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    iterations: usize,
+) -> BenchmarkReport {
+    let mut results = Vec::new();
+    for (provider, parameters) in providers {
+        debug!("Benchmarking provider {}", provider);
+        let service = LookupService::new(provider.clone(), parameters);
+        let mut latencies = Vec::new();
+        let mut errors = 0;
+        for _ in 0..iterations {
+            let start = Instant::now();
+            match service.lookup(None).await {
+                Ok(_) => latencies.push(start.elapsed()),
+                Err(_) => errors += 1,
+            }
+        }
+        results.push(ProviderBenchmark::new(provider, latencies, errors));
+    }
+    BenchmarkReport { results }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let latencies = (1..=10).rev().map(Duration::from_millis).collect();
+        let result = ProviderBenchmark::new(LookupProvider::IpInfo, latencies, 10);
+        assert_eq!(result.median(), Some(Duration::from_millis(5)));
+        assert_eq!(result.percentile(90.0), Some(Duration::from_millis(9)));
+        assert_eq!(result.percentile(100.0), Some(Duration::from_millis(10)));
+        assert_eq!(result.percentile(0.0), Some(Duration::from_millis(1)));
+        assert_eq!(result.success_rate(), 0.5);
+
+        let failed = ProviderBenchmark::new(LookupProvider::IpWhoIs, vec![], 3);
+        assert_eq!(failed.median(), None);
+        let report = BenchmarkReport {
+            results: vec![failed, result],
+        };
+        assert_eq!(report.ranked()[0].provider, LookupProvider::IpInfo);
+        assert_eq!(report.to_string().lines().count(), 3);
+    }
+}
@@ -5,9 +5,27 @@
 //! The `ResponseCache` can be saved to disk, loaded from disk, and deleted from disk. It also provides methods to clear the cache,
 //! update the cache with a new response, check if the cache has expired, and retrieve the IP address or the entire response from the cache.
 //!
-//! The cache is stored in a JSON format by default in the system cache directory. And a custom file name can be provided.
+//! The cache is stored in a JSON format by default in the `public-ip-address` directory of the platform cache directory,
+//! see [`get_cache_path`]. And a custom file name can be provided.
 //!
-//! If the `encryption` feature is enabled, the cache is encrypted using AEAD.
+//! The cache format is versioned with [`CACHE_VERSION`]: caches written by older versions of the crate are migrated
+//! when loaded, and caches that can't be read are discarded and replaced by the next lookup.
+//!
+//! Responses of target lookups are cached per address, for at most [`max_targets`] addresses: when the bound is
+//! reached, the least recently used addresses are evicted.
+//!
+//! The cache also keeps a changelog of the current IP address, the last [`MAX_HISTORY`] addresses the host had with
+//! the time they were first seen, see [`ResponseCache::history`].
+//!
+//! The cached responses can be discarded with [`clear`], or only the ones of a provider with [`invalidate`], for
+//! example after the IP address changed. The cached-lookup functions also take a `force_refresh` flag.
+//!
+//! If the `compression` feature is enabled, the cache is compressed with zstd before it's encrypted. Uncompressed
+//! caches, written without the feature, are still read.
+//!
+//! If the `encryption` feature is enabled, the cache is encrypted using AEAD, with a key derived from the machine ID
+//! or the key given to `set_encryption_key`. A cache that can't be decrypted, for example after the key changed,
+//! is discarded and replaced by the next lookup.
 //!
 //! ## Example
 //! ```rust
@@ -30,25 +48,48 @@
 //! }
 //! ```
 
-use crate::{error::CacheError, LookupResponse};
-use directories::BaseDirs;
-use log::{debug, trace};
+use crate::backend::backend;
+use crate::logging::{debug, trace, warn};
+use crate::{error::CacheError, lookup::LookupProvider, response::LookupResponse};
+use directories::{BaseDirs, ProjectDirs};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeMap,
     fs,
-    fs::File,
-    io::prelude::*,
     net::IpAddr,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
     time::{Duration, SystemTime},
 };
 
 #[cfg(feature = "encryption")]
 use cocoon::Cocoon;
+#[cfg(feature = "encryption")]
+use std::sync::RwLock;
 
 /// Result type wrapper for the cache
 pub type Result<T> = std::result::Result<T, CacheError>;
 
+/// Name of the cache when no file name is given
+pub const DEFAULT_FILE_NAME: &str = "lookup.cache";
+
+/// Version of the cache format, increased when the format changes
+///
+/// Caches of older versions are migrated when loaded, caches of newer versions are discarded.
+pub const CACHE_VERSION: u32 = 1;
+
+/// Maximum number of target addresses cached when none is set with [`set_max_targets`]
+pub const DEFAULT_MAX_TARGETS: usize = 1024;
+
+/// Maximum number of entries kept in the history of the current IP address
+pub const MAX_HISTORY: usize = 100;
+
+static MAX_TARGETS: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_TARGETS);
+
+/// Time after which a cache hit marks the target as used again, so hits don't rewrite the cache
+/// every time
+pub(crate) const TOUCH_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Represents an entry of the cached response
 ///
 /// It contains the `LookupResponse`, the time when the response was cached, and the time-to-live (TTL) of the cache.
@@ -59,6 +100,10 @@ pub struct ResponseRecord {
     pub response: LookupResponse,
     response_time: SystemTime,
     ttl: Option<u64>,
+    #[serde(default)]
+    verified_time: Option<SystemTime>,
+    #[serde(default)]
+    used_time: Option<SystemTime>,
 }
 
 impl ResponseRecord {
@@ -68,12 +113,14 @@ impl ResponseRecord {
     ///
     /// * `response` - A `LookupResponse` to be cached.
     /// * `ttl` - An optional `u64` value representing after how many seconds the cached value expires.
-    /// None means the cache never expires.
+    ///   None means the cache never expires.
     pub fn new(response: LookupResponse, ttl: Option<u64>) -> ResponseRecord {
         ResponseRecord {
             response,
             response_time: SystemTime::now(),
             ttl,
+            verified_time: None,
+            used_time: None,
         }
     }
 
@@ -92,27 +139,199 @@ impl ResponseRecord {
         }
     }
 
+    /// Determines if the cached response has been expired for at least `max_stale` seconds.
+    ///
+    /// A response that never expires is never stale.
+    pub fn is_stale(&self, max_stale: u64) -> bool {
+        self.expires_at()
+            .is_some_and(|expires| SystemTime::now() >= expires + Duration::from_secs(max_stale))
+    }
+
+    /// Determines if the IP address of the cached response needs to be verified again.
+    ///
+    /// The IP address is considered fresh for `ip_ttl` seconds after the response was cached
+    /// or after it was last verified with [`ResponseRecord::verify`], whichever is later.
+    /// If `ip_ttl` is `None`, the IP address never needs to be verified.
+    pub fn ip_is_expired(&self, ip_ttl: Option<u64>) -> bool {
+        if let Some(ip_ttl) = ip_ttl {
+            let checked = self.verified_time.map_or(self.response_time, |verified| {
+                verified.max(self.response_time)
+            });
+            let difference = SystemTime::now()
+                .duration_since(checked)
+                .unwrap_or_default();
+            difference >= Duration::from_secs(ip_ttl)
+        } else {
+            false
+        }
+    }
+
+    /// Marks the IP address of the cached response as verified now.
+    pub fn verify(&mut self) {
+        self.verified_time = Some(SystemTime::now());
+    }
+
+    /// Marks the cached response as used now.
+    pub fn touch(&mut self) {
+        self.used_time = Some(SystemTime::now());
+    }
+
+    /// Returns the time the cached response was last used, or cached if it was never used.
+    pub fn last_used(&self) -> SystemTime {
+        self.used_time
+            .map_or(self.response_time, |used| used.max(self.response_time))
+    }
+
     /// Returns the IP address of the cached response.
     pub fn ip(&self) -> std::net::IpAddr {
         self.response.ip
     }
+
+    /// Returns the time the response was cached.
+    pub fn response_time(&self) -> SystemTime {
+        self.response_time
+    }
+
+    /// Returns the time the cached response expires, `None` if it never expires.
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.ttl
+            .map(|ttl| self.response_time + Duration::from_secs(ttl))
+    }
+}
+
+/// Represents a change of the current IP address, kept in the history of the cache
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct HistoryEntry {
+    /// Time the address was first seen
+    pub time: SystemTime,
+    /// The current IP address from that time
+    pub ip: IpAddr,
+    /// Provider the address was looked up with
+    pub provider: LookupProvider,
+}
+
+/// Refresh policy with separate lifetimes for the IP address and the geolocation data.
+///
+/// The IP address of a host can change at any time, while the geolocation and ASN data attached to it
+/// rarely do. A `RefreshPolicy` allows the IP address to be re-verified often with a lightweight
+/// IP-only request, while the full geolocation lookup is only repeated once `geo_ttl` has passed.
+///
+/// # Example
+///
+/// ```
+/// # use public_ip_address::cache::RefreshPolicy;
+/// // verify the IP every minute, reuse the geolocation data for a day
+/// let policy = RefreshPolicy::new(Some(60), Some(24 * 60 * 60));
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct RefreshPolicy {
+    /// Seconds after which the cached IP address must be re-verified. `None` never re-verifies.
+    pub ip_ttl: Option<u64>,
+    /// Seconds after which the geolocation data must be fetched again. `None` never expires.
+    pub geo_ttl: Option<u64>,
+}
+
+impl RefreshPolicy {
+    /// Creates a new `RefreshPolicy` from the IP and geolocation time-to-live in seconds.
+    pub fn new(ip_ttl: Option<u64>, geo_ttl: Option<u64>) -> RefreshPolicy {
+        RefreshPolicy { ip_ttl, geo_ttl }
+    }
+}
+
+/// Recorded performance of a lookup provider
+///
+/// Used by the [`Adaptive`](crate::strategy::Adaptive) strategy to try the most reliable
+/// and fastest providers first.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct ProviderStats {
+    /// Number of successful lookups
+    pub successes: u64,
+    /// Number of failed lookups
+    pub failures: u64,
+    /// Moving average of the latency of successful lookups
+    pub average_latency: Option<Duration>,
+}
+
+impl ProviderStats {
+    /// Weight of the latest latency in the moving average
+    const LATENCY_SMOOTHING: f64 = 0.2;
+
+    /// Records the outcome and latency of a lookup.
+    pub fn record(&mut self, success: bool, latency: Duration) {
+        if success {
+            self.successes += 1;
+            self.average_latency = Some(match self.average_latency {
+                Some(average) => {
+                    average.mul_f64(1.0 - Self::LATENCY_SMOOTHING)
+                        + latency.mul_f64(Self::LATENCY_SMOOTHING)
+                }
+                None => latency,
+            });
+        } else {
+            self.failures += 1;
+        }
+    }
+
+    /// Returns the success rate of the provider.
+    ///
+    /// The rate is smoothed towards 50% so a few lookups don't rule out a provider for good.
+    pub fn success_rate(&self) -> f64 {
+        (self.successes as f64 + 1.0) / ((self.successes + self.failures) as f64 + 2.0)
+    }
+
+    /// Returns the score of the provider, higher is better.
+    ///
+    /// The score is the success rate divided by the expected latency in seconds,
+    /// providers without a known latency are assumed to answer in one second.
+    pub fn score(&self) -> f64 {
+        let latency = self
+            .average_latency
+            .unwrap_or(Duration::from_secs(1))
+            .as_secs_f64();
+        self.success_rate() / (1.0 + latency)
+    }
 }
 
 /// Holds the current IP address lookup response
 ///
 /// The cache can be saved to disk, loaded from disk, and deleted from disk. It also provides methods to clear the cache,
 /// update the cache with a new response, check if the cache has expired, and retrieve the IP address or the entire response from the cache.
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[non_exhaustive]
 pub struct ResponseCache {
+    /// Version of the cache format, `0` for the caches written before the format was versioned
+    #[serde(default)]
+    version: u32,
     /// The current IP address lookup response
     pub current_address: Option<ResponseRecord>,
     /// A tree of arbitrary IP address responses
     pub lookup_address: BTreeMap<IpAddr, ResponseRecord>,
+    /// The current IP address lookup responses restricted to an IP version, to the address only or
+    /// to the provider of a stream, keyed by the restriction
+    #[serde(default)]
+    pub restricted_address: BTreeMap<String, ResponseRecord>,
+    /// Recorded performance of the lookup providers
+    #[serde(default)]
+    pub provider_stats: BTreeMap<String, ProviderStats>,
+    /// Times the rate limits of the throttled lookup providers reset
+    #[serde(default)]
+    pub rate_limits: BTreeMap<String, SystemTime>,
+    /// Changes of the current IP address, oldest first
+    #[serde(default)]
+    history: Vec<HistoryEntry>,
     /// The cache file name
     file_name: Option<String>,
 }
 
+impl Default for ResponseCache {
+    fn default() -> Self {
+        ResponseCache::new(None)
+    }
+}
+
 impl ResponseCache {
     /// Creates a new `ResponseCache` instance.
     ///
@@ -143,8 +362,13 @@ impl ResponseCache {
     pub fn new(file_name: Option<String>) -> ResponseCache {
         trace!("Creating new cache structure");
         ResponseCache {
+            version: CACHE_VERSION,
             current_address: None,
             lookup_address: BTreeMap::new(),
+            restricted_address: BTreeMap::new(),
+            provider_stats: BTreeMap::new(),
+            rate_limits: BTreeMap::new(),
+            history: Vec::new(),
             file_name,
         }
     }
@@ -163,17 +387,51 @@ impl ResponseCache {
         trace!("Clearing cache");
         self.current_address = None;
         self.lookup_address.clear();
+        self.restricted_address.clear();
     }
 
     /// Updates the cache entry for the current host with a new response.
     ///
+    /// If the IP address changed, the change is added to the history.
+    ///
     /// # Arguments
     ///
     /// * `response` - A `LookupResponse` instance representing the new address to be cached.
     /// * `ttl` - An `Option<u64>` representing the time-to-live (TTL) in seconds for the new cached response. If `None`, the cache never expires.
     ///
     pub fn update_current(&mut self, response: &LookupResponse, ttl: Option<u64>) {
-        self.current_address = Some(ResponseRecord::new(response.to_owned(), ttl));
+        let record = ResponseRecord::new(response.to_owned(), ttl);
+        if self.history.last().map(|entry| entry.ip) != Some(record.ip()) {
+            debug!("Current IP address changed to {}", record.ip());
+            self.history.push(HistoryEntry {
+                time: record.response_time(),
+                ip: record.ip(),
+                provider: response.provider.to_owned(),
+            });
+            let excess = self.history.len().saturating_sub(MAX_HISTORY);
+            self.history.drain(..excess);
+        }
+        self.current_address = Some(record);
+    }
+
+    /// Returns the changes of the current IP address, oldest first.
+    ///
+    /// An entry is added each time a lookup returns a different address than the previous one,
+    /// the last [`MAX_HISTORY`] are kept. Clearing the cached responses keeps the history.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use public_ip_address::{cache::ResponseCache, lookup::LookupProvider, response::LookupResponse};
+    /// let mut cache = ResponseCache::default();
+    /// for ip in ["1.1.1.1", "1.1.1.1", "2.2.2.2"] {
+    ///     let response = LookupResponse::new(ip.parse().unwrap(), LookupProvider::Ipify);
+    ///     cache.update_current(&response, None);
+    /// }
+    /// assert_eq!(cache.history().len(), 2);
+    /// ```
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.history
     }
 
     /// Checks if the `current_address` cache entry has expired.
@@ -184,6 +442,21 @@ impl ResponseCache {
         }
     }
 
+    /// Checks if the IP address of the `current_address` cache entry needs to be verified again.
+    pub fn current_ip_is_expired(&self, ip_ttl: Option<u64>) -> bool {
+        match self.current_address {
+            Some(ref current) => current.ip_is_expired(ip_ttl),
+            None => true,
+        }
+    }
+
+    /// Marks the IP address of the `current_address` cache entry as verified now.
+    pub fn verify_current(&mut self) {
+        if let Some(current) = self.current_address.as_mut() {
+            current.verify();
+        }
+    }
+
     /// Returns the IP address of the current host cache entry.
     pub fn current_ip(&self) -> Option<std::net::IpAddr> {
         self.current_address.as_ref().map(|current| current.ip())
@@ -197,9 +470,37 @@ impl ResponseCache {
     }
 
     /// Updates the lookup cache with a new response.
+    ///
+    /// If more than [`max_targets`] addresses are cached, the least recently used ones are evicted.
     pub fn update_target(&mut self, ip: IpAddr, response: &LookupResponse, ttl: Option<u64>) {
         self.lookup_address
             .insert(ip, ResponseRecord::new(response.to_owned(), ttl));
+        self.evict_targets(max_targets());
+    }
+
+    /// Marks the lookup cache entry for the given IP address as used now.
+    pub fn touch_target(&mut self, ip: &IpAddr) {
+        if let Some(lookup) = self.lookup_address.get_mut(ip) {
+            lookup.touch();
+        }
+    }
+
+    /// Evicts the least recently used lookup cache entries until at most `max` are left.
+    pub fn evict_targets(&mut self, max: usize) {
+        let excess = self.lookup_address.len().saturating_sub(max);
+        if excess == 0 {
+            return;
+        }
+        let mut targets: Vec<(SystemTime, IpAddr)> = self
+            .lookup_address
+            .iter()
+            .map(|(ip, lookup)| (lookup.last_used(), *ip))
+            .collect();
+        targets.sort_unstable();
+        debug!("Evicting {} cached targets", excess);
+        for (_, ip) in targets.into_iter().take(excess) {
+            self.lookup_address.remove(&ip);
+        }
     }
 
     /// Checks if the lookup cache entry for the given IP address has expired.
@@ -217,11 +518,121 @@ impl ResponseCache {
             .map(|lookup| lookup.response.to_owned())
     }
 
+    /// Updates the cache entry of a restricted lookup of the current host with a new response.
+    ///
+    /// Responses restricted to an IP version, to the address only or to the provider of a stream are
+    /// kept apart from the `current_address`, and are not added to the history.
+    pub fn update_restricted(&mut self, key: &str, response: &LookupResponse, ttl: Option<u64>) {
+        self.restricted_address.insert(
+            key.to_string(),
+            ResponseRecord::new(response.to_owned(), ttl),
+        );
+    }
+
+    /// Checks if the restricted lookup cache entry with the given key has expired.
+    pub fn restricted_is_expired(&self, key: &str) -> bool {
+        match self.restricted_address.get(key) {
+            Some(lookup) => lookup.is_expired(),
+            None => true,
+        }
+    }
+
+    /// Returns the restricted lookup cache entry with the given key.
+    pub fn restricted_response(&self, key: &str) -> Option<LookupResponse> {
+        self.restricted_address
+            .get(key)
+            .map(|lookup| lookup.response.to_owned())
+    }
+
+    /// Records the outcome and latency of a lookup made with the given provider.
+    pub fn record_provider(&mut self, provider: &LookupProvider, success: bool, latency: Duration) {
+        self.provider_stats
+            .entry(provider.to_string())
+            .or_default()
+            .record(success, latency);
+    }
+
+    /// Returns the recorded performance of the given provider.
+    pub fn provider_stats(&self, provider: &LookupProvider) -> Option<&ProviderStats> {
+        self.provider_stats.get(&provider.to_string())
+    }
+
+    /// Records that the given provider is rate limited until `reset`, or clears its rate limit if `None`.
+    pub fn record_rate_limit(&mut self, provider: &LookupProvider, reset: Option<SystemTime>) {
+        match reset {
+            Some(reset) => self.rate_limits.insert(provider.to_string(), reset),
+            None => self.rate_limits.remove(&provider.to_string()),
+        };
+    }
+
+    /// Returns the time the rate limit of the given provider resets, if it's still rate limited.
+    pub fn rate_limited_until(&self, provider: &LookupProvider) -> Option<SystemTime> {
+        self.rate_limits
+            .get(&provider.to_string())
+            .filter(|reset| **reset > SystemTime::now())
+            .copied()
+    }
+
+    /// Removes the cached responses of the given provider.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use public_ip_address::{cache::ResponseCache, lookup::LookupProvider, response::LookupResponse};
+    /// let mut cache = ResponseCache::default();
+    /// let response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::Ipify);
+    /// cache.update_current(&response, None);
+    /// cache.invalidate(&LookupProvider::Ipify);
+    /// assert!(cache.current_response().is_none());
+    /// ```
+    pub fn invalidate(&mut self, provider: &LookupProvider) {
+        trace!("Invalidating responses of {}", provider);
+        if self
+            .current_address
+            .as_ref()
+            .is_some_and(|current| current.response.provider == *provider)
+        {
+            self.current_address = None;
+        }
+        self.lookup_address
+            .retain(|_, lookup| lookup.response.provider != *provider);
+        self.restricted_address
+            .retain(|_, lookup| lookup.response.provider != *provider);
+    }
+
+    /// Returns the time all the cached responses and rate limits expire.
+    ///
+    /// `None` if a cached response never expires, or if there is no response or rate limit.
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        let mut expires = self.rate_limits.values().copied().max();
+        for record in self
+            .current_address
+            .iter()
+            .chain(self.lookup_address.values())
+            .chain(self.restricted_address.values())
+        {
+            expires = Some(
+                expires
+                    .unwrap_or(SystemTime::UNIX_EPOCH)
+                    .max(record.expires_at()?),
+            );
+        }
+        expires
+    }
+
+    /// Returns the name the cache is stored under.
+    fn name(&self) -> &str {
+        self.file_name.as_deref().unwrap_or(DEFAULT_FILE_NAME)
+    }
+
     /// Writes the `ResponseCache` instance to a file on disk.
     ///
     /// This method serializes the `ResponseCache` instance into a JSON string, encrypts the data if the "encryption" feature is enabled,
     /// and then writes the encrypted (or plain text) data to a file. The file is located at the path specified by the `file_name` field of the `ResponseCache` instance.
     ///
+    /// With a global [`backend`](crate::backend) set, the data is written to the backend instead, and the
+    /// cached responses are recorded in its history.
+    ///
     /// # Examples
     ///
     /// ```
@@ -230,26 +641,36 @@ impl ResponseCache {
     /// _ = cache.save();
     /// ```
     pub fn save(&self) -> Result<()> {
-        debug!("Saving cache to {}", get_cache_path(&self.file_name));
+        debug!("Saving cache {}", self.name());
         let data = serde_json::to_string(self)?.into_bytes();
 
+        #[cfg(feature = "compression")]
+        let data = compress(data)?;
+
         #[cfg(feature = "encryption")]
         let data = encrypt(data)?;
 
-        let mut file = File::create(get_cache_path(&self.file_name))?;
-        file.write_all(&data)?;
+        let backend = backend();
+        backend.save(self.name(), &data, self.expires_at())?;
+        if let Some(current) = &self.current_address {
+            backend.record(None, current)?;
+        }
+        for (target, record) in &self.lookup_address {
+            backend.record(Some(*target), record)?;
+        }
         Ok(())
     }
 
     /// Loads the `ResponseCache` instance from a file on disk.
     ///
     /// This method reads the file specified by `file_name`, decrypts the data if the "encryption" feature is enabled,
-    /// and then deserializes the data into a `ResponseCache` instance.
+    /// and then deserializes the data into a `ResponseCache` instance. With a global [`backend`](crate::backend)
+    /// set, the data is read from the backend instead.
     ///
     /// # Arguments
     ///
     /// * `file_name` - An `Option<String>` representing the name of the file from which the cache will be loaded.
-    /// If `None`, the default file name `lookup.cache` will be used.
+    ///   If `None`, the default file name `lookup.cache` will be used.
     ///
     /// # Examples
     ///
@@ -258,35 +679,169 @@ impl ResponseCache {
     /// let cache = ResponseCache::load(Some("cache.txt".to_string()));
     /// ```
     pub fn load(file_name: Option<String>) -> Result<ResponseCache> {
-        debug!("Loading cache from {}", get_cache_path(&file_name));
-        let mut file = File::open(get_cache_path(&file_name))?;
-        let mut data = Vec::new();
-        file.read_to_end(&mut data)?;
+        let name = file_name.as_deref().unwrap_or(DEFAULT_FILE_NAME);
+        debug!("Loading cache {}", name);
+        let data = backend().load(name)?;
 
         #[cfg(feature = "encryption")]
-        let data = decrypt(data)?;
+        let data = decrypt(data).inspect_err(|e| {
+            warn!(
+                "Failed to decrypt cache, starting with an empty cache: {:?}",
+                e
+            );
+        })?;
 
-        let decoded = String::from_utf8(data).unwrap_or_default();
-        let deserialized: ResponseCache = serde_json::from_str(&decoded)?;
-        Ok(deserialized)
+        #[cfg(feature = "compression")]
+        let data = decompress(data)?;
+
+        let cache = decode(&data).inspect_err(|e| {
+            warn!("Unreadable cache, starting with an empty cache: {:?}", e);
+        })?;
+        crate::quota::restore(&cache);
+        Ok(cache)
     }
 
-    /// Deletes the `ResponseCache` instance from disk.
+    /// Deletes the `ResponseCache` instance from disk, or from the global [`backend`](crate::backend).
     pub fn delete(self) -> Result<()> {
-        trace!("Deleting cache file {}", get_cache_path(&self.file_name));
-        fs::remove_file(get_cache_path(&self.file_name))?;
-        Ok(())
+        trace!("Deleting cache {}", self.name());
+        backend().delete(self.name())
+    }
+}
+
+/// Deletes the cache, so the next cached lookup makes a new request.
+///
+/// # Examples
+///
+/// ```
+/// # use public_ip_address::cache;
+/// // the VPN was just reconnected
+/// cache::clear()?;
+/// # Ok::<(), public_ip_address::error::CacheError>(())
+/// ```
+pub fn clear() -> Result<()> {
+    debug!("Clearing cache {}", DEFAULT_FILE_NAME);
+    match backend().delete(DEFAULT_FILE_NAME) {
+        Err(CacheError::IOError(e)) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Returns the changes of the current IP address recorded in the cache, oldest first.
+///
+/// See [`ResponseCache::history`], the history is empty if nothing is cached.
+///
+/// # Examples
+///
+/// ```
+/// # use public_ip_address::cache;
+/// for entry in cache::history() {
+///     println!("{:?}: {} ({})", entry.time, entry.ip, entry.provider);
+/// }
+/// ```
+pub fn history() -> Vec<HistoryEntry> {
+    ResponseCache::load(None)
+        .map(|cache| cache.history)
+        .unwrap_or_default()
+}
+
+/// Removes the cached responses of the given provider from the cache, keeping the others.
+///
+/// # Examples
+///
+/// ```
+/// # use public_ip_address::{cache, lookup::LookupProvider};
+/// cache::invalidate(&LookupProvider::IpWhoIs)?;
+/// # Ok::<(), public_ip_address::error::CacheError>(())
+/// ```
+pub fn invalidate(provider: &LookupProvider) -> Result<()> {
+    let mut cache = match ResponseCache::load(None) {
+        Ok(cache) => cache,
+        // nothing cached
+        Err(_) => return Ok(()),
+    };
+    cache.invalidate(provider);
+    cache.save()
+}
+
+/// Sets the maximum number of target addresses kept in the cache, [`DEFAULT_MAX_TARGETS`] by default.
+///
+/// The bound applies when a target response is cached, the least recently used addresses are evicted
+/// first. `0` disables the caching of target lookups.
+///
+/// # Examples
+///
+/// ```
+/// # use public_ip_address::cache;
+/// cache::set_max_targets(10_000);
+/// # cache::set_max_targets(cache::DEFAULT_MAX_TARGETS);
+/// ```
+pub fn set_max_targets(max: usize) {
+    MAX_TARGETS.store(max, Ordering::Relaxed);
+}
+
+/// Returns the maximum number of target addresses kept in the cache, see [`set_max_targets`].
+pub fn max_targets() -> usize {
+    MAX_TARGETS.load(Ordering::Relaxed)
+}
+
+/// Magic number starting the zstd frames
+#[cfg(feature = "compression")]
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compresses the serialized cache with zstd, at the default compression level
+#[cfg(feature = "compression")]
+fn compress(data: Vec<u8>) -> Result<Vec<u8>> {
+    trace!("Compressing data");
+    Ok(zstd::encode_all(data.as_slice(), 0)?)
+}
+
+/// Decompresses the serialized cache, returning uncompressed data as is
+#[cfg(feature = "compression")]
+fn decompress(data: Vec<u8>) -> Result<Vec<u8>> {
+    if !data.starts_with(&ZSTD_MAGIC) {
+        trace!("Cache not compressed");
+        return Ok(data);
+    }
+    trace!("Decompressing data");
+    Ok(zstd::decode_all(data.as_slice())?)
+}
+
+/// Deserializes a cache, migrating it from an older version of the format
+fn decode(data: &[u8]) -> Result<ResponseCache> {
+    let mut value: serde_json::Value = serde_json::from_slice(data)?;
+    let version = value
+        .get("version")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(0) as u32;
+    if version > CACHE_VERSION {
+        return Err(CacheError::VersionError(version));
+    }
+    if version < CACHE_VERSION {
+        debug!("Migrating cache from version {}", version);
+        // every version is migrated to the next one in turn
+        for version in version..CACHE_VERSION {
+            match version {
+                // the unversioned format is the same as the version 1
+                0 => {}
+                _ => return Err(CacheError::VersionError(version)),
+            }
+        }
+        if let Some(object) = value.as_object_mut() {
+            object.insert("version".to_string(), CACHE_VERSION.into());
+        }
     }
+    Ok(serde_json::from_value(value)?)
 }
 
 /// Determines the path for the cache file.
 ///
-/// This function uses a series of fallbacks to find a suitable directory for the cache file:
-/// 1. It first tries to use the system's cache directory, as determined by the `BaseDirs` struct.
-/// 2. If the cache directory doesn't exist, it tries to create it.
-/// 3. If it can't create the cache directory, it falls back to the system's data directory.
-/// 4. If it can't use the data directory, it falls back to the user's home directory.
-/// 5. If it can't use the home directory, it falls back to the current directory.
+/// The cache file is stored in the directory of the crate in the platform cache directory, as determined by the
+/// `ProjectDirs` struct: `$XDG_CACHE_HOME/public-ip-address` on Linux, `~/Library/Caches/public-ip-address` on macOS
+/// and `%LOCALAPPDATA%\public-ip-address\cache` on Windows. If this directory can't be created, the cache file is
+/// stored in the `public-ip-address` directory of the system temporary directory.
+///
+/// A cache file found at the location used by older versions of the crate, directly in the system cache, data or
+/// home directory, is moved to the new location.
 ///
 /// The cache file is named "lookup.cache" by default. However, this can be overridden by providing a different name as a parameter.
 ///
@@ -308,26 +863,96 @@ pub fn get_cache_path(file_name: &Option<String>) -> String {
     let file_name = if let Some(file_name) = file_name {
         file_name
     } else {
-        "lookup.cache"
+        DEFAULT_FILE_NAME
     };
 
-    if let Some(base_dirs) = BaseDirs::new() {
-        let mut dir = base_dirs.cache_dir();
-        // Create cache directory if it doesn't exist
-        if !dir.exists() && fs::create_dir_all(dir).is_err() {
-            // If we can't create the cache directory, fallback to data directory
-            dir = base_dirs.data_dir();
-            if !dir.exists() && fs::create_dir_all(dir).is_err() {
-                // If we can't create the data directory, fallback to home directory
-                dir = base_dirs.home_dir();
-            }
-        }
-        if let Some(path) = dir.join(file_name).to_str() {
-            return path.to_string();
+    let path = cache_dir().join(file_name);
+    if !path.exists() {
+        migrate_legacy_file(file_name, &path);
+    }
+    path.to_string_lossy().into_owned()
+}
+
+/// Returns the cache directory of the crate, creating it if needed
+fn cache_dir() -> PathBuf {
+    if let Some(project_dirs) = ProjectDirs::from("", "", env!("CARGO_PKG_NAME")) {
+        let dir = project_dirs.cache_dir();
+        if dir.exists() || fs::create_dir_all(dir).is_ok() {
+            return dir.to_path_buf();
         }
+    }
+    // no usable cache directory, fallback to the temporary directory
+    let dir = std::env::temp_dir().join(env!("CARGO_PKG_NAME"));
+    _ = fs::create_dir_all(&dir);
+    dir
+}
+
+/// Moves a cache file from the locations used by older versions of the crate to `path`
+fn migrate_legacy_file(file_name: &str, path: &Path) {
+    let Some(base_dirs) = BaseDirs::new() else {
+        return;
     };
-    // As last resort, fallback to current directory
-    file_name.to_string()
+    let legacy_dirs = [
+        base_dirs.cache_dir(),
+        base_dirs.data_dir(),
+        base_dirs.home_dir(),
+    ];
+    for dir in legacy_dirs {
+        let legacy_path = dir.join(file_name);
+        if legacy_path.is_file() {
+            debug!(
+                "Moving cache file {} to {}",
+                legacy_path.display(),
+                path.display()
+            );
+            // renaming fails across file systems, copy the file instead
+            if fs::rename(&legacy_path, path).is_err() && fs::copy(&legacy_path, path).is_ok() {
+                _ = fs::remove_file(&legacy_path);
+            }
+            return;
+        }
+    }
+}
+
+/// Key set with `set_encryption_key`, the machine ID is used if `None`
+#[cfg(feature = "encryption")]
+static ENCRYPTION_KEY: RwLock<Option<Vec<u8>>> = RwLock::new(None);
+
+/// Sets the key encrypting the cache, replacing the default key derived from the machine ID.
+///
+/// The key can be a passphrase, or a secret read from the OS keyring. The caches encrypted with
+/// another key can't be read anymore, they are replaced by the next lookup.
+///
+/// # Examples
+///
+/// ```
+/// # use public_ip_address::cache;
+/// cache::set_encryption_key("correct horse battery staple");
+/// # cache::clear_encryption_key();
+/// ```
+#[cfg(feature = "encryption")]
+pub fn set_encryption_key<K: Into<Vec<u8>>>(key: K) {
+    *ENCRYPTION_KEY.write().unwrap_or_else(|e| e.into_inner()) = Some(key.into());
+}
+
+/// Removes the key set with [`set_encryption_key`], encrypting the cache with the machine ID again.
+#[cfg(feature = "encryption")]
+pub fn clear_encryption_key() {
+    *ENCRYPTION_KEY.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Returns the key set with `set_encryption_key`, or the machine ID
+#[cfg(feature = "encryption")]
+fn encryption_key() -> Vec<u8> {
+    ENCRYPTION_KEY
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| {
+            mid::get(env!("CARGO_PKG_NAME"))
+                .unwrap_or("lookup".to_string())
+                .into_bytes()
+        })
 }
 
 /// Decrypts the given data using AEAD.
@@ -345,11 +970,11 @@ pub fn get_cache_path(file_name: &Option<String>) -> String {
 #[cfg(feature = "encryption")]
 fn decrypt(data: Vec<u8>) -> Result<Vec<u8>> {
     trace!("Decrypting data");
-    let password = mid::get(env!("CARGO_PKG_NAME")).unwrap_or("lookup".to_string());
+    let password = encryption_key();
     let cocoon = if cfg!(debug_assertions) {
-        Cocoon::new(password.as_bytes()).with_weak_kdf()
+        Cocoon::new(&password).with_weak_kdf()
     } else {
-        Cocoon::new(password.as_bytes())
+        Cocoon::new(&password)
     };
     match cocoon.unwrap(&data) {
         Ok(data) => Ok(data),
@@ -375,11 +1000,11 @@ fn decrypt(data: Vec<u8>) -> Result<Vec<u8>> {
 #[cfg(feature = "encryption")]
 fn encrypt(data: Vec<u8>) -> Result<Vec<u8>> {
     trace!("Encrypting data");
-    let password = mid::get(env!("CARGO_PKG_NAME")).unwrap_or("lookup".to_string());
+    let password = encryption_key();
     let mut cocoon = if cfg!(debug_assertions) {
-        Cocoon::new(password.as_bytes()).with_weak_kdf()
+        Cocoon::new(&password).with_weak_kdf()
     } else {
-        Cocoon::new(password.as_bytes())
+        Cocoon::new(&password)
     };
     match cocoon.wrap(&data) {
         Ok(data) => Ok(data),
@@ -399,10 +1024,11 @@ mod tests {
     #[test]
     #[serial]
     fn test_cache_file() {
-        let response = LookupResponse::new(
+        let mut response = LookupResponse::new(
             "1.1.1.1".parse().unwrap(),
             LookupProvider::Mock("1.1.1.1".to_string()),
         );
+        response.looked_up_at = Some(SystemTime::now());
         println!("{}", get_cache_path(&None));
         let mut cache = ResponseCache::new(None);
         cache.update_current(&response, None);
@@ -413,9 +1039,79 @@ mod tests {
             "1.1.1.1".parse::<std::net::IpAddr>().unwrap(),
             "IP address not matching"
         );
+        assert_eq!(
+            cached.current_response().unwrap().looked_up_at,
+            response.looked_up_at,
+            "Lookup time not preserved"
+        );
         cache.delete().unwrap();
     }
 
+    #[test]
+    fn test_decode() {
+        let cache = ResponseCache::default();
+        let data = serde_json::to_vec(&cache).unwrap();
+        assert_eq!(decode(&data).unwrap(), cache);
+
+        let legacy = r#"{"current_address":null,"lookup_address":{},"file_name":null}"#;
+        assert_eq!(
+            decode(legacy.as_bytes()).unwrap(),
+            cache,
+            "Unversioned cache not migrated"
+        );
+
+        let future = r#"{"version":1000,"current_address":"new format"}"#;
+        assert!(
+            matches!(
+                decode(future.as_bytes()),
+                Err(CacheError::VersionError(1000))
+            ),
+            "Newer cache not discarded"
+        );
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_compress_decompress() {
+        let data = serde_json::to_vec(&ResponseCache::default()).unwrap();
+        let compressed = compress(data.clone()).unwrap();
+        assert!(compressed.starts_with(&ZSTD_MAGIC), "Data not compressed");
+        assert_eq!(decompress(compressed).unwrap(), data);
+        assert_eq!(
+            decompress(data.clone()).unwrap(),
+            data,
+            "Legacy data changed"
+        );
+    }
+
+    #[test]
+    fn test_cache_path() {
+        let path = get_cache_path(&Some("path.cache".to_string()));
+        let path = Path::new(&path);
+        assert_eq!(path.file_name().unwrap(), "path.cache");
+        assert!(
+            path.parent().unwrap().ends_with(env!("CARGO_PKG_NAME"))
+                || path.parent().unwrap().ends_with("cache"),
+            "Cache file not in the crate directory: {}",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn test_migrate_legacy_file() {
+        let Some(base_dirs) = BaseDirs::new() else {
+            return;
+        };
+        let legacy_path = base_dirs.cache_dir().join("legacy.cache");
+        if fs::write(&legacy_path, "legacy").is_err() {
+            return;
+        }
+        let path = get_cache_path(&Some("legacy.cache".to_string()));
+        assert!(!legacy_path.exists(), "Legacy cache file not moved");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "legacy");
+        fs::remove_file(path).unwrap();
+    }
+
     #[test]
     fn test_expired() {
         let response = LookupResponse::new(
@@ -448,6 +1144,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ip_expired() {
+        let response = LookupResponse::new(
+            "1.1.1.1".parse().unwrap(),
+            LookupProvider::Mock("1.1.1.1".to_string()),
+        );
+        let mut cache = ResponseCache::default();
+        assert!(
+            cache.current_ip_is_expired(Some(1)),
+            "Empty cache should be expired"
+        );
+        cache.update_current(&response, None);
+        assert!(
+            !cache.current_ip_is_expired(None),
+            "IP with no TTL should not be expired"
+        );
+        assert!(
+            cache.current_ip_is_expired(Some(0)),
+            "IP with zero TTL should be expired"
+        );
+        std::thread::sleep(Duration::from_secs(1));
+        assert!(
+            cache.current_ip_is_expired(Some(1)),
+            "IP should be expired after the TTL"
+        );
+        cache.verify_current();
+        assert!(
+            !cache.current_ip_is_expired(Some(1)),
+            "Verified IP should not be expired"
+        );
+        assert!(
+            !cache.current_is_expired(),
+            "Verifying should not expire the cached response"
+        );
+    }
+
     #[test]
     fn test_cache_tree() {
         let addresses = [
@@ -471,6 +1203,91 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_evict_targets() {
+        let mut cache = ResponseCache::default();
+        let addresses: Vec<IpAddr> = ["1.1.1.1", "2.2.2.2", "3.3.3.3"]
+            .iter()
+            .map(|address| address.parse().unwrap())
+            .collect();
+        for address in &addresses {
+            let response = LookupResponse::new(*address, LookupProvider::Ipify);
+            cache.update_target(*address, &response, None);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        cache.touch_target(&addresses[0]);
+        cache.evict_targets(2);
+        assert!(
+            cache.target_response(&addresses[1]).is_none(),
+            "Least recently used target not evicted"
+        );
+        assert!(cache.target_response(&addresses[0]).is_some());
+        assert!(cache.target_response(&addresses[2]).is_some());
+        cache.evict_targets(0);
+        assert!(cache.lookup_address.is_empty(), "Targets not evicted");
+    }
+
+    #[test]
+    fn test_history() {
+        let mut cache = ResponseCache::default();
+        for ip in ["1.1.1.1", "1.1.1.1", "2.2.2.2", "1.1.1.1"] {
+            let response = LookupResponse::new(ip.parse().unwrap(), LookupProvider::Ipify);
+            cache.update_current(&response, None);
+        }
+        let addresses: Vec<_> = cache
+            .history()
+            .iter()
+            .map(|entry| entry.ip.to_string())
+            .collect();
+        assert_eq!(
+            addresses,
+            ["1.1.1.1", "2.2.2.2", "1.1.1.1"],
+            "Wrong history"
+        );
+        cache.clear();
+        assert_eq!(cache.history().len(), 3, "History cleared");
+
+        for i in 0..=MAX_HISTORY {
+            let ip = IpAddr::from([10, 0, (i / 256) as u8, (i % 256) as u8]);
+            cache.update_current(&LookupResponse::new(ip, LookupProvider::Ipify), None);
+        }
+        assert_eq!(cache.history().len(), MAX_HISTORY, "History not bounded");
+        assert_eq!(cache.history()[0].ip.to_string(), "10.0.0.1");
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let mut cache = ResponseCache::default();
+        let response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::Ipify);
+        cache.update_current(&response, None);
+        let target = LookupResponse::new("2.2.2.2".parse().unwrap(), LookupProvider::IpWhoIs);
+        cache.update_target(target.ip, &target, None);
+        let other = LookupResponse::new("3.3.3.3".parse().unwrap(), LookupProvider::Ipify);
+        cache.update_target(other.ip, &other, None);
+
+        cache.invalidate(&LookupProvider::IpWhoIs);
+        assert!(
+            cache.current_response().is_some(),
+            "Current response removed"
+        );
+        assert!(cache.target_response(&target.ip).is_none());
+        cache.invalidate(&LookupProvider::Ipify);
+        assert!(cache.current_response().is_none());
+        assert!(cache.lookup_address.is_empty(), "Responses not invalidated");
+    }
+
+    #[test]
+    #[serial]
+    fn test_clear() {
+        let response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::Ipify);
+        let mut cache = ResponseCache::new(None);
+        cache.update_current(&response, None);
+        cache.save().unwrap();
+        clear().unwrap();
+        assert!(ResponseCache::load(None).is_err(), "Cache not deleted");
+        clear().unwrap();
+    }
+
     #[test]
     fn test_cache_clear() {
         let response = LookupResponse::new(
@@ -491,12 +1308,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_provider_stats() {
+        let mut cache = ResponseCache::default();
+        let provider = LookupProvider::Ipify;
+        assert!(cache.provider_stats(&provider).is_none());
+        cache.record_provider(&provider, true, Duration::from_millis(100));
+        cache.record_provider(&provider, true, Duration::from_millis(200));
+        cache.record_provider(&provider, false, Duration::from_secs(5));
+        let stats = cache.provider_stats(&provider).unwrap();
+        assert_eq!((stats.successes, stats.failures), (2, 1));
+        assert_eq!(stats.average_latency, Some(Duration::from_millis(120)));
+        assert_eq!(stats.success_rate(), 0.6);
+
+        let mut slow = ProviderStats::default();
+        slow.record(true, Duration::from_secs(3));
+        assert!(
+            stats.score() > slow.score(),
+            "Faster provider should score higher"
+        );
+    }
+
+    #[test]
+    fn test_rate_limits() {
+        let mut cache = ResponseCache::default();
+        let provider = LookupProvider::IpApiCom;
+        assert!(cache.rate_limited_until(&provider).is_none());
+        let reset = SystemTime::now() + Duration::from_secs(60);
+        cache.record_rate_limit(&provider, Some(reset));
+        assert_eq!(cache.rate_limited_until(&provider), Some(reset));
+        cache.record_rate_limit(&provider, Some(SystemTime::now() - Duration::from_secs(1)));
+        assert!(
+            cache.rate_limited_until(&provider).is_none(),
+            "Past reset should not limit"
+        );
+        cache.record_rate_limit(&provider, None);
+        assert!(cache.rate_limits.is_empty());
+    }
+
     #[test]
     #[cfg(feature = "encryption")]
+    #[serial]
     fn test_encrypt_decrypt() {
         let data = b"hello world".to_vec();
         let encrypted = encrypt(data.clone()).unwrap();
         let decrypted = decrypt(encrypted).unwrap();
         assert_eq!(data, decrypted);
     }
+
+    #[test]
+    #[cfg(feature = "encryption")]
+    #[serial]
+    fn test_encryption_key() {
+        let data = b"hello world".to_vec();
+        let encrypted = encrypt(data.clone()).unwrap();
+        set_encryption_key("secret");
+        assert!(
+            decrypt(encrypted).is_err(),
+            "Data decrypted with another key"
+        );
+        let encrypted = encrypt(data.clone()).unwrap();
+        assert_eq!(decrypt(encrypted).unwrap(), data);
+
+        // a cache encrypted with another key is discarded
+        let response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::Ipify);
+        let mut cache = ResponseCache::new(Some("key.cache".to_string()));
+        cache.update_current(&response, None);
+        cache.save().unwrap();
+        clear_encryption_key();
+        assert!(ResponseCache::load(Some("key.cache".to_string())).is_err());
+        cache.delete().unwrap();
+    }
 }
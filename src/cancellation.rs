@@ -0,0 +1,44 @@
+//! Token to cancel the blocking lookups
+//!
+//! The async lookups use the `tokio-util` token, the blocking lookups only check the token
+//! between their requests, so a flag shared by the clones is enough and no runtime is needed.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Token to cancel lookups, cancelling a token cancels all its clones
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a new token, not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the token and its clones
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    /// Returns true if the token or one of its clones was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled(), "New token is cancelled");
+        token.cancel();
+        assert!(clone.is_cancelled(), "Clone not cancelled");
+    }
+}
@@ -0,0 +1,133 @@
+//! # ⛓️ Weighted provider chains
+//!
+//! A `ProviderChain` attaches a weight to each provider of a fallback list. Preferred providers
+//! are tried first more often, while providers with a weight of `0` are only used as emergency
+//! fallbacks, once all the other providers failed.
+//!
+//! ## Example
+//! ```rust
+//! use public_ip_address::chain::ProviderChain;
+//! use public_ip_address::lookup::LookupProvider;
+//!
+//! # use std::error::Error;
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() -> Result<(), Box<dyn Error>> {
+//! let chain = ProviderChain::new()
+//!     .add(LookupProvider::IpInfo, 10)
+//!     .add(LookupProvider::IpWhoIs, 5)
+//!     .fallback(LookupProvider::FreeIpApi);
+//! match chain.lookup(None).await {
+//!     Ok(response) => {
+//!         // Handle successful response
+//!     }
+//!     Err(e) => {
+//!         // Handle error
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Result;
+use crate::lookup::{LookupProvider, Parameters};
+use crate::options::LookupOptions;
+use crate::perform_lookup_with_options;
+use crate::response::LookupResponse;
+use crate::strategy::Weighted;
+use std::net::IpAddr;
+
+/// List of providers with a weight attached to each entry
+#[derive(Debug, Clone, Default)]
+pub struct ProviderChain {
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    weights: Vec<u8>,
+}
+
+impl ProviderChain {
+    /// Creates a new empty chain.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a provider with the given weight to the chain.
+    pub fn add(self, provider: LookupProvider, weight: u8) -> Self {
+        self.add_with_parameters(provider, None, weight)
+    }
+
+    /// Adds a provider with its `Parameters` and the given weight to the chain.
+    pub fn add_with_parameters(
+        mut self,
+        provider: LookupProvider,
+        parameters: Option<Parameters>,
+        weight: u8,
+    ) -> Self {
+        self.providers.push((provider, parameters));
+        self.weights.push(weight);
+        self
+    }
+
+    /// Adds a provider only used once all the weighted providers failed.
+    pub fn fallback(self, provider: LookupProvider) -> Self {
+        self.add_with_parameters(provider, None, 0)
+    }
+
+    /// Returns the providers of the chain.
+    pub fn providers(&self) -> &[(LookupProvider, Option<Parameters>)] {
+        &self.providers
+    }
+
+    /// Returns the weighted strategy ordering the providers of the chain.
+    pub fn strategy(&self) -> Weighted {
+        Weighted::new(self.weights.clone())
+    }
+
+    /// Performs a lookup trying the providers of the chain in the order picked by their weights.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Target address for the lookup, `None` will look up the current public address.
+    ///
+    /// # Returns
+    ///
+    /// * A `Result` containing either a successful `LookupResponse` or a `LookupError` containing a list of all errors received.
+    #[maybe_async::maybe_async]
+    pub async fn lookup(&self, target: Option<IpAddr>) -> Result<LookupResponse> {
+        let options = LookupOptions::new().strategy(self.strategy());
+        perform_lookup_with_options(self.providers.clone(), target, &options).await
+    }
+}
+
+impl From<Vec<(LookupProvider, u8)>> for ProviderChain {
+    fn from(providers: Vec<(LookupProvider, u8)>) -> Self {
+        providers
+            .into_iter()
+            .fold(ProviderChain::new(), |chain, (provider, weight)| {
+                chain.add(provider, weight)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain() {
+        let chain = ProviderChain::from(vec![
+            (LookupProvider::IpInfo, 10),
+            (LookupProvider::IpWhoIs, 5),
+        ])
+        .add_with_parameters(
+            LookupProvider::IpData,
+            Some(Parameters::new("key".to_string())),
+            1,
+        )
+        .fallback(LookupProvider::FreeIpApi);
+        assert_eq!(chain.providers().len(), 4);
+        let strategy = chain.strategy();
+        assert_eq!(strategy.weight(0), 10);
+        assert_eq!(strategy.weight(2), 1);
+        assert_eq!(strategy.weight(3), 0, "Fallback should have zero weight");
+    }
+}
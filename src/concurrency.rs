@@ -0,0 +1,222 @@
+//! # 🚦 Concurrency limits
+//!
+//! Fan-out lookups like racing or enriching providers send many requests at the same time.
+//! On constrained devices, like routers or a Raspberry Pi, dozens of simultaneous TLS handshakes
+//! can be too much. A `ConcurrencyLimit` bounds the number of requests in flight, globally and
+//! for each provider.
+//!
+//! Clones of a `ConcurrencyLimit` share the same limits, so a single limit can bound all the
+//! lookups of an application.
+//!
+//! ## Example
+//! ```rust
+//! use public_ip_address::concurrency::ConcurrencyLimit;
+//! use public_ip_address::lookup::LookupProvider;
+//! use public_ip_address::options::LookupOptions;
+//!
+//! # use std::error::Error;
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() -> Result<(), Box<dyn Error>> {
+//! // at most two requests in flight, one per provider
+//! let limit = ConcurrencyLimit::new(Some(2), Some(1));
+//! let providers = vec![
+//!     (LookupProvider::IpInfo, None),
+//!     (LookupProvider::IpWhoIs, None),
+//!     (LookupProvider::FreeIpApi, None),
+//! ];
+//! let options = LookupOptions::new().concurrency_limit(limit);
+//! match public_ip_address::perform_race_lookup_with_options(providers, None, &options).await {
+//!     Ok(response) => {
+//!         // Handle successful response
+//!     }
+//!     Err(e) => {
+//!         // Handle error
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::lookup::LookupProvider;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Limit on the number of lookup requests in flight
+#[derive(Debug, Clone, Default)]
+pub struct ConcurrencyLimit {
+    global_limit: Option<usize>,
+    per_provider_limit: Option<usize>,
+    global: Option<Arc<Semaphore>>,
+    providers: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+}
+
+impl ConcurrencyLimit {
+    /// Creates a new limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `global` - Maximum number of requests in flight, `None` for no limit.
+    /// * `per_provider` - Maximum number of requests in flight to the same provider, `None` for no limit.
+    ///
+    /// A limit of `0` is treated as `1`.
+    pub fn new(global: Option<usize>, per_provider: Option<usize>) -> Self {
+        let global_limit = global.map(|limit| limit.max(1));
+        ConcurrencyLimit {
+            global_limit,
+            per_provider_limit: per_provider.map(|limit| limit.max(1)),
+            global: global_limit.map(|limit| Arc::new(Semaphore::new(limit))),
+            providers: Arc::default(),
+        }
+    }
+
+    /// Creates a limit that doesn't bound the number of requests.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Returns the maximum number of requests in flight.
+    pub fn global(&self) -> Option<usize> {
+        self.global_limit
+    }
+
+    /// Returns the maximum number of requests in flight to the same provider.
+    pub fn per_provider(&self) -> Option<usize> {
+        self.per_provider_limit
+    }
+
+    /// Waits until a request to the provider is allowed.
+    ///
+    /// The request is allowed as long as the returned permit is alive.
+    #[maybe_async::maybe_async]
+    pub(crate) async fn acquire(&self, provider: &LookupProvider) -> Permit {
+        let mut permits = Vec::new();
+        // provider permit first, so waiting on a busy provider doesn't hold a global slot
+        if let Some(semaphore) = self.provider_semaphore(provider) {
+            permits.push(semaphore.acquire().await);
+        }
+        if let Some(semaphore) = self.global.clone() {
+            permits.push(semaphore.acquire().await);
+        }
+        Permit { _permits: permits }
+    }
+
+    fn provider_semaphore(&self, provider: &LookupProvider) -> Option<Arc<Semaphore>> {
+        let limit = self.per_provider_limit?;
+        let mut providers = self.providers.lock().unwrap_or_else(|e| e.into_inner());
+        Some(
+            providers
+                .entry(provider.to_string())
+                .or_insert_with(|| Arc::new(Semaphore::new(limit)))
+                .clone(),
+        )
+    }
+}
+
+/// Permit allowing a request, released when dropped
+#[derive(Debug)]
+pub(crate) struct Permit {
+    _permits: Vec<SemaphorePermit>,
+}
+
+#[cfg(not(feature = "blocking"))]
+type SemaphorePermit = tokio::sync::OwnedSemaphorePermit;
+
+/// Counting semaphore
+#[cfg(not(feature = "blocking"))]
+#[derive(Debug)]
+struct Semaphore(Arc<tokio::sync::Semaphore>);
+
+#[cfg(not(feature = "blocking"))]
+impl Semaphore {
+    fn new(permits: usize) -> Self {
+        Semaphore(Arc::new(tokio::sync::Semaphore::new(permits)))
+    }
+
+    async fn acquire(&self) -> SemaphorePermit {
+        self.0
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed")
+    }
+}
+
+/// Counting semaphore
+#[cfg(feature = "blocking")]
+#[derive(Debug)]
+struct Semaphore {
+    available: Mutex<usize>,
+    released: std::sync::Condvar,
+}
+
+#[cfg(feature = "blocking")]
+impl Semaphore {
+    fn new(permits: usize) -> Self {
+        Semaphore {
+            available: Mutex::new(permits),
+            released: std::sync::Condvar::new(),
+        }
+    }
+
+    fn acquire(self: Arc<Self>) -> SemaphorePermit {
+        let available = self.available.lock().unwrap_or_else(|e| e.into_inner());
+        let mut available = self
+            .released
+            .wait_while(available, |available| *available == 0)
+            .unwrap_or_else(|e| e.into_inner());
+        *available -= 1;
+        drop(available);
+        SemaphorePermit(self)
+    }
+}
+
+#[cfg(feature = "blocking")]
+#[derive(Debug)]
+struct SemaphorePermit(Arc<Semaphore>);
+
+#[cfg(feature = "blocking")]
+impl Drop for SemaphorePermit {
+    fn drop(&mut self) {
+        *self.0.available.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+        self.0.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_limits() {
+        let limit = ConcurrencyLimit::new(Some(0), Some(1));
+        assert_eq!(
+            limit.global(),
+            Some(1),
+            "Zero limit should be raised to one"
+        );
+        assert_eq!(limit.per_provider(), Some(1));
+        let permit = limit.acquire(&LookupProvider::IpInfo).await;
+        let shared = limit.clone();
+        assert!(
+            Arc::ptr_eq(
+                limit.global.as_ref().unwrap(),
+                shared.global.as_ref().unwrap()
+            ),
+            "Clones should share the limits"
+        );
+        drop(permit);
+        let _permit = shared.acquire(&LookupProvider::IpWhoIs).await;
+        assert_eq!(shared.providers.lock().unwrap().len(), 2);
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_unlimited() {
+        let limit = ConcurrencyLimit::unlimited();
+        let _first = limit.acquire(&LookupProvider::IpInfo).await;
+        let _second = limit.acquire(&LookupProvider::IpInfo).await;
+        assert!(limit.global().is_none() && limit.per_provider().is_none());
+    }
+}
@@ -0,0 +1,304 @@
+//! # 🗂️ Lookup configuration
+//!
+//! A `Config` holds the whole lookup configuration: the ordered providers with their API keys and
+//! weights, the cache time-to-live, the request timeout and retries and the provider selection
+//! strategy. It implements `Serialize` and `Deserialize`, so applications can embed it in their own
+//! configuration files.
+//!
+//! ## Example
+//! ```rust
+//! use public_ip_address::config::Config;
+//!
+//! # use std::error::Error;
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() -> Result<(), Box<dyn Error>> {
+//! let config: Config = serde_json::from_str(
+//!     r#"{
+//!         "providers": [
+//!             { "provider": "IpInfo", "api_key": "secret", "weight": 10 },
+//!             { "provider": "IpWhoIs" }
+//!         ],
+//!         "ttl": 300,
+//!         "timeout_ms": 2000,
+//!         "strategy": "weighted"
+//!     }"#,
+//! )?;
+//! match config.cached_lookup(None).await {
+//!     Ok(response) => {
+//!         // Handle successful response
+//!     }
+//!     Err(e) => {
+//!         // Handle error
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cache::ResponseCache;
+use crate::error::Result;
+use crate::lookup::{IpVersion, LookupProvider, Parameters};
+use crate::options::LookupOptions;
+use crate::response::LookupResponse;
+use crate::strategy::{Adaptive, Random, Sequential, Weighted};
+use crate::{perform_cached_lookup_with_options, perform_lookup_with_options};
+use serde::{Deserialize, Serialize};
+use std::{
+    net::IpAddr,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+/// Lookup configuration, with the ordered providers and the `LookupOptions`
+///
+/// Missing fields get their default value when deserialized.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct Config {
+    /// Providers, in the order they are tried by the sequential strategy
+    pub providers: Vec<ProviderConfig>,
+    /// Seconds before the cached response expires, `None` never expires
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
+    /// Name of the cache file, `None` uses the default cache
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_file: Option<String>,
+    /// Timeout of each request in milliseconds, `None` waits as long as the HTTP client does
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Number of times a failed provider is retried before the next one is tried
+    pub retries: u32,
+    /// IP version used to connect to the providers
+    pub ip_version: IpVersion,
+    /// Only use the providers returning nothing but the address
+    pub ip_only: bool,
+    /// Fail the lookups returning a non-global address
+    pub reject_non_global: bool,
+    /// Keep the raw body of the provider responses
+    pub keep_raw: bool,
+    /// Order in which the providers are tried
+    pub strategy: StrategyConfig,
+    /// Adaptive strategy, loaded from the cache the first time the options are built
+    #[serde(skip)]
+    adaptive: LoadedAdaptive,
+}
+
+/// Adaptive strategy of a `Config`, loaded once and shared by its clones
+#[derive(Debug, Clone, Default)]
+struct LoadedAdaptive(Arc<OnceLock<Adaptive>>);
+
+impl PartialEq for LoadedAdaptive {
+    /// The loaded statistics come from the cache, they are not part of the configuration
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Provider of a `Config`, with its API key and weight
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ProviderConfig {
+    /// Lookup provider
+    pub provider: LookupProvider,
+    /// API key for the lookup provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Weight of the provider for the weighted strategy, `1` if not set, `0` for an emergency fallback
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<u8>,
+}
+
+impl std::fmt::Debug for ProviderConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderConfig")
+            .field("provider", &self.provider)
+            .field("api_key", &self.api_key.as_ref().map(|_| "***"))
+            .field("weight", &self.weight)
+            .finish()
+    }
+}
+
+/// Provider selection strategy of a `Config`, see [`strategy`](crate::strategy)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum StrategyConfig {
+    /// Tries the providers in the order they were given
+    #[default]
+    Sequential,
+    /// Tries the providers in a random order
+    Random,
+    /// Tries the providers in a random order biased by their weight
+    Weighted,
+    /// Tries the providers with the best performance recorded in the cache first
+    Adaptive,
+}
+
+impl Config {
+    /// Creates a configuration with the given providers and the default options.
+    pub fn new(providers: Vec<(LookupProvider, Option<Parameters>)>) -> Self {
+        Config {
+            providers: providers
+                .into_iter()
+                .map(|(provider, parameters)| ProviderConfig::new(provider, parameters))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the providers and their `Parameters`, in the configured order.
+    pub fn providers(&self) -> Vec<(LookupProvider, Option<Parameters>)> {
+        self.providers
+            .iter()
+            .map(|provider| {
+                let parameters = provider.api_key.clone().map(Parameters::new);
+                (provider.provider.clone(), parameters)
+            })
+            .collect()
+    }
+
+    /// Returns the `LookupOptions` of the configuration.
+    ///
+    /// The adaptive strategy uses the provider statistics recorded in the configured cache, loaded
+    /// the first time the options are built.
+    pub fn options(&self) -> LookupOptions {
+        let options = LookupOptions::new()
+            .ttl(self.ttl)
+            .retries(self.retries)
+            .ip_version(self.ip_version)
+            .ip_only(self.ip_only)
+            .reject_non_global(self.reject_non_global)
+            .keep_raw(self.keep_raw);
+        let options = match &self.cache_file {
+            Some(cache_file) => options.cache_file(cache_file),
+            None => options,
+        };
+        let options = match self.timeout_ms {
+            Some(timeout) => options.timeout(Duration::from_millis(timeout)),
+            None => options,
+        };
+        match self.strategy {
+            StrategyConfig::Sequential => options.strategy(Sequential),
+            StrategyConfig::Random => options.strategy(Random),
+            StrategyConfig::Weighted => options.strategy(Weighted::new(
+                self.providers
+                    .iter()
+                    .map(|provider| provider.weight.unwrap_or(1))
+                    .collect(),
+            )),
+            StrategyConfig::Adaptive => options.strategy(
+                self.adaptive
+                    .0
+                    .get_or_init(|| {
+                        let cache =
+                            ResponseCache::load(self.cache_file.clone()).unwrap_or_default();
+                        Adaptive::from_cache(&cache)
+                    })
+                    .clone(),
+            ),
+        }
+    }
+
+    /// Performs a lookup with the configured providers and options, see [`perform_lookup_with_options`].
+    #[maybe_async::maybe_async]
+    pub async fn lookup(&self, target: Option<IpAddr>) -> Result<LookupResponse> {
+        perform_lookup_with_options(self.providers(), target, &self.options()).await
+    }
+
+    /// Performs a cached lookup with the configured providers and options, see [`perform_cached_lookup_with_options`].
+    #[maybe_async::maybe_async]
+    pub async fn cached_lookup(&self, target: Option<IpAddr>) -> Result<LookupResponse> {
+        perform_cached_lookup_with_options(self.providers(), target, &self.options()).await
+    }
+}
+
+impl ProviderConfig {
+    /// Creates a provider configuration, with the API key of the parameters.
+    pub fn new(provider: LookupProvider, parameters: Option<Parameters>) -> Self {
+        ProviderConfig {
+            provider,
+            api_key: parameters.map(|parameters| parameters.api_key),
+            weight: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_round_trip() {
+        let mut config = Config::new(vec![
+            (
+                LookupProvider::IpInfo,
+                Some(Parameters::new("Secret-Key".to_string())),
+            ),
+            (LookupProvider::Mock("1.1.1.1".to_string()), None),
+        ]);
+        config.providers[0].weight = Some(10);
+        config.ttl = Some(300);
+        config.timeout_ms = Some(2000);
+        config.ip_version = IpVersion::V4;
+        config.strategy = StrategyConfig::Weighted;
+
+        let json = serde_json::to_string(&config).unwrap();
+        let parsed: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, config, "Config not round-tripped: {}", json);
+        assert_eq!(
+            parsed.providers()[0],
+            (
+                LookupProvider::IpInfo,
+                Some(Parameters::new("Secret-Key".to_string()))
+            ),
+            "API key not kept"
+        );
+
+        let options = parsed.options();
+        assert_eq!(options.ttl, Some(300));
+        assert_eq!(options.timeout, Some(Duration::from_secs(2)));
+        assert_eq!(options.ip_version, IpVersion::V4);
+    }
+
+    #[test]
+    fn test_config_debug() {
+        let config = Config::new(vec![(
+            LookupProvider::IpInfo,
+            Some(Parameters::new("Secret-Key".to_string())),
+        )]);
+        let debug = format!("{:?}", config);
+        assert!(!debug.contains("Secret-Key"), "API key printed: {}", debug);
+        assert!(debug.contains(r#"api_key: Some("***")"#), "{}", debug);
+    }
+
+    #[test]
+    fn test_config_provider_case() {
+        let config: Config = serde_json::from_str(
+            r#"{"providers": [{"provider": "ipinfo"}, {"provider": "IPWHOIS"}, {"provider": "IpApiCo"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.providers(),
+            vec![
+                (LookupProvider::IpInfo, None),
+                (LookupProvider::IpWhoIs, None),
+                (LookupProvider::IpApiCo, None)
+            ]
+        );
+        assert!(
+            serde_json::from_str::<Config>(r#"{"providers": [{"provider": "nope"}]}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_config_defaults() {
+        let config: Config =
+            serde_json::from_str(r#"{"providers": [{"provider": "IpWhoIs"}]}"#).unwrap();
+        assert_eq!(config.providers(), vec![(LookupProvider::IpWhoIs, None)]);
+        assert_eq!(config.strategy, StrategyConfig::Sequential);
+        assert_eq!(config.ttl, None);
+        assert_eq!(config.retries, 0);
+    }
+}
@@ -0,0 +1,391 @@
+//! # 😈 Daemon mode
+//!
+//! A `Daemon` runs a [`Monitor`] as a long-running service, instead of running a command line
+//! program from cron:
+//!
+//! - the configuration is loaded on start, and loaded again on `SIGHUP` to replace the monitor;
+//! - the listeners of the changes, like DDNS updaters or an MQTT publisher, are attached to every
+//!   monitor with [`Daemon::on_monitor`];
+//! - systemd is notified when the daemon is ready, reloading and stopping, and the watchdog is kept
+//!   alive when `WatchdogSec` is set, as long as the monitor looks up the address successfully;
+//! - `SIGTERM` and `SIGINT` stop the monitor cleanly before [`Daemon::run`] returns.
+//!
+//! The daemon is meant to run as a `Type=notify-reload` systemd service:
+//!
+//! ```ini
+//! [Service]
+//! Type=notify-reload
+//! ExecStart=/usr/local/bin/public-ip-daemon
+//! WatchdogSec=30
+//! ```
+//!
+//! ## Example
+//! ```rust,no_run
+//! use public_ip_address::daemon::{Daemon, DaemonConfig};
+//! use public_ip_address::lookup::LookupProvider;
+//! use std::time::Duration;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() -> Result<(), public_ip_address::error::Error> {
+//! Daemon::new(|| {
+//!     // read the configuration file here
+//!     Ok(DaemonConfig::new(vec![(LookupProvider::IpWhoIs, None)], Duration::from_secs(300)))
+//! })
+//! .on_monitor(|monitor| {
+//!     monitor.on_change(|change| println!("Public address changed to {}", change.new_ip));
+//! })
+//! .run()
+//! .await
+//! # }
+//! ```
+//!
+//! [`Monitor`]: crate::monitor::Monitor
+
+use crate::error::Result;
+use crate::logging::{debug, trace, warn};
+use crate::{
+    lookup::{LookupProvider, Parameters},
+    monitor::Monitor,
+};
+use sd_notify::NotifyState;
+use signal_hook::{
+    consts::{SIGHUP, SIGINT, SIGTERM},
+    SigId,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Interval between checks of the signals and of the watchdog
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Number of lookup intervals without a successful lookup before the watchdog isn't kept alive
+const MISSED_LOOKUPS: u32 = 3;
+
+/// Configuration of the monitor of a [`Daemon`]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DaemonConfig {
+    /// Providers used for the lookups
+    pub providers: Vec<(LookupProvider, Option<Parameters>)>,
+    /// Interval between lookups
+    pub interval: Duration,
+}
+
+impl DaemonConfig {
+    /// Creates a configuration looking up the current address every `interval` with the providers.
+    pub fn new(providers: Vec<(LookupProvider, Option<Parameters>)>, interval: Duration) -> Self {
+        DaemonConfig {
+            providers,
+            interval,
+        }
+    }
+}
+
+/// Loads the configuration of the daemon
+type Loader = Box<dyn FnMut() -> Result<DaemonConfig> + Send>;
+
+/// Attaches listeners to a new monitor
+type Setup = Box<dyn FnMut(&mut Monitor) + Send>;
+
+/// Long-running service monitoring the public address, see the [module documentation](self)
+pub struct Daemon {
+    load: Loader,
+    setups: Vec<Setup>,
+}
+
+impl std::fmt::Debug for Daemon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Daemon")
+            .field("setups", &self.setups.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Daemon {
+    /// Creates a daemon loading its configuration with `load`, on start and on `SIGHUP`.
+    pub fn new<F>(load: F) -> Self
+    where
+        F: FnMut() -> Result<DaemonConfig> + Send + 'static,
+    {
+        Daemon {
+            load: Box::new(load),
+            setups: Vec::new(),
+        }
+    }
+
+    /// Calls `setup` with every monitor before it's started, to attach the listeners of the changes.
+    pub fn on_monitor<F>(mut self, setup: F) -> Self
+    where
+        F: FnMut(&mut Monitor) + Send + 'static,
+    {
+        self.setups.push(Box::new(setup));
+        self
+    }
+
+    /// Runs the daemon until `SIGTERM` or `SIGINT` is received.
+    ///
+    /// Fails if the configuration can't be loaded on start or the signal handlers can't be
+    /// registered. A configuration that can't be loaded on `SIGHUP` keeps the current monitor.
+    #[maybe_async::maybe_async]
+    pub async fn run(self) -> Result<()> {
+        let signals = Signals::register()?;
+        self.run_with(&signals, &Systemd).await
+    }
+
+    /// Runs the daemon until the stop flag of the signals is set, notifying the notifier
+    #[maybe_async::maybe_async]
+    async fn run_with(mut self, signals: &Signals, notifier: &dyn Notifier) -> Result<()> {
+        let (mut monitor, mut health) = self.monitor()?;
+        notifier.notify(&[NotifyState::Ready]);
+        let watchdog = notifier.watchdog_interval();
+        let mut pinged = Instant::now();
+
+        while !signals.stop.load(Ordering::Relaxed) {
+            if signals.reload.swap(false, Ordering::Relaxed) {
+                debug!("Reloading daemon configuration");
+                if let Ok(now) = NotifyState::monotonic_usec_now() {
+                    notifier.notify(&[NotifyState::Reloading, now]);
+                }
+                match self.monitor() {
+                    Ok((reloaded, reloaded_health)) => {
+                        health = reloaded_health;
+                        std::mem::replace(&mut monitor, reloaded).shutdown().await
+                    }
+                    Err(e) => warn!("Failed to reload daemon configuration: {:?}", e),
+                }
+                notifier.notify(&[NotifyState::Ready]);
+            }
+            if watchdog.is_some_and(|watchdog| pinged.elapsed() >= watchdog) {
+                if !monitor.is_finished() && health.is_healthy() {
+                    notifier.notify(&[NotifyState::Watchdog]);
+                } else {
+                    warn!("No successful lookup recently, not keeping the watchdog alive");
+                }
+                pinged = Instant::now();
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+
+        debug!("Stopping daemon");
+        notifier.notify(&[NotifyState::Stopping]);
+        monitor.shutdown().await;
+        Ok(())
+    }
+
+    /// Loads the configuration and starts a monitor with the listeners attached, returning it
+    /// with the health of its lookups
+    fn monitor(&mut self) -> Result<(Monitor, Health)> {
+        let config = (self.load)()?;
+        let health = Health::new(config.interval);
+        let mut monitor = Monitor::new(config.providers, config.interval);
+        let recorded = health.clone();
+        monitor.on_lookup(move |_| recorded.record());
+        for setup in &mut self.setups {
+            setup(&mut monitor);
+        }
+        monitor.start();
+        Ok((monitor, health))
+    }
+}
+
+/// Time of the last successful lookup of a monitor
+#[derive(Debug, Clone)]
+struct Health {
+    last_lookup: Arc<Mutex<Option<Instant>>>,
+    interval: Duration,
+}
+
+impl Health {
+    /// Creates the health of a monitor looking up the address every `interval`
+    fn new(interval: Duration) -> Self {
+        Health {
+            last_lookup: Arc::default(),
+            interval,
+        }
+    }
+
+    /// Records a successful lookup
+    fn record(&self) {
+        *self.last_lookup.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+    }
+
+    /// Checks if a lookup succeeded within the last `MISSED_LOOKUPS` intervals
+    fn is_healthy(&self) -> bool {
+        let last_lookup = *self.last_lookup.lock().unwrap_or_else(|e| e.into_inner());
+        last_lookup.is_some_and(|time| time.elapsed() < self.interval * MISSED_LOOKUPS)
+    }
+}
+
+/// Flags set by the signal handlers, unregistered when dropped
+struct Signals {
+    reload: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    ids: Vec<SigId>,
+}
+
+impl Signals {
+    /// Registers the handlers of `SIGHUP`, `SIGTERM` and `SIGINT`
+    fn register() -> Result<Signals> {
+        let (reload, stop) = (Arc::default(), Arc::<AtomicBool>::default());
+        let ids = vec![
+            signal_hook::flag::register(SIGHUP, Arc::clone(&reload))?,
+            signal_hook::flag::register(SIGTERM, Arc::clone(&stop))?,
+            signal_hook::flag::register(SIGINT, Arc::clone(&stop))?,
+        ];
+        Ok(Signals { reload, stop, ids })
+    }
+}
+
+impl Drop for Signals {
+    fn drop(&mut self) {
+        for id in self.ids.drain(..) {
+            signal_hook::low_level::unregister(id);
+        }
+    }
+}
+
+/// Receiver of the state of the daemon
+trait Notifier: Sync {
+    /// Notifies the state of the daemon
+    fn notify(&self, state: &[NotifyState]);
+
+    /// Returns the interval between watchdog pings, `None` if there is no watchdog
+    fn watchdog_interval(&self) -> Option<Duration>;
+}
+
+/// Notifies systemd, if the daemon is running as a systemd service
+struct Systemd;
+
+impl Notifier for Systemd {
+    fn notify(&self, state: &[NotifyState]) {
+        trace!("Notifying systemd: {:?}", state);
+        if let Err(e) = sd_notify::notify(false, state) {
+            warn!("Failed to notify systemd: {:?}", e);
+        }
+    }
+
+    /// Returns half the systemd watchdog timeout
+    fn watchdog_interval(&self) -> Option<Duration> {
+        let mut usec = 0;
+        sd_notify::watchdog_enabled(false, &mut usec).then(|| Duration::from_micros(usec) / 2)
+    }
+}
+
+/// Waits for the duration
+#[maybe_async::async_impl]
+async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Waits for the duration
+#[maybe_async::sync_impl]
+fn sleep(duration: Duration) {
+    std::thread::sleep(duration);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::ResponseCache;
+    use serial_test::serial;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Notifier recording the notified states, with a watchdog pinged every 50 milliseconds
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl Notifier for Recorder {
+        fn notify(&self, state: &[NotifyState]) {
+            self.0.lock().unwrap().push(state[0].to_string());
+        }
+
+        fn watchdog_interval(&self) -> Option<Duration> {
+            Some(Duration::from_millis(50))
+        }
+    }
+
+    impl Recorder {
+        fn count(&self, state: &str) -> usize {
+            self.0
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|s| *s == state)
+                .count()
+        }
+    }
+
+    /// Flags of signals never registered, set by the test instead
+    fn signals() -> Signals {
+        Signals {
+            reload: Arc::default(),
+            stop: Arc::default(),
+            ids: Vec::new(),
+        }
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    #[serial]
+    async fn test_daemon() {
+        let (loads, setups) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let (counted_loads, counted_setups) = (loads.clone(), setups.clone());
+        let daemon = Daemon::new(move || {
+            counted_loads.fetch_add(1, Ordering::SeqCst);
+            let provider = LookupProvider::Mock("1.1.1.1".to_string());
+            Ok(DaemonConfig::new(
+                vec![(provider, None)],
+                Duration::from_secs(60),
+            ))
+        })
+        .on_monitor(move |_| {
+            counted_setups.fetch_add(1, Ordering::SeqCst);
+        });
+        let signals = signals();
+        let (reload, stop) = (signals.reload.clone(), signals.stop.clone());
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            reload.store(true, Ordering::Relaxed);
+            std::thread::sleep(Duration::from_millis(300));
+            stop.store(true, Ordering::Relaxed);
+        });
+        let recorder = Recorder::default();
+        daemon.run_with(&signals, &recorder).await.unwrap();
+        assert_eq!(
+            loads.load(Ordering::SeqCst),
+            2,
+            "Configuration not reloaded"
+        );
+        assert_eq!(setups.load(Ordering::SeqCst), 2, "Monitor not set up");
+        assert_eq!(recorder.count("READY=1"), 2, "Ready not notified");
+        assert_eq!(recorder.count("STOPPING=1"), 1, "Stopping not notified");
+        assert!(recorder.count("WATCHDOG=1") > 0, "Watchdog not kept alive");
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    #[serial]
+    async fn test_daemon_unhealthy() {
+        _ = ResponseCache::default().delete();
+        // without providers, every lookup fails
+        let daemon = Daemon::new(|| Ok(DaemonConfig::new(vec![], Duration::from_millis(50))));
+        let signals = signals();
+        let stop = signals.stop.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(600));
+            stop.store(true, Ordering::Relaxed);
+        });
+        let recorder = Recorder::default();
+        daemon.run_with(&signals, &recorder).await.unwrap();
+        assert_eq!(recorder.count("READY=1"), 1, "Ready not notified");
+        assert_eq!(
+            recorder.count("WATCHDOG=1"),
+            0,
+            "Watchdog kept alive without successful lookups"
+        );
+    }
+}
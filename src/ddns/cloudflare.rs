@@ -0,0 +1,206 @@
+//! [Cloudflare](https://developers.cloudflare.com/api/resources/dns/subresources/records/) updater
+//!
+//! Updates a DNS record of a Cloudflare zone, creating it if it doesn't exist. The API token needs
+//! the `Zone.DNS` edit permission on the zone.
+
+use super::{client, record_type, with_query, DdnsUpdater, Result, UpdateOutcome};
+use crate::error::DdnsError;
+use crate::logging::{debug, trace};
+use crate::lookup::{
+    client::{Client, StatusCode},
+    error::LookupError,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::net::IpAddr;
+
+/// Base URL of the Cloudflare API
+const API_URL: &str = "https://api.cloudflare.com/client/v4";
+
+/// Updates an `A` or `AAAA` record of a Cloudflare zone
+#[derive(Clone)]
+pub struct CloudflareUpdater {
+    token: String,
+    zone_id: String,
+    record: String,
+    client: Option<Client>,
+}
+
+impl std::fmt::Debug for CloudflareUpdater {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CloudflareUpdater")
+            .field("zone_id", &self.zone_id)
+            .field("record", &self.record)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Envelope of the Cloudflare API responses
+#[derive(Deserialize, Debug)]
+struct Envelope<T> {
+    success: bool,
+    #[serde(default)]
+    errors: Vec<ApiError>,
+    result: Option<T>,
+}
+
+/// Error reported by the Cloudflare API
+#[derive(Deserialize, Debug)]
+struct ApiError {
+    code: u32,
+    message: String,
+}
+
+/// DNS record of the Cloudflare API
+#[derive(Deserialize, Debug)]
+struct Record {
+    id: String,
+    content: String,
+}
+
+impl CloudflareUpdater {
+    /// Creates an updater of the `record` name, such as `home.example.com`, in the zone `zone_id`.
+    ///
+    /// `token` is an API token with the `Zone.DNS` edit permission on the zone.
+    pub fn new(token: &str, zone_id: &str, record: &str) -> Self {
+        CloudflareUpdater {
+            token: token.to_string(),
+            zone_id: zone_id.to_string(),
+            record: record.to_string(),
+            client: None,
+        }
+    }
+
+    /// Sets the client used to make the requests of the updater, like a client bound to the
+    /// interface of the record, see [`LookupService::set_client`](crate::lookup::LookupService::set_client)
+    pub fn set_client(&mut self, client: Client) -> &Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Returns the URL of the DNS records of the zone
+    fn records_url(&self) -> String {
+        format!("{}/zones/{}/dns_records", API_URL, self.zone_id)
+    }
+
+    /// Returns the URL searching the record of the address type
+    fn search_url(&self, ip: &IpAddr) -> String {
+        with_query(
+            &self.records_url(),
+            &[("type", record_type(ip)), ("name", &self.record)],
+        )
+    }
+}
+
+#[maybe_async::maybe_async]
+impl DdnsUpdater for CloudflareUpdater {
+    /// Points the record of the address type at the address, creating the record if needed.
+    async fn update(&self, ip: IpAddr) -> Result<UpdateOutcome> {
+        let records_url = self.records_url();
+        let client = client(self.client.as_ref())?;
+        let request = client.get(self.search_url(&ip)).bearer_auth(&self.token);
+        let records: Vec<Record> = send(request).await?;
+        let body = |body: serde_json::Value| body.to_string();
+        match records.first() {
+            Some(record) if record.content.parse() == Ok(ip) => {
+                trace!("{} already points at {}", self.record, ip);
+                Ok(UpdateOutcome::Unchanged)
+            }
+            Some(record) => {
+                debug!("Updating {} to {}", self.record, ip);
+                let request = client
+                    .patch(format!("{}/{}", records_url, record.id))
+                    .bearer_auth(&self.token)
+                    .header("Content-Type", "application/json")
+                    .body(body(serde_json::json!({ "content": ip.to_string() })));
+                send::<serde_json::Value>(request).await?;
+                Ok(UpdateOutcome::Updated)
+            }
+            None => {
+                debug!("Creating {} pointing at {}", self.record, ip);
+                let request = client
+                    .post(records_url)
+                    .bearer_auth(&self.token)
+                    .header("Content-Type", "application/json")
+                    .body(body(serde_json::json!({
+                        "type": record_type(&ip),
+                        "name": self.record,
+                        "content": ip.to_string(),
+                        "ttl": 1,
+                    })));
+                send::<serde_json::Value>(request).await?;
+                Ok(UpdateOutcome::Updated)
+            }
+        }
+    }
+}
+
+/// Sends the request and returns the result of the response
+#[maybe_async::maybe_async]
+async fn send<T: DeserializeOwned>(request: crate::lookup::client::RequestBuilder) -> Result<T> {
+    let response = request.send().await.map_err(LookupError::from)?;
+    let status = response.status();
+    let body = response.text().await.map_err(LookupError::from)?;
+    parse(status, &body)
+}
+
+/// Parses the result of a response, failing with the errors it reports
+fn parse<T: DeserializeOwned>(status: StatusCode, body: &str) -> Result<T> {
+    let envelope: Envelope<T> = match serde_json::from_str(body) {
+        Ok(envelope) => envelope,
+        Err(_) if !status.is_success() => {
+            return Err(crate::lookup::status_error(status).into());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if !envelope.success {
+        let errors: Vec<String> = envelope
+            .errors
+            .iter()
+            .map(|error| format!("{} ({})", error.message, error.code))
+            .collect();
+        return Err(DdnsError::Rejected(errors.join(", ")));
+    }
+    envelope
+        .result
+        .ok_or_else(|| DdnsError::Rejected("Missing result".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let body =
+            r#"{"success":true,"errors":[],"result":[{"id":"372e6795","content":"1.1.1.1"}]}"#;
+        let records: Vec<Record> = parse(StatusCode::OK, body).unwrap();
+        assert_eq!(records[0].id, "372e6795");
+        assert_eq!(records[0].content, "1.1.1.1");
+
+        let body =
+            r#"{"success":false,"errors":[{"code":10000,"message":"Authentication error"}]}"#;
+        let error = parse::<Vec<Record>>(StatusCode::FORBIDDEN, body).unwrap_err();
+        assert!(
+            matches!(&error, DdnsError::Rejected(message) if message == "Authentication error (10000)"),
+            "Wrong error: {:?}",
+            error
+        );
+        let error = parse::<Vec<Record>>(StatusCode::BAD_GATEWAY, "Bad gateway").unwrap_err();
+        assert!(
+            matches!(
+                error,
+                DdnsError::RequestError(LookupError::RequestStatus(_))
+            ),
+            "Status not reported"
+        );
+    }
+
+    #[test]
+    fn test_search_url() {
+        let updater = CloudflareUpdater::new("token", "023e105f", "home&type=TXT.example.com");
+        assert_eq!(
+            updater.search_url(&"1.1.1.1".parse().unwrap()),
+            "https://api.cloudflare.com/client/v4/zones/023e105f/dns_records?type=A&name=home%26type%3DTXT.example.com"
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! [DuckDNS](https://www.duckdns.org/spec.jsp) updater
+//!
+//! Updates the `A` or `AAAA` record of a DuckDNS subdomain. The token is shown on the DuckDNS
+//! account page.
+
+use super::{client, with_query, DdnsUpdater, Result, UpdateOutcome};
+use crate::error::DdnsError;
+use crate::logging::debug;
+use crate::lookup::{client::Client, handle_response};
+use std::net::IpAddr;
+
+/// URL of the DuckDNS update API
+const API_URL: &str = "https://www.duckdns.org/update";
+
+/// Suffix of the DuckDNS domains
+const DOMAIN_SUFFIX: &str = ".duckdns.org";
+
+/// Updates the `A` or `AAAA` record of a DuckDNS subdomain
+#[derive(Clone)]
+pub struct DuckDnsUpdater {
+    domain: String,
+    token: String,
+    client: Option<Client>,
+}
+
+impl std::fmt::Debug for DuckDnsUpdater {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DuckDnsUpdater")
+            .field("domain", &self.domain)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DuckDnsUpdater {
+    /// Creates an updater of the `domain` subdomain, such as `myhome` or `myhome.duckdns.org`.
+    pub fn new(domain: &str, token: &str) -> Self {
+        DuckDnsUpdater {
+            domain: domain.trim_end_matches(DOMAIN_SUFFIX).to_string(),
+            token: token.to_string(),
+            client: None,
+        }
+    }
+
+    /// Sets the client used to make the requests of the updater, like a client bound to the
+    /// interface of the record, see [`LookupService::set_client`](crate::lookup::LookupService::set_client)
+    pub fn set_client(&mut self, client: Client) -> &Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Returns the URL pointing the record of the address type at the address
+    fn update_url(&self, ip: &IpAddr) -> String {
+        let param = match ip {
+            IpAddr::V4(_) => "ip",
+            IpAddr::V6(_) => "ipv6",
+        };
+        with_query(
+            API_URL,
+            &[
+                ("domains", &self.domain),
+                ("token", &self.token),
+                (param, &ip.to_string()),
+                ("verbose", "true"),
+            ],
+        )
+    }
+}
+
+#[maybe_async::maybe_async]
+impl DdnsUpdater for DuckDnsUpdater {
+    /// Points the record of the address type at the address.
+    async fn update(&self, ip: IpAddr) -> Result<UpdateOutcome> {
+        let response = client(self.client.as_ref())?
+            .get(self.update_url(&ip))
+            .send()
+            .await;
+        let outcome = parse(&handle_response(response).await?)?;
+        debug!("{}{} {:?} to {}", self.domain, DOMAIN_SUFFIX, outcome, ip);
+        Ok(outcome)
+    }
+}
+
+/// Parses the verbose response, `OK` or `KO` followed by the addresses and the change
+fn parse(body: &str) -> Result<UpdateOutcome> {
+    let mut lines = body.lines();
+    match lines.next().map(str::trim) {
+        Some("OK") => match lines.nth(2).map(str::trim) {
+            Some("UPDATED") => Ok(UpdateOutcome::Updated),
+            Some("NOCHANGE") => Ok(UpdateOutcome::Unchanged),
+            _ => Err(DdnsError::Rejected(format!(
+                "Unexpected response: {}",
+                body
+            ))),
+        },
+        // the domain or the token is wrong
+        _ => Err(DdnsError::Rejected("Invalid domain or token".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let body = "OK\n1.1.1.1\n\nUPDATED";
+        assert_eq!(parse(body).unwrap(), UpdateOutcome::Updated);
+        let body = "OK\n\n2606:4700:4700::1111\nNOCHANGE";
+        assert_eq!(parse(body).unwrap(), UpdateOutcome::Unchanged);
+        assert!(matches!(parse("KO"), Err(DdnsError::Rejected(_))));
+        assert_eq!(
+            DuckDnsUpdater::new("myhome.duckdns.org", "token").domain,
+            "myhome"
+        );
+    }
+
+    #[test]
+    fn test_update_url() {
+        let updater = DuckDnsUpdater::new("myhome", "a+b&c");
+        assert_eq!(
+            updater.update_url(&"2606:4700:4700::1111".parse().unwrap()),
+            "https://www.duckdns.org/update?domains=myhome&token=a%2Bb%26c&ipv6=2606%3A4700%3A4700%3A%3A1111&verbose=true"
+        );
+    }
+}
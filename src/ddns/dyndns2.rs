@@ -0,0 +1,177 @@
+//! [dyndns2](https://help.dyn.com/remote-access-api/) protocol updater
+//!
+//! Updates a hostname of the many services speaking the dyndns2 protocol, like No-IP, Dynu or
+//! DynDNS, with `GET /nic/update?hostname=...&myip=...` and basic authentication.
+//!
+//! The services block the clients sending the same update again and again, or retrying after an
+//! error other than a server error: the monitor only updates on changes, and only the failed updates
+//! with a server error are retried.
+
+use super::{client, with_query, DdnsUpdater, Result, UpdateOutcome};
+use crate::error::DdnsError;
+use crate::logging::debug;
+use crate::lookup::{client::Client, handle_response};
+use std::net::IpAddr;
+
+/// Return code of a dyndns2 update
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReturnCode {
+    /// `good`, the hostname now points at the address
+    Good(Option<IpAddr>),
+    /// `nochg`, the hostname already pointed at the address
+    NoChange(Option<IpAddr>),
+    /// `badauth`, the username or the password is wrong
+    BadAuth,
+    /// `nohost`, the hostname doesn't exist in the account
+    NoHost,
+    /// `notfqdn`, the hostname is not a fully qualified domain name
+    NotFqdn,
+    /// `badagent`, the client is blocked
+    BadAgent,
+    /// `abuse`, the hostname is blocked for update abuse
+    Abuse,
+    /// `!donator`, the update uses a feature of paid accounts
+    NotDonator,
+    /// `dnserr` or `911`, the service failed and the update may be retried later
+    ServerError,
+    /// Unknown return code
+    Other(String),
+}
+
+impl ReturnCode {
+    /// Parses the first line of the response
+    pub fn parse(body: &str) -> ReturnCode {
+        let line = body.lines().next().unwrap_or_default().trim();
+        let (code, ip) = line.split_once(' ').unwrap_or((line, ""));
+        let ip = ip.trim().parse().ok();
+        match code {
+            "good" => ReturnCode::Good(ip),
+            "nochg" => ReturnCode::NoChange(ip),
+            "badauth" => ReturnCode::BadAuth,
+            "nohost" => ReturnCode::NoHost,
+            "notfqdn" => ReturnCode::NotFqdn,
+            "badagent" => ReturnCode::BadAgent,
+            "abuse" => ReturnCode::Abuse,
+            "!donator" => ReturnCode::NotDonator,
+            "dnserr" | "911" => ReturnCode::ServerError,
+            _ => ReturnCode::Other(line.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for ReturnCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReturnCode::Good(_) => write!(f, "good"),
+            ReturnCode::NoChange(_) => write!(f, "nochg"),
+            ReturnCode::BadAuth => write!(f, "badauth"),
+            ReturnCode::NoHost => write!(f, "nohost"),
+            ReturnCode::NotFqdn => write!(f, "notfqdn"),
+            ReturnCode::BadAgent => write!(f, "badagent"),
+            ReturnCode::Abuse => write!(f, "abuse"),
+            ReturnCode::NotDonator => write!(f, "!donator"),
+            ReturnCode::ServerError => write!(f, "911"),
+            ReturnCode::Other(code) => write!(f, "{}", code),
+        }
+    }
+}
+
+/// Updates a hostname through the dyndns2 protocol
+#[derive(Clone)]
+pub struct Dyndns2Updater {
+    server: String,
+    hostname: String,
+    username: String,
+    password: String,
+    client: Option<Client>,
+}
+
+impl std::fmt::Debug for Dyndns2Updater {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dyndns2Updater")
+            .field("server", &self.server)
+            .field("hostname", &self.hostname)
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Dyndns2Updater {
+    /// Creates an updater of `hostname` on the update `server`, such as `https://dynupdate.no-ip.com`
+    /// or `https://api.dynu.com`.
+    pub fn new(server: &str, hostname: &str, username: &str, password: &str) -> Self {
+        Dyndns2Updater {
+            server: server.trim_end_matches('/').to_string(),
+            hostname: hostname.to_string(),
+            username: username.to_string(),
+            password: password.to_string(),
+            client: None,
+        }
+    }
+
+    /// Sets the client used to make the requests of the updater, like a client bound to the
+    /// interface of the record, see [`LookupService::set_client`](crate::lookup::LookupService::set_client)
+    pub fn set_client(&mut self, client: Client) -> &Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Returns the URL pointing the hostname at the address
+    fn update_url(&self, ip: &IpAddr) -> String {
+        with_query(
+            &format!("{}/nic/update", self.server),
+            &[("hostname", &self.hostname), ("myip", &ip.to_string())],
+        )
+    }
+}
+
+#[maybe_async::maybe_async]
+impl DdnsUpdater for Dyndns2Updater {
+    /// Points the hostname at the address.
+    ///
+    /// Return codes other than `good` and `nochg` fail with [`DdnsError::Refused`].
+    async fn update(&self, ip: IpAddr) -> Result<UpdateOutcome> {
+        let response = client(self.client.as_ref())?
+            .get(self.update_url(&ip))
+            .basic_auth(&self.username, Some(&self.password))
+            .send()
+            .await;
+        let code = ReturnCode::parse(&handle_response(response).await?);
+        debug!("{} update returned {}", self.hostname, code);
+        match code {
+            ReturnCode::Good(_) => Ok(UpdateOutcome::Updated),
+            ReturnCode::NoChange(_) => Ok(UpdateOutcome::Unchanged),
+            code => Err(DdnsError::Refused(code)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let ip = "1.1.1.1".parse().ok();
+        assert_eq!(ReturnCode::parse("good 1.1.1.1\n"), ReturnCode::Good(ip));
+        assert_eq!(ReturnCode::parse("nochg 1.1.1.1"), ReturnCode::NoChange(ip));
+        assert_eq!(ReturnCode::parse("good"), ReturnCode::Good(None));
+        assert_eq!(ReturnCode::parse("abuse"), ReturnCode::Abuse);
+        assert_eq!(ReturnCode::parse("911"), ReturnCode::ServerError);
+        assert_eq!(
+            ReturnCode::parse("wat"),
+            ReturnCode::Other("wat".to_string())
+        );
+    }
+
+    #[test]
+    fn test_update_url() {
+        let updater =
+            Dyndns2Updater::new("https://api.dynu.com/", "home&myip=6.6.6.6", "user", "pass");
+        assert_eq!(
+            updater.update_url(&"1.1.1.1".parse().unwrap()),
+            "https://api.dynu.com/nic/update?hostname=home%26myip%3D6.6.6.6&myip=1.1.1.1"
+        );
+    }
+}
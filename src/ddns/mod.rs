@@ -0,0 +1,182 @@
+//! # 🌐 Dynamic DNS updaters
+//!
+//! This module keeps DNS records pointing at the public address of the host. A [`DdnsUpdater`]
+//! updates its record on demand, or is attached to a [`Monitor`] to update the record every time the
+//! address changes. The record type follows the address, `A` for IPv4 and `AAAA` for IPv6.
+//!
+//! The built-in updaters cover the common services, other registrars are plugged in by implementing
+//! [`DdnsUpdater`]:
+//!
+//! - [`CloudflareUpdater`](cloudflare::CloudflareUpdater) updates a record through the Cloudflare API;
+//! - [`DuckDnsUpdater`](duckdns::DuckDnsUpdater) updates a free DuckDNS subdomain;
+//! - [`Dyndns2Updater`](dyndns2::Dyndns2Updater) updates a hostname of the services speaking the
+//!   dyndns2 protocol, like No-IP or Dynu.
+//!
+//! The records are checked against the public addresses with [`verify_dns`](verify::verify_dns).
+//!
+//! ## Example
+//! ```rust,no_run
+//! use public_ip_address::ddns::{cloudflare::CloudflareUpdater, DdnsUpdater};
+//! use public_ip_address::lookup::LookupProvider;
+//! use public_ip_address::monitor::Monitor;
+//! use std::time::Duration;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() {
+//! let mut monitor = Monitor::new(vec![(LookupProvider::IpWhoIs, None)], Duration::from_secs(300));
+//! CloudflareUpdater::new("api-token", "zone-id", "home.example.com").attach(&mut monitor);
+//! monitor.start();
+//! # }
+//! ```
+//!
+//! [`Monitor`]: crate::monitor::Monitor
+
+use crate::error::DdnsError;
+use crate::logging::{debug, warn};
+use crate::lookup::client::{self, Binding, Client};
+use crate::monitor::Monitor;
+use std::{
+    net::IpAddr,
+    sync::{Arc, Mutex},
+};
+
+pub mod cloudflare;
+pub mod duckdns;
+pub mod dyndns2;
+pub mod verify;
+
+/// Result type wrapper for the DDNS updaters
+pub type Result<T> = std::result::Result<T, DdnsError>;
+
+/// Outcome of a successful update of a DNS record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UpdateOutcome {
+    /// The record was created or now points at the new address
+    Updated,
+    /// The record already pointed at the address
+    Unchanged,
+}
+
+/// DNS record updater trait, implemented by the built-in updaters
+///
+/// The trait is async unless the `blocking` feature is enabled.
+#[maybe_async::maybe_async]
+pub trait DdnsUpdater: Send + Sync {
+    /// Points the record of the address type at the address
+    async fn update(&self, ip: IpAddr) -> Result<UpdateOutcome>;
+
+    /// Updates the record with the new address of every change reported by the monitor.
+    ///
+    /// Without the `blocking` feature the updates run as `tokio` tasks, otherwise they run on their
+    /// own thread. Failed updates are logged, and retried after the next lookup of the monitor if
+    /// the error is retryable, see [`DdnsError::is_retryable`].
+    fn attach(self, monitor: &mut Monitor)
+    where
+        Self: Sized + 'static,
+    {
+        spawn_updates(Arc::new(self), monitor);
+    }
+}
+
+/// Address the record should point at, and whether an update is running
+#[derive(Debug, Default)]
+struct Pending {
+    ip: Option<IpAddr>,
+    running: bool,
+}
+
+/// Address pending for an updater, shared with its running update
+type SharedPending = Arc<Mutex<Pending>>;
+
+/// Runs an update for every change reported by the monitor, retrying after the next lookups
+#[maybe_async::async_impl]
+fn spawn_updates<U: DdnsUpdater + 'static>(updater: Arc<U>, monitor: &mut Monitor) {
+    let pending = watch_changes(monitor);
+    monitor.on_lookup(move |_| {
+        let Some(ip) = start_update(&pending) else {
+            return;
+        };
+        let (updater, pending) = (updater.clone(), pending.clone());
+        tokio::spawn(async move {
+            let result = updater.update(ip).await;
+            finish_update(&pending, ip, result);
+        });
+    });
+}
+
+/// Runs an update for every change reported by the monitor, retrying after the next lookups
+#[maybe_async::sync_impl]
+fn spawn_updates<U: DdnsUpdater + 'static>(updater: Arc<U>, monitor: &mut Monitor) {
+    let pending = watch_changes(monitor);
+    monitor.on_lookup(move |_| {
+        let Some(ip) = start_update(&pending) else {
+            return;
+        };
+        let (updater, pending) = (updater.clone(), pending.clone());
+        std::thread::spawn(move || {
+            let result = updater.update(ip);
+            finish_update(&pending, ip, result);
+        });
+    });
+}
+
+/// Keeps the address of every change reported by the monitor as pending
+fn watch_changes(monitor: &mut Monitor) -> SharedPending {
+    let pending = SharedPending::default();
+    let changes = pending.clone();
+    monitor.on_change(move |change| {
+        changes.lock().unwrap_or_else(|e| e.into_inner()).ip = Some(change.new_ip);
+    });
+    pending
+}
+
+/// Takes the pending address, unless an update is already running
+fn start_update(pending: &SharedPending) -> Option<IpAddr> {
+    let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+    if pending.running {
+        return None;
+    }
+    let ip = pending.ip.take()?;
+    pending.running = true;
+    Some(ip)
+}
+
+/// Keeps the address pending after a retryable failure, unless a newer change is pending
+fn finish_update(pending: &SharedPending, ip: IpAddr, result: Result<UpdateOutcome>) {
+    let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+    pending.running = false;
+    match result {
+        Ok(outcome) => debug!("DNS record update to {}: {:?}", ip, outcome),
+        Err(e) if e.is_retryable() => {
+            warn!("Failed to update DNS record to {}, will retry: {:?}", ip, e);
+            pending.ip.get_or_insert(ip);
+        }
+        Err(e) => warn!("Failed to update DNS record to {}: {:?}", ip, e),
+    }
+}
+
+/// Returns the type of the DNS record of the address, `A` or `AAAA`
+pub fn record_type(ip: &IpAddr) -> &'static str {
+    match ip {
+        IpAddr::V4(_) => "A",
+        IpAddr::V6(_) => "AAAA",
+    }
+}
+
+/// Returns the URL with the query parameters, encoded
+fn with_query(url: &str, params: &[(&str, &str)]) -> String {
+    let query = form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .finish();
+    format!("{}?{}", url, query)
+}
+
+/// Returns the custom client of the updater, or the HTTP client shared with the lookups
+fn client(client: Option<&Client>) -> Result<Client> {
+    match client {
+        Some(client) => Ok(client.clone()),
+        None => Ok(client::shared(Binding::default())?),
+    }
+}
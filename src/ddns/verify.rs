@@ -0,0 +1,255 @@
+//! DNS record verification
+//!
+//! Resolves a hostname and compares its addresses with the public addresses of the host, looked up
+//! over IPv4 and over IPv6. This is the check half of DDNS, useful to alert when a record went
+//! stale without updating it.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use public_ip_address::ddns::verify::verify_dns;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() -> Result<(), public_ip_address::error::Error> {
+//! let report = verify_dns("home.example.com").await?;
+//! for mismatch in &report.mismatches {
+//!     eprintln!("{}: {:?}", report.hostname, mismatch);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{DdnsError, Error, Result};
+use crate::logging::{debug, trace};
+use crate::lookup::error::{ErrorKind, LookupError};
+use crate::lookup::{IpVersion, LookupProvider, Parameters};
+use crate::{default_providers, options::LookupOptions, perform_lookup_with_options};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, ToSocketAddrs};
+
+/// Difference between the addresses of a hostname and the public addresses of the host
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DnsMismatch {
+    /// The hostname has no address of the version of the public address
+    Missing {
+        /// Public address of the host
+        expected: IpAddr,
+    },
+    /// The addresses of the version don't include the public address
+    Stale {
+        /// Public address of the host
+        expected: IpAddr,
+        /// Addresses of the hostname of the same version
+        found: Vec<IpAddr>,
+    },
+    /// The hostname has addresses of a version the host has no public address of, as no provider
+    /// could be reached over the version
+    Unexpected {
+        /// Version of the addresses
+        version: IpVersion,
+        /// Addresses of the hostname of the version
+        found: Vec<IpAddr>,
+    },
+    /// The hostname has addresses of a version the public address couldn't be looked up of, so
+    /// they are not verified
+    LookupFailed {
+        /// Version of the addresses
+        version: IpVersion,
+        /// Addresses of the hostname of the version
+        found: Vec<IpAddr>,
+        /// Error of the lookup
+        error: String,
+    },
+}
+
+/// Result of the verification of a hostname
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DnsReport {
+    /// Verified hostname
+    pub hostname: String,
+    /// Addresses the hostname resolves to
+    pub resolved: Vec<IpAddr>,
+    /// Public IPv4 address of the host, `None` if it couldn't be looked up
+    pub public_v4: Option<IpAddr>,
+    /// Public IPv6 address of the host, `None` if it couldn't be looked up
+    pub public_v6: Option<IpAddr>,
+    /// Differences found, empty if the hostname points at the public addresses
+    pub mismatches: Vec<DnsMismatch>,
+}
+
+impl DnsReport {
+    /// Checks if the hostname points at the public addresses of the host.
+    pub fn is_match(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Verifies that the hostname resolves to the public addresses of the host.
+///
+/// The public addresses are looked up, bypassing the cache, with the providers of
+/// [`perform_lookup`](crate::perform_lookup).
+#[maybe_async::maybe_async]
+pub async fn verify_dns(hostname: &str) -> Result<DnsReport> {
+    verify_dns_with(hostname, default_providers()).await
+}
+
+/// Verifies that the hostname resolves to the public addresses of the host, looked up with the
+/// providers.
+///
+/// Fails if the hostname can't be resolved, or if neither the public IPv4 nor the public IPv6
+/// address can be looked up.
+#[maybe_async::maybe_async]
+pub async fn verify_dns_with(
+    hostname: &str,
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+) -> Result<DnsReport> {
+    let resolved = resolve(hostname).await?;
+    trace!("{} resolves to {:?}", hostname, resolved);
+    let options = LookupOptions::new().ip_version(IpVersion::V4);
+    let v4 = perform_lookup_with_options(providers.clone(), None, &options).await;
+    let options = LookupOptions::new().ip_version(IpVersion::V6);
+    let v6 = perform_lookup_with_options(providers, None, &options).await;
+    let (v4, v6) = match (v4, v6) {
+        (Err(e), Err(_)) => return Err(e),
+        (v4, v6) => (
+            v4.map(|response| response.ip),
+            v6.map(|response| response.ip),
+        ),
+    };
+    let mut mismatches = compare(&resolved, IpVersion::V4, &v4);
+    mismatches.extend(compare(&resolved, IpVersion::V6, &v6));
+    let (public_v4, public_v6) = (v4.ok(), v6.ok());
+    debug!("{} verified with {} mismatches", hostname, mismatches.len());
+    Ok(DnsReport {
+        hostname: hostname.to_string(),
+        resolved,
+        public_v4,
+        public_v6,
+        mismatches,
+    })
+}
+
+/// Compares the resolved addresses of the version with the result of the lookup of the version
+fn compare(resolved: &[IpAddr], version: IpVersion, public: &Result<IpAddr>) -> Vec<DnsMismatch> {
+    let found: Vec<IpAddr> = resolved
+        .iter()
+        .filter(|ip| version.matches(ip))
+        .copied()
+        .collect();
+    match public {
+        Ok(expected) if found.is_empty() => vec![DnsMismatch::Missing {
+            expected: *expected,
+        }],
+        Ok(expected) if !found.contains(expected) => vec![DnsMismatch::Stale {
+            expected: *expected,
+            found,
+        }],
+        Err(e) if !found.is_empty() && is_unreachable(e) => {
+            vec![DnsMismatch::Unexpected { version, found }]
+        }
+        Err(e) if !found.is_empty() => vec![DnsMismatch::LookupFailed {
+            version,
+            found,
+            // the message of the lookup error itself, its wrapper only displays "Lookup error"
+            error: match e {
+                Error::LookupError(lookup) => lookup.to_string(),
+                other => other.to_string(),
+            },
+        }],
+        _ => Vec::new(),
+    }
+}
+
+/// Checks if the lookup failed because no provider could be reached, as when the host has no
+/// connectivity over the IP version
+fn is_unreachable(error: &Error) -> bool {
+    fn unreachable(error: &LookupError) -> bool {
+        match error {
+            LookupError::AllProvidersFailed(errors) => {
+                !errors.is_empty() && errors.iter().all(|(_, error)| unreachable(error))
+            }
+            error => error.kind() == ErrorKind::Network,
+        }
+    }
+    matches!(error, Error::LookupError(error) if unreachable(error))
+}
+
+/// Resolves the addresses of the hostname with the system resolver
+#[maybe_async::async_impl]
+async fn resolve(hostname: &str) -> Result<Vec<IpAddr>> {
+    let hostname = hostname.to_string();
+    tokio::task::spawn_blocking(move || resolve_blocking(&hostname))
+        .await
+        .map_err(|e| DdnsError::ResolveError(std::io::Error::other(e)))?
+}
+
+/// Resolves the addresses of the hostname with the system resolver
+#[maybe_async::sync_impl]
+fn resolve(hostname: &str) -> Result<Vec<IpAddr>> {
+    resolve_blocking(hostname)
+}
+
+/// Resolves the addresses of the hostname, without duplicates
+fn resolve_blocking(hostname: &str) -> Result<Vec<IpAddr>> {
+    let mut addresses: Vec<IpAddr> = (hostname, 0)
+        .to_socket_addrs()
+        .map_err(DdnsError::ResolveError)?
+        .map(|address| address.ip())
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+    Ok(addresses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let ip = |ip: &str| ip.parse::<IpAddr>().unwrap();
+        let resolved = vec![ip("1.1.1.1"), ip("2606:4700:4700::1111")];
+        assert!(compare(&resolved, IpVersion::V4, &Ok(ip("1.1.1.1"))).is_empty());
+        assert_eq!(
+            compare(&resolved, IpVersion::V4, &Ok(ip("2.2.2.2"))),
+            vec![DnsMismatch::Stale {
+                expected: ip("2.2.2.2"),
+                found: vec![ip("1.1.1.1")]
+            }]
+        );
+        assert_eq!(
+            compare(&resolved[..1], IpVersion::V6, &Ok(ip("::2"))),
+            vec![DnsMismatch::Missing {
+                expected: ip("::2")
+            }]
+        );
+        let failed = LookupError::AllProvidersFailed(vec![(
+            LookupProvider::IpInfo,
+            LookupError::TooManyRequests("Too many requests".to_string()),
+        )]);
+        assert_eq!(
+            compare(&resolved, IpVersion::V6, &Err(failed.into())),
+            vec![DnsMismatch::LookupFailed {
+                version: IpVersion::V6,
+                found: vec![ip("2606:4700:4700::1111")],
+                error: "All providers failed".to_string(),
+            }]
+        );
+        assert!(compare(
+            &resolved[..1],
+            IpVersion::V6,
+            &Err(LookupError::Cancelled.into())
+        )
+        .is_empty());
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_verify_dns() {
+        let providers = vec![(LookupProvider::Mock("127.0.0.1".to_string()), None)];
+        let report = verify_dns_with("localhost", providers).await.unwrap();
+        assert!(report.resolved.contains(&"127.0.0.1".parse().unwrap()));
+        assert_eq!(report.public_v4, "127.0.0.1".parse().ok());
+    }
+}
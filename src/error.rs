@@ -19,6 +19,32 @@ pub enum Error {
     /// System time error, usually when converting from a timestamp
     #[error("Time error")]
     TimeError(#[from] std::time::SystemTimeError),
+    /// MQTT error when publishing to the broker
+    #[cfg(feature = "mqtt")]
+    #[error("MQTT error")]
+    MqttError(#[from] rumqttc::ClientError),
+    /// Notification error when showing a desktop notification
+    #[cfg(feature = "notification")]
+    #[error("Notification error")]
+    NotificationError(#[from] notify_rust::error::Error),
+    /// Daemon error when registering the signal handlers
+    #[cfg(all(unix, feature = "daemon"))]
+    #[error("Daemon error")]
+    DaemonError(#[from] std::io::Error),
+    /// DDNS error when updating a DNS record
+    #[error("DDNS error")]
+    DdnsError(#[from] DdnsError),
+}
+
+impl Error {
+    /// Returns `true` if the same lookup may succeed later, see [`LookupError::is_retryable`]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::LookupError(e) => e.is_retryable(),
+            Error::DdnsError(e) => e.is_retryable(),
+            _ => false,
+        }
+    }
 }
 
 /// Error type for the cache module
@@ -37,4 +63,55 @@ pub enum CacheError {
     /// Encryption error when encrypting or decrypting data
     #[error("Encryption error")]
     EncryptionError(String),
+    /// Cache written with an unsupported version of the cache format
+    #[error("Unsupported cache version")]
+    VersionError(u32),
+    /// Error of the sled database backend
+    #[cfg(feature = "sled")]
+    #[error("Sled error")]
+    SledError(#[from] sled::Error),
+    /// Error of the SQLite database backend
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error")]
+    SqliteError(#[from] rusqlite::Error),
+    /// Error of the Redis backend
+    #[cfg(feature = "redis")]
+    #[error("Redis error")]
+    RedisError(#[from] redis::RedisError),
+}
+
+/// Error type for the DDNS updaters
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum DdnsError {
+    /// Request error when calling the DNS provider
+    #[error("Request error")]
+    RequestError(#[from] LookupError),
+    /// Serde error when parsing the response of the DNS provider
+    #[error("Serde error")]
+    SerdeError(#[from] serde_json::Error),
+    /// Update rejected by the DNS provider
+    #[error("Update rejected: {0}")]
+    Rejected(String),
+    /// IO error when resolving a hostname
+    #[error("Resolve error")]
+    ResolveError(#[from] std::io::Error),
+    /// Update refused with a dyndns2 return code
+    #[error("Update refused: {0}")]
+    Refused(crate::ddns::dyndns2::ReturnCode),
+}
+
+impl DdnsError {
+    /// Returns `true` if the same update may succeed later
+    ///
+    /// Retryable request errors and the server errors of the dyndns2 services are retryable, see
+    /// [`LookupError::is_retryable`]. Rejected updates are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            DdnsError::RequestError(e) => e.is_retryable(),
+            DdnsError::Refused(code) => *code == crate::ddns::dyndns2::ReturnCode::ServerError,
+            DdnsError::ResolveError(_) => true,
+            _ => false,
+        }
+    }
 }
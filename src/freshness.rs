@@ -0,0 +1,246 @@
+//! # 🕰️ Provider response freshness
+//!
+//! Providers can declare for how long their responses stay fresh with the `Cache-Control: max-age`
+//! or `Expires` response headers. The freshness is returned with the response in
+//! [`LookupResponse::fresh_until`], and the cached lookups use it as an upper bound of the TTL of the
+//! cached response, so a response is not cached for much longer than its provider allows. Responses
+//! are cached for at least [`MIN_TTL`] seconds though, so a `max-age=0` doesn't disable the cache.
+//!
+//! `no-cache` and `no-store` directives are ignored, the TTL given to the cached lookups applies.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use public_ip_address::lookup::{LookupProvider, LookupService};
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() {
+//! let service = LookupService::new(LookupProvider::Ipify, None);
+//! if let Ok(response) = service.lookup(None).await {
+//!     if let Some(fresh_until) = response.fresh_until {
+//!         println!("The response of ipify is fresh until {:?}", fresh_until);
+//!     }
+//! }
+//! # }
+//! ```
+
+use crate::logging::trace;
+use crate::lookup::{
+    client::{header::HeaderMap, StatusCode},
+    LookupProvider,
+};
+use crate::response::LookupResponse;
+use std::time::{Duration, SystemTime};
+
+/// Minimum TTL in seconds of a cached response, whatever its freshness
+pub const MIN_TTL: u64 = 60;
+
+/// Status and freshness of the response received by a request, unknown if the provider didn't
+/// receive an HTTP response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Observed {
+    /// HTTP status of the response
+    pub status: Option<u16>,
+    /// Time the response stops being fresh
+    pub fresh_until: Option<SystemTime>,
+}
+
+#[cfg(not(feature = "blocking"))]
+tokio::task_local! {
+    /// Response received by the current request
+    static OBSERVED: std::cell::Cell<Observed>;
+}
+
+#[cfg(feature = "blocking")]
+thread_local! {
+    /// Response received by the current request, `None` outside of a request
+    static OBSERVED: std::cell::Cell<Option<Observed>> = const { std::cell::Cell::new(None) };
+}
+
+/// Runs a request, returning its output and the status and freshness of the response it received.
+#[cfg(not(feature = "blocking"))]
+pub(crate) async fn scope<F: std::future::Future>(request: F) -> (F::Output, Observed) {
+    OBSERVED
+        .scope(std::cell::Cell::new(Observed::default()), async {
+            let output = request.await;
+            (output, OBSERVED.with(|observed| observed.get()))
+        })
+        .await
+}
+
+/// Runs a request, returning its output and the status and freshness of the response it received.
+#[cfg(feature = "blocking")]
+pub(crate) fn scope<T>(request: impl FnOnce() -> T) -> (T, Observed) {
+    let outer = OBSERVED.with(|observed| observed.replace(Some(Observed::default())));
+    let output = request();
+    let observed = OBSERVED.with(|observed| observed.replace(outer));
+    (output, observed.unwrap_or_default())
+}
+
+/// Records the status and the freshness declared by the response headers of the provider for the
+/// current request.
+pub(crate) fn observe(provider: &LookupProvider, status: StatusCode, headers: &HeaderMap) {
+    let fresh_until = freshness(headers, SystemTime::now());
+    if let Some(time) = fresh_until {
+        trace!("{} response fresh until {:?}", provider, time);
+    }
+    record(Observed {
+        status: Some(status.as_u16()),
+        fresh_until,
+    });
+}
+
+#[cfg(not(feature = "blocking"))]
+fn record(response: Observed) {
+    _ = OBSERVED.try_with(|observed| observed.set(response));
+}
+
+#[cfg(feature = "blocking")]
+fn record(response: Observed) {
+    OBSERVED.with(|observed| {
+        if observed.get().is_some() {
+            observed.set(Some(response));
+        }
+    });
+}
+
+/// Returns the TTL bounded by the freshness of the response, but not below [`MIN_TTL`].
+///
+/// A TTL of `None` is replaced by the remaining freshness.
+pub(crate) fn bound_ttl(response: &LookupResponse, ttl: Option<u64>) -> Option<u64> {
+    let Some(fresh_until) = response.fresh_until else {
+        return ttl;
+    };
+    let remaining = fresh_until
+        .duration_since(SystemTime::now())
+        .unwrap_or_default()
+        .as_secs()
+        .max(MIN_TTL);
+    Some(ttl.map_or(remaining, |ttl| ttl.min(remaining)))
+}
+
+/// Returns the time the response stops being fresh, from its `Cache-Control` or `Expires` header.
+///
+/// `max-age` takes precedence over `Expires`, and is reduced by the `Age` of the response.
+fn freshness(headers: &HeaderMap, now: SystemTime) -> Option<SystemTime> {
+    let header = |name: &str| headers.get(name)?.to_str().ok();
+    let max_age = header("cache-control").and_then(|cache_control| {
+        cache_control.split(',').find_map(|directive| {
+            let (name, value) = directive.split_once('=')?;
+            if !name.trim().eq_ignore_ascii_case("max-age") {
+                return None;
+            }
+            value.trim().trim_matches('"').parse::<u64>().ok()
+        })
+    });
+    if let Some(max_age) = max_age {
+        let age = header("age")
+            .and_then(|age| age.trim().parse::<u64>().ok())
+            .unwrap_or(0);
+        return Some(now + Duration::from_secs(max_age.saturating_sub(age)));
+    }
+    // invalid dates, like "0", mean the response is already stale
+    header("expires").map(|expires| httpdate::parse_http_date(expires.trim()).unwrap_or(now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(headers: &[(&'static str, &'static str)]) -> HeaderMap {
+        headers
+            .iter()
+            .map(|(name, value)| (name.parse().unwrap(), value.parse().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_freshness() {
+        let now = SystemTime::now();
+        let max_age = headers(&[("Cache-Control", "public, max-age=300")]);
+        assert_eq!(
+            freshness(&max_age, now),
+            Some(now + Duration::from_secs(300))
+        );
+        let aged = headers(&[("Cache-Control", "max-age=300"), ("Age", "100")]);
+        assert_eq!(freshness(&aged, now), Some(now + Duration::from_secs(200)));
+        let both = headers(&[
+            ("Cache-Control", "max-age=60"),
+            ("Expires", "Wed, 21 Oct 2015 07:28:00 GMT"),
+        ]);
+        assert_eq!(
+            freshness(&both, now),
+            Some(now + Duration::from_secs(60)),
+            "max-age should take precedence"
+        );
+
+        let expires = headers(&[("Expires", "Wed, 21 Oct 2015 07:28:00 GMT")]);
+        assert_eq!(
+            freshness(&expires, now),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_445_412_480))
+        );
+        assert_eq!(freshness(&headers(&[("Expires", "0")]), now), Some(now));
+        let no_cache = headers(&[("Cache-Control", "no-cache")]);
+        assert_eq!(freshness(&no_cache, now), None);
+    }
+
+    #[test]
+    fn test_bound_ttl() {
+        let mut response = LookupResponse::new(
+            "1.1.1.1".parse().unwrap(),
+            LookupProvider::Mock("fresh".to_string()),
+        );
+        assert_eq!(bound_ttl(&response, Some(600)), Some(600));
+        assert_eq!(bound_ttl(&response, None), None);
+
+        response.fresh_until = Some(SystemTime::now() + Duration::from_secs(300));
+        let bounded = bound_ttl(&response, Some(600)).unwrap();
+        assert!((299..=300).contains(&bounded), "TTL not bounded");
+        assert_eq!(bound_ttl(&response, Some(30)), Some(30));
+        assert!(bound_ttl(&response, None).is_some());
+
+        response.fresh_until = Some(SystemTime::now());
+        assert_eq!(
+            bound_ttl(&response, Some(600)),
+            Some(MIN_TTL),
+            "Stale response not cached for the minimum TTL"
+        );
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_scope() {
+        let provider = LookupProvider::Mock("fresh".to_string());
+        let max_age = headers(&[("Cache-Control", "max-age=300")]);
+        let ((), observed) = scope(async { observe(&provider, StatusCode::OK, &max_age) }).await;
+        assert!(observed.fresh_until.is_some(), "Freshness not returned");
+        assert_eq!(observed.status, Some(200), "Status not returned");
+        let ((), observed) = scope(async {}).await;
+        assert_eq!(
+            observed,
+            Observed::default(),
+            "Response leaked between requests"
+        );
+        // outside of a request, nothing is recorded
+        observe(&provider, StatusCode::OK, &max_age);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_scope() {
+        let provider = LookupProvider::Mock("fresh".to_string());
+        let max_age = headers(&[("Cache-Control", "max-age=300")]);
+        let ((), observed) = scope(|| observe(&provider, StatusCode::OK, &max_age));
+        assert!(observed.fresh_until.is_some(), "Freshness not returned");
+        assert_eq!(observed.status, Some(200), "Status not returned");
+        let ((), observed) = scope(|| {});
+        assert_eq!(
+            observed,
+            Observed::default(),
+            "Response leaked between requests"
+        );
+        // outside of a request, nothing is recorded
+        observe(&provider, StatusCode::OK, &max_age);
+        assert_eq!(OBSERVED.with(|observed| observed.get()), None);
+    }
+}
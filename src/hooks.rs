@@ -0,0 +1,103 @@
+//! # 🪝 Response hooks
+//!
+//! This module provides global hooks post-processing the responses of the lookup providers, so
+//! applications can normalize, redact or augment the results in one place, for example to hide the
+//! city or fix known wrong geolocation data of their network.
+//!
+//! The hooks run on every response parsed by a `LookupService`, before the services' own
+//! [`on_response`](crate::lookup::LookupService::on_response) hooks. The fallback, race and cached
+//! lookups only see the post-processed responses, so the cache stores them too.
+//!
+//! ## Example
+//! ```rust
+//! use public_ip_address::hooks;
+//!
+//! hooks::add_response_hook(|response| {
+//!     if response.city.is_some() {
+//!         response.city = Some("REDACTED".to_string());
+//!     }
+//! });
+//! ```
+
+use crate::response::LookupResponse;
+use std::sync::{Arc, RwLock};
+
+/// Hook called with every parsed response
+pub(crate) type ResponseHook = Arc<dyn Fn(&mut LookupResponse) + Send + Sync>;
+
+static RESPONSE_HOOKS: RwLock<Vec<ResponseHook>> = RwLock::new(Vec::new());
+
+/// Adds a global response hook, called after the hooks added before it.
+///
+/// The hooks are called synchronously from the lookup, so they should return quickly.
+pub fn add_response_hook<F>(hook: F)
+where
+    F: Fn(&mut LookupResponse) + Send + Sync + 'static,
+{
+    RESPONSE_HOOKS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Arc::new(hook));
+}
+
+/// Removes all the global response hooks.
+pub fn clear_response_hooks() {
+    RESPONSE_HOOKS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+}
+
+/// Runs the global response hooks on the response.
+pub(crate) fn process_response(response: &mut LookupResponse) {
+    // clone the hooks so they're not called while holding the lock
+    let hooks = RESPONSE_HOOKS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    for hook in hooks {
+        hook(response);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup::LookupProvider;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_response_hooks() {
+        // other tests may parse responses concurrently, only change this one
+        add_response_hook(|response| {
+            if response.ip.to_string() == "192.0.2.1" {
+                response.city = Some("REDACTED".to_string());
+            }
+        });
+        add_response_hook(|response| {
+            if response.city.as_deref() == Some("REDACTED") {
+                response.country = Some("Hidden".to_string());
+            }
+        });
+        let mut response = LookupResponse::new(
+            "192.0.2.1".parse().unwrap(),
+            LookupProvider::Mock("hooks".to_string()),
+        );
+        process_response(&mut response);
+        clear_response_hooks();
+        assert_eq!(response.city.as_deref(), Some("REDACTED"));
+        assert_eq!(
+            response.country.as_deref(),
+            Some("Hidden"),
+            "Hooks should run in order"
+        );
+
+        let mut response = LookupResponse::new(
+            "192.0.2.1".parse().unwrap(),
+            LookupProvider::Mock("hooks".to_string()),
+        );
+        process_response(&mut response);
+        assert_eq!(response.city, None, "Hooks not cleared");
+    }
+}
@@ -8,7 +8,8 @@
 //! The library provides an asynchronous and blocking interfaces to make it easy to integrate with other `async` codebase.
 //!
 //! The library also includes caching functionality to improve performance for repeated lookups and minimaze rate-limiting.
-//! The cache file can be encrypted when enabled through the `encryption` feature flag for additional privacy.
+//! The cache file can be encrypted when enabled through the `encryption` feature flag for additional privacy,
+//! with a key derived from the machine ID or set with `cache::set_encryption_key`.
 //!
 //! ## Usage
 //! ```toml
@@ -31,23 +32,167 @@
 //! - Unified interface for multiple IP lookup providers
 //! - Caching of lookup results to improve performance
 //! - Customizable cache expiration time
+//! - `public_ip()` returning only the address, from fast providers without geolocation
+//! - IP-only lookups skipping the geolocation providers, sharing the least data with third parties
+//! - IPv4-only or IPv6-only lookups on dual-stack hosts
+//! - Lookup options grouping the cache lifetime, request timeout and retries, IP version and provider strategy
+//! - Lookups bound to a local address or network interface
+//! - Custom `reqwest` clients for proxies, TLS settings and timeouts
+//! - Custom `User-Agent` and request headers
+//! - Request hooks to sign, trace or log the outbound requests
+//! - Response hooks to normalize, redact or augment the results
+//! - Response builder for the providers implemented outside of this crate
+//! - Typed ISO 3166-1 country codes, with their alpha-3 and numeric codes and names (`iso3166` feature)
+//! - Missing country codes filled from the country name, and opt-in ISO 3166-1 country names (`iso3166` feature)
+//! - ISO 3166-2 region codes, from the provider codes or the US state and Canadian province names
+//! - Time zone and local time at the location of the IP address (`chrono` feature)
+//! - CSV output of the responses for spreadsheets and log enrichment (`csv` feature)
+//! - YAML and TOML serialization of the responses (`yaml` and `toml` features)
+//! - Responses flattened to key/value maps for templates and environment variables
+//! - Responses rendered as aligned, boxed tables for terminals (`table` feature)
+//! - Colored terminal output of the responses, disabled outside terminals and with `NO_COLOR`
+//! - Private and bogon addresses echoed by the providers flagged, or rejected to try the next provider
+//! - Raw provider responses kept for auditing and unmodeled fields, when enabled
+//! - Connections pooled across providers and lookups
+//! - Lightweight `ureq` HTTP backend for blocking programs, without tokio (`ureq` feature)
+//! - TLS with `rustls` by default, or the platform library (`native-tls` feature), one of them is required
+//! - Custom DNS resolution, with DNS-over-HTTPS or DNS-over-TLS (`hickory-dns` feature, `reqwest` backend only)
+//! - Lookups of the Tor exit address through an embedded Tor client (`tor` feature, async only)
+//! - `log` records of the provider selection, failed requests and cache recovery (`log` feature, enabled by default)
+//! - `tracing` spans and events for the lookups, fallbacks and cache hits (`tracing` feature)
+//! - Request, failure, latency and cache metrics through the `metrics` facade (`metrics` feature)
+//! - Pluggable cache storage backends, with a sled database keeping the lookup history (`sled` feature)
+//! - SQLite cache backend with a queryable history of the lookups (`sqlite` feature)
+//! - Redis cache backend sharing the cache and the provider rate limits between hosts (`redis` feature)
+//! - Cache compressed with zstd (`compression` feature)
+//! - Deduplication of concurrent cached lookups
+//! - Cache lifetime bounded by the `Cache-Control` and `Expires` headers of the providers
+//! - Background cache refresh with instant access to the latest response
+//! - Public address change monitor, delivering the changes to callbacks, channels or streams
+//! - Address changes published to an MQTT broker, for Home Assistant and the like (`mqtt` feature)
+//! - Desktop notifications of the address changes (`notification` feature)
+//! - Cloudflare, DuckDNS and dyndns2 (No-IP, Dynu...) records updated when the public address changes, with custom updaters through the `DdnsUpdater` trait
+//! - Verification of the DNS records of a hostname against the public addresses
+//! - Daemon mode with systemd readiness, watchdog and reload support (`daemon` feature, Unix only)
+//! - Stale-while-revalidate cached lookups, answering instantly from an expired cache while it's refreshed
+//! - Pluggable provider selection strategies and weighted provider chains
+//! - Serde-loadable lookup configuration, with the ordered providers and their keys, to embed in application config files
+//! - Adaptive provider ordering learning from recorded success rate and latency
+//! - Configurable concurrency limits for fan-out lookups
+//! - Bulk target lookups with bounded parallelism
+//! - Cooperative cancellation of fallback and race lookups
+//! - Throttled providers skipped until their rate limit resets, remembered across runs
+//! - Global error observer hook for provider failure telemetry
+//! - Provider benchmarking with latency percentiles
+//! - Per-provider `provider-<name>` features to compile only the providers in use
+//! - Region and network quality estimation with latency beacons (`beacons` feature)
 //!
 //! For more details, please refer to the API documentation.
 
 #![warn(missing_docs)]
 
-use log::{debug, trace, warn};
+#[cfg(not(any(feature = "reqwest", feature = "ureq")))]
+compile_error!("an HTTP backend is required, enable the `reqwest` or the `ureq` feature");
+
+#[cfg(not(any(feature = "rustls-tls", feature = "native-tls")))]
+compile_error!(
+    "a TLS backend is required for the HTTPS providers, enable the `rustls-tls` or the `native-tls` feature"
+);
+
+use crate::logging::{debug, trace, warn};
 use std::net::IpAddr;
 
-use cache::ResponseCache;
+use cache::{RefreshPolicy, ResponseCache};
+use concurrency::ConcurrencyLimit;
 use error::{Error, Result};
-use lookup::{error::LookupError, LookupProvider, LookupService, Parameters};
+use lookup::{
+    error::{ErrorKind, LookupError},
+    IpVersion, LookupProvider, LookupService, Parameters,
+};
+use options::LookupOptions;
+use refresher::Refresher;
 use response::LookupResponse;
+use singleflight::SingleFlight;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        LazyLock, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use strategy::{Adaptive, SelectionStrategy};
+
+/// Token to cancel lookups, re-exported from `tokio-util`
+#[cfg(not(feature = "blocking"))]
+pub use tokio_util::sync::CancellationToken;
+
+#[cfg(feature = "blocking")]
+pub use cancellation::CancellationToken;
 
+pub mod backend;
+pub mod background;
+#[cfg(feature = "beacons")]
+pub mod beacon;
+pub mod benchmark;
 pub mod cache;
+#[cfg(feature = "blocking")]
+mod cancellation;
+pub mod chain;
+pub mod concurrency;
+pub mod config;
+#[cfg(all(unix, feature = "daemon"))]
+pub mod daemon;
+pub mod ddns;
 pub mod error;
+pub mod freshness;
+pub mod hooks;
+mod logging;
 pub mod lookup;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod monitor;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+#[cfg(feature = "notification")]
+pub mod notification;
+pub mod options;
+pub mod quota;
+pub mod refresher;
 pub mod response;
+mod singleflight;
+pub mod strategy;
+pub mod telemetry;
+
+/// Cache miss shared by the concurrent callers making the same lookup
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CachedLookupKey {
+    target: Option<IpAddr>,
+    cache_file: Option<String>,
+    /// Restriction of the lookup, see [`LookupOptions::restriction`]
+    restriction: Option<String>,
+    providers: Vec<String>,
+    ttl: Option<u64>,
+    keep_raw: bool,
+}
+
+/// Cache misses in flight, keyed by lookup
+static CACHED_LOOKUPS: LazyLock<SingleFlight<CachedLookupKey, LookupResponse>> =
+    LazyLock::new(SingleFlight::new);
+
+/// Set while a stale cached response is revalidated in the background
+static REVALIDATING: AtomicBool = AtomicBool::new(false);
+
+/// Clears `REVALIDATING` once the background refresh is over, even if it panicked
+struct Revalidating;
+
+impl Drop for Revalidating {
+    fn drop(&mut self) {
+        REVALIDATING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Serializes the updates of the cache made after a lookup, from its reload to its save
+static CACHE_UPDATES: Mutex<()> = Mutex::new(());
 
 /// Performs a lookup using a predefined list of `LookupProvider`s and caches the result.
 ///
@@ -83,18 +228,92 @@ pub mod response;
 /// * A `Result` containing either a successful `LookupResponse` or an `Error` if the lookup or caching failed.
 #[maybe_async::maybe_async]
 pub async fn perform_lookup(target: Option<IpAddr>) -> Result<LookupResponse> {
-    perform_cached_lookup_with(
-        vec![
-            (LookupProvider::IpInfo, None),
-            (LookupProvider::IpWhoIs, None),
-            (LookupProvider::MyIp, None),
-            (LookupProvider::FreeIpApi, None),
-        ],
-        target,
-        Some(5),
-        false,
-    )
-    .await
+    perform_cached_lookup_with(default_providers(), target, Some(5), false).await
+}
+
+/// Providers of [`perform_lookup`], in the order they are tried
+const DEFAULT_PROVIDERS: [LookupProvider; 4] = [
+    LookupProvider::IpInfo,
+    LookupProvider::IpWhoIs,
+    LookupProvider::MyIp,
+    LookupProvider::FreeIpApi,
+];
+
+/// Returns the providers of [`perform_lookup`], without parameters
+pub(crate) fn default_providers() -> Vec<(LookupProvider, Option<Parameters>)> {
+    DEFAULT_PROVIDERS
+        .into_iter()
+        .map(|provider| (provider, None))
+        .collect()
+}
+
+/// Returns the fast providers returning only the address, reachable with the IP version
+///
+/// Used by [`public_ip`] in the IP-only mode and to verify a cached response.
+fn ip_only_providers(ip_version: IpVersion) -> Vec<(LookupProvider, Option<Parameters>)> {
+    LookupProvider::all()
+        .into_iter()
+        .filter(|provider| {
+            let capabilities = provider.capabilities();
+            capabilities.ip_only && (ip_version != IpVersion::V6 || capabilities.ipv6)
+        })
+        .map(|provider| (provider, None))
+        .collect()
+}
+
+/// Returns the public IP address of the host.
+///
+/// This function looks up the address with a built-in list of fast providers returning only the
+/// address, without the geolocation information. Use [`perform_lookup`] for the full response.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::error::Error;
+/// # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+/// # #[maybe_async::maybe_async]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// match public_ip_address::public_ip().await {
+///     Ok(ip) => println!("Public IP address: {}", ip),
+///     Err(e) => {
+///         // Handle error
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[maybe_async::maybe_async]
+pub async fn public_ip() -> Result<IpAddr> {
+    public_ip_with_version(IpVersion::Any).await
+}
+
+/// Returns the public IPv4 address of the host, see [`public_ip`].
+#[maybe_async::maybe_async]
+pub async fn public_ip_v4() -> Result<IpAddr> {
+    public_ip_with_version(IpVersion::V4).await
+}
+
+/// Returns the public IPv6 address of the host, see [`public_ip`].
+///
+/// Fails when the host has no IPv6 connectivity.
+#[maybe_async::maybe_async]
+pub async fn public_ip_v6() -> Result<IpAddr> {
+    public_ip_with_version(IpVersion::V6).await
+}
+
+#[maybe_async::maybe_async]
+async fn public_ip_with_version(ip_version: IpVersion) -> Result<IpAddr> {
+    let providers = ip_only_providers(ip_version);
+    let options = LookupOptions::new().ip_version(ip_version).ip_only(true);
+    let response = perform_lookup_with_options(providers, None, &options).await?;
+    if !ip_version.matches(&response.ip) {
+        return Err(LookupError::GenericError(format!(
+            "Not an {} address: {}",
+            ip_version, response.ip
+        ))
+        .into());
+    }
+    Ok(response.ip)
 }
 
 /// Performs a lookup using a list of providers until a successful response is received.
@@ -103,8 +322,8 @@ pub async fn perform_lookup(target: Option<IpAddr>) -> Result<LookupResponse> {
 /// until a successful `LookupResponse` is received. If a provider fails to return a successful response,
 /// the error is stored and the next provider is tried.
 ///
-/// If all providers fail to return a successful response, a `LookupError` is returned containing a list
-/// of all the errors received.
+/// If all providers fail to return a successful response, a `LookupError::AllProvidersFailed` is returned
+/// with the error of every provider, in the order they were tried.
 ///
 /// # Arguments
 ///
@@ -146,68 +365,206 @@ pub async fn perform_lookup_with(
     providers: Vec<(LookupProvider, Option<Parameters>)>,
     target: Option<IpAddr>,
 ) -> Result<LookupResponse> {
-    let mut errors = Vec::new();
-    if providers.is_empty() {
-        return Err(Error::LookupError(LookupError::GenericError(
-            "No providers given".to_string(),
-        )));
-    }
-
-    for (provider, param) in providers {
-        debug!("Performing lookup with provider {}", &provider);
-        let response = LookupService::new(provider, param).lookup(target).await;
-        if let Ok(response) = response {
-            trace!("Successful response from provider");
-            return Ok(response);
-        }
-        warn!("Provider failed to perform lookup");
-        errors.push(response.unwrap_err());
-    }
+    perform_lookup_with_options(providers, target, &LookupOptions::new()).await
+}
 
-    // if we reach here no responses were found
-    warn!("No responses from providers");
-    Err(Error::LookupError(LookupError::GenericError(format!(
-        "No responses from providers: {:?}",
-        errors
-    ))))
+/// Performs a lookup using a list of providers, tried in the order picked by a `SelectionStrategy`.
+///
+/// See [`perform_lookup_with_options`] with [`LookupOptions::strategy`].
+#[maybe_async::maybe_async]
+#[deprecated(note = "use `perform_lookup_with_options` with `LookupOptions::strategy`")]
+pub async fn perform_lookup_with_strategy(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    strategy: &dyn SelectionStrategy,
+) -> Result<LookupResponse> {
+    lookup_with_options(providers, target, &LookupOptions::new(), strategy).await
 }
 
-/// Performs a lookup with a list of specific service providers and caches the result.
+/// Performs a lookup using a list of providers over the given IP version until a successful response is received.
 ///
-/// This function performs a lookup using the provided list of `LookupProvider`s. The result of the lookup
-/// is cached locally.
-/// If subsequent requests are made, the cached result is returned as long as the previous
-/// request was made within `cache_expire_time` seconds.
+/// See [`perform_lookup_with_options`] with [`LookupOptions::ip_version`].
+#[maybe_async::maybe_async]
+#[deprecated(note = "use `perform_lookup_with_options` with `LookupOptions::ip_version`")]
+pub async fn perform_lookup_with_ip_version(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    ip_version: IpVersion,
+) -> Result<LookupResponse> {
+    let options = LookupOptions::new().ip_version(ip_version);
+    perform_lookup_with_options(providers, target, &options).await
+}
+
+/// Performs a lookup using a list of providers and `LookupOptions` until a successful response is received.
 ///
-/// If `cache_expire_time` is `None`, then the cache never expires.
+/// This function behaves like [`perform_lookup_with`], with the providers tried in the order of the
+/// strategy of the options, over their IP version, with their request timeout and retries. The
+/// lookup stops with a `LookupError::Cancelled` error once the cancellation token of the options is
+/// cancelled, aborting the pending request. With the `blocking` feature enabled, the token is
+/// checked before each provider is tried. The cache options are ignored, see
+/// [`perform_cached_lookup_with_options`] for cached lookups.
 ///
-/// If `cache_expire_time` is `0`, then the cache is expired immediately after the request.
+/// With [`LookupOptions::ip_only`], only the providers returning nothing but the address are used,
+/// see [`Capabilities::ip_only`](lookup::capabilities::Capabilities::ip_only). The other providers
+/// fail with a `LookupError::IpOnlyNotSupported` error without any request, minimizing the latency,
+/// the bandwidth and the data shared with third parties.
 ///
 /// # Arguments
 ///
 /// * `providers` - A vector of `LookupProvider`s and their `Parameters` to use for the lookup.
 /// * `target` - Target address for the lookup, `None` will look up the current public address.
-/// * `cache_expire_time` - An `Option` containing the number of seconds before the cache expires. If `None`,
-///   the cache never expires. If `0`, the cache expires immediately after the request.
-/// * `flush` - A `bool` indicating whether to force the cache to flush and make a new request.
+/// * `options` - The `LookupOptions` of the lookup.
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::{lookup::{IpVersion, LookupProvider}, options::LookupOptions};
+/// use std::time::Duration;
+///
+/// # use std::error::Error;
+/// # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+/// # #[maybe_async::maybe_async]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// let providers = vec![
+///     // List of providers to use for the lookup
+///     // (LookupProvider::Ipify, None)
+/// ];
+/// let options = LookupOptions::new()
+///     .ip_version(IpVersion::V4)
+///     .timeout(Duration::from_secs(2));
+///
+/// match public_ip_address::perform_lookup_with_options(providers, None, &options).await {
+///     Ok(response) => {
+///         // Handle successful response
+///     }
+///     Err(e) => {
+///         // Handle error
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// * A `Result` containing either a successful `LookupResponse` or a `LookupError` containing a list of all errors received.
+#[maybe_async::maybe_async]
+pub async fn perform_lookup_with_options(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    options: &LookupOptions,
+) -> Result<LookupResponse> {
+    lookup_with_options(providers, target, options, options.strategy.as_ref()).await
+}
+
+/// Tries the providers in the order picked by the strategy, with the services set up and the
+/// lookup cancelled by the options.
+#[maybe_async::maybe_async]
+async fn lookup_with_options(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    options: &LookupOptions,
+    strategy: &dyn SelectionStrategy,
+) -> Result<LookupResponse> {
+    lookup_in_order(
+        providers,
+        target,
+        strategy,
+        &mut |_, _, _| {},
+        options.cancellation.as_ref(),
+        &|service| options.configure(service),
+    )
+    .await
+}
+
+/// Performs a lookup of the public address only, using a list of providers until a successful response is received.
+///
+/// See [`perform_lookup_with_options`] with [`LookupOptions::ip_only`].
+#[maybe_async::maybe_async]
+#[deprecated(note = "use `perform_lookup_with_options` with `LookupOptions::ip_only`")]
+pub async fn perform_ip_only_lookup_with(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+) -> Result<LookupResponse> {
+    let options = LookupOptions::new().ip_only(true);
+    perform_lookup_with_options(providers, None, &options).await
+}
+
+/// Performs a lookup through Tor using a list of providers until a successful response is received.
+///
+/// This function behaves like [`perform_lookup_with`], but every request is routed through the
+/// embedded Tor client, so the address of the Tor exit relay is looked up and the real address of
+/// the host is never revealed to the providers. The Tor client is bootstrapped on the first call
+/// and shared by later lookups. The response is not cached.
+///
+/// This function is only available with the `tor` feature, and without the `blocking` feature.
+///
+/// # Arguments
+///
+/// * `providers` - A vector of `LookupProvider`s and their `Parameters` to use for the lookup.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use public_ip_address::lookup::LookupProvider;
+///
+/// # use std::error::Error;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// let providers = vec![(LookupProvider::IpWhoIs, None), (LookupProvider::IpInfo, None)];
+/// let response = public_ip_address::perform_tor_lookup(providers).await?;
+/// println!("Tor exit address: {}", response.ip);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// * A `Result` containing either a successful `LookupResponse` or a `LookupError` containing a list of all errors received.
+#[cfg(all(feature = "tor", not(feature = "blocking")))]
+pub async fn perform_tor_lookup(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+) -> Result<LookupResponse> {
+    let tor_client = lookup::tor::bootstrap().await?;
+    lookup_in_order(
+        providers,
+        None,
+        &strategy::Sequential,
+        &mut |_, _, _| {},
+        None,
+        &|service| {
+            service.set_tor_client(tor_client.clone());
+        },
+    )
+    .await
+}
+
+/// Performs a lookup of a target address using a list of providers until a successful response is received.
+///
+/// Only the providers supporting target lookups are tried, in the given order, the others are skipped.
+/// If none of the providers supports target lookups, a `TargetNotSupported` error is returned.
+///
+/// # Arguments
+///
+/// * `ip` - Target address for the lookup.
+/// * `providers` - A vector of `LookupProvider`s and their `Parameters` to use for the lookup.
 ///
 /// # Example
 ///
 /// ```rust
 /// use public_ip_address::lookup::LookupProvider;
+/// use std::net::IpAddr;
 ///
 /// # use std::error::Error;
 /// # #[cfg_attr(not(feature = "blocking"), tokio::main)]
 /// # #[maybe_async::maybe_async]
 /// # async fn main() -> Result<(), Box<dyn Error>> {
+/// let ip: IpAddr = "8.8.8.8".parse()?;
 /// let providers = vec![
 ///     // List of providers to use for the lookup
 ///     // (LookupProvider::IpWhoIs, Some(Parameters::new(apikey)))
 /// ];
-/// let expire_time = Some(60); // Cache expires after 60 seconds
-/// let flush = false; // Do not force cache flush
 ///
-/// match public_ip_address::perform_cached_lookup_with(providers, None, expire_time, flush).await {
+/// match public_ip_address::perform_lookup_for(ip, providers).await {
 ///     Ok(response) => {
 ///         // Handle successful response
 ///     }
@@ -221,50 +578,1189 @@ pub async fn perform_lookup_with(
 ///
 /// # Returns
 ///
-/// * A `Result` containing either a successful `LookupResponse` or an `Error` if the lookup or caching failed.
+/// * A `Result` containing either a successful `LookupResponse` or a `LookupError` containing a list of all errors received.
 #[maybe_async::maybe_async]
-pub async fn perform_cached_lookup_with(
+pub async fn perform_lookup_for(
+    ip: IpAddr,
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+) -> Result<LookupResponse> {
+    let providers: Vec<_> = providers
+        .into_iter()
+        .filter(|(provider, _)| {
+            let supported = provider.capabilities().target_lookup;
+            if !supported {
+                debug!("Skipping provider {}, no target lookup", provider);
+            }
+            supported
+        })
+        .collect();
+    if providers.is_empty() {
+        return Err(Error::LookupError(LookupError::TargetNotSupported));
+    }
+    perform_lookup_with(providers, Some(ip)).await
+}
+
+/// Performs a lookup using a list of providers, ordered by their recorded performance.
+///
+/// This function tries the providers in the order picked by the [`Adaptive`] strategy, from the
+/// statistics persisted in the cache file. The success and latency of every attempt is recorded,
+/// so the providers that are most reliable and fastest for this host are tried first over time.
+/// This makes long-running applications robust to providers that are down or slow.
+///
+/// If all providers fail to return a successful response, a `LookupError` is returned containing a list
+/// of all the errors received.
+///
+/// # Arguments
+///
+/// * `providers` - A vector of `LookupProvider`s and their `Parameters` to use for the lookup.
+/// * `target` - Target address for the lookup, `None` will look up the current public address.
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::lookup::LookupProvider;
+///
+/// # use std::error::Error;
+/// # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+/// # #[maybe_async::maybe_async]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+///
+/// let providers = vec![
+///     (LookupProvider::IpInfo, None),
+///     (LookupProvider::IpWhoIs, None),
+///     (LookupProvider::FreeIpApi, None),
+/// ];
+///
+/// match public_ip_address::perform_adaptive_lookup_with(providers, None).await {
+///     Ok(response) => {
+///         // Handle successful response
+///     }
+///     Err(e) => {
+///         // Handle error
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// * A `Result` containing either a successful `LookupResponse` or a `LookupError` containing a list of all errors received.
+#[maybe_async::maybe_async]
+pub async fn perform_adaptive_lookup_with(
     providers: Vec<(LookupProvider, Option<Parameters>)>,
     target: Option<IpAddr>,
-    ttl: Option<u64>,
-    flush: bool,
 ) -> Result<LookupResponse> {
-    let cached_file = ResponseCache::load(None);
-    // load the cache if it exists
-    let mut cache = match cached_file {
-        Ok(cache) => {
-            // check if we are looking for a specific target
-            if let Some(target) = target {
-                if !cache.target_is_expired(&target) && !flush {
-                    if let Some(target) = cache.lookup_address.get(&target) {
-                        trace!("Using cached value");
-                        return Ok(target.response.to_owned());
-                    }
-                }
-            } else if !cache.current_is_expired() && !flush {
-                if let Some(current) = cache.current_address {
-                    trace!("Using cached value");
-                    return Ok(current.response);
+    let strategy = Adaptive::from_cache(&ResponseCache::load(None).unwrap_or_default());
+    let mut attempts = Vec::new();
+    let result = lookup_in_order(
+        providers,
+        target,
+        &strategy,
+        &mut |provider, success, latency| attempts.push((provider.clone(), success, latency)),
+        None,
+        &|_| {},
+    )
+    .await;
+    record_provider_stats(&attempts);
+    result
+}
+
+/// Records the outcome and latency of the attempts in the provider statistics of the cache.
+///
+/// The cache is reloaded under a lock before the attempts are recorded, so the statistics
+/// recorded by the concurrent lookups of the process are kept.
+fn record_provider_stats(attempts: &[(LookupProvider, bool, Duration)]) {
+    let _guard = CACHE_UPDATES.lock().unwrap_or_else(|e| e.into_inner());
+    let mut cache = ResponseCache::load(None).unwrap_or_default();
+    for (provider, success, latency) in attempts {
+        cache.record_provider(provider, *success, *latency);
+    }
+    quota::store(&mut cache);
+    if let Err(e) = cache.save() {
+        warn!("Failed to save provider statistics: {}", e);
+    }
+}
+
+/// Tries the providers in the order picked by the strategy until a successful response is received.
+///
+/// Every service is set up by `configure` before its lookup. The outcome and latency of every
+/// attempt is reported to `record`. Stops with a `LookupError::Cancelled` error once the `cancel`
+/// token is cancelled.
+#[maybe_async::maybe_async]
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(?target)))]
+async fn lookup_in_order(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    strategy: &dyn SelectionStrategy,
+    record: &mut (dyn FnMut(&LookupProvider, bool, Duration) + Send),
+    cancel: Option<&CancellationToken>,
+    configure: &(dyn Fn(&mut LookupService) + Sync),
+) -> Result<LookupResponse> {
+    let mut errors = Vec::new();
+    if providers.is_empty() {
+        return Err(Error::LookupError(LookupError::GenericError(
+            "No providers given".to_string(),
+        )));
+    }
+
+    let order = strategy.order(
+        &providers
+            .iter()
+            .map(|(provider, _)| provider.clone())
+            .collect::<Vec<_>>(),
+    );
+    debug!(
+        "Providers ordered by the strategy: {:?}",
+        order
+            .iter()
+            .filter_map(|&i| providers.get(i).map(|(provider, _)| provider))
+            .collect::<Vec<_>>()
+    );
+    let mut providers: Vec<_> = providers.into_iter().map(Some).collect();
+    let mut order = order.into_iter().peekable();
+    while let Some(i) = order.next() {
+        let Some((provider, param)) = providers.get_mut(i).and_then(Option::take) else {
+            continue;
+        };
+        debug!("Performing lookup with provider {}", &provider);
+        let start = Instant::now();
+        let mut service = LookupService::new(provider.clone(), param);
+        configure(&mut service);
+        let mut response = lookup_cancellable(&service, target, cancel).await;
+        for attempt in 1..=service.get_retries() {
+            match &response {
+                Err(e) if e.is_retryable() && e.kind() != ErrorKind::RateLimited => {
+                    debug!("Retrying provider {} ({}): {}", provider, attempt, e);
+                    response = lookup_cancellable(&service, target, cancel).await;
                 }
+                _ => break,
             }
-            cache
         }
-        // no cache file, create a new cache
-        Err(_) => ResponseCache::default(),
-    };
-
-    trace!("Performing new lookup");
-    // no cache or it's too old, make a new request.
-    match perform_lookup_with(providers, target).await {
-        Ok(result) => {
-            if let Some(target) = target {
-                cache.update_target(target, &result, ttl);
-            } else {
-                cache.update_current(&result, ttl);
+        if let Err(LookupError::Cancelled) = response {
+            warn!("Lookup cancelled");
+            return Err(Error::LookupError(LookupError::Cancelled));
+        }
+        // the round-trip time of the provider, without the time spent creating the service
+        let elapsed = response
+            .as_ref()
+            .ok()
+            .and_then(|response| response.latency)
+            .unwrap_or_else(|| start.elapsed());
+        record(&provider, response.is_ok(), elapsed);
+        match response {
+            Ok(response) => {
+                trace!(
+                    "Successful response from provider {} in {:?}",
+                    provider,
+                    elapsed
+                );
+                return Ok(response);
+            }
+            Err(e) if order.peek().is_some() => {
+                warn!(
+                    "Provider {} failed after {:?}, trying the next provider: {}",
+                    provider, elapsed, e
+                );
+                errors.push((provider, e));
+            }
+            Err(e) => {
+                warn!(
+                    "Last provider {} failed after {:?}: {}",
+                    provider, elapsed, e
+                );
+                errors.push((provider, e));
             }
-            cache.save()?;
-            Ok(result)
         }
-        Err(e) => Err(e),
     }
+
+    // if we reach here no responses were found
+    warn!("No responses from providers");
+    Err(Error::LookupError(LookupError::AllProvidersFailed(errors)))
+}
+
+/// Performs a lookup using a list of providers until a successful response is received or the lookup is cancelled.
+///
+/// See [`perform_lookup_with_options`] with [`LookupOptions::cancellation`].
+#[maybe_async::maybe_async]
+#[deprecated(note = "use `perform_lookup_with_options` with `LookupOptions::cancellation`")]
+pub async fn perform_lookup_with_cancellation(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    cancel: &CancellationToken,
+) -> Result<LookupResponse> {
+    let options = LookupOptions::new().cancellation(cancel.clone());
+    perform_lookup_with_options(providers, target, &options).await
+}
+
+/// Performs a lookup with the service, aborting it once the token is cancelled.
+#[maybe_async::async_impl]
+async fn lookup_cancellable(
+    service: &LookupService,
+    target: Option<IpAddr>,
+    cancel: Option<&CancellationToken>,
+) -> lookup::error::Result<LookupResponse> {
+    match cancel {
+        Some(cancel) => tokio::select! {
+            biased;
+            _ = cancel.cancelled() => Err(LookupError::Cancelled),
+            response = service.lookup(target) => response,
+        },
+        None => service.lookup(target).await,
+    }
+}
+
+/// Performs a lookup with the service, unless the token is already cancelled.
+#[maybe_async::sync_impl]
+fn lookup_cancellable(
+    service: &LookupService,
+    target: Option<IpAddr>,
+    cancel: Option<&CancellationToken>,
+) -> lookup::error::Result<LookupResponse> {
+    if cancel.is_some_and(CancellationToken::is_cancelled) {
+        return Err(LookupError::Cancelled);
+    }
+    service.lookup(target)
+}
+
+/// Performs a lookup racing a list of providers concurrently and returns the first successful response.
+///
+/// This function sends a request to every provider in the list at the same time. As soon as one of them
+/// returns a successful `LookupResponse`, it is returned and the remaining requests are cancelled.
+/// This trades a few extra requests for lower latency compared to the sequential fallback of
+/// [`perform_lookup_with`].
+///
+/// With the `blocking` feature enabled, each provider is queried on its own thread and the remaining
+/// requests are left to finish in the background.
+///
+/// If all providers fail to return a successful response, a `LookupError` is returned containing a list
+/// of all the errors received.
+///
+/// # Arguments
+///
+/// * `providers` - A vector of `LookupProvider`s and their `Parameters` to race against each other.
+/// * `target` - Target address for the lookup, `None` will look up the current public address.
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::lookup::LookupProvider;
+///
+/// # use std::error::Error;
+/// # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+/// # #[maybe_async::maybe_async]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// let providers = vec![
+///     // List of providers to race
+///     // (LookupProvider::IpWhoIs, None),
+///     // (LookupProvider::IpInfo, None),
+/// ];
+///
+/// match public_ip_address::perform_race_lookup_with(providers, None).await {
+///     Ok(response) => {
+///         // Handle the fastest successful response
+///     }
+///     Err(e) => {
+///         // Handle error
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// * A `Result` containing either the first successful `LookupResponse` or a `LookupError` containing a list of all errors received.
+#[maybe_async::async_impl]
+pub async fn perform_race_lookup_with(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+) -> Result<LookupResponse> {
+    perform_race_lookup_with_options(providers, target, &LookupOptions::new()).await
+}
+
+/// Performs a lookup racing a list of providers concurrently with `LookupOptions`, and returns the first successful response.
+///
+/// This function behaves like [`perform_race_lookup_with`], with the services set up by the options,
+/// like their IP version and request timeout. Each request waits for the concurrency limit of the
+/// options to allow it before being sent, and once a successful response is received, the requests
+/// still waiting for the limit are never sent. The race stops as soon as the cancellation token of
+/// the options is cancelled, aborting all the pending requests. The strategy, the retries and the
+/// cache options are ignored.
+///
+/// # Arguments
+///
+/// * `providers` - A vector of `LookupProvider`s and their `Parameters` to race against each other.
+/// * `target` - Target address for the lookup, `None` will look up the current public address.
+/// * `options` - The `LookupOptions` of the lookup.
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::{concurrency::ConcurrencyLimit, lookup::LookupProvider, options::LookupOptions};
+///
+/// # use std::error::Error;
+/// # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+/// # #[maybe_async::maybe_async]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// let providers = vec![
+///     // List of providers to race
+///     // (LookupProvider::IpWhoIs, None),
+///     // (LookupProvider::IpInfo, None),
+/// ];
+/// let options = LookupOptions::new().concurrency_limit(ConcurrencyLimit::new(Some(2), None));
+///
+/// match public_ip_address::perform_race_lookup_with_options(providers, None, &options).await {
+///     Ok(response) => {
+///         // Handle the fastest successful response
+///     }
+///     Err(e) => {
+///         // Handle error or cancellation
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// * A `Result` containing either the first successful `LookupResponse`, a `LookupError::Cancelled` error,
+///   or a `LookupError` containing a list of all errors received.
+#[maybe_async::async_impl]
+pub async fn perform_race_lookup_with_options(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    options: &LookupOptions,
+) -> Result<LookupResponse> {
+    race_lookup(providers, target, options).await
+}
+
+/// Performs a lookup racing a list of providers concurrently, with a bound on the requests in flight.
+///
+/// See [`perform_race_lookup_with_options`] with [`LookupOptions::concurrency_limit`].
+#[maybe_async::maybe_async]
+#[deprecated(
+    note = "use `perform_race_lookup_with_options` with `LookupOptions::concurrency_limit`"
+)]
+pub async fn perform_race_lookup_with_limit(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    limit: &ConcurrencyLimit,
+) -> Result<LookupResponse> {
+    let options = LookupOptions::new().concurrency_limit(limit.clone());
+    perform_race_lookup_with_options(providers, target, &options).await
+}
+
+/// Performs a lookup racing a list of providers concurrently, until a response is received or the lookup is cancelled.
+///
+/// See [`perform_race_lookup_with_options`] with [`LookupOptions::cancellation`].
+#[maybe_async::maybe_async]
+#[deprecated(note = "use `perform_race_lookup_with_options` with `LookupOptions::cancellation`")]
+pub async fn perform_race_lookup_with_cancellation(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    cancel: &CancellationToken,
+) -> Result<LookupResponse> {
+    let options = LookupOptions::new().cancellation(cancel.clone());
+    perform_race_lookup_with_options(providers, target, &options).await
+}
+
+/// Races the providers set up by the options, bounded by their limit, until a response is received
+/// or the lookup is cancelled.
+#[maybe_async::async_impl]
+async fn race_lookup(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    options: &LookupOptions,
+) -> Result<LookupResponse> {
+    use futures::stream::{FuturesUnordered, StreamExt};
+
+    if providers.is_empty() {
+        return Err(Error::LookupError(LookupError::GenericError(
+            "No providers given".to_string(),
+        )));
+    }
+
+    let services = configured_services(providers, options);
+    let limit = &options.concurrency_limit;
+    let mut lookups = services
+        .iter()
+        .map(|service| async move {
+            let _permit = limit.acquire(&service.get_provider_type()).await;
+            debug!(
+                "Racing lookup with provider {}",
+                service.get_provider_type()
+            );
+            (service.get_provider_type(), service.lookup(target).await)
+        })
+        .collect::<FuturesUnordered<_>>();
+
+    let race = async {
+        let mut errors = Vec::new();
+        while let Some((provider, response)) = lookups.next().await {
+            match response {
+                Ok(response) => {
+                    trace!("Successful response from provider {}", response.provider);
+                    // dropping the remaining futures cancels the pending requests
+                    return Ok(response);
+                }
+                Err(e) => {
+                    warn!("Provider failed to perform lookup");
+                    errors.push((provider, e));
+                }
+            }
+        }
+
+        warn!("No responses from providers");
+        Err(Error::LookupError(LookupError::AllProvidersFailed(errors)))
+    };
+
+    match &options.cancellation {
+        Some(cancel) => tokio::select! {
+            biased;
+            _ = cancel.cancelled() => {
+                warn!("Lookup cancelled");
+                Err(Error::LookupError(LookupError::Cancelled))
+            }
+            response = race => response,
+        },
+        None => race.await,
+    }
+}
+
+/// Performs a lookup racing a list of providers concurrently and returns the first successful response.
+///
+/// This function sends a request to every provider in the list at the same time, each on its own thread.
+/// As soon as one of them returns a successful `LookupResponse`, it is returned and the remaining
+/// requests are left to finish in the background.
+///
+/// If all providers fail to return a successful response, a `LookupError` is returned containing a list
+/// of all the errors received.
+///
+/// # Arguments
+///
+/// * `providers` - A vector of `LookupProvider`s and their `Parameters` to race against each other.
+/// * `target` - Target address for the lookup, `None` will look up the current public address.
+///
+/// # Returns
+///
+/// * A `Result` containing either the first successful `LookupResponse` or a `LookupError` containing a list of all errors received.
+#[maybe_async::sync_impl]
+pub fn perform_race_lookup_with(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+) -> Result<LookupResponse> {
+    perform_race_lookup_with_options(providers, target, &LookupOptions::new())
+}
+
+/// Performs a lookup racing a list of providers concurrently with `LookupOptions`, and returns the first successful response.
+///
+/// This function behaves like [`perform_race_lookup_with`], with the services set up by the options,
+/// like their IP version and request timeout. Each thread waits for the concurrency limit of the
+/// options to allow its request before sending it. The race stops waiting for the responses as soon
+/// as the cancellation token of the options is cancelled, the pending requests are left to finish
+/// in the background. The strategy, the retries and the cache options are ignored.
+///
+/// # Arguments
+///
+/// * `providers` - A vector of `LookupProvider`s and their `Parameters` to race against each other.
+/// * `target` - Target address for the lookup, `None` will look up the current public address.
+/// * `options` - The `LookupOptions` of the lookup.
+///
+/// # Returns
+///
+/// * A `Result` containing either the first successful `LookupResponse`, a `LookupError::Cancelled` error,
+///   or a `LookupError` containing a list of all errors received.
+#[maybe_async::sync_impl]
+pub fn perform_race_lookup_with_options(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    options: &LookupOptions,
+) -> Result<LookupResponse> {
+    race_lookup(providers, target, options)
+}
+
+/// Races the providers set up by the options, bounded by their limit, until a response is received
+/// or the lookup is cancelled.
+#[maybe_async::sync_impl]
+fn race_lookup(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    options: &LookupOptions,
+) -> Result<LookupResponse> {
+    use std::{
+        sync::mpsc::{self, RecvTimeoutError},
+        thread,
+    };
+
+    if providers.is_empty() {
+        return Err(Error::LookupError(LookupError::GenericError(
+            "No providers given".to_string(),
+        )));
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    for service in configured_services(providers, options) {
+        let sender = sender.clone();
+        let limit = options.concurrency_limit.clone();
+        thread::spawn(move || {
+            let provider = service.get_provider_type();
+            let _permit = limit.acquire(&provider);
+            debug!("Racing lookup with provider {}", &provider);
+            let response = service.lookup(target);
+            // the receiver is gone once a winner was found
+            _ = sender.send((provider, response));
+        });
+    }
+    drop(sender);
+
+    let mut errors = Vec::new();
+    loop {
+        if options
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            warn!("Lookup cancelled");
+            return Err(Error::LookupError(LookupError::Cancelled));
+        }
+        // wake up regularly to check the cancellation token
+        match receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok((_, Ok(response))) => {
+                trace!("Successful response from provider {}", response.provider);
+                return Ok(response);
+            }
+            Ok((provider, Err(e))) => {
+                warn!("Provider failed to perform lookup");
+                errors.push((provider, e));
+            }
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    warn!("No responses from providers");
+    Err(Error::LookupError(LookupError::AllProvidersFailed(errors)))
+}
+
+/// Performs a lookup with several providers and merges their responses into one enriched response.
+///
+/// Single providers often miss some of the fields, like the ASN or the time zone. This function
+/// queries every provider in the list and merges the responses field-by-field: the order of the list
+/// defines the priority, and the first provider returning a value for a field wins.
+///
+/// The first successful response defines the IP address. Responses from other providers reporting
+/// a different IP address are ignored, so data of different hosts is never mixed.
+///
+/// If all providers fail to return a successful response, a `LookupError` is returned containing a list
+/// of all the errors received.
+///
+/// # Arguments
+///
+/// * `providers` - A vector of `LookupProvider`s and their `Parameters`, in order of priority.
+/// * `target` - Target address for the lookup, `None` will look up the current public address.
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::lookup::LookupProvider;
+///
+/// # use std::error::Error;
+/// # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+/// # #[maybe_async::maybe_async]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// let providers = vec![
+///     // List of providers to merge, in order of priority
+///     // (LookupProvider::IpWhoIs, None),
+///     // (LookupProvider::IpInfo, None),
+/// ];
+///
+/// match public_ip_address::perform_enriched_lookup_with(providers, None).await {
+///     Ok(response) => {
+///         // Handle the merged response
+///     }
+///     Err(e) => {
+///         // Handle error
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// * A `Result` containing either the merged `LookupResponse` or a `LookupError` containing a list of all errors received.
+#[maybe_async::maybe_async]
+pub async fn perform_enriched_lookup_with(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+) -> Result<LookupResponse> {
+    perform_enriched_lookup_with_options(providers, target, &LookupOptions::new()).await
+}
+
+/// Performs a lookup with several providers and merges their responses, with a bound on the requests in flight.
+///
+/// See [`perform_enriched_lookup_with_options`] with [`LookupOptions::concurrency_limit`].
+#[maybe_async::maybe_async]
+#[deprecated(
+    note = "use `perform_enriched_lookup_with_options` with `LookupOptions::concurrency_limit`"
+)]
+pub async fn perform_enriched_lookup_with_limit(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    limit: &ConcurrencyLimit,
+) -> Result<LookupResponse> {
+    let options = LookupOptions::new().concurrency_limit(limit.clone());
+    perform_enriched_lookup_with_options(providers, target, &options).await
+}
+
+/// Performs a lookup with several providers and `LookupOptions`, and merges their responses into one enriched response.
+///
+/// This function behaves like [`perform_enriched_lookup_with`], with the services set up by the
+/// options, like their IP version and request timeout. Each request waits for the concurrency limit
+/// of the options to allow it before being sent. The strategy, the retries, the cancellation token
+/// and the cache options are ignored.
+///
+/// # Arguments
+///
+/// * `providers` - A vector of `LookupProvider`s and their `Parameters`, in order of priority.
+/// * `target` - Target address for the lookup, `None` will look up the current public address.
+/// * `options` - The `LookupOptions` of the lookup.
+///
+/// # Returns
+///
+/// * A `Result` containing either the merged `LookupResponse` or a `LookupError` containing a list of all errors received.
+#[maybe_async::maybe_async]
+pub async fn perform_enriched_lookup_with_options(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    options: &LookupOptions,
+) -> Result<LookupResponse> {
+    if providers.is_empty() {
+        return Err(Error::LookupError(LookupError::GenericError(
+            "No providers given".to_string(),
+        )));
+    }
+
+    let services = configured_services(providers, options);
+
+    let mut errors = Vec::new();
+    let mut enriched: Option<LookupResponse> = None;
+    for (service, response) in services
+        .iter()
+        .zip(lookup_all(&services, target, &options.concurrency_limit).await)
+    {
+        match response {
+            Ok(response) => match enriched.as_mut() {
+                Some(enriched) if enriched.ip == response.ip => {
+                    trace!("Enriching response with provider {}", response.provider);
+                    enriched.enrich_from(&response);
+                }
+                Some(_) => warn!("Provider returned a different IP address, ignoring"),
+                None => enriched = Some(response),
+            },
+            Err(e) => {
+                warn!("Provider failed to perform lookup");
+                errors.push((service.get_provider_type(), e));
+            }
+        }
+    }
+
+    match enriched {
+        Some(enriched) => Ok(enriched),
+        None => {
+            warn!("No responses from providers");
+            Err(Error::LookupError(LookupError::AllProvidersFailed(errors)))
+        }
+    }
+}
+
+/// Creates the services of the providers, set up by the options
+fn configured_services(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    options: &LookupOptions,
+) -> Vec<LookupService> {
+    providers
+        .into_iter()
+        .map(|(provider, param)| {
+            let mut service = LookupService::new(provider, param);
+            options.configure(&mut service);
+            service
+        })
+        .collect()
+}
+
+/// Performs a lookup with every service concurrently, returning the results in the same order.
+#[maybe_async::async_impl]
+async fn lookup_all(
+    services: &[LookupService],
+    target: Option<IpAddr>,
+    limit: &ConcurrencyLimit,
+) -> Vec<lookup::error::Result<LookupResponse>> {
+    futures::future::join_all(services.iter().map(|service| async move {
+        let _permit = limit.acquire(&service.get_provider_type()).await;
+        service.lookup(target).await
+    }))
+    .await
+}
+
+/// Performs a lookup with every service concurrently, returning the results in the same order.
+#[maybe_async::sync_impl]
+fn lookup_all(
+    services: &[LookupService],
+    target: Option<IpAddr>,
+    limit: &ConcurrencyLimit,
+) -> Vec<lookup::error::Result<LookupResponse>> {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = services
+            .iter()
+            .map(|service| {
+                scope.spawn(move || {
+                    let _permit = limit.acquire(&service.get_provider_type());
+                    service.lookup(target)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle.join().unwrap_or_else(|_| {
+                    Err(LookupError::GenericError(
+                        "Lookup thread panicked".to_string(),
+                    ))
+                })
+            })
+            .collect()
+    })
+}
+
+/// Performs a lookup with a list of specific service providers and caches the result.
+///
+/// This function performs a lookup using the provided list of `LookupProvider`s. The result of the lookup
+/// is cached locally.
+/// If subsequent requests are made, the cached result is returned as long as the previous
+/// request was made within `cache_expire_time` seconds.
+///
+/// If `cache_expire_time` is `None`, then the cache never expires.
+///
+/// If `cache_expire_time` is `0`, then the cache is expired immediately after the request.
+///
+/// Concurrent calls looking up the same target while the cache is expired share a single request,
+/// so only one of them reaches the providers and the others receive a copy of its result.
+///
+/// If the provider declares the freshness of its response with the `Cache-Control: max-age` or
+/// `Expires` headers, the response is not cached for longer, see [`freshness`].
+///
+/// # Arguments
+///
+/// * `providers` - A vector of `LookupProvider`s and their `Parameters` to use for the lookup.
+/// * `target` - Target address for the lookup, `None` will look up the current public address.
+/// * `cache_expire_time` - An `Option` containing the number of seconds before the cache expires. If `None`,
+///   the cache never expires. If `0`, the cache expires immediately after the request.
+/// * `force_refresh` - A `bool` indicating whether to bypass the cached response and make a new request, for example
+///   after the IP address changed when reconnecting a VPN.
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::lookup::LookupProvider;
+///
+/// # use std::error::Error;
+/// # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+/// # #[maybe_async::maybe_async]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// let providers = vec![
+///     // List of providers to use for the lookup
+///     // (LookupProvider::IpWhoIs, Some(Parameters::new(apikey)))
+/// ];
+/// let expire_time = Some(60); // Cache expires after 60 seconds
+/// let force_refresh = false; // Use the cached response if it's still valid
+///
+/// match public_ip_address::perform_cached_lookup_with(providers, None, expire_time, force_refresh).await {
+///     Ok(response) => {
+///         // Handle successful response
+///     }
+///     Err(e) => {
+///         // Handle error
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// * A `Result` containing either a successful `LookupResponse` or an `Error` if the lookup or caching failed.
+#[maybe_async::maybe_async]
+pub async fn perform_cached_lookup_with(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    ttl: Option<u64>,
+    force_refresh: bool,
+) -> Result<LookupResponse> {
+    let options = LookupOptions::new().ttl(ttl).force_refresh(force_refresh);
+    perform_cached_lookup_with_options(providers, target, &options).await
+}
+
+/// Performs a lookup with a list of providers and `LookupOptions`, and caches the result.
+///
+/// This function behaves like [`perform_cached_lookup_with`], with the cache time-to-live, the
+/// cache file and the forced refresh taken from the options. The lookup itself is made with
+/// [`perform_lookup_with_options`]. Lookups of the current address restricted to an IP version or
+/// to the address only are cached apart from the unrestricted ones.
+///
+/// With a [`RefreshPolicy`] in the options, the cached geolocation data of the unrestricted current
+/// address is reused until `geo_ttl` expires. In between full refreshes, once `ip_ttl` expires, a
+/// lightweight IP-only request is made to one of the bare IP providers, see
+/// [`Capabilities::ip_only`](lookup::capabilities::Capabilities::ip_only). If the IP address did
+/// not change, the cached response is returned and marked as verified. Otherwise, or if the
+/// geolocation data expired, a full lookup is performed using the given providers. This keeps the
+/// IP address up to date while minimizing the number of requests to rate-limited geolocation
+/// providers.
+///
+/// # Arguments
+///
+/// * `providers` - A vector of `LookupProvider`s and their `Parameters` to use for the lookup.
+/// * `target` - Target address for the lookup, `None` will look up the current public address.
+/// * `options` - The `LookupOptions` of the lookup and the cache.
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::{lookup::LookupProvider, options::LookupOptions};
+///
+/// # use std::error::Error;
+/// # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+/// # #[maybe_async::maybe_async]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// let providers = vec![
+///     // List of providers to use for the lookup
+///     // (LookupProvider::IpWhoIs, Some(Parameters::new(apikey)))
+/// ];
+/// let options = LookupOptions::new().ttl(Some(60)).cache_file("lookup-options.cache");
+///
+/// match public_ip_address::perform_cached_lookup_with_options(providers, None, &options).await {
+///     Ok(response) => {
+///         // Handle successful response
+///     }
+///     Err(e) => {
+///         // Handle error
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// * A `Result` containing either a successful `LookupResponse` or an `Error` if the lookup or caching failed.
+#[maybe_async::maybe_async]
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(?target, force_refresh = options.force_refresh)))]
+pub async fn perform_cached_lookup_with_options(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    target: Option<IpAddr>,
+    options: &LookupOptions,
+) -> Result<LookupResponse> {
+    let force_refresh = options.force_refresh;
+    // lookups restricted to an IP version or to the address only are cached apart
+    let restriction = options.restriction();
+    if let (Some(policy), None, None) = (options.refresh_policy, target, &restriction) {
+        return cached_lookup_with_policy(providers, policy, options).await;
+    }
+    // check the cache if it exists, a new cache is created after the lookup otherwise
+    if let Ok(mut cache) = ResponseCache::load(options.cache_file.clone()) {
+        // check if we are looking for a specific target
+        if let Some(target) = target {
+            if !cache.target_is_expired(&target) && !force_refresh {
+                if let Some(response) = cache.target_response(&target) {
+                    #[cfg(feature = "metrics")]
+                    metrics::record_cache_lookup(true);
+                    debug!("Cache hit, using cached value");
+                    // keep the most used targets when the cache is full, a failed save
+                    // of the new use doesn't fail the hit
+                    let last_used = cache.lookup_address.get(&target).map(|l| l.last_used());
+                    if last_used.is_some_and(|used| {
+                        used.elapsed().unwrap_or_default() > cache::TOUCH_INTERVAL
+                    }) {
+                        cache.touch_target(&target);
+                        if let Err(e) = cache.save() {
+                            warn!("Failed to save cache: {}", e);
+                        }
+                    }
+                    return Ok(response);
+                }
+            }
+        } else if let Some(restriction) = &restriction {
+            if !cache.restricted_is_expired(restriction) && !force_refresh {
+                if let Some(response) = cache.restricted_response(restriction) {
+                    #[cfg(feature = "metrics")]
+                    metrics::record_cache_lookup(true);
+                    debug!("Cache hit, using cached value");
+                    return Ok(response);
+                }
+            }
+        } else if !cache.current_is_expired() && !force_refresh {
+            if let Some(current) = cache.current_address {
+                #[cfg(feature = "metrics")]
+                metrics::record_cache_lookup(true);
+                debug!("Cache hit, using cached value");
+                return Ok(current.response);
+            }
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    metrics::record_cache_lookup(false);
+    debug!("Cache miss, performing new lookup");
+    // no cache or it's too old, make a new request.
+    // concurrent callers making the same lookup share a single request, unless forcing a refresh.
+    let key = CachedLookupKey {
+        target,
+        cache_file: options.cache_file.clone(),
+        restriction: restriction.clone(),
+        providers: providers
+            .iter()
+            .map(|(provider, _)| provider.to_string())
+            .collect(),
+        ttl: options.ttl,
+        keep_raw: options.keep_raw,
+    };
+    let options = options.clone();
+    let lookup = move || async move {
+        let result = perform_lookup_with_options(providers, target, &options).await?;
+        let ttl = freshness::bound_ttl(&result, options.ttl);
+        // reload the cache, so the entries saved by the concurrent lookups are kept
+        let _guard = CACHE_UPDATES.lock().unwrap_or_else(|e| e.into_inner());
+        let mut cache = ResponseCache::load(options.cache_file.clone())
+            .unwrap_or_else(|_| ResponseCache::new(options.cache_file.clone()));
+        if let Some(target) = target {
+            cache.update_target(target, &result, ttl);
+        } else if let Some(restriction) = &restriction {
+            cache.update_restricted(restriction, &result, ttl);
+        } else {
+            cache.update_current(&result, ttl);
+        }
+        quota::store(&mut cache);
+        cache.save()?;
+        Ok(result)
+    };
+    if force_refresh {
+        lookup().await
+    } else {
+        CACHED_LOOKUPS.run(key, lookup).await
+    }
+}
+
+/// Performs a lookup of the current public address following a `RefreshPolicy` and caches the result.
+///
+/// See [`perform_cached_lookup_with_options`] with [`LookupOptions::refresh_policy`].
+#[maybe_async::maybe_async]
+#[deprecated(
+    note = "use `perform_cached_lookup_with_options` with `LookupOptions::refresh_policy`"
+)]
+pub async fn perform_cached_lookup_with_policy(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    policy: RefreshPolicy,
+    force_refresh: bool,
+) -> Result<LookupResponse> {
+    let options = LookupOptions::new()
+        .refresh_policy(policy)
+        .force_refresh(force_refresh);
+    perform_cached_lookup_with_options(providers, None, &options).await
+}
+
+/// Looks up the current public address following the `RefreshPolicy`, verifying the cached address
+/// with an IP-only lookup before the geolocation data expires.
+#[maybe_async::maybe_async]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(force_refresh = options.force_refresh))
+)]
+async fn cached_lookup_with_policy(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    policy: RefreshPolicy,
+    options: &LookupOptions,
+) -> Result<LookupResponse> {
+    // load the cache if it exists, otherwise create a new cache
+    let mut cache = ResponseCache::load(options.cache_file.clone())
+        .unwrap_or_else(|_| ResponseCache::new(options.cache_file.clone()));
+
+    if !options.force_refresh && !cache.current_is_expired() {
+        if let Some(cached) = cache.current_response() {
+            if !cache.current_ip_is_expired(policy.ip_ttl) {
+                #[cfg(feature = "metrics")]
+                metrics::record_cache_lookup(true);
+                debug!("Cache hit, using cached value");
+                return Ok(cached);
+            }
+
+            trace!("Verifying cached IP address");
+            match perform_lookup_with(ip_only_providers(IpVersion::Any), None).await {
+                Ok(check) if check.ip == cached.ip => {
+                    trace!("IP address unchanged, using cached value");
+                    cache.verify_current();
+                    quota::store(&mut cache);
+                    cache.save()?;
+                    return Ok(cached);
+                }
+                Ok(_) => debug!("IP address changed, performing new lookup"),
+                Err(_) => warn!("Failed to verify IP address, performing new lookup"),
+            }
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    metrics::record_cache_lookup(false);
+    debug!("Cache miss, performing new lookup");
+    let result = perform_lookup_with_options(providers, None, options).await?;
+    let ttl = freshness::bound_ttl(&result, policy.geo_ttl);
+    cache.update_current(&result, ttl);
+    quota::store(&mut cache);
+    cache.save()?;
+    Ok(result)
+}
+
+/// Performs a cached lookup of the current public address, returning an expired response instantly while it's refreshed.
+///
+/// This function behaves like [`perform_cached_lookup_with`], except that a cached response expired for less
+/// than `max_stale` seconds is returned immediately, and a new lookup is started in the background to refresh
+/// the cache. Once the response is expired for `max_stale` seconds or more, the lookup waits for a new response.
+/// This gives interactive tools instant answers while keeping the cache eventually fresh.
+///
+/// Without the `blocking` feature the refresh runs as a `tokio` task and the function must be called from
+/// within a `tokio` runtime, otherwise the refresh runs on its own thread.
+///
+/// # Arguments
+///
+/// * `providers` - A vector of `LookupProvider`s and their `Parameters` to use for the lookup.
+/// * `ttl` - An `Option<u64>` representing the time-to-live (TTL) in seconds for the cached response. If `None`, the cache never expires.
+/// * `max_stale` - A `u64` representing for how many seconds an expired response is still returned.
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::lookup::LookupProvider;
+///
+/// # use std::error::Error;
+/// # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+/// # #[maybe_async::maybe_async]
+/// # async fn main() -> Result<(), Box<dyn Error>> {
+/// let providers = vec![
+///     // List of providers to use for the lookup
+///     // (LookupProvider::IpWhoIs, None)
+/// ];
+/// // fresh for 5 minutes, then still answered instantly for an hour
+/// match public_ip_address::perform_stale_while_revalidate_lookup_with(providers, Some(300), 3600).await {
+///     Ok(response) => {
+///         // Handle successful response
+///     }
+///     Err(e) => {
+///         // Handle error
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// * A `Result` containing either a successful `LookupResponse` or an `Error` if the lookup or caching failed.
+#[maybe_async::maybe_async]
+pub async fn perform_stale_while_revalidate_lookup_with(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    ttl: Option<u64>,
+    max_stale: u64,
+) -> Result<LookupResponse> {
+    if let Ok(cache) = ResponseCache::load(None) {
+        if let Some(current) = &cache.current_address {
+            if !current.is_expired() {
+                #[cfg(feature = "metrics")]
+                metrics::record_cache_lookup(true);
+                debug!("Cache hit, using cached value");
+                return Ok(current.response.to_owned());
+            }
+            if !current.is_stale(max_stale) {
+                #[cfg(feature = "metrics")]
+                metrics::record_cache_lookup(true);
+                debug!("Stale cache hit, using cached value while revalidating");
+                revalidate(providers, ttl);
+                return Ok(current.response.to_owned());
+            }
+        }
+    }
+    perform_cached_lookup_with(providers, None, ttl, true).await
+}
+
+/// Refreshes the cached lookup in the background, unless a refresh is already running
+#[maybe_async::async_impl]
+fn revalidate(providers: Vec<(LookupProvider, Option<Parameters>)>, ttl: Option<u64>) {
+    if REVALIDATING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let guard = Revalidating;
+    tokio::spawn(async move {
+        let _guard = guard;
+        if let Err(e) = perform_cached_lookup_with(providers, None, ttl, true).await {
+            warn!("Failed to revalidate cached lookup: {:?}", e);
+        }
+    });
+}
+
+/// Refreshes the cached lookup in the background, unless a refresh is already running
+#[maybe_async::sync_impl]
+fn revalidate(providers: Vec<(LookupProvider, Option<Parameters>)>, ttl: Option<u64>) {
+    if REVALIDATING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let guard = Revalidating;
+    std::thread::spawn(move || {
+        let _guard = guard;
+        if let Err(e) = perform_cached_lookup_with(providers, None, ttl, true) {
+            warn!("Failed to revalidate cached lookup: {:?}", e);
+        }
+    });
+}
+
+/// Keeps the cached lookup of the current public address warm in the background.
+///
+/// The lookup is refreshed every `ttl` using the given providers and cached for the same duration.
+/// The returned `Refresher` gives instant access to the latest response through its `current()`
+/// method, which never waits on the network.
+///
+/// Without the `blocking` feature the refresher runs as a `tokio` task and must be spawned from
+/// within a `tokio` runtime, otherwise it runs on its own thread.
+///
+/// # Arguments
+///
+/// * `providers` - A vector of `LookupProvider`s and their `Parameters` to use for the lookup.
+/// * `ttl` - Interval between refreshes, also used as the cache expiration time.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use public_ip_address::lookup::LookupProvider;
+/// use std::time::Duration;
+///
+/// # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+/// # #[maybe_async::maybe_async]
+/// # async fn main() {
+/// let providers = vec![
+///     // List of providers to use for the lookup
+///     // (LookupProvider::IpWhoIs, Some(Parameters::new(apikey)))
+/// ];
+/// let refresher = public_ip_address::spawn_refresher(providers, Duration::from_secs(300));
+/// if let Some(response) = refresher.current() {
+///     // Handle the latest response
+/// }
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// * A `Refresher` controlling the background refresh.
+pub fn spawn_refresher(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    ttl: Duration,
+) -> Refresher {
+    Refresher::spawn(providers, ttl)
 }
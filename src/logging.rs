@@ -0,0 +1,32 @@
+//! Diagnostics macros, from `tracing` with the `tracing` feature and from `log` with the `log` feature
+//!
+//! With both features, the `tracing` events are also emitted as `log` records while no tracing
+//! subscriber is set, so `log` users keep seeing them. With neither, the macros compile to nothing
+//! but still type-check their arguments.
+
+#[cfg(all(feature = "log", not(feature = "tracing")))]
+#[allow(unused_imports)]
+pub(crate) use log::{debug, trace, warn};
+#[cfg(feature = "tracing")]
+#[allow(unused_imports)]
+pub(crate) use tracing::{debug, trace, warn};
+
+#[cfg(not(any(feature = "log", feature = "tracing")))]
+mod disabled {
+    /// Discards a diagnostics event without evaluating its arguments
+    macro_rules! discard {
+        ($($arg:tt)*) => {
+            {
+                if false {
+                    let _ = ::std::format_args!($($arg)*);
+                }
+            }
+        };
+    }
+
+    #[allow(unused_imports)]
+    pub(crate) use {discard as debug, discard as trace, discard as warn};
+}
+#[cfg(not(any(feature = "log", feature = "tracing")))]
+#[allow(unused_imports)]
+pub(crate) use disabled::{debug, trace, warn};
@@ -3,7 +3,7 @@
 use super::{ProviderResponse, Result};
 use crate::{
     lookup::{LookupProvider, Provider},
-    LookupResponse,
+    response, LookupResponse,
 };
 use serde::{Deserialize, Serialize};
 use std::net::{IpAddr, Ipv4Addr};
@@ -25,6 +25,8 @@ pub struct AbstractApiResponse {
     latitude: Option<f64>,
     security: Option<Security>,
     timezone: Option<Timezone>,
+    currency: Option<Currency>,
+    flag: Option<Flag>,
     connection: Option<Connection>,
 }
 
@@ -33,6 +35,18 @@ struct Security {
     is_vpn: Option<bool>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+struct Currency {
+    currency_name: Option<String>,
+    currency_code: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Flag {
+    emoji: Option<String>,
+    svg: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct Timezone {
     name: Option<String>,
@@ -55,9 +69,15 @@ impl ProviderResponse<AbstractApiResponse> for AbstractApiResponse {
             LookupProvider::AbstractApi,
         );
         response.country = self.country;
-        response.continent = self.continent;
+        response.continent =
+            super::continent(self.continent.as_deref(), self.continent_code.as_deref());
         response.country_code = self.country_code;
+        response.is_eu = self.country_is_eu;
         response.region = self.region;
+        response.region_code = super::region_code(
+            response.country_code.as_deref(),
+            self.region_iso_code.as_deref(),
+        );
         response.postal_code = self.postal_code;
         response.city = self.city;
         response.latitude = self.latitude;
@@ -65,12 +85,26 @@ impl ProviderResponse<AbstractApiResponse> for AbstractApiResponse {
         if let Some(timezone) = self.timezone {
             response.time_zone = timezone.name;
         }
+        if let Some(currency) = self.currency {
+            response.currency = currency.currency_code.map(|code| response::Currency {
+                code,
+                name: currency.currency_name,
+                symbol: None,
+            });
+        }
+        if let Some(flag) = self.flag {
+            response.flag_emoji = flag.emoji;
+            response.flag_url = flag.svg;
+        }
         if let Some(connection) = self.connection {
             response.asn_org = connection.organization_name;
             response.asn = connection.isp_name;
         }
         if let Some(security) = self.security {
-            response.is_proxy = security.is_vpn;
+            response.security = Some(response::Security {
+                is_vpn: security.is_vpn,
+                ..Default::default()
+            });
         }
 
         response
@@ -167,7 +201,10 @@ mod tests {
 
         let service = Box::new(AbstractApi);
         let target = "8.8.8.8".parse().ok();
-        let result = service.get_client(key, target).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), key, target)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("AbstractApi: {:#?}", result);
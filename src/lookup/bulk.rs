@@ -0,0 +1,340 @@
+//! Bulk target lookups
+//!
+//! `LookupService::lookup_many` looks up a list of addresses with a bounded number of requests
+//! in flight, for example to enrich the addresses found in a log file. The results are returned
+//! in the same order as the addresses.
+//!
+//! Providers with a batch API, like `IpInfo` when an API key is given, look up many addresses
+//! in a single request. Other providers make one request per address. Either way, the responses
+//! are checked, normalized and passed to the hooks like the responses of single lookups.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use public_ip_address::lookup::{LookupProvider, LookupService};
+//! use std::net::IpAddr;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() {
+//! let ips: Vec<IpAddr> = vec!["1.1.1.1".parse().unwrap(), "8.8.8.8".parse().unwrap()];
+//! let service = LookupService::new(LookupProvider::IpWhoIs, None);
+//! // at most four requests in flight
+//! for (ip, response) in ips.iter().zip(service.lookup_many(&ips, 4).await) {
+//!     println!("{}: {:?}", ip, response.map(|response| response.country));
+//! }
+//! # }
+//! ```
+
+use super::{error::LookupError, LookupService, Result};
+use crate::freshness::Observed;
+use crate::logging::{debug, warn};
+use crate::{telemetry, LookupResponse};
+use std::{net::IpAddr, sync::Arc, time::Instant};
+
+impl LookupService {
+    /// Looks up every address in `ips`, with at most `max_concurrency` requests in flight.
+    ///
+    /// The results are returned in the same order as the addresses. A `max_concurrency` of `0`
+    /// is treated as `1`.
+    ///
+    /// If the provider has a batch API and an API key is available, the addresses are looked up
+    /// in batches, otherwise one request is made per address.
+    #[maybe_async::maybe_async]
+    pub async fn lookup_many(
+        &self,
+        ips: &[IpAddr],
+        max_concurrency: usize,
+    ) -> Vec<Result<LookupResponse>> {
+        debug!(
+            "Looking up {} addresses with {}",
+            ips.len(),
+            self.get_provider_type()
+        );
+        match (self.provider.batch_size(), self.api_key()) {
+            (Some(batch_size), Some(key)) => {
+                let batches: Vec<&[IpAddr]> = ips.chunks(batch_size.max(1)).collect();
+                self.bounded(&batches, max_concurrency, |batch| {
+                    self.lookup_batch(&key, batch)
+                })
+                .await
+                .into_iter()
+                .flatten()
+                .collect()
+            }
+            _ => {
+                self.bounded(ips, max_concurrency, |ip| self.lookup(Some(ip)))
+                    .await
+            }
+        }
+    }
+
+    /// Looks up a batch of addresses in a single request.
+    ///
+    /// If the request fails, every address of the batch fails with a `BatchFailed` error sharing
+    /// the error of the request.
+    #[maybe_async::maybe_async]
+    async fn lookup_batch(&self, key: &str, targets: &[IpAddr]) -> Vec<Result<LookupResponse>> {
+        let start = Instant::now();
+        let (response, observed) = self.batch_request(key, targets).await;
+        let response = match response {
+            Ok(body) => {
+                let latency = start.elapsed();
+                match self.provider.parse_batch_reply(body.clone(), targets) {
+                    Ok(responses) => Ok((responses, latency, self.keep_raw.then_some(body))),
+                    Err(e) => Err(e.with_response(self.provider.get_type(), observed, &body)),
+                }
+            }
+            Err(e) => Err(e),
+        };
+        match response {
+            // completed like the responses of single lookups
+            Ok((responses, latency, raw)) => responses
+                .into_iter()
+                .map(|response| {
+                    response.and_then(|mut response| {
+                        response.fresh_until = observed.fresh_until;
+                        self.finish(response, latency, raw.clone())
+                    })
+                })
+                .collect(),
+            Err(e) => {
+                warn!("Batch lookup failed");
+                telemetry::report_error(&self.provider.get_type(), &e, start.elapsed());
+                let error = Arc::new(e);
+                targets
+                    .iter()
+                    .map(|_| Err(LookupError::BatchFailed(error.clone())))
+                    .collect()
+            }
+        }
+    }
+
+    /// Makes the batch request, returning the raw response body and its freshness
+    #[maybe_async::async_impl]
+    async fn batch_request(&self, key: &str, targets: &[IpAddr]) -> (Result<String>, Observed) {
+        let client = match self
+            .check(targets.first().copied())
+            .and_then(|_| self.client())
+        {
+            Ok(client) => client,
+            Err(e) => return (Err(e), Observed::default()),
+        };
+        let request = self
+            .provider
+            .make_batch_request(&client, key.to_string(), targets);
+        crate::freshness::scope(request).await
+    }
+
+    /// Makes the batch request, returning the raw response body and its freshness
+    #[maybe_async::sync_impl]
+    fn batch_request(&self, key: &str, targets: &[IpAddr]) -> (Result<String>, Observed) {
+        let client = match self
+            .check(targets.first().copied())
+            .and_then(|_| self.client())
+        {
+            Ok(client) => client,
+            Err(e) => return (Err(e), Observed::default()),
+        };
+        crate::freshness::scope(|| {
+            self.provider
+                .make_batch_request(&client, key.to_string(), targets)
+        })
+    }
+
+    /// Runs `lookup` for every item, with at most `max_concurrency` in flight, keeping the order.
+    #[maybe_async::async_impl]
+    async fn bounded<T, R, F, Fut>(&self, items: &[T], max_concurrency: usize, lookup: F) -> Vec<R>
+    where
+        T: Copy,
+        F: Fn(T) -> Fut,
+        Fut: std::future::Future<Output = R>,
+    {
+        let limit = crate::concurrency::ConcurrencyLimit::new(Some(max_concurrency), None);
+        let provider = self.get_provider_type();
+        futures::future::join_all(items.iter().map(|item| {
+            let (limit, provider, lookup) = (&limit, &provider, &lookup);
+            async move {
+                let _permit = limit.acquire(provider).await;
+                lookup(*item).await
+            }
+        }))
+        .await
+    }
+
+    /// Runs `lookup` for every item, with at most `max_concurrency` in flight, keeping the order.
+    #[maybe_async::sync_impl]
+    fn bounded<T, R, F>(&self, items: &[T], max_concurrency: usize, lookup: F) -> Vec<R>
+    where
+        T: Copy + Sync,
+        R: Send,
+        F: Fn(T) -> R + Sync,
+    {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // a fixed pool of workers, so long lists don't spawn a thread per item
+        let workers = max_concurrency.clamp(1, items.len().max(1));
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<(usize, R)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut results = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::SeqCst);
+                            let Some(item) = items.get(index) else {
+                                break results;
+                            };
+                            results.push((index, lookup(*item)));
+                        }
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|e| std::panic::resume_unwind(e))
+                })
+                .collect()
+        });
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup::{client::Client, LookupProvider, Parameters, Provider};
+
+    struct Batch {
+        fail: bool,
+    }
+
+    #[maybe_async::maybe_async]
+    impl Provider for Batch {
+        fn get_endpoint(&self, _key: &Option<String>, _target: &Option<IpAddr>) -> String {
+            String::new()
+        }
+
+        fn parse_reply(&self, _json: String) -> Result<LookupResponse> {
+            Err(LookupError::GenericError("Batches only".to_string()))
+        }
+
+        fn get_type(&self) -> LookupProvider {
+            LookupProvider::Mock("batch".to_string())
+        }
+
+        fn supports_target_lookup(&self) -> bool {
+            true
+        }
+
+        fn batch_size(&self) -> Option<usize> {
+            Some(2)
+        }
+
+        async fn make_batch_request(
+            &self,
+            _client: &Client,
+            _key: String,
+            _targets: &[IpAddr],
+        ) -> Result<String> {
+            match self.fail {
+                true => Err(LookupError::TooManyRequests(
+                    "Too many requests".to_string(),
+                )),
+                false => Ok(String::new()),
+            }
+        }
+
+        fn parse_batch_reply(
+            &self,
+            _json: String,
+            targets: &[IpAddr],
+        ) -> Result<Vec<Result<LookupResponse>>> {
+            Ok(targets
+                .iter()
+                .map(|target| {
+                    let mut response = LookupResponse::new(*target, self.get_type());
+                    response.country_code = Some("DE".to_string());
+                    Ok(response)
+                })
+                .collect())
+        }
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_lookup_many_batches() {
+        let mut service = LookupService {
+            provider: Box::new(Batch { fail: false }),
+            ..LookupService::new(
+                LookupProvider::Mock("1.1.1.1".to_string()),
+                Some(Parameters::new("key".to_string())),
+            )
+        };
+        service.on_response(|response| response.city = Some("Berlin".to_string()));
+        let ips: Vec<IpAddr> = vec!["8.8.8.8".parse().unwrap(), "10.0.0.1".parse().unwrap()];
+        let results = service.lookup_many(&ips, 2).await;
+        let responses: Vec<LookupResponse> = results.into_iter().map(Result::unwrap).collect();
+        assert_eq!(responses.len(), 2, "Missing results");
+        for response in &responses {
+            assert_eq!(response.city.as_deref(), Some("Berlin"), "Hooks not run");
+            assert!(response.flag_emoji.is_some(), "Flag not derived");
+            assert!(response.looked_up_at.is_some());
+        }
+        let bogon = responses[1].security.as_ref().and_then(|s| s.is_bogon);
+        assert_eq!(bogon, Some(true), "Non-global address not flagged");
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_lookup_many_failed_batch() {
+        let service = LookupService {
+            provider: Box::new(Batch { fail: true }),
+            ..LookupService::new(
+                LookupProvider::Mock("1.1.1.1".to_string()),
+                Some(Parameters::new("key".to_string())),
+            )
+        };
+        let ips: Vec<IpAddr> = vec!["8.8.8.8".parse().unwrap(); 3];
+        let results = service.lookup_many(&ips, 2).await;
+        assert_eq!(results.len(), 3, "Missing results");
+        for result in results {
+            let error = result.unwrap_err();
+            assert!(
+                matches!(&error, LookupError::BatchFailed(e) if matches!(**e, LookupError::TooManyRequests(_))),
+                "Error not kept: {:?}",
+                error
+            );
+            assert!(error.is_retryable());
+        }
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_lookup_many() {
+        let service = LookupService::new(LookupProvider::Mock("1.1.1.1".to_string()), None);
+        let ips: Vec<IpAddr> = (1..=10)
+            .map(|i| format!("10.0.0.{}", i).parse().unwrap())
+            .collect();
+        let results = service.lookup_many(&ips, 3).await;
+        assert_eq!(results.len(), ips.len(), "Missing results");
+        assert!(results.iter().all(|result| result.is_ok()));
+
+        let results = service.lookup_many(&ips[..1], 0).await;
+        assert_eq!(results.len(), 1, "Zero concurrency should be raised to one");
+        let results = service.lookup_many(&[], 3).await;
+        assert!(results.is_empty());
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_lookup_many_without_batches() {
+        // the mock provider has no batch API, so the key doesn't switch to batches
+        let service = LookupService::new(
+            LookupProvider::Mock("1.1.1.1".to_string()),
+            Some(Parameters::new("key".to_string())),
+        );
+        let ips: Vec<IpAddr> = vec!["8.8.8.8".parse().unwrap(); 3];
+        let results = service.lookup_many(&ips, 2).await;
+        assert!(results.iter().all(|result| result.is_ok()));
+    }
+}
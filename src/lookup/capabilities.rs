@@ -0,0 +1,130 @@
+//! Lookup provider capabilities
+//!
+//! Describes what each provider supports and returns, so orchestration code can pick
+//! providers suited to a request, like skipping keyed providers when no API key is available.
+
+use super::LookupProvider;
+use std::time::Duration;
+
+/// Documented rate limit of a provider on its free tier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RateLimit {
+    /// The rate limit is not documented
+    Unknown,
+    /// The provider has no rate limit
+    Unlimited,
+    /// The provider allows a number of requests per period
+    Limited {
+        /// Number of requests allowed in a period
+        requests: u32,
+        /// Length of the period
+        period: Duration,
+    },
+}
+
+/// Features supported by a lookup provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// The provider only answers requests with an API key
+    pub requires_key: bool,
+    /// The provider accepts an API key
+    pub supports_key: bool,
+    /// The provider can look up arbitrary target addresses
+    pub target_lookup: bool,
+    /// The provider can be reached and reports addresses over IPv6
+    pub ipv6: bool,
+    /// The provider returns the Autonomous System Number
+    pub asn: bool,
+    /// The provider returns security flags, like the proxy, VPN or Tor flag
+    pub security: bool,
+    /// The provider returns only the address, without geolocation information
+    pub ip_only: bool,
+    /// Documented rate limit on the free tier, from the provider `metadata()`
+    pub rate_limit: RateLimit,
+}
+
+impl LookupProvider {
+    /// Returns the `Capabilities` of the provider.
+    ///
+    /// # Example
+    /// ```
+    /// use public_ip_address::lookup::LookupProvider;
+    ///
+    /// let capabilities = LookupProvider::IpData.capabilities();
+    /// assert!(capabilities.requires_key);
+    /// ```
+    pub fn capabilities(&self) -> Capabilities {
+        // (requires key, supports key, target lookup, ipv6, asn, security, IP only)
+        #[rustfmt::skip]
+        let (requires_key, supports_key, target_lookup, ipv6, asn, security, ip_only) = match self {
+            LookupProvider::FreeIpApi =>       (false, true,  true,  true,  false, true,  false),
+            LookupProvider::IfConfig =>        (false, false, true,  true,  true,  false, false),
+            LookupProvider::IpInfo =>          (false, true,  true,  false, true,  false, false),
+            LookupProvider::MyIp =>            (false, false, false, true,  true,  false, false),
+            LookupProvider::IpApiCom =>        (false, false, true,  false, true,  true,  false),
+            LookupProvider::IpWhoIs =>         (false, false, true,  true,  true,  false, false),
+            LookupProvider::IpApiCo =>         (false, false, true,  true,  true,  false, false),
+            LookupProvider::IpApiIo =>         (false, true,  true,  false, false, true,  false),
+            LookupProvider::IpBase =>          (false, true,  true,  true,  true,  true,  false),
+            LookupProvider::IpLocateIo =>      (false, true,  true,  true,  true,  true,  false),
+            LookupProvider::IpLeak =>          (false, false, true,  true,  true,  false, false),
+            LookupProvider::Mullvad =>         (false, false, false, true,  false, true,  false),
+            LookupProvider::AbstractApi =>     (true,  true,  true,  true,  true,  true,  false),
+            LookupProvider::IpGeolocation =>   (true,  true,  true,  true,  true,  false, false),
+            LookupProvider::IpData =>          (true,  true,  true,  true,  true,  true,  false),
+            LookupProvider::Ip2Location =>     (false, true,  true,  true,  true,  true,  false),
+            LookupProvider::MyIpCom =>         (false, false, false, false, false, false, false),
+            LookupProvider::Ipify =>           (false, false, false, true,  false, false, true),
+            LookupProvider::GetJsonIp =>       (false, false, false, false, false, false, true),
+            LookupProvider::Mock(_) =>         (false, false, true,  true,  false, false, true),
+        };
+        Capabilities {
+            requires_key,
+            supports_key,
+            target_lookup,
+            ipv6,
+            asn,
+            security,
+            ip_only,
+            rate_limit: self.metadata().rate_limit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = LookupProvider::IpInfo.capabilities();
+        assert!(capabilities.target_lookup, "IpInfo supports target lookup");
+        assert!(capabilities.supports_key && !capabilities.requires_key);
+        assert_eq!(
+            capabilities.rate_limit,
+            RateLimit::Limited {
+                requests: 50000,
+                period: Duration::from_secs(30 * 24 * 60 * 60)
+            }
+        );
+
+        let capabilities = LookupProvider::Ipify.capabilities();
+        assert!(!capabilities.target_lookup, "Ipify has no target lookup");
+        assert_eq!(capabilities.rate_limit, RateLimit::Unlimited);
+        assert!(capabilities.ip_only, "Ipify returns only the address");
+    }
+
+    #[test]
+    fn test_target_lookup_matches_provider() {
+        for provider in LookupProvider::all() {
+            assert_eq!(
+                provider.capabilities().target_lookup,
+                provider.clone().build().supports_target_lookup(),
+                "Wrong target lookup capability for {}",
+                provider
+            );
+        }
+    }
+}
@@ -1,5 +1,218 @@
-#[cfg(not(feature = "blocking"))]
+//! HTTP client used by the lookup providers
+//!
+//! The clients are shared by all lookups, so the connections to the providers are pooled and
+//! reused across requests and providers instead of paying for a TLS handshake every time.
+//! The async clients are shared by the lookups running on the same tokio runtime, since their
+//! pooled connections are tied to the runtime that created them.
+//!
+//! The client is `reqwest`, or `ureq` with the `ureq` feature.
+
+#[cfg(all(not(feature = "blocking"), not(feature = "ureq")))]
 pub use ::reqwest::*;
 
-#[cfg(feature = "blocking")]
+#[cfg(all(feature = "blocking", not(feature = "ureq")))]
 pub use reqwest::blocking::*;
+
+#[cfg(not(feature = "ureq"))]
+pub use reqwest::{header, Error, StatusCode};
+
+#[cfg(feature = "ureq")]
+mod ureq;
+
+#[cfg(feature = "ureq")]
+pub use self::ureq::*;
+
+use super::error::LookupError;
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{LazyLock, Mutex},
+};
+
+/// Local address, interface, DNS resolver, default headers and runtime of a client
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct Binding {
+    pub(crate) local_address: Option<IpAddr>,
+    pub(crate) interface: Option<String>,
+    pub(crate) user_agent: Option<String>,
+    pub(crate) headers: Vec<(String, String)>,
+    #[cfg(all(feature = "hickory-dns", not(feature = "ureq")))]
+    pub(crate) resolver: Option<super::dns::DnsResolver>,
+    /// Runtime the async client was created on, set by [`shared`]
+    #[cfg(not(feature = "blocking"))]
+    pub(crate) runtime: Option<tokio::runtime::Id>,
+}
+
+/// Maximum number of shared clients, the pool is emptied when it's exceeded
+const MAX_CLIENTS: usize = 32;
+
+/// Shared clients, one for each binding
+static CLIENTS: LazyLock<Mutex<HashMap<Binding, Client>>> = LazyLock::new(Mutex::default);
+
+/// Returns the shared client for the binding, creating it on first use.
+///
+/// Each DNS resolver, header, local address or async runtime gets its own client, so the pool is emptied once it
+/// holds [`MAX_CLIENTS`] clients. The clients already handed out keep working.
+pub(crate) fn shared(binding: Binding) -> std::result::Result<Client, LookupError> {
+    #[cfg(not(feature = "blocking"))]
+    let binding = Binding {
+        runtime: tokio::runtime::Handle::try_current()
+            .ok()
+            .map(|handle| handle.id()),
+        ..binding
+    };
+    let mut clients = CLIENTS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(client) = clients.get(&binding) {
+        return Ok(client.clone());
+    }
+    crate::logging::trace!("Creating client for {:?}", binding);
+    let client = build(&binding)?;
+    if clients.len() >= MAX_CLIENTS {
+        crate::logging::debug!("Too many shared clients, dropping {}", clients.len());
+        clients.clear();
+    }
+    clients.insert(binding, client.clone());
+    Ok(client)
+}
+
+/// Builds a client for the binding.
+fn build(binding: &Binding) -> std::result::Result<Client, LookupError> {
+    let builder = Client::builder()
+        .local_address(binding.local_address)
+        .default_headers(header_map(&binding.headers)?);
+    let builder = match &binding.user_agent {
+        Some(user_agent) => builder.user_agent(user_agent),
+        None => builder,
+    };
+    #[cfg(all(feature = "hickory-dns", not(feature = "ureq")))]
+    let builder = match &binding.resolver {
+        Some(resolver) => builder.dns_resolver(std::sync::Arc::new(resolver.clone())),
+        None => builder,
+    };
+    #[cfg(any(
+        target_os = "android",
+        target_os = "fuchsia",
+        target_os = "illumos",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos",
+        target_os = "solaris",
+        target_os = "tvos",
+        target_os = "visionos",
+        target_os = "watchos",
+    ))]
+    let builder = match &binding.interface {
+        Some(interface) => builder.interface(interface),
+        None => builder,
+    };
+    #[cfg(not(any(
+        target_os = "android",
+        target_os = "fuchsia",
+        target_os = "illumos",
+        target_os = "ios",
+        target_os = "linux",
+        target_os = "macos",
+        target_os = "solaris",
+        target_os = "tvos",
+        target_os = "visionos",
+        target_os = "watchos",
+    )))]
+    if binding.interface.is_some() {
+        return Err(LookupError::GenericError(
+            "Binding to an interface is not supported on this platform".to_string(),
+        ));
+    }
+    Ok(builder.build()?)
+}
+
+/// Converts the headers into a header map, failing on invalid names or values.
+pub(crate) fn header_map(
+    headers: &[(String, String)],
+) -> std::result::Result<header::HeaderMap, LookupError> {
+    let mut map = header::HeaderMap::new();
+    for (name, value) in headers {
+        let name = header::HeaderName::try_from(name)
+            .map_err(|e| LookupError::GenericError(format!("Invalid header {}: {}", name, e)))?;
+        let value = header::HeaderValue::try_from(value)
+            .map_err(|e| LookupError::GenericError(format!("Invalid header {}: {}", name, e)))?;
+        map.append(name, value);
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "ureq"))]
+    use super::*;
+    #[cfg(not(feature = "ureq"))]
+    use serial_test::serial;
+
+    // the ureq backend only binds to unspecified addresses
+    #[cfg(not(feature = "ureq"))]
+    #[test]
+    #[serial]
+    fn test_shared() {
+        let local = Some("127.0.0.2".parse::<IpAddr>().unwrap());
+        let binding = Binding {
+            local_address: local,
+            ..Default::default()
+        };
+        let count = || {
+            CLIENTS
+                .lock()
+                .unwrap()
+                .keys()
+                .filter(|binding| binding.local_address == local)
+                .count()
+        };
+        assert!(shared(binding.clone()).is_ok());
+        assert_eq!(count(), 1, "Client not shared");
+        assert!(shared(binding).is_ok());
+        assert_eq!(count(), 1, "Client should be reused");
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[test]
+    #[serial]
+    fn test_shared_per_runtime() {
+        let runtime = || {
+            tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap()
+        };
+        let id = |runtime: tokio::runtime::Runtime| {
+            runtime.block_on(async {
+                assert!(shared(Binding::default()).is_ok());
+                tokio::runtime::Handle::current().id()
+            })
+        };
+        let (first, second) = (id(runtime()), id(runtime()));
+        assert_ne!(first, second);
+        let clients = CLIENTS.lock().unwrap();
+        for runtime in [first, second] {
+            assert!(
+                clients
+                    .keys()
+                    .any(|binding| binding.runtime == Some(runtime)),
+                "Client not shared per runtime"
+            );
+        }
+    }
+
+    #[cfg(not(feature = "ureq"))]
+    #[test]
+    #[serial]
+    fn test_shared_bound() {
+        for i in 0..=MAX_CLIENTS {
+            let binding = Binding {
+                headers: vec![("X-Client".to_string(), i.to_string())],
+                ..Default::default()
+            };
+            assert!(shared(binding).is_ok());
+        }
+        assert!(
+            CLIENTS.lock().unwrap().len() <= MAX_CLIENTS,
+            "Shared clients not bounded"
+        );
+    }
+}
@@ -0,0 +1,363 @@
+//! `ureq` backend exposing the subset of the `reqwest` blocking API used by the providers
+
+use std::{io, net::IpAddr, time::Duration};
+
+pub use ::ureq::{
+    http::{header, StatusCode},
+    Error,
+};
+
+/// HTTP client wrapping a `ureq::Agent`
+#[derive(Clone, Debug)]
+pub struct Client {
+    agent: ::ureq::Agent,
+    default_headers: header::HeaderMap,
+}
+
+impl Client {
+    /// Creates a new client with the default configuration.
+    pub fn new() -> Self {
+        ClientBuilder::default()
+            .build()
+            .expect("default client configuration is valid")
+    }
+
+    /// Creates a builder to configure a client.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// Starts a `GET` request to the URL.
+    pub fn get<U: Into<String>>(&self, url: U) -> RequestBuilder {
+        self.request(Method::Get, url)
+    }
+
+    /// Starts a `POST` request to the URL.
+    pub fn post<U: Into<String>>(&self, url: U) -> RequestBuilder {
+        self.request(Method::Post, url)
+    }
+
+    /// Starts a `PATCH` request to the URL.
+    pub fn patch<U: Into<String>>(&self, url: U) -> RequestBuilder {
+        self.request(Method::Patch, url)
+    }
+
+    /// Starts a `HEAD` request to the URL.
+    pub fn head<U: Into<String>>(&self, url: U) -> RequestBuilder {
+        self.request(Method::Head, url)
+    }
+
+    /// Sends the request, adding the default headers it doesn't set itself.
+    pub fn execute(&self, mut request: Request) -> Result<Response, Error> {
+        for (name, value) in &self.default_headers {
+            if !request.headers.contains_key(name) {
+                request.headers.insert(name, value.clone());
+            }
+        }
+        let response = match request.method {
+            Method::Get => configure(self.agent.get(&request.url), &request).call(),
+            Method::Head => configure(self.agent.head(&request.url), &request).call(),
+            Method::Post => configure(self.agent.post(&request.url), &request)
+                .send(request.body.unwrap_or_default()),
+            Method::Patch => configure(self.agent.patch(&request.url), &request)
+                .send(request.body.unwrap_or_default()),
+        }?;
+        Ok(Response(response))
+    }
+
+    fn request<U: Into<String>>(&self, method: Method, url: U) -> RequestBuilder {
+        RequestBuilder {
+            client: self.clone(),
+            request: Ok(Request {
+                method,
+                url: url.into(),
+                headers: header::HeaderMap::new(),
+                body: None,
+                timeout: None,
+            }),
+        }
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Wraps an agent, its configuration is used as is.
+///
+/// The agent should be built with `http_status_as_error(false)`, so error statuses can be mapped
+/// to lookup errors.
+impl From<::ureq::Agent> for Client {
+    fn from(agent: ::ureq::Agent) -> Self {
+        Client {
+            agent,
+            default_headers: header::HeaderMap::new(),
+        }
+    }
+}
+
+/// Builder of a `Client`
+#[derive(Debug, Default)]
+pub struct ClientBuilder {
+    local_address: Option<IpAddr>,
+    interface: Option<String>,
+    timeout: Option<Duration>,
+    default_headers: header::HeaderMap,
+}
+
+impl ClientBuilder {
+    /// Binds the connections to a local address.
+    ///
+    /// Only the unspecified addresses are supported, to restrict the connections to IPv4 or IPv6.
+    pub fn local_address<T: Into<Option<IpAddr>>>(mut self, address: T) -> Self {
+        self.local_address = address.into();
+        self
+    }
+
+    /// Binds the connections to a network interface, not supported by `ureq`.
+    pub fn interface(mut self, interface: &str) -> Self {
+        self.interface = Some(interface.to_string());
+        self
+    }
+
+    /// Sets the `User-Agent` header sent with every request.
+    pub fn user_agent<V: Into<String>>(mut self, user_agent: V) -> Self {
+        if let Ok(value) = header::HeaderValue::try_from(user_agent.into()) {
+            self.default_headers.insert(header::USER_AGENT, value);
+        }
+        self
+    }
+
+    /// Sets the headers sent with every request, unless the request sets them itself.
+    pub fn default_headers(mut self, headers: header::HeaderMap) -> Self {
+        self.default_headers.extend(headers);
+        self
+    }
+
+    /// Sets the timeout of a whole request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Builds the client.
+    pub fn build(self) -> Result<Client, Error> {
+        let ip_family = match self.local_address {
+            None => ::ureq::config::IpFamily::Any,
+            Some(IpAddr::V4(address)) if address.is_unspecified() => {
+                ::ureq::config::IpFamily::Ipv4Only
+            }
+            Some(IpAddr::V6(address)) if address.is_unspecified() => {
+                ::ureq::config::IpFamily::Ipv6Only
+            }
+            Some(address) => return Err(unsupported(format!("binding to {}", address))),
+        };
+        if let Some(interface) = self.interface {
+            return Err(unsupported(format!("binding to interface {}", interface)));
+        }
+        let config = ::ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .ip_family(ip_family)
+            .timeout_global(self.timeout);
+        // like reqwest, the platform TLS library wins when both TLS features are enabled
+        #[cfg(feature = "native-tls")]
+        let config = config.tls_config(
+            ::ureq::tls::TlsConfig::builder()
+                .provider(::ureq::tls::TlsProvider::NativeTls)
+                .build(),
+        );
+        let agent = config.build().new_agent();
+        Ok(Client {
+            agent,
+            default_headers: self.default_headers,
+        })
+    }
+}
+
+/// Builder of a request
+#[derive(Debug)]
+pub struct RequestBuilder {
+    client: Client,
+    request: Result<Request, String>,
+}
+
+impl RequestBuilder {
+    /// Adds a header to the request.
+    pub fn header<K: AsRef<str>, V: AsRef<str>>(mut self, key: K, value: V) -> Self {
+        if let Ok(request) = &mut self.request {
+            let name = header::HeaderName::try_from(key.as_ref());
+            let value = header::HeaderValue::try_from(value.as_ref());
+            match (name, value) {
+                (Ok(name), Ok(value)) => {
+                    request.headers.append(name, value);
+                }
+                _ => self.request = Err(format!("invalid header {}", key.as_ref())),
+            }
+        }
+        self
+    }
+
+    /// Adds a bearer token `Authorization` header to the request.
+    pub fn bearer_auth<T: std::fmt::Display>(self, token: T) -> Self {
+        self.header("Authorization", format!("Bearer {}", token))
+    }
+
+    /// Adds a basic `Authorization` header to the request.
+    pub fn basic_auth<U: std::fmt::Display, P: std::fmt::Display>(
+        self,
+        username: U,
+        password: Option<P>,
+    ) -> Self {
+        use base64::Engine;
+        let credentials = match password {
+            Some(password) => format!("{}:{}", username, password),
+            None => format!("{}:", username),
+        };
+        let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+        self.header("Authorization", format!("Basic {}", encoded))
+    }
+
+    /// Sets the body of the request.
+    pub fn body<B: Into<String>>(mut self, body: B) -> Self {
+        if let Ok(request) = &mut self.request {
+            request.body = Some(body.into());
+        }
+        self
+    }
+
+    /// Sets the timeout of the whole request, overriding the timeout of the client.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        if let Ok(request) = &mut self.request {
+            request.timeout = Some(timeout);
+        }
+        self
+    }
+
+    /// Builds the request.
+    pub fn build(self) -> Result<Request, Error> {
+        self.request
+            .map_err(|e| Error::Io(io::Error::new(io::ErrorKind::InvalidInput, e)))
+    }
+
+    /// Sends the request.
+    pub fn send(self) -> Result<Response, Error> {
+        let client = self.client.clone();
+        client.execute(self.build()?)
+    }
+}
+
+/// Request built by a `RequestBuilder`
+#[derive(Debug)]
+pub struct Request {
+    method: Method,
+    url: String,
+    headers: header::HeaderMap,
+    body: Option<String>,
+    timeout: Option<Duration>,
+}
+
+impl Request {
+    /// Returns the URL of the request.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the headers of the request.
+    pub fn headers(&self) -> &header::HeaderMap {
+        &self.headers
+    }
+
+    /// Returns the headers of the request, for modification.
+    pub fn headers_mut(&mut self) -> &mut header::HeaderMap {
+        &mut self.headers
+    }
+}
+
+/// Response to a request
+#[derive(Debug)]
+pub struct Response(::ureq::http::Response<::ureq::Body>);
+
+impl Response {
+    /// Returns the status code of the response.
+    pub fn status(&self) -> StatusCode {
+        self.0.status()
+    }
+
+    /// Returns the headers of the response.
+    pub fn headers(&self) -> &header::HeaderMap {
+        self.0.headers()
+    }
+
+    /// Reads the body of the response as text.
+    pub fn text(self) -> Result<String, Error> {
+        self.0.into_body().read_to_string()
+    }
+}
+
+/// Sends a `GET` request to the URL with a default client.
+#[cfg(test)]
+pub fn get<U: Into<String>>(url: U) -> Result<Response, Error> {
+    Client::new().get(url).send()
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Method {
+    Get,
+    Head,
+    Post,
+    Patch,
+}
+
+/// Adds the headers and the timeout of the request to the `ureq` request.
+fn configure<B>(
+    mut builder: ::ureq::RequestBuilder<B>,
+    request: &Request,
+) -> ::ureq::RequestBuilder<B> {
+    for (key, value) in &request.headers {
+        builder = builder.header(key, value);
+    }
+    match request.timeout {
+        Some(timeout) => builder.config().timeout_global(Some(timeout)).build(),
+        None => builder,
+    }
+}
+
+fn unsupported(what: String) -> Error {
+    Error::Io(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{} is not supported by the ureq backend", what),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let build = |address: &str| {
+            Client::builder()
+                .local_address(address.parse().ok())
+                .build()
+        };
+        assert!(build("0.0.0.0").is_ok(), "IPv4 only client should build");
+        assert!(build("::").is_ok(), "IPv6 only client should build");
+        assert!(
+            build("127.0.0.1").is_err(),
+            "Binding to an address is unsupported"
+        );
+        assert!(Client::builder().interface("lo").build().is_err());
+    }
+
+    #[test]
+    fn test_basic_auth() {
+        let request = Client::new()
+            .get("https://example.com")
+            .basic_auth("user", Some("pass"))
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()["Authorization"], "Basic dXNlcjpwYXNz");
+    }
+}
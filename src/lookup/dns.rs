@@ -0,0 +1,142 @@
+//! Custom DNS resolution
+//!
+//! With the `hickory-dns` feature, the hostnames of the providers can be resolved with a
+//! [hickory](https://github.com/hickory-dns/hickory-dns) resolver instead of the system resolver,
+//! for example to keep the DNS queries inside a VPN tunnel or to get past a captive portal
+//! tampering with DNS. The `dns-over-https` and `dns-over-tls` features enable the encrypted
+//! resolver configurations, like `ResolverConfig::cloudflare_https()`.
+//!
+//! ## Example
+//! ```rust
+//! use public_ip_address::lookup::{
+//!     dns::{DnsResolver, ResolverConfig},
+//!     LookupProvider, LookupService,
+//! };
+//!
+//! let mut service = LookupService::new(LookupProvider::IpWhoIs, None);
+//! service.set_dns_resolver(DnsResolver::new(ResolverConfig::quad9()));
+//! ```
+
+use super::{error::LookupError, Result};
+use hickory_resolver::{
+    config::LookupIpStrategy, name_server::TokioConnectionProvider, TokioResolver,
+};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    net::SocketAddr,
+    sync::{Arc, OnceLock},
+};
+
+pub use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+
+/// DNS resolver used to resolve the hostnames of the providers
+///
+/// Clones share the same resolver and its cache.
+#[derive(Clone)]
+pub struct DnsResolver {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    config: ResolverConfig,
+    options: ResolverOpts,
+    // built on first use, from within the runtime of the HTTP client
+    resolver: OnceLock<TokioResolver>,
+}
+
+impl DnsResolver {
+    /// Creates a resolver querying the name servers of the configuration.
+    pub fn new(config: ResolverConfig) -> Self {
+        let mut options = ResolverOpts::default();
+        // both address families, so lookups over either IP version can connect
+        options.ip_strategy = LookupIpStrategy::Ipv4AndIpv6;
+        Self::with_options(config, options)
+    }
+
+    /// Creates a resolver querying the name servers of the configuration with the given options.
+    pub fn with_options(config: ResolverConfig, options: ResolverOpts) -> Self {
+        DnsResolver {
+            inner: Arc::new(Inner {
+                config,
+                options,
+                resolver: OnceLock::new(),
+            }),
+        }
+    }
+
+    /// Creates a resolver from the system configuration, like `/etc/resolv.conf` on Unix.
+    ///
+    /// The queries are made by the hickory resolver instead of the system resolver.
+    pub fn from_system_conf() -> Result<Self> {
+        let (config, mut options) = hickory_resolver::system_conf::read_system_conf()
+            .map_err(|e| LookupError::GenericError(format!("DNS configuration: {}", e)))?;
+        options.ip_strategy = LookupIpStrategy::Ipv4AndIpv6;
+        Ok(Self::with_options(config, options))
+    }
+
+    fn resolver(&self) -> &TokioResolver {
+        self.inner.resolver.get_or_init(|| {
+            TokioResolver::builder_with_config(
+                self.inner.config.clone(),
+                TokioConnectionProvider::default(),
+            )
+            .with_options(self.inner.options.clone())
+            .build()
+        })
+    }
+}
+
+impl Resolve for DnsResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            crate::logging::trace!("Resolving {}", name.as_str());
+            let lookup = resolver.resolver().lookup_ip(name.as_str()).await?;
+            let addrs: Addrs = Box::new(
+                lookup
+                    .into_iter()
+                    .map(|ip| SocketAddr::new(ip, 0))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            );
+            Ok(addrs)
+        })
+    }
+}
+
+impl fmt::Debug for DnsResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DnsResolver")
+            .field("config", &self.inner.config)
+            .finish()
+    }
+}
+
+// resolvers are compared by identity, so clients are shared between clones
+impl PartialEq for DnsResolver {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for DnsResolver {}
+
+impl Hash for DnsResolver {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.inner).hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity() {
+        let resolver = DnsResolver::new(ResolverConfig::cloudflare());
+        assert_eq!(resolver, resolver.clone());
+        assert_ne!(resolver, DnsResolver::new(ResolverConfig::cloudflare()));
+    }
+}
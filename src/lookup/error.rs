@@ -1,4 +1,5 @@
 //! Lookup error types
+use crate::lookup::LookupProvider;
 use thiserror::Error;
 
 /// Result type for the lookup crate
@@ -9,21 +10,227 @@ pub type Result<T> = std::result::Result<T, LookupError>;
 #[non_exhaustive]
 pub enum LookupError {
     /// API request error
+    #[cfg(feature = "reqwest")]
     #[error("Reqwuest error")]
     ReqwestError(#[from] reqwest::Error),
+    /// API request error of the `ureq` backend
+    #[cfg(feature = "ureq")]
+    #[error("Ureq error")]
+    UreqError(#[from] ureq::Error),
+    /// Tor client error
+    #[cfg(feature = "tor")]
+    #[error("Tor error")]
+    TorError(#[from] arti_client::Error),
     /// Too many requests
     #[error("Too many API requests")]
     TooManyRequests(String),
+    /// API key rejected by the provider
+    #[error("Invalid API key")]
+    InvalidApiKey(String),
+    /// API key required by the provider but not given
+    #[error("Missing API key")]
+    MissingApiKey,
+    /// Provider not compiled in, enable its `provider-<name>` feature
+    #[error("Provider not enabled")]
+    ProviderNotEnabled(String),
+    /// Lookup cancelled through a `CancellationToken`
+    #[error("Lookup cancelled")]
+    Cancelled,
     /// Other HTTP code
     #[error("Request status")]
     RequestStatus(String),
     /// Serde error
     #[error("Serde error")]
     SerdeError(#[from] serde_json::Error),
+    /// Response of the provider not parsable, such as an error object sent with `200 OK`
+    #[error("Failed to parse {provider} response with status {status}: {message}, body: {body}")]
+    ParseError {
+        /// Provider that sent the response
+        provider: LookupProvider,
+        /// HTTP status of the response
+        status: u16,
+        /// Reason the response could not be parsed
+        message: String,
+        /// Body of the response, truncated to [`MAX_BODY_LENGTH`] characters
+        body: String,
+    },
+    /// Every provider of a fallback chain failed, with the error of each provider in the order tried
+    #[error("All providers failed")]
+    AllProvidersFailed(Vec<(LookupProvider, LookupError)>),
+    /// Batch request failed, with the error shared by every address of the batch
+    #[error("Batch lookup failed")]
+    BatchFailed(#[source] std::sync::Arc<LookupError>),
+    /// Lookup shared by concurrent callers failed, with the error shared by every caller
+    #[error("Shared lookup failed")]
+    SharedFailed(#[source] std::sync::Arc<crate::error::Error>),
     /// Generic error
     #[error("Lookup error")]
     GenericError(String),
     /// Target address not supported by this provider
     #[error("Target lookup not supported")]
     TargetNotSupported,
+    /// IP-only lookup not supported by this provider, as it returns geolocation information
+    #[error("IP-only lookup not supported")]
+    IpOnlyNotSupported,
+    /// Private, loopback, link-local or other non-global address returned by the provider,
+    /// rejected when enabled with [`LookupService::set_reject_non_global`](crate::lookup::LookupService::set_reject_non_global)
+    #[error("Non-global address {0}")]
+    NonGlobalAddress(std::net::IpAddr),
+}
+
+/// Category of a [`LookupError`], to decide how to handle it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Request not completed, such as a connection failure or a timeout
+    Network,
+    /// Request rejected with an HTTP status other than `200 OK`
+    Status,
+    /// Provider rate limit reached
+    RateLimited,
+    /// API key missing or rejected
+    Auth,
+    /// Response not parsable
+    Parse,
+    /// Provider not enabled or not supporting the lookup
+    Unsupported,
+    /// Lookup cancelled
+    Cancelled,
+    /// Any other error, including the failure of a whole fallback chain
+    Other,
+}
+
+/// Maximum number of characters of the response body kept in a [`LookupError::ParseError`]
+pub const MAX_BODY_LENGTH: usize = 512;
+
+impl LookupError {
+    /// Returns the category of the error
+    ///
+    /// # Example
+    /// ```
+    /// use public_ip_address::lookup::error::{ErrorKind, LookupError};
+    ///
+    /// assert_eq!(LookupError::MissingApiKey.kind(), ErrorKind::Auth);
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            #[cfg(feature = "reqwest")]
+            LookupError::ReqwestError(_) => ErrorKind::Network,
+            #[cfg(feature = "ureq")]
+            LookupError::UreqError(_) => ErrorKind::Network,
+            #[cfg(feature = "tor")]
+            LookupError::TorError(_) => ErrorKind::Network,
+            LookupError::RequestStatus(_) => ErrorKind::Status,
+            LookupError::TooManyRequests(_) => ErrorKind::RateLimited,
+            LookupError::InvalidApiKey(_) | LookupError::MissingApiKey => ErrorKind::Auth,
+            LookupError::SerdeError(_)
+            | LookupError::ParseError { .. }
+            | LookupError::NonGlobalAddress(_) => ErrorKind::Parse,
+            LookupError::ProviderNotEnabled(_)
+            | LookupError::TargetNotSupported
+            | LookupError::IpOnlyNotSupported => ErrorKind::Unsupported,
+            LookupError::Cancelled => ErrorKind::Cancelled,
+            LookupError::BatchFailed(error) => error.kind(),
+            LookupError::SharedFailed(error) => match &**error {
+                crate::error::Error::LookupError(error) => error.kind(),
+                _ => ErrorKind::Other,
+            },
+            LookupError::AllProvidersFailed(_) | LookupError::GenericError(_) => ErrorKind::Other,
+        }
+    }
+
+    /// Returns `true` if the same request may succeed later
+    ///
+    /// Network errors, server errors and rate limits are retryable, while retrying after an
+    /// authentication, parse or unsupported lookup error is pointless. A failed fallback chain is
+    /// retryable if any of its providers is, a failed batch or shared lookup if its error is.
+    ///
+    /// # Example
+    /// ```
+    /// use public_ip_address::lookup::error::LookupError;
+    ///
+    /// assert!(LookupError::TooManyRequests("Too many requests".to_string()).is_retryable());
+    /// assert!(!LookupError::MissingApiKey.is_retryable());
+    /// ```
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            // the status is formatted by `status_error` as "Status: <code> <reason>"
+            LookupError::RequestStatus(status) => status
+                .split_whitespace()
+                .nth(1)
+                .and_then(|code| code.parse::<u16>().ok())
+                .is_some_and(|code| code >= 500),
+            LookupError::AllProvidersFailed(errors) => {
+                errors.iter().any(|(_, error)| error.is_retryable())
+            }
+            LookupError::BatchFailed(error) => error.is_retryable(),
+            LookupError::SharedFailed(error) => error.is_retryable(),
+            error => matches!(error.kind(), ErrorKind::Network | ErrorKind::RateLimited),
+        }
+    }
+
+    /// Turns a serde error from parsing a response into a `ParseError` with the status and the
+    /// start of the body of the response, other errors are returned unchanged
+    pub(crate) fn with_response(
+        self,
+        provider: LookupProvider,
+        response: crate::freshness::Observed,
+        body: &str,
+    ) -> Self {
+        match self {
+            LookupError::SerdeError(e) => LookupError::ParseError {
+                provider,
+                // bodies not received over HTTP, like the mock ones, are successful responses
+                status: response.status.unwrap_or(200),
+                message: e.to_string(),
+                body: excerpt(body),
+            },
+            e => e,
+        }
+    }
+}
+
+/// Returns the start of the body kept in a `ParseError`
+fn excerpt(body: &str) -> String {
+    match body.char_indices().nth(MAX_BODY_LENGTH) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_retryable() {
+        let server = LookupError::RequestStatus("Status: 503 Service Unavailable".to_string());
+        let client = LookupError::RequestStatus("Status: 404 Not Found".to_string());
+        assert_eq!(server.kind(), ErrorKind::Status);
+        assert!(server.is_retryable(), "Server errors should be retried");
+        assert!(
+            !client.is_retryable(),
+            "Client errors should not be retried"
+        );
+        assert!(!LookupError::InvalidApiKey("Invalid API key".to_string()).is_retryable());
+        assert!(!LookupError::Cancelled.is_retryable());
+
+        let chain = LookupError::AllProvidersFailed(vec![
+            (LookupProvider::IpData, LookupError::MissingApiKey),
+            (LookupProvider::IpWhoIs, server),
+        ]);
+        assert_eq!(chain.kind(), ErrorKind::Other);
+        assert!(chain.is_retryable(), "Chain with a retryable error");
+        let chain = LookupError::AllProvidersFailed(vec![(
+            LookupProvider::IpData,
+            LookupError::MissingApiKey,
+        )]);
+        assert!(!chain.is_retryable(), "Chain without retryable errors");
+
+        let batch = LookupError::BatchFailed(std::sync::Arc::new(LookupError::TooManyRequests(
+            "Too many requests".to_string(),
+        )));
+        assert_eq!(batch.kind(), ErrorKind::RateLimited);
+        assert!(batch.is_retryable(), "Batch with a retryable error");
+    }
 }
@@ -3,6 +3,7 @@
 use super::{client::RequestBuilder, ProviderResponse, Result};
 use crate::{
     lookup::{LookupProvider, Provider},
+    response::Security,
     LookupResponse,
 };
 use serde::{Deserialize, Serialize};
@@ -37,14 +38,18 @@ impl ProviderResponse<FreeIpApiResponse> for FreeIpApiResponse {
         );
         response.country = self.country_name;
         response.country_code = self.country_code;
-        response.continent = self.continent;
+        response.continent =
+            super::continent(self.continent.as_deref(), self.continent_code.as_deref());
         response.region = self.region_name;
         response.postal_code = self.zip_code;
         response.city = self.city_name;
         response.latitude = self.latitude;
         response.longitude = self.longitude;
         response.time_zone = self.time_zone;
-        response.is_proxy = self.is_proxy;
+        response.security = Some(Security {
+            is_proxy: self.is_proxy,
+            ..Default::default()
+        });
         response
     }
 }
@@ -106,7 +111,10 @@ mod tests {
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_request() {
         let service = Box::new(FreeIpApi);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("FreeIpApi: {:#?}", result);
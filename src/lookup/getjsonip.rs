@@ -55,7 +55,10 @@ mod tests {
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_request() {
         let service = Box::new(GetJsonIp);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("GetJsonIp: {:#?}", result);
@@ -3,6 +3,7 @@
 use super::{ProviderResponse, Result};
 use crate::{
     lookup::{LookupProvider, Provider},
+    response::Continent,
     LookupResponse,
 };
 use serde::{Deserialize, Serialize};
@@ -40,10 +41,15 @@ impl ProviderResponse<IfConfigResponse> for IfConfigResponse {
         );
         response.country = self.country;
         response.country_code = self.country_iso;
+        response.is_eu = self.country_eu;
         if self.country_eu.unwrap_or(false) {
-            response.continent = Some("Europe".to_string());
+            response.continent = Some(Continent::Europe);
         }
         response.region = self.region_name;
+        response.region_code = super::region_code(
+            response.country_code.as_deref(),
+            self.region_code.as_deref(),
+        );
         response.postal_code = self.zip_code;
         response.city = self.city;
         response.latitude = self.latitude;
@@ -91,7 +97,10 @@ mod tests {
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_request() {
         let service = Box::new(IfConfig);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("Ifconfig: {:#?}", result);
@@ -3,6 +3,7 @@
 use super::{ProviderResponse, Result};
 use crate::{
     lookup::{LookupProvider, Provider},
+    response::Security,
     LookupResponse,
 };
 use serde::{Deserialize, Serialize};
@@ -44,7 +45,10 @@ impl ProviderResponse<Ip2LocationResponse> for Ip2LocationResponse {
         response.time_zone = self.time_zone;
         response.asn_org = self.as_name;
         response.asn = self.asn;
-        response.is_proxy = self.is_proxy;
+        response.security = Some(Security {
+            is_proxy: self.is_proxy,
+            ..Default::default()
+        });
 
         response
     }
@@ -55,15 +59,17 @@ pub struct Ip2Location;
 
 impl Provider for Ip2Location {
     fn get_endpoint(&self, key: &Option<String>, target: &Option<IpAddr>) -> String {
-        let key = match key {
-            Some(k) => format!("?key={}", k),
+        let target = match target.map(|t| t.to_string()) {
+            Some(t) => format!("?ip={}", t),
             None => "".to_string(),
         };
-        let target = match target.map(|t| t.to_string()) {
-            Some(t) => format!("&ip={}", t),
+        // the key is optional, so the target may be the first query parameter
+        let key = match key {
+            Some(k) if target.is_empty() => format!("?key={}", k),
+            Some(k) => format!("&key={}", k),
             None => "".to_string(),
         };
-        format!("https://api.ip2location.io/{}{}", key, target)
+        format!("https://api.ip2location.io/{}{}", target, key)
     }
 
     fn parse_reply(&self, json: String) -> Result<LookupResponse> {
@@ -104,7 +110,10 @@ mod tests {
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_request() {
         let service = Box::new(Ip2Location);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("Ip2Location: {:#?}", result);
@@ -121,7 +130,10 @@ mod tests {
         assert!(key.is_some(), "Missing APIKEY");
 
         let service = Box::new(Ip2Location);
-        let result = service.get_client(key, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), key, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("Ip2Location: {:#?}", result);
@@ -139,7 +151,10 @@ mod tests {
 
         let target = "8.8.8.8".parse::<IpAddr>().ok();
         let service = Box::new(Ip2Location);
-        let result = service.get_client(key, target).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), key, target)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("Ip2Location: {:#?}", result);
@@ -161,4 +176,24 @@ mod tests {
             "IP address not matching"
         );
     }
+
+    #[test]
+    fn test_endpoint() {
+        let target = "8.8.8.8".parse::<IpAddr>().ok();
+        assert_eq!(
+            Ip2Location.get_endpoint(&None, &target),
+            "https://api.ip2location.io/?ip=8.8.8.8",
+            "Target without key"
+        );
+        assert_eq!(
+            Ip2Location.get_endpoint(&Some("key".to_string()), &target),
+            "https://api.ip2location.io/?ip=8.8.8.8&key=key",
+            "Target with key"
+        );
+        assert_eq!(
+            Ip2Location.get_endpoint(&Some("key".to_string()), &None),
+            "https://api.ip2location.io/?key=key",
+            "Key without target"
+        );
+    }
 }
@@ -3,6 +3,7 @@
 use super::{client::RequestBuilder, ProviderResponse, Result};
 use crate::{
     lookup::{LookupProvider, Provider},
+    response::Currency,
     LookupResponse,
 };
 use serde::{Deserialize, Serialize};
@@ -24,7 +25,9 @@ pub struct IpApiCoResponse {
     latitude: Option<f64>,
     longitude: Option<f64>,
     timezone: Option<String>,
+    country_calling_code: Option<String>,
     currency: Option<String>,
+    languages: Option<String>,
     isp: Option<String>,
     asn: Option<String>,
     org: Option<String>,
@@ -39,9 +42,15 @@ impl ProviderResponse<IpApiCoResponse> for IpApiCoResponse {
                 .unwrap_or(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0))),
             LookupProvider::IpApiCo,
         );
+        response.continent = super::continent(None, self.continent_code.as_deref());
         response.country = self.country_name;
         response.country_code = self.country_code;
+        response.is_eu = self.in_eu;
         response.region = self.region;
+        response.region_code = super::region_code(
+            response.country_code.as_deref(),
+            self.region_code.as_deref(),
+        );
         response.postal_code = self.postal;
         response.city = self.city;
         response.latitude = self.latitude;
@@ -50,6 +59,12 @@ impl ProviderResponse<IpApiCoResponse> for IpApiCoResponse {
         response.asn_org = self.org;
         response.asn = self.asn;
         response.hostname = self.hostname;
+        response.calling_code = self
+            .country_calling_code
+            .as_deref()
+            .and_then(super::calling_code);
+        response.currency = self.currency.map(Currency::new);
+        response.languages = self.languages.as_deref().and_then(super::split_list);
         response
     }
 }
@@ -114,7 +129,10 @@ mod tests {
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_request() {
         let service = Box::new(IpApiCo);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("IpApiCo: {:#?}", result);
@@ -132,5 +150,15 @@ mod tests {
             "1.1.1.1".parse::<IpAddr>().unwrap(),
             "IP address not matching"
         );
+        assert_eq!(
+            lookup.languages,
+            Some(vec![
+                "en-US".into(),
+                "es-US".into(),
+                "haw".into(),
+                "fr".into()
+            ]),
+            "Languages not split"
+        );
     }
 }
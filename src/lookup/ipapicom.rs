@@ -3,6 +3,7 @@
 use super::{ProviderResponse, Result};
 use crate::{
     lookup::{LookupProvider, Provider},
+    response::{Currency, Security},
     LookupResponse,
 };
 use serde::{Deserialize, Serialize};
@@ -47,9 +48,13 @@ impl ProviderResponse<IpApiComResponse> for IpApiComResponse {
                 .unwrap_or(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0))),
             LookupProvider::IpApiCom,
         );
+        response.continent =
+            super::continent(self.continent.as_deref(), self.continent_code.as_deref());
         response.country = self.country;
         response.country_code = self.country_code;
         response.region = self.region_name;
+        response.region_code =
+            super::region_code(response.country_code.as_deref(), self.region.as_deref());
         response.postal_code = self.zip;
         response.city = self.city;
         response.latitude = self.lat;
@@ -58,7 +63,12 @@ impl ProviderResponse<IpApiComResponse> for IpApiComResponse {
         response.asn_org = self.org;
         response.asn = self.asn;
         response.hostname = self.reverse;
-        response.is_proxy = self.proxy;
+        response.security = Some(Security {
+            is_proxy: self.proxy,
+            is_datacenter: self.hosting,
+            ..Default::default()
+        });
+        response.currency = self.currency.map(Currency::new);
         response
     }
 }
@@ -125,7 +135,10 @@ mod tests {
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_request() {
         let service = Box::new(IpApiCom);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("IpApiCom: {:#?}", result);
@@ -143,5 +156,6 @@ mod tests {
             "1.1.1.1".parse::<IpAddr>().unwrap(),
             "IP address not matching"
         );
+        assert_eq!(lookup.region_code.as_deref(), Some("CA-QC"));
     }
 }
@@ -3,6 +3,7 @@
 use super::{ProviderResponse, Result};
 use crate::{
     lookup::{LookupProvider, Provider},
+    response::{Continent, Currency, Security},
     LookupResponse,
 };
 use serde::{Deserialize, Serialize};
@@ -16,6 +17,14 @@ pub struct IpApiIoResponse {
     country_code: Option<String>,
     country_name: Option<String>,
     currency: Option<String>,
+    #[serde(rename = "currencySymbol")]
+    currency_symbol: Option<String>,
+    #[serde(rename = "callingCode")]
+    calling_code: Option<String>,
+    #[serde(rename = "emojiFlag")]
+    emoji_flag: Option<String>,
+    #[serde(rename = "flagUrl")]
+    flag_url: Option<String>,
     is_in_european_union: Option<bool>,
     latitude: Option<f64>,
     longitude: Option<f64>,
@@ -47,18 +56,40 @@ impl ProviderResponse<IpApiIoResponse> for IpApiIoResponse {
         );
         response.country = self.country_name;
         response.country_code = self.country_code;
+        response.is_eu = self.is_in_european_union;
         response.region = self.region_name;
+        response.region_code = super::region_code(
+            response.country_code.as_deref(),
+            self.region_code.as_deref(),
+        );
         response.postal_code = self.zip_code;
         response.city = self.city;
         response.latitude = self.latitude;
         response.longitude = self.longitude;
         response.time_zone = self.time_zone;
         response.asn_org = self.organisation;
+        response.calling_code = self.calling_code.as_deref().and_then(super::calling_code);
+        response.flag_emoji = self.emoji_flag;
+        response.flag_url = self.flag_url;
+        // the currencies of the country are separated by commas, the first is the main one
+        let first = |list: String| list.split(',').next().map(str::to_string);
+        if let Some(code) = self.currency.and_then(first) {
+            response.currency = Some(Currency {
+                code,
+                name: None,
+                symbol: self.currency_symbol.and_then(first),
+            });
+        }
         if let Some(suspicious) = self.suspicious_factors {
-            response.is_proxy = suspicious.is_proxy;
+            response.security = Some(Security {
+                is_tor: suspicious.is_tor_node,
+                is_proxy: suspicious.is_proxy,
+                is_abuser: suspicious.is_spam,
+                ..Default::default()
+            });
         }
-        if self.is_in_european_union.unwrap_or(false) {
-            response.continent = Some("Europe".to_string());
+        if response.is_eu.unwrap_or(false) {
+            response.continent = Some(Continent::Europe);
         }
         response
     }
@@ -131,7 +162,10 @@ mod tests {
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_request() {
         let service = Box::new(IpApiIo);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("IpApiIo: {:#?}", result);
@@ -149,5 +183,8 @@ mod tests {
             "1.1.1.1".parse::<IpAddr>().unwrap(),
             "IP address not matching"
         );
+        let currency = lookup.currency.expect("Currency not mapped");
+        assert_eq!(currency.code, "USD", "Main currency not picked");
+        assert_eq!(currency.symbol.as_deref(), Some("$"));
     }
 }
@@ -3,7 +3,7 @@
 use super::{client::RequestBuilder, ProviderResponse, Result};
 use crate::{
     lookup::{LookupProvider, Provider},
-    LookupResponse,
+    response, LookupResponse,
 };
 use serde::{Deserialize, Serialize};
 use std::net::{IpAddr, Ipv4Addr};
@@ -46,6 +46,7 @@ struct Location {
 
 #[derive(Serialize, Deserialize, Debug)]
 struct Continent {
+    code: Option<String>,
     name: Option<String>,
 }
 
@@ -54,6 +55,17 @@ struct Country {
     #[serde(rename = "alpha2")]
     code: Option<String>,
     name: Option<String>,
+    calling_codes: Option<Vec<String>>,
+    is_in_european_union: Option<bool>,
+    currencies: Option<Vec<Currency>>,
+    emoji: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Currency {
+    code: Option<String>,
+    name: Option<String>,
+    symbol: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -78,6 +90,8 @@ struct Security {
     is_proxy: Option<bool>,
     is_vpn: Option<bool>,
     is_tor: Option<bool>,
+    is_datacenter: Option<bool>,
+    is_abuser: Option<bool>,
 }
 
 impl ProviderResponse<IpBaseResponse> for IpBaseResponse {
@@ -100,15 +114,36 @@ impl ProviderResponse<IpBaseResponse> for IpBaseResponse {
         if let Some(location) = data.location {
             response.latitude = location.latitude;
             response.longitude = location.longitude;
+            if let Some(continent) = location.continent {
+                response.continent =
+                    super::continent(continent.name.as_deref(), continent.code.as_deref());
+            }
             if let Some(country) = location.country {
                 response.country = country.name;
                 response.country_code = country.code;
+                response.is_eu = country.is_in_european_union;
+                response.flag_emoji = country.emoji;
+                response.calling_code = country
+                    .calling_codes
+                    .iter()
+                    .flatten()
+                    .find_map(|code| super::calling_code(code));
+                let currency = country.currencies.into_iter().flatten().next();
+                if let Some(currency) = currency {
+                    response.currency = currency.code.map(|code| response::Currency {
+                        code,
+                        name: currency.name,
+                        symbol: currency.symbol,
+                    });
+                }
             }
             if let Some(city) = location.city {
                 response.city = city.name;
             }
             if let Some(region) = location.region {
                 response.region = region.name;
+                response.region_code =
+                    super::region_code(response.country_code.as_deref(), region.code.as_deref());
             }
         }
 
@@ -116,6 +151,17 @@ impl ProviderResponse<IpBaseResponse> for IpBaseResponse {
             response.time_zone = timezone.id;
         }
 
+        if let Some(security) = data.security {
+            response.security = Some(response::Security {
+                is_vpn: security.is_vpn,
+                is_tor: security.is_tor,
+                is_proxy: security.is_proxy,
+                is_datacenter: security.is_datacenter,
+                is_abuser: security.is_abuser,
+                is_bogon: None,
+            });
+        }
+
         response
     }
 }
@@ -308,7 +354,10 @@ mod tests {
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_request() {
         let service = Box::new(IpBase);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("IpBase: {:#?}", result);
@@ -326,5 +375,10 @@ mod tests {
             "1.1.1.1".parse::<IpAddr>().unwrap(),
             "IP address not matching"
         );
+        let currency = lookup.currency.expect("Currency not mapped");
+        assert_eq!(currency.to_string(), "USD ($)");
+        assert_eq!(currency.name.as_deref(), Some("US Dollar"));
+        assert_eq!(lookup.calling_code.as_deref(), Some("+1"));
+        assert_eq!(lookup.region_code.as_deref(), Some("US-CA"));
     }
 }
@@ -3,6 +3,7 @@
 use super::{ProviderResponse, Result};
 use crate::{
     lookup::{LookupProvider, Provider},
+    response::{self, Security},
     LookupResponse,
 };
 use serde::{Deserialize, Serialize};
@@ -25,8 +26,12 @@ pub struct IpDataResponse {
     latitude: Option<f64>,
     postal: Option<String>,
     calling_code: Option<String>,
+    flag: Option<String>,
+    emoji_flag: Option<String>,
     asn: Option<Asn>,
     carrier: Option<Carrier>,
+    currency: Option<Currency>,
+    languages: Option<Vec<Language>>,
     time_zone: Option<Timezone>,
     threat: Option<Threat>,
 }
@@ -53,6 +58,18 @@ struct Blocklist {
     block_type: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+struct Currency {
+    name: Option<String>,
+    code: Option<String>,
+    symbol: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Language {
+    code: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct Timezone {
     name: Option<String>,
@@ -83,14 +100,25 @@ impl ProviderResponse<IpDataResponse> for IpDataResponse {
                 .unwrap_or(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0))),
             LookupProvider::IpData,
         );
-        response.continent = self.continent_name;
+        response.continent = super::continent(
+            self.continent_name.as_deref(),
+            self.continent_code.as_deref(),
+        );
         response.country = self.country_name;
         response.country_code = self.country_code;
+        response.is_eu = self.is_eu;
         response.region = self.region;
+        response.region_code = super::region_code(
+            response.country_code.as_deref(),
+            self.region_code.as_deref(),
+        );
         response.postal_code = self.postal;
         response.city = self.city;
         response.latitude = self.latitude;
         response.longitude = self.longitude;
+        response.calling_code = self.calling_code.as_deref().and_then(super::calling_code);
+        response.flag_emoji = self.emoji_flag;
+        response.flag_url = self.flag;
         if let Some(time_zone) = self.time_zone {
             response.time_zone = time_zone.name;
         }
@@ -98,8 +126,33 @@ impl ProviderResponse<IpDataResponse> for IpDataResponse {
             response.asn_org = asn.name;
             response.asn = asn.asn;
         }
+        if let Some(carrier) = self.carrier {
+            response.carrier = Some(response::Carrier {
+                name: carrier.name,
+                mcc: carrier.mcc,
+                mnc: carrier.mnc,
+            });
+        }
+        if let Some(currency) = self.currency {
+            response.currency = currency.code.map(|code| response::Currency {
+                code,
+                name: currency.name,
+                symbol: currency.symbol,
+            });
+        }
+        if let Some(languages) = self.languages {
+            let codes: Vec<String> = languages.into_iter().filter_map(|l| l.code).collect();
+            response.languages = (!codes.is_empty()).then_some(codes);
+        }
         if let Some(threat) = self.threat {
-            response.is_proxy = threat.is_proxy;
+            response.security = Some(Security {
+                is_vpn: threat.is_vpn,
+                is_tor: threat.is_tor,
+                is_proxy: threat.is_proxy,
+                is_datacenter: threat.is_datacenter,
+                is_abuser: threat.is_known_abuser,
+                is_bogon: threat.is_bogon,
+            });
         }
 
         response
@@ -215,7 +268,10 @@ mod tests {
         assert!(key.is_some(), "Missing APIKEY");
 
         let service = Box::new(IpData);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("IpData: {:#?}", result);
@@ -233,7 +289,10 @@ mod tests {
 
         let service = Box::new(IpData);
         let target = "8.8.8.8".parse().ok();
-        let result = service.get_client(key, target).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), key, target)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("IpData: {:#?}", result);
@@ -252,5 +311,12 @@ mod tests {
             "1.1.1.1".parse::<IpAddr>().unwrap(),
             "IP address not matching"
         );
+        assert_eq!(lookup.languages, Some(vec!["en".to_string()]));
+        assert_eq!(lookup.region_code.as_deref(), Some("US-NY"));
+        let carrier = lookup.carrier.expect("Carrier not mapped");
+        assert_eq!(carrier.to_string(), "T-Mobile (310-160)");
+        let security = lookup.security.expect("Security flags not mapped");
+        assert_eq!(security.is_bogon, Some(false), "Bogon flag not mapped");
+        assert_eq!(security.is_anonymous(), Some(false));
     }
 }
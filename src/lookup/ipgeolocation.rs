@@ -3,7 +3,7 @@
 use super::{ProviderResponse, Result};
 use crate::{
     lookup::{LookupProvider, Provider},
-    LookupResponse,
+    response, LookupResponse,
 };
 use serde::{Deserialize, Serialize};
 use std::net::{IpAddr, Ipv4Addr};
@@ -28,9 +28,20 @@ pub struct IpGeolocationResponse {
     connection_type: Option<String>,
     organization: Option<String>,
     isp: Option<String>,
+    currency: Option<Currency>,
+    calling_code: Option<String>,
+    languages: Option<String>,
+    country_flag: Option<String>,
     time_zone: Option<Timezone>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+struct Currency {
+    code: Option<String>,
+    name: Option<String>,
+    symbol: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct Timezone {
     name: Option<String>,
@@ -44,10 +55,16 @@ impl ProviderResponse<IpGeolocationResponse> for IpGeolocationResponse {
                 .unwrap_or(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0))),
             LookupProvider::IpGeolocation,
         );
-        response.continent = self.continent_name;
+        response.continent = super::continent(
+            self.continent_name.as_deref(),
+            self.continent_code.as_deref(),
+        );
         response.country = self.country_name;
         response.country_code = self.country_code2;
+        response.is_eu = self.is_eu;
         response.region = self.state_prov;
+        response.region_code =
+            super::region_code(response.country_code.as_deref(), self.state_code.as_deref());
         response.postal_code = self.zipcode;
         response.city = self.city;
         if let Some(lat) = self.latitude {
@@ -59,6 +76,16 @@ impl ProviderResponse<IpGeolocationResponse> for IpGeolocationResponse {
         if let Some(timezone) = self.time_zone {
             response.time_zone = timezone.name;
         }
+        if let Some(currency) = self.currency {
+            response.currency = currency.code.map(|code| response::Currency {
+                code,
+                name: currency.name,
+                symbol: currency.symbol,
+            });
+        }
+        response.languages = self.languages.as_deref().and_then(super::split_list);
+        response.calling_code = self.calling_code.as_deref().and_then(super::calling_code);
+        response.flag_url = self.country_flag;
         response.hostname = self.hostname;
         response.asn_org = self.organization;
         response.asn = self.isp;
@@ -150,7 +177,10 @@ mod tests {
         assert!(key.is_some(), "Missing APIKEY");
 
         let service = Box::new(IpGeolocation);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("IpGeolocation: {:#?}", result);
@@ -168,7 +198,10 @@ mod tests {
 
         let target = "8.8.8.8".parse().ok();
         let service = Box::new(IpGeolocation);
-        let result = service.get_client(key, target).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), key, target)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("IpGeolocation: {:#?}", result);
@@ -1,12 +1,15 @@
 //! <https://ipinfo.io> lookup provider
 
-use super::{ProviderResponse, Result};
+use super::{client::Client, error::LookupError, handle_response, ProviderResponse, Result};
 use crate::{
     lookup::{LookupProvider, Provider},
     LookupResponse,
 };
 use serde::{Deserialize, Serialize};
-use std::net::{IpAddr, Ipv4Addr};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr},
+};
 
 /// <https://ipinfo.io/json>
 #[derive(Serialize, Deserialize, Debug)]
@@ -60,6 +63,7 @@ impl ProviderResponse<IpInfoResponse> for IpInfoResponse {
 /// IpInfo lookup provider
 pub struct IpInfo;
 
+#[maybe_async::maybe_async]
 impl Provider for IpInfo {
     fn get_endpoint(&self, key: &Option<String>, target: &Option<IpAddr>) -> String {
         let key = match key {
@@ -85,6 +89,48 @@ impl Provider for IpInfo {
     fn supports_target_lookup(&self) -> bool {
         true
     }
+
+    /// <https://ipinfo.io/developers/batch>
+    fn batch_size(&self) -> Option<usize> {
+        Some(1000)
+    }
+
+    async fn make_batch_request(
+        &self,
+        client: &Client,
+        key: String,
+        targets: &[IpAddr],
+    ) -> Result<String> {
+        let response = client
+            .post(format!("https://ipinfo.io/batch?token={}", key))
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(targets)?)
+            .send()
+            .await;
+        if let Ok(response) = &response {
+            crate::quota::observe(&self.get_type(), response.status(), response.headers());
+            crate::freshness::observe(&self.get_type(), response.status(), response.headers());
+        }
+        handle_response(response).await
+    }
+
+    fn parse_batch_reply(
+        &self,
+        json: String,
+        targets: &[IpAddr],
+    ) -> Result<Vec<Result<LookupResponse>>> {
+        let mut responses: HashMap<String, serde_json::Value> = serde_json::from_str(&json)?;
+        Ok(targets
+            .iter()
+            .map(|target| {
+                let response = responses.remove(&target.to_string()).ok_or_else(|| {
+                    LookupError::GenericError(format!("No batch response for {}", target))
+                })?;
+                let response: IpInfoResponse = serde_json::from_value(response)?;
+                Ok(response.into_response())
+            })
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -102,13 +148,35 @@ mod tests {
   "postal": "01101",
   "timezone": "America/New_York"
 }
+"#;
+
+    const TEST_BATCH_INPUT: &str = r#"
+{
+  "8.8.8.8": {
+    "ip": "8.8.8.8",
+    "city": "Mountain View",
+    "country": "US",
+    "loc": "37.4056,-122.0775"
+  },
+  "1.1.1.1": {
+    "ip": "1.1.1.1",
+    "city": "Brisbane",
+    "country": "AU"
+  },
+  "10.0.0.1": {
+    "error": "Bogon IP"
+  }
+}
 "#;
 
     #[ignore]
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_request() {
         let service = Box::new(IpInfo);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("IpInfo: {:#?}", result);
@@ -127,4 +195,24 @@ mod tests {
             "IP address not matching"
         );
     }
+
+    #[test]
+    fn test_parse_batch() {
+        let targets: Vec<IpAddr> = ["1.1.1.1", "8.8.8.8", "10.0.0.1", "9.9.9.9"]
+            .iter()
+            .map(|ip| ip.parse().unwrap())
+            .collect();
+        let responses = IpInfo
+            .parse_batch_reply(TEST_BATCH_INPUT.to_string(), &targets)
+            .unwrap();
+        assert_eq!(responses.len(), targets.len(), "Missing responses");
+        assert_eq!(
+            responses[0].as_ref().unwrap().city,
+            Some("Brisbane".to_string()),
+            "Responses not in the order of the targets"
+        );
+        assert_eq!(responses[1].as_ref().unwrap().ip, targets[1]);
+        assert!(responses[2].is_err(), "Error entry should fail");
+        assert!(responses[3].is_err(), "Missing entry should fail");
+    }
 }
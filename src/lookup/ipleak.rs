@@ -39,8 +39,15 @@ impl ProviderResponse<IpLeakResponse> for IpLeakResponse {
         response.country = self.country_name;
         response.country_code = self.country_code;
         response.region = self.region_name;
+        response.region_code = super::region_code(
+            response.country_code.as_deref(),
+            self.region_code.as_deref(),
+        );
         response.postal_code = self.postal_code;
-        response.continent = self.continent_name;
+        response.continent = super::continent(
+            self.continent_name.as_deref(),
+            self.continent_code.as_deref(),
+        );
         response.city = self.city_name;
         response.latitude = self.latitude;
         response.longitude = self.longitude;
@@ -115,7 +122,10 @@ mod tests {
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_request() {
         let service = Box::new(IpLeak);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("IpLeak: {:#?}", result);
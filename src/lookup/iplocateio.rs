@@ -3,6 +3,7 @@
 use super::{ProviderResponse, Result};
 use crate::{
     lookup::{LookupProvider, Provider},
+    response::Security,
     LookupResponse,
 };
 use serde::{Deserialize, Serialize};
@@ -41,8 +42,9 @@ impl ProviderResponse<IpLocateIoResponse> for IpLocateIoResponse {
             LookupProvider::IpLocateIo,
         );
         response.country = self.country;
-        response.continent = self.continent;
+        response.continent = super::continent(self.continent.as_deref(), None);
         response.country_code = self.country_code;
+        response.is_eu = self.is_eu;
         response.region = self.subdivision;
         response.postal_code = self.postal_code;
         response.city = self.city;
@@ -52,7 +54,10 @@ impl ProviderResponse<IpLocateIoResponse> for IpLocateIoResponse {
         response.asn_org = self.org;
         response.asn = self.asn;
         if let Some(threat) = self.threat {
-            response.is_proxy = threat.is_proxy;
+            response.security = Some(Security {
+                is_proxy: threat.is_proxy,
+                ..Default::default()
+            });
         }
         response
     }
@@ -112,7 +117,10 @@ mod tests {
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_request() {
         let service = Box::new(IpLocateIo);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("IpLocateIo: {:#?}", result);
@@ -3,7 +3,7 @@
 use super::{ProviderResponse, Result};
 use crate::{
     lookup::{LookupProvider, Provider},
-    LookupResponse,
+    response, LookupResponse,
 };
 use serde::{Deserialize, Serialize};
 use std::net::{IpAddr, Ipv4Addr};
@@ -13,6 +13,7 @@ use std::net::{IpAddr, Ipv4Addr};
 pub struct IpWhoIsResponse {
     ip: String,
     continent: Option<String>,
+    continent_code: Option<String>,
     region: Option<String>,
     region_code: Option<String>,
     country: Option<String>,
@@ -22,8 +23,11 @@ pub struct IpWhoIsResponse {
     longitude: Option<f64>,
     is_eu: Option<bool>,
     postal: Option<String>,
+    calling_code: Option<String>,
     connection: Option<Connection>,
     timezone: Option<Timezone>,
+    currency: Option<Currency>,
+    flag: Option<Flag>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -34,6 +38,19 @@ struct Connection {
     domain: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+struct Currency {
+    name: Option<String>,
+    code: Option<String>,
+    symbol: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Flag {
+    img: Option<String>,
+    emoji: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct Timezone {
     id: Option<String>,
@@ -47,17 +64,35 @@ impl ProviderResponse<IpWhoIsResponse> for IpWhoIsResponse {
                 .unwrap_or(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0))),
             LookupProvider::IpWhoIs,
         );
-        response.continent = self.continent;
+        response.continent =
+            super::continent(self.continent.as_deref(), self.continent_code.as_deref());
         response.region = self.region;
         response.country = self.country;
         response.country_code = self.country_code;
+        response.region_code = super::region_code(
+            response.country_code.as_deref(),
+            self.region_code.as_deref(),
+        );
+        response.is_eu = self.is_eu;
         response.postal_code = self.postal;
         response.city = self.city;
         response.latitude = self.latitude;
         response.longitude = self.longitude;
+        response.calling_code = self.calling_code.as_deref().and_then(super::calling_code);
         if let Some(timezone) = self.timezone {
             response.time_zone = timezone.id;
         }
+        if let Some(currency) = self.currency {
+            response.currency = currency.code.map(|code| response::Currency {
+                code,
+                name: currency.name,
+                symbol: currency.symbol,
+            });
+        }
+        if let Some(flag) = self.flag {
+            response.flag_emoji = flag.emoji;
+            response.flag_url = flag.img;
+        }
         if let Some(connection) = self.connection {
             response.asn_org = connection.org;
             if let Some(asn) = connection.asn {
@@ -139,7 +174,10 @@ mod tests {
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_request() {
         let service = Box::new(IpWhoIs);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("IpWhoIs: {:#?}", result);
@@ -157,5 +195,13 @@ mod tests {
             "1.1.1.1".parse::<std::net::IpAddr>().unwrap(),
             "IP address not matching"
         );
+        assert_eq!(lookup.is_eu, Some(false), "EU flag not mapped");
+        assert_eq!(lookup.continent, Some(response::Continent::NorthAmerica));
+        assert_eq!(lookup.calling_code.as_deref(), Some("+1"));
+        assert_eq!(lookup.flag_emoji.as_deref(), Some("🇺🇸"));
+        assert_eq!(
+            lookup.flag_url.as_deref(),
+            Some("https://cdn.ipwhois.io/flags/us.svg")
+        );
     }
 }
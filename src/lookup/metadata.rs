@@ -0,0 +1,205 @@
+//! Lookup provider metadata registry
+//!
+//! Static descriptions of the lookup providers, like their documented rate limits, privacy
+//! jurisdiction and key signup page, so applications can show informed choices to end users.
+//!
+//! The metadata is collected from the documentation of each provider, `None` means the
+//! information is not documented.
+
+use super::{capabilities::RateLimit, LookupProvider};
+use std::time::Duration;
+
+/// Static description of a lookup provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ProviderMetadata {
+    /// Name of the provider
+    pub name: &'static str,
+    /// Website of the provider
+    pub url: &'static str,
+    /// Documented rate limit on the free tier
+    pub rate_limit: RateLimit,
+    /// Whether the provider keeps logs of the requests
+    pub logs_requests: Option<bool>,
+    /// Whether the requests are only sent over HTTPS
+    pub https_only: bool,
+    /// ISO code of the country whose laws apply to the provider
+    pub jurisdiction: Option<&'static str>,
+    /// Page to sign up for an API key
+    pub signup_url: Option<&'static str>,
+}
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+const MONTH: u64 = 30 * DAY;
+
+const fn limited(requests: u32, period: u64) -> RateLimit {
+    RateLimit::Limited {
+        requests,
+        period: Duration::from_secs(period),
+    }
+}
+
+const fn metadata(name: &'static str, url: &'static str) -> ProviderMetadata {
+    ProviderMetadata {
+        name,
+        url,
+        rate_limit: RateLimit::Unknown,
+        logs_requests: None,
+        https_only: true,
+        jurisdiction: None,
+        signup_url: None,
+    }
+}
+
+const FREEIPAPI: ProviderMetadata = ProviderMetadata {
+    rate_limit: limited(60, MINUTE),
+    ..metadata("FreeIpApi", "https://freeipapi.com")
+};
+const IFCONFIG: ProviderMetadata = ProviderMetadata {
+    rate_limit: limited(1, MINUTE),
+    jurisdiction: Some("NO"),
+    ..metadata("IfConfig", "https://ifconfig.co")
+};
+const IPINFO: ProviderMetadata = ProviderMetadata {
+    rate_limit: limited(50000, MONTH),
+    jurisdiction: Some("US"),
+    signup_url: Some("https://ipinfo.io/signup"),
+    ..metadata("IpInfo", "https://ipinfo.io")
+};
+const MYIP: ProviderMetadata = metadata("MyIp", "https://my-ip.io");
+const IPAPICOM: ProviderMetadata = ProviderMetadata {
+    rate_limit: limited(45, MINUTE),
+    https_only: false,
+    jurisdiction: Some("DE"),
+    ..metadata("IpApiCom", "https://ip-api.com")
+};
+const IPWHOIS: ProviderMetadata = ProviderMetadata {
+    rate_limit: limited(10000, MONTH),
+    ..metadata("IpWhoIs", "https://ipwhois.io")
+};
+const IPAPICO: ProviderMetadata = ProviderMetadata {
+    rate_limit: limited(30000, MONTH),
+    jurisdiction: Some("US"),
+    ..metadata("IpApiCo", "https://ipapi.co")
+};
+const IPAPIIO: ProviderMetadata = metadata("IpApiIo", "https://ip-api.io");
+const IPBASE: ProviderMetadata = ProviderMetadata {
+    rate_limit: limited(10, HOUR),
+    jurisdiction: Some("AT"),
+    signup_url: Some("https://app.ipbase.com/register"),
+    ..metadata("IpBase", "https://ipbase.com")
+};
+const IPLOCATEIO: ProviderMetadata = ProviderMetadata {
+    rate_limit: limited(50, DAY),
+    signup_url: Some("https://www.iplocate.io/signup"),
+    ..metadata("IpLocateIo", "https://iplocate.io")
+};
+const IPLEAK: ProviderMetadata = ProviderMetadata {
+    jurisdiction: Some("IT"),
+    ..metadata("IpLeak", "https://ipleak.net")
+};
+const MULLVAD: ProviderMetadata = ProviderMetadata {
+    logs_requests: Some(false),
+    jurisdiction: Some("SE"),
+    ..metadata("Mullvad", "https://mullvad.net")
+};
+const ABSTRACTAPI: ProviderMetadata = ProviderMetadata {
+    rate_limit: limited(1000, DAY),
+    jurisdiction: Some("US"),
+    signup_url: Some("https://app.abstractapi.com/users/signup"),
+    ..metadata("AbstractApi", "https://abstractapi.com")
+};
+const IPGEOLOCATION: ProviderMetadata = ProviderMetadata {
+    rate_limit: limited(1000, DAY),
+    signup_url: Some("https://ipgeolocation.io/signup.html"),
+    ..metadata("IpGeolocation", "https://ipgeolocation.io")
+};
+const IPDATA: ProviderMetadata = ProviderMetadata {
+    rate_limit: limited(1500, DAY),
+    jurisdiction: Some("IE"),
+    signup_url: Some("https://dashboard.ipdata.co/sign-up.html"),
+    ..metadata("IpData", "https://ipdata.co")
+};
+const IP2LOCATION: ProviderMetadata = ProviderMetadata {
+    rate_limit: limited(500, DAY),
+    jurisdiction: Some("MY"),
+    signup_url: Some("https://www.ip2location.io/sign-up"),
+    ..metadata("Ip2Location", "https://www.ip2location.io")
+};
+const MYIPCOM: ProviderMetadata = ProviderMetadata {
+    rate_limit: RateLimit::Unlimited,
+    ..metadata("MyIpCom", "https://www.myip.com")
+};
+const IPIFY: ProviderMetadata = ProviderMetadata {
+    rate_limit: RateLimit::Unlimited,
+    logs_requests: Some(false),
+    jurisdiction: Some("US"),
+    ..metadata("Ipify", "https://www.ipify.org")
+};
+const GETJSONIP: ProviderMetadata = ProviderMetadata {
+    rate_limit: RateLimit::Unlimited,
+    ..metadata("GetJsonIp", "https://getjsonip.com")
+};
+const MOCK: ProviderMetadata = ProviderMetadata {
+    rate_limit: RateLimit::Unlimited,
+    ..metadata("Mock", "https://httpbin.org")
+};
+
+impl LookupProvider {
+    /// Returns the static `ProviderMetadata` of the provider.
+    ///
+    /// # Example
+    /// ```
+    /// use public_ip_address::lookup::LookupProvider;
+    ///
+    /// let metadata = LookupProvider::IpInfo.metadata();
+    /// if let Some(url) = metadata.signup_url {
+    ///     println!("Get an API key for {} at {}", metadata.name, url);
+    /// }
+    /// ```
+    pub fn metadata(&self) -> &'static ProviderMetadata {
+        match self {
+            LookupProvider::FreeIpApi => &FREEIPAPI,
+            LookupProvider::IfConfig => &IFCONFIG,
+            LookupProvider::IpInfo => &IPINFO,
+            LookupProvider::MyIp => &MYIP,
+            LookupProvider::IpApiCom => &IPAPICOM,
+            LookupProvider::IpWhoIs => &IPWHOIS,
+            LookupProvider::IpApiCo => &IPAPICO,
+            LookupProvider::IpApiIo => &IPAPIIO,
+            LookupProvider::IpBase => &IPBASE,
+            LookupProvider::IpLocateIo => &IPLOCATEIO,
+            LookupProvider::IpLeak => &IPLEAK,
+            LookupProvider::Mullvad => &MULLVAD,
+            LookupProvider::AbstractApi => &ABSTRACTAPI,
+            LookupProvider::IpGeolocation => &IPGEOLOCATION,
+            LookupProvider::IpData => &IPDATA,
+            LookupProvider::Ip2Location => &IP2LOCATION,
+            LookupProvider::MyIpCom => &MYIPCOM,
+            LookupProvider::Ipify => &IPIFY,
+            LookupProvider::GetJsonIp => &GETJSONIP,
+            LookupProvider::Mock(_) => &MOCK,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata() {
+        for provider in LookupProvider::all() {
+            let metadata = provider.metadata();
+            assert_eq!(metadata.name, provider.to_string(), "Wrong metadata entry");
+            assert!(metadata.url.starts_with("https://"));
+        }
+        assert!(!LookupProvider::IpApiCom.metadata().https_only);
+        assert_eq!(
+            LookupProvider::Mullvad.metadata().logs_requests,
+            Some(false)
+        );
+    }
+}
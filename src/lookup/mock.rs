@@ -1,6 +1,9 @@
 //! Mock lookup provider
 
-use super::Result;
+use super::{
+    client::{Client, Request},
+    Result,
+};
 use crate::{
     lookup::{LookupProvider, Provider},
     LookupResponse,
@@ -13,11 +16,17 @@ pub struct Mock {
     pub ip: String,
 }
 
+#[maybe_async::maybe_async]
 impl Provider for Mock {
     fn get_endpoint(&self, _key: &Option<String>, _target: &Option<IpAddr>) -> String {
         "https://httpbin.org/status/200".to_string()
     }
 
+    /// Returns an empty body without sending the request
+    async fn send_request(&self, _client: &Client, _request: Request) -> Result<String> {
+        Ok(String::new())
+    }
+
     fn parse_reply(&self, _json: String) -> Result<LookupResponse> {
         Ok(LookupResponse::new(
             self.ip.parse::<std::net::IpAddr>().unwrap(),
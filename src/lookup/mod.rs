@@ -20,39 +20,87 @@
 //! }
 //! ```
 
-use crate::LookupResponse;
-use client::{Client, RequestBuilder, Response};
+use crate::freshness::Observed;
+use crate::logging::{debug, trace, warn};
+use crate::{telemetry, LookupResponse};
+use client::{Client, Request, RequestBuilder, Response, StatusCode};
 use error::{LookupError, Result};
-use reqwest::StatusCode;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::{fmt, net::IpAddr, str::FromStr};
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
 
-mod client;
+pub(crate) mod client;
 
+#[cfg(feature = "provider-abstractapi")]
 pub mod abstractapi;
+pub mod bulk;
+pub mod capabilities;
+#[cfg(all(feature = "hickory-dns", not(feature = "ureq")))]
+pub mod dns;
 pub mod error;
+#[cfg(feature = "provider-freeipapi")]
 pub mod freeipapi;
+#[cfg(feature = "provider-getjsonip")]
 pub mod getjsonip;
+#[cfg(feature = "provider-ifconfig")]
 pub mod ifconfig;
+#[cfg(feature = "provider-ip2location")]
 pub mod ip2location;
+#[cfg(feature = "provider-ipapico")]
 pub mod ipapico;
+#[cfg(feature = "provider-ipapicom")]
 pub mod ipapicom;
+#[cfg(feature = "provider-ipapiio")]
 pub mod ipapiio;
+#[cfg(feature = "provider-ipbase")]
 pub mod ipbase;
+#[cfg(feature = "provider-ipdata")]
 pub mod ipdata;
+#[cfg(feature = "provider-ipgeolocation")]
 pub mod ipgeolocation;
+#[cfg(feature = "provider-ipify")]
 pub mod ipify;
+#[cfg(feature = "provider-ipinfo")]
 pub mod ipinfo;
+#[cfg(feature = "provider-ipleak")]
 pub mod ipleak;
+#[cfg(feature = "provider-iplocateio")]
 pub mod iplocateio;
+#[cfg(feature = "provider-ipwhois")]
 pub mod ipwhois;
+pub mod metadata;
 pub mod mock;
+#[cfg(feature = "provider-mullvad")]
 pub mod mullvad;
+#[cfg(feature = "provider-myip")]
 pub mod myip;
+#[cfg(feature = "provider-myipcom")]
 pub mod myipcom;
+pub mod preset;
+pub mod rotating;
+pub mod stream;
+#[cfg(all(feature = "tor", not(feature = "blocking")))]
+pub mod tor;
 
 /// Provider trait to define the methods that a provider must implement
+///
+/// The trait is async unless the `blocking` feature is enabled, sending the request is
+/// done by `send_request` which can be overridden by providers not using a plain HTTP request.
+/// The lookups always send the request built by `build_request` with `send_request`, so overriding
+/// `make_request` alone has no effect on them.
+///
+/// Both variants are generated from the same code by `maybe_async`. The async trait is an
+/// [`async_trait`](https://docs.rs/async-trait) trait, so it can be used as `dyn Provider` and its
+/// futures are `Send`: custom providers implement it with `#[async_trait::async_trait]`, or with
+/// `#[maybe_async::maybe_async]` to support both variants. The blocking variant is not derived
+/// from the async one, it sends the requests with the blocking HTTP client.
+#[maybe_async::maybe_async]
 pub trait Provider {
     /// Returns the API endpoint for the provider
     fn get_endpoint(&self, _key: &Option<String>, _target: &Option<IpAddr>) -> String;
@@ -62,9 +110,24 @@ pub trait Provider {
     fn get_type(&self) -> LookupProvider;
 
     /// Returns a request client for the provider
+    ///
+    /// Not used by the lookups, which build their requests with `build_request` and a shared client.
+    #[deprecated(
+        note = "not used by the lookups, override `build_request` and call `make_request`"
+    )]
     fn get_client(&self, key: Option<String>, target: Option<IpAddr>) -> RequestBuilder {
-        let client = Client::new().get(self.get_endpoint(&key, &target));
-        self.add_auth(client, &key)
+        self.build_request(&Client::new(), key, target)
+    }
+
+    /// Builds the request to the provider using the given client
+    fn build_request(
+        &self,
+        client: &Client,
+        key: Option<String>,
+        target: Option<IpAddr>,
+    ) -> RequestBuilder {
+        let request = client.get(self.get_endpoint(&key, &target));
+        self.add_auth(request, &key)
     }
 
     /// Add authentication header to the request
@@ -72,10 +135,83 @@ pub trait Provider {
         request
     }
 
+    /// Makes the request to the provider with the client and returns the response body
+    ///
+    /// Builds the request with `build_request` and sends it with `send_request`.
+    async fn make_request(
+        &self,
+        client: &Client,
+        key: Option<String>,
+        target: Option<IpAddr>,
+    ) -> Result<String> {
+        let request = self.build_request(client, key, target).build()?;
+        self.send_request(client, request).await
+    }
+
+    /// Sends a request built by `build_request` with the client and returns the response body
+    ///
+    /// Used by every lookup, after the `LookupService` has set its timeout and run its request hooks
+    /// on the request.
+    async fn send_request(&self, client: &Client, request: Request) -> Result<String> {
+        let response = client.execute(request).await;
+        if let Ok(response) = &response {
+            crate::quota::observe(&self.get_type(), response.status(), response.headers());
+            crate::freshness::observe(&self.get_type(), response.status(), response.headers());
+        }
+        handle_response(response).await
+    }
+
     /// Check if the provider supports target lookup
     fn supports_target_lookup(&self) -> bool {
         false
     }
+
+    /// Returns the maximum number of targets in a batch request, `None` if batches are not supported
+    fn batch_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// Makes a batch request for the targets and returns the response body
+    async fn make_batch_request(
+        &self,
+        _client: &Client,
+        _key: String,
+        _targets: &[IpAddr],
+    ) -> Result<String> {
+        Err(LookupError::GenericError(format!(
+            "{} does not support batch lookups",
+            self.get_type()
+        )))
+    }
+
+    /// Parses the response of a batch request, in the same order as the targets
+    fn parse_batch_reply(
+        &self,
+        _json: String,
+        _targets: &[IpAddr],
+    ) -> Result<Vec<Result<LookupResponse>>> {
+        Err(LookupError::GenericError(format!(
+            "{} does not support batch lookups",
+            self.get_type()
+        )))
+    }
+}
+
+/// Stand-in for a provider whose feature is not enabled
+struct Disabled(LookupProvider);
+
+impl Provider for Disabled {
+    fn get_endpoint(&self, _key: &Option<String>, _target: &Option<IpAddr>) -> String {
+        String::new()
+    }
+
+    fn parse_reply(&self, _json: String) -> Result<LookupResponse> {
+        Err(LookupError::ProviderNotEnabled(self.0.to_string()))
+    }
+
+    fn get_type(&self) -> LookupProvider {
+        self.0.clone()
+    }
 }
 
 /// ProviderResponse trait that define methods to parse the response from the provider
@@ -90,7 +226,10 @@ pub trait ProviderResponse<T: DeserializeOwned> {
 }
 
 /// Available lookup service providers
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+///
+/// Providers are deserialized from their name regardless of its case, like `"IpInfo"` or
+/// `"ipinfo"`, and the mock provider from `{"Mock": "<address>"}`.
+#[derive(Serialize, Debug, PartialEq, Clone)]
 #[non_exhaustive]
 pub enum LookupProvider {
     /// FreeIpApi provider (<https://freeipapi.com>)
@@ -141,6 +280,30 @@ impl fmt::Display for LookupProvider {
     }
 }
 
+impl<'de> Deserialize<'de> for LookupProvider {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        enum Tagged {
+            Mock(String),
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Name(String),
+            Tagged(Tagged),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Name(name) => name.parse().map_err(serde::de::Error::custom),
+            Repr::Tagged(Tagged::Mock(address)) => Ok(LookupProvider::Mock(address)),
+        }
+    }
+}
+
 impl FromStr for LookupProvider {
     type Err = LookupError;
     /// Parse a `&str` into a LookupProvider
@@ -169,7 +332,7 @@ impl FromStr for LookupProvider {
             "iplocateio" => Ok(LookupProvider::IpLocateIo),
             "ipleak" => Ok(LookupProvider::IpLeak),
             "mullvad" => Ok(LookupProvider::Mullvad),
-            "abstract" => Ok(LookupProvider::AbstractApi),
+            "abstract" | "abstractapi" => Ok(LookupProvider::AbstractApi),
             "ipgeolocation" => Ok(LookupProvider::IpGeolocation),
             "ipdata" => Ok(LookupProvider::IpData),
             "ip2location" => Ok(LookupProvider::Ip2Location),
@@ -185,29 +348,127 @@ impl FromStr for LookupProvider {
 }
 
 impl LookupProvider {
+    /// Returns every available provider, excluding the `Mock` provider.
+    ///
+    /// Providers disabled through the `provider-<name>` features are not listed.
+    ///
+    /// # Example
+    /// ```
+    /// use public_ip_address::lookup::LookupProvider;
+    ///
+    /// for provider in LookupProvider::all() {
+    ///     println!("{}", provider);
+    /// }
+    /// ```
+    pub fn all() -> Vec<LookupProvider> {
+        vec![
+            LookupProvider::FreeIpApi,
+            LookupProvider::IfConfig,
+            LookupProvider::IpInfo,
+            LookupProvider::MyIp,
+            LookupProvider::IpApiCom,
+            LookupProvider::IpWhoIs,
+            LookupProvider::IpApiCo,
+            LookupProvider::IpApiIo,
+            LookupProvider::IpBase,
+            LookupProvider::IpLocateIo,
+            LookupProvider::IpLeak,
+            LookupProvider::Mullvad,
+            LookupProvider::AbstractApi,
+            LookupProvider::IpGeolocation,
+            LookupProvider::IpData,
+            LookupProvider::Ip2Location,
+            LookupProvider::MyIpCom,
+            LookupProvider::Ipify,
+            LookupProvider::GetJsonIp,
+        ]
+        .into_iter()
+        .filter(LookupProvider::is_enabled)
+        .collect()
+    }
+
+    /// Returns every available provider without `Parameters`, ready to be used for a lookup.
+    pub fn all_with_parameters() -> Vec<(LookupProvider, Option<Parameters>)> {
+        Self::all()
+            .into_iter()
+            .map(|provider| (provider, None))
+            .collect()
+    }
+
+    /// Checks if the provider is compiled in.
+    ///
+    /// Each provider is gated behind a `provider-<name>` feature, all of them are enabled by the
+    /// default `all-providers` feature. Lookups with a disabled provider fail with
+    /// `LookupError::ProviderNotEnabled`.
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            LookupProvider::FreeIpApi => cfg!(feature = "provider-freeipapi"),
+            LookupProvider::IfConfig => cfg!(feature = "provider-ifconfig"),
+            LookupProvider::IpInfo => cfg!(feature = "provider-ipinfo"),
+            LookupProvider::MyIp => cfg!(feature = "provider-myip"),
+            LookupProvider::IpApiCom => cfg!(feature = "provider-ipapicom"),
+            LookupProvider::IpWhoIs => cfg!(feature = "provider-ipwhois"),
+            LookupProvider::IpApiCo => cfg!(feature = "provider-ipapico"),
+            LookupProvider::IpApiIo => cfg!(feature = "provider-ipapiio"),
+            LookupProvider::IpBase => cfg!(feature = "provider-ipbase"),
+            LookupProvider::IpLocateIo => cfg!(feature = "provider-iplocateio"),
+            LookupProvider::IpLeak => cfg!(feature = "provider-ipleak"),
+            LookupProvider::Mullvad => cfg!(feature = "provider-mullvad"),
+            LookupProvider::AbstractApi => cfg!(feature = "provider-abstractapi"),
+            LookupProvider::IpGeolocation => cfg!(feature = "provider-ipgeolocation"),
+            LookupProvider::IpData => cfg!(feature = "provider-ipdata"),
+            LookupProvider::Ip2Location => cfg!(feature = "provider-ip2location"),
+            LookupProvider::MyIpCom => cfg!(feature = "provider-myipcom"),
+            LookupProvider::Ipify => cfg!(feature = "provider-ipify"),
+            LookupProvider::GetJsonIp => cfg!(feature = "provider-getjsonip"),
+            LookupProvider::Mock(_) => true,
+        }
+    }
+
     /// Builds the concrete lookup service out of a LookupProvider enum
     pub fn build(self) -> Box<dyn Provider + Send + Sync> {
         match self {
+            #[cfg(feature = "provider-freeipapi")]
             LookupProvider::FreeIpApi => Box::new(freeipapi::FreeIpApi),
+            #[cfg(feature = "provider-ifconfig")]
             LookupProvider::IfConfig => Box::new(ifconfig::IfConfig),
+            #[cfg(feature = "provider-ipinfo")]
             LookupProvider::IpInfo => Box::new(ipinfo::IpInfo),
+            #[cfg(feature = "provider-myip")]
             LookupProvider::MyIp => Box::new(myip::MyIp),
+            #[cfg(feature = "provider-ipapicom")]
             LookupProvider::IpApiCom => Box::new(ipapicom::IpApiCom),
+            #[cfg(feature = "provider-ipapico")]
             LookupProvider::IpApiCo => Box::new(ipapico::IpApiCo),
+            #[cfg(feature = "provider-ipapiio")]
             LookupProvider::IpApiIo => Box::new(ipapiio::IpApiIo),
+            #[cfg(feature = "provider-ipwhois")]
             LookupProvider::IpWhoIs => Box::new(ipwhois::IpWhoIs),
+            #[cfg(feature = "provider-ipbase")]
             LookupProvider::IpBase => Box::new(ipbase::IpBase),
+            #[cfg(feature = "provider-iplocateio")]
             LookupProvider::IpLocateIo => Box::new(iplocateio::IpLocateIo),
+            #[cfg(feature = "provider-ipleak")]
             LookupProvider::IpLeak => Box::new(ipleak::IpLeak),
+            #[cfg(feature = "provider-mullvad")]
             LookupProvider::Mullvad => Box::new(mullvad::Mullvad),
+            #[cfg(feature = "provider-abstractapi")]
             LookupProvider::AbstractApi => Box::new(abstractapi::AbstractApi),
+            #[cfg(feature = "provider-ipgeolocation")]
             LookupProvider::IpGeolocation => Box::new(ipgeolocation::IpGeolocation),
+            #[cfg(feature = "provider-ipdata")]
             LookupProvider::IpData => Box::new(ipdata::IpData),
+            #[cfg(feature = "provider-ip2location")]
             LookupProvider::Ip2Location => Box::new(ip2location::Ip2Location),
+            #[cfg(feature = "provider-myipcom")]
             LookupProvider::MyIpCom => Box::new(myipcom::MyIpCom),
+            #[cfg(feature = "provider-ipify")]
             LookupProvider::Ipify => Box::new(ipify::Ipify),
+            #[cfg(feature = "provider-getjsonip")]
             LookupProvider::GetJsonIp => Box::new(getjsonip::GetJsonIp),
             LookupProvider::Mock(ip) => Box::new(mock::Mock { ip }),
+            #[allow(unreachable_patterns)]
+            provider => Box::new(Disabled(provider)),
         }
     }
 
@@ -216,8 +477,7 @@ impl LookupProvider {
     /// This function parses a `&str` into a LookupProvider enum variant and extracts the API key as parameter if it exists.
     /// The `&str` should be formatted as `<provider> <api_key>` or `<provider>`.
     pub fn from_str_with_params(s: &str) -> Result<(LookupProvider, Option<Parameters>)> {
-        let s = s.trim().to_lowercase();
-        // split the string into parts
+        // split the string into parts, keeping the case of the key
         let s = s
             .split_whitespace()
             .map(str::to_string)
@@ -248,6 +508,89 @@ impl Parameters {
     }
 }
 
+/// IP version used to connect to the lookup providers
+///
+/// On dual-stack hosts, the public address found depends on the IP version used to reach the
+/// provider. Forcing `V4` or `V6` binds the connection to the unspecified address of that family,
+/// so the provider sees, and returns, the public address of the chosen family.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[non_exhaustive]
+pub enum IpVersion {
+    /// Use the IP version preferred by the operating system
+    #[default]
+    Any,
+    /// Connect over IPv4 only
+    V4,
+    /// Connect over IPv6 only
+    V6,
+}
+
+impl IpVersion {
+    /// Returns the local address to bind the connection to, `None` for `Any`.
+    pub fn local_address(&self) -> Option<IpAddr> {
+        match self {
+            IpVersion::Any => None,
+            IpVersion::V4 => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            IpVersion::V6 => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        }
+    }
+
+    /// Checks if the address belongs to this IP version.
+    pub fn matches(&self, ip: &IpAddr) -> bool {
+        match self {
+            IpVersion::Any => true,
+            IpVersion::V4 => ip.is_ipv4(),
+            IpVersion::V6 => ip.is_ipv6(),
+        }
+    }
+}
+
+impl fmt::Display for IpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Supplies API keys to a `LookupService`
+///
+/// Unlike static `Parameters`, a `KeyProvider` can fetch keys from a secret store and
+/// hand out new keys when they are rotated. When the lookup provider rejects a key with
+/// a `LookupError::InvalidApiKey` error, the key is refreshed and the request retried once.
+///
+/// The methods are called from the lookup, implementations should cache the key
+/// instead of fetching it on every call.
+///
+/// Closures returning an `Option<String>` implement `KeyProvider`.
+///
+/// # Example
+/// ```
+/// use public_ip_address::lookup::{LookupProvider, LookupService};
+/// use std::sync::Arc;
+///
+/// let mut service = LookupService::new(LookupProvider::IpData, None);
+/// service.set_key_provider(Arc::new(|| std::env::var("IPDATA_KEY").ok()));
+/// ```
+pub trait KeyProvider: Send + Sync {
+    /// Returns the current API key.
+    fn api_key(&self) -> Option<String>;
+
+    /// Returns a new API key after the current one was rejected.
+    ///
+    /// Defaults to fetching the current key again.
+    fn refresh(&self) -> Option<String> {
+        self.api_key()
+    }
+}
+
+impl<F> KeyProvider for F
+where
+    F: Fn() -> Option<String> + Send + Sync,
+{
+    fn api_key(&self) -> Option<String> {
+        self()
+    }
+}
+
 /// LookupService instance to handle the lookup process
 ///
 /// # Example
@@ -260,6 +603,24 @@ impl Parameters {
 pub struct LookupService {
     provider: Box<dyn Provider + Send + Sync>,
     parameters: Option<Parameters>,
+    key_provider: Option<Arc<dyn KeyProvider>>,
+    ip_version: IpVersion,
+    ip_only: bool,
+    reject_non_global: bool,
+    keep_raw: bool,
+    local_address: Option<IpAddr>,
+    interface: Option<String>,
+    user_agent: Option<String>,
+    headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    retries: u32,
+    request_hooks: Vec<RequestHook>,
+    response_hooks: Vec<ResponseHook>,
+    client: Option<Client>,
+    #[cfg(all(feature = "hickory-dns", not(feature = "ureq")))]
+    dns_resolver: Option<dns::DnsResolver>,
+    #[cfg(all(feature = "tor", not(feature = "blocking")))]
+    tor_client: Option<tor::TorClient>,
 }
 
 impl LookupService {
@@ -268,9 +629,92 @@ impl LookupService {
         LookupService {
             provider: provider.build(),
             parameters,
+            key_provider: None,
+            ip_version: IpVersion::Any,
+            ip_only: false,
+            reject_non_global: false,
+            keep_raw: false,
+            local_address: None,
+            interface: None,
+            user_agent: None,
+            headers: Vec::new(),
+            timeout: None,
+            retries: 0,
+            request_hooks: Vec::new(),
+            response_hooks: Vec::new(),
+            client: None,
+            #[cfg(all(feature = "hickory-dns", not(feature = "ureq")))]
+            dns_resolver: None,
+            #[cfg(all(feature = "tor", not(feature = "blocking")))]
+            tor_client: None,
         }
     }
 
+    /// Creates a new `LookupService` instance making its requests with the given client.
+    ///
+    /// The client is used as is, so proxies, TLS settings, timeouts or default headers configured
+    /// on it apply to the lookups. The user agent, headers and timeout of the service are added to
+    /// each request, the local address, interface and IP version settings of the service are
+    /// ignored for a custom client. With the `blocking` feature, the client is a
+    /// `reqwest::blocking::Client`. With the `ureq` feature, a `ureq::Agent` is converted with
+    /// `agent.into()`, it must be configured with `http_status_as_error(false)`.
+    ///
+    /// # Example
+    /// ```
+    /// use public_ip_address::lookup::{LookupProvider, LookupService};
+    /// use std::time::Duration;
+    ///
+    /// # #[cfg(not(feature = "blocking"))]
+    /// let client = reqwest::Client::builder()
+    ///     .timeout(Duration::from_secs(2))
+    ///     .build()
+    ///     .unwrap();
+    /// # #[cfg(all(feature = "blocking", not(feature = "ureq")))]
+    /// # let client = reqwest::blocking::Client::builder()
+    /// #     .timeout(Duration::from_secs(2))
+    /// #     .build()
+    /// #     .unwrap();
+    /// # #[cfg(feature = "ureq")]
+    /// # let client = ureq::Agent::config_builder()
+    /// #     .http_status_as_error(false)
+    /// #     .timeout_global(Some(Duration::from_secs(2)))
+    /// #     .build()
+    /// #     .new_agent()
+    /// #     .into();
+    /// let service = LookupService::with_client(client, LookupProvider::IpWhoIs);
+    /// ```
+    pub fn with_client(client: Client, provider: LookupProvider) -> Self {
+        let mut service = Self::new(provider, None);
+        service.set_client(client);
+        service
+    }
+
+    /// Sets the client used to make the requests of the LookupService
+    pub fn set_client(&mut self, client: Client) -> &Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Sets the DNS resolver used to resolve the hostname of the lookup provider
+    ///
+    /// The hostname is then resolved by the resolver instead of the system resolver. The resolver
+    /// is ignored for a custom client.
+    #[cfg(all(feature = "hickory-dns", not(feature = "ureq")))]
+    pub fn set_dns_resolver(&mut self, dns_resolver: dns::DnsResolver) -> &Self {
+        self.dns_resolver = Some(dns_resolver);
+        self
+    }
+
+    /// Sets the Tor client the requests of the LookupService are routed through
+    ///
+    /// The lookup then returns the address of the Tor exit relay. The client, local address,
+    /// interface and IP version settings of the service are ignored for requests through Tor.
+    #[cfg(all(feature = "tor", not(feature = "blocking")))]
+    pub fn set_tor_client(&mut self, tor_client: tor::TorClient) -> &Self {
+        self.tor_client = Some(tor_client);
+        self
+    }
+
     /// Changes the provider for the LookupService
     pub fn set_provider(&mut self, provider: LookupProvider) -> &Self {
         self.provider = provider.build();
@@ -283,6 +727,200 @@ impl LookupService {
         self
     }
 
+    /// Sets the `KeyProvider` supplying the API keys for the LookupService
+    ///
+    /// Keys from the key provider take precedence over the key set in the parameters.
+    pub fn set_key_provider(&mut self, key_provider: Arc<dyn KeyProvider>) -> &Self {
+        self.key_provider = Some(key_provider);
+        self
+    }
+
+    /// Sets the IP version used to connect to the lookup provider
+    pub fn set_ip_version(&mut self, ip_version: IpVersion) -> &Self {
+        self.ip_version = ip_version;
+        self
+    }
+
+    /// Sets whether only the address is looked up, see [`Capabilities::ip_only`](capabilities::Capabilities::ip_only)
+    ///
+    /// The lookups then fail with a `LookupError::IpOnlyNotSupported` error, without sending any
+    /// request, when the provider returns geolocation information.
+    pub fn set_ip_only(&mut self, ip_only: bool) -> &Self {
+        self.ip_only = ip_only;
+        self
+    }
+
+    /// Returns whether only the address is looked up.
+    pub fn get_ip_only(&self) -> bool {
+        self.ip_only
+    }
+
+    /// Sets whether the lookups returning a non-global address fail, see [`is_global_ip`](crate::response::is_global_ip)
+    ///
+    /// Providers occasionally echo the internal address of a proxy. By default these responses are
+    /// kept and flagged with [`Security::is_bogon`](crate::response::Security::is_bogon), when
+    /// rejected the lookups fail with a `NonGlobalAddress` error and the fallback chains try the
+    /// next provider.
+    pub fn set_reject_non_global(&mut self, reject_non_global: bool) -> &Self {
+        self.reject_non_global = reject_non_global;
+        self
+    }
+
+    /// Returns whether the lookups returning a non-global address fail.
+    pub fn get_reject_non_global(&self) -> bool {
+        self.reject_non_global
+    }
+
+    /// Sets whether the lookups keep the raw body of the provider responses in [`LookupResponse::raw`].
+    ///
+    /// Disabled by default, the raw bodies are cached with the responses and can be large.
+    pub fn set_keep_raw(&mut self, keep_raw: bool) -> &Self {
+        self.keep_raw = keep_raw;
+        self
+    }
+
+    /// Returns whether the lookups keep the raw body of the provider responses.
+    pub fn get_keep_raw(&self) -> bool {
+        self.keep_raw
+    }
+
+    /// Returns the IP version used to connect to the lookup provider.
+    pub fn get_ip_version(&self) -> IpVersion {
+        self.ip_version
+    }
+
+    /// Sets the local address the connections to the lookup provider are made from
+    ///
+    /// On multi-homed hosts, this looks up the public address of the network the local address
+    /// belongs to. The local address takes precedence over the IP version.
+    pub fn set_local_address(&mut self, local_address: IpAddr) -> &Self {
+        self.local_address = Some(local_address);
+        self
+    }
+
+    /// Returns the local address the connections are made from, if set.
+    pub fn get_local_address(&self) -> Option<IpAddr> {
+        self.local_address
+    }
+
+    /// Sets the network interface the connections to the lookup provider are bound to, like `eth1` or `wg0`
+    ///
+    /// This looks up the public address seen when egressing through the interface, for example
+    /// through a VPN tunnel. Binding to an interface is supported on Linux, Android, Fuchsia,
+    /// illumos, Solaris and the Apple platforms, elsewhere the lookup fails.
+    pub fn set_interface(&mut self, interface: String) -> &Self {
+        self.interface = Some(interface);
+        self
+    }
+
+    /// Returns the network interface the connections are bound to, if set.
+    pub fn get_interface(&self) -> Option<&str> {
+        self.interface.as_deref()
+    }
+
+    /// Sets the `User-Agent` header sent to the lookup provider
+    ///
+    /// Some providers throttle or vary their responses by user agent. Providers requiring a
+    /// specific user agent keep their own.
+    pub fn set_user_agent(&mut self, user_agent: String) -> &Self {
+        self.user_agent = Some(user_agent);
+        self
+    }
+
+    /// Returns the `User-Agent` header sent to the lookup provider, if set.
+    pub fn get_user_agent(&self) -> Option<&str> {
+        self.user_agent.as_deref()
+    }
+
+    /// Adds a header sent with every request to the lookup provider, like an `Authorization`
+    /// header for a self-hosted gateway
+    ///
+    /// Headers set by the provider itself take precedence. An invalid header name or value fails
+    /// the lookup.
+    pub fn add_header(&mut self, name: String, value: String) -> &Self {
+        self.headers.push((name, value));
+        self
+    }
+
+    /// Returns the headers added to the requests to the lookup provider.
+    pub fn get_headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Sets the timeout of the requests to the lookup provider, from connecting to reading the response
+    ///
+    /// The timeout is set on each request, so it also applies to a custom client.
+    pub fn set_timeout(&mut self, timeout: Duration) -> &Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Returns the timeout of the requests to the lookup provider, if set.
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Sets how many times a failed lookup is retried by the fallback lookups, like [`perform_lookup_with`](crate::perform_lookup_with)
+    ///
+    /// Only the retryable errors are retried right away, see [`LookupError::is_retryable`], except
+    /// the rate limits. No lookup is retried by default, the next provider is tried instead.
+    pub fn set_retries(&mut self, retries: u32) -> &Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Returns how many times a failed lookup is retried.
+    pub fn get_retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// Registers a hook called with every request before it's sent to the lookup provider
+    ///
+    /// Hooks can modify the request, for example to sign it or add tracing headers, or log the
+    /// outbound calls. They run in registration order, after the provider built the request.
+    ///
+    /// # Example
+    /// ```
+    /// use public_ip_address::lookup::{LookupProvider, LookupService};
+    ///
+    /// let mut service = LookupService::new(LookupProvider::IpWhoIs, None);
+    /// service.on_request(|request| {
+    ///     println!("Requesting {}", request.url());
+    ///     request
+    ///         .headers_mut()
+    ///         .insert("X-Request-Id", "42".parse().unwrap());
+    /// });
+    /// ```
+    pub fn on_request<F>(&mut self, hook: F) -> &Self
+    where
+        F: Fn(&mut Request) + Send + Sync + 'static,
+    {
+        self.request_hooks.push(Arc::new(hook));
+        self
+    }
+
+    /// Registers a hook called with every response after it's parsed
+    ///
+    /// Hooks can normalize, redact or augment the response, and run in registration order after the
+    /// global [`hooks`](crate::hooks). Lookups return the post-processed response.
+    ///
+    /// # Example
+    /// ```
+    /// use public_ip_address::lookup::{LookupProvider, LookupService};
+    ///
+    /// let mut service = LookupService::new(LookupProvider::IpWhoIs, None);
+    /// service.on_response(|response| {
+    ///     response.city = None;
+    /// });
+    /// ```
+    pub fn on_response<F>(&mut self, hook: F) -> &Self
+    where
+        F: Fn(&mut LookupResponse) + Send + Sync + 'static,
+    {
+        self.response_hooks.push(Arc::new(hook));
+        self
+    }
+
     /// Returns the type of the current lookup provider.
     ///
     /// This function returns the `LookupProvider` enum variant that represents the type of the current lookup provider.
@@ -294,39 +932,320 @@ impl LookupService {
     ///
     /// This function makes an API request to the current lookup provider and parses the response into a `LookupResponse` instance.
     #[maybe_async::maybe_async]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(provider = %self.provider.get_type()))
+    )]
     pub async fn lookup(&self, target: Option<IpAddr>) -> Result<LookupResponse> {
+        self.check(target)?;
+        let start = Instant::now();
+        let (response, observed) = self.request(target).await;
+        let response = match response {
+            Ok(body) => {
+                let latency = start.elapsed();
+                match self.provider.parse_reply(body.clone()) {
+                    Ok(mut response) => {
+                        response.fresh_until = observed.fresh_until;
+                        self.finish(response, latency, self.keep_raw.then_some(body))
+                    }
+                    Err(e) => Err(e.with_response(self.provider.get_type(), observed, &body)),
+                }
+            }
+            Err(e) => Err(e),
+        };
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_request(
+            &self.provider.get_type(),
+            response.is_ok(),
+            start.elapsed(),
+        );
+        match &response {
+            Ok(_) => trace!("Lookup completed in {:?}", start.elapsed()),
+            Err(e) => {
+                debug!("Lookup failed after {:?}: {}", start.elapsed(), e);
+                telemetry::report_error(&self.provider.get_type(), e, start.elapsed());
+            }
+        }
+        response
+    }
+
+    /// Checks that the lookup can be made with the provider, before sending any request
+    fn check(&self, target: Option<IpAddr>) -> Result<()> {
+        if !self.provider.get_type().is_enabled() {
+            return Err(LookupError::ProviderNotEnabled(
+                self.provider.get_type().to_string(),
+            ));
+        }
         if target.is_some() && !self.provider.supports_target_lookup() {
             return Err(LookupError::TargetNotSupported);
         }
-        let response = self.make_api_request(target).await?;
-        self.provider.parse_reply(response)
+        if self.ip_only && !self.provider.get_type().capabilities().ip_only {
+            return Err(LookupError::IpOnlyNotSupported);
+        }
+        if self.api_key().is_none() && self.provider.get_type().capabilities().requires_key {
+            return Err(LookupError::MissingApiKey);
+        }
+        if let Some(reset) = crate::quota::rate_limited_until(&self.provider.get_type()) {
+            let remaining = reset.duration_since(SystemTime::now()).unwrap_or_default();
+            debug!("Skipping rate limited provider for another {:?}", remaining);
+            return Err(LookupError::TooManyRequests(format!(
+                "Rate limited for another {}s",
+                remaining.as_secs()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Completes a parsed response, with the checks, normalization and hooks of every lookup
+    fn finish(
+        &self,
+        mut response: LookupResponse,
+        latency: Duration,
+        raw: Option<String>,
+    ) -> Result<LookupResponse> {
+        if !response.is_global() {
+            warn!("Provider returned the non-global address {}", response.ip);
+            if self.reject_non_global {
+                return Err(LookupError::NonGlobalAddress(response.ip));
+            }
+            response
+                .security
+                .get_or_insert_with(Default::default)
+                .is_bogon = Some(true);
+        }
+        #[cfg(feature = "iso3166")]
+        if crate::response::normalize_countries() {
+            response.normalize_country();
+        } else {
+            response.fill_country_code();
+        }
+        response.normalize_region();
+        response.looked_up_at = Some(SystemTime::now());
+        response.latency = Some(latency);
+        response.raw = raw;
+        if response.flag_emoji.is_none() {
+            response.flag_emoji = response
+                .country_code
+                .as_deref()
+                .and_then(crate::response::flag_emoji);
+        }
+        crate::hooks::process_response(&mut response);
+        for hook in &self.response_hooks {
+            hook(&mut response);
+        }
+        Ok(response)
     }
 
-    /// Internal function to make the API request
+    /// Makes the request to the lookup provider, returning the raw response body, its status and its freshness
+    #[maybe_async::async_impl]
+    async fn request(&self, target: Option<IpAddr>) -> (Result<String>, Observed) {
+        crate::freshness::scope(self.make_request(target)).await
+    }
+
+    /// Makes the request to the lookup provider, returning the raw response body, its status and its freshness
+    #[maybe_async::sync_impl]
+    fn request(&self, target: Option<IpAddr>) -> (Result<String>, Observed) {
+        crate::freshness::scope(|| self.make_request(target))
+    }
+
+    /// Makes the request to the lookup provider and returns the raw response body
+    ///
+    /// A key rejected by the provider is refreshed through the key provider and the request retried once.
     #[maybe_async::maybe_async]
-    async fn make_api_request(&self, target: Option<IpAddr>) -> Result<String> {
-        let key = self.parameters.as_ref().map(|p| p.api_key.clone());
-        let response = self.provider.get_client(key, target).send().await;
-        handle_response(response).await
+    pub async fn make_request(&self, target: Option<IpAddr>) -> Result<String> {
+        let client = self.client()?;
+        let response = self.send(&client, self.api_key(), target).await;
+        match (response, &self.key_provider) {
+            (Err(LookupError::InvalidApiKey(_)), Some(key_provider)) => {
+                warn!("API key rejected, retrying with a refreshed key");
+                self.send(&client, key_provider.refresh(), target).await
+            }
+            (response, _) => response,
+        }
+    }
+
+    /// Sends the request to the provider with the client, or through Tor if a Tor client is set
+    #[maybe_async::maybe_async]
+    async fn send(
+        &self,
+        client: &Client,
+        key: Option<String>,
+        target: Option<IpAddr>,
+    ) -> Result<String> {
+        #[cfg(all(feature = "tor", not(feature = "blocking")))]
+        if let Some(tor_client) = &self.tor_client {
+            let request = self.build_request(client, key, target)?;
+            let mut headers = self.headers.clone();
+            if let Some(user_agent) = &self.user_agent {
+                headers.push(("User-Agent".to_string(), user_agent.clone()));
+            }
+            // boxed, the Tor stream futures are too deep to be inlined in every lookup future
+            return Box::pin(tor::send(tor_client, request, &headers)).await;
+        }
+        let request = self.build_request(client, key, target)?;
+        self.provider.send_request(client, request).await
+    }
+
+    /// Builds the request to the provider with the timeout, and the user agent and headers for a
+    /// custom client, and runs the request hooks on it
+    fn build_request(
+        &self,
+        client: &Client,
+        key: Option<String>,
+        target: Option<IpAddr>,
+    ) -> Result<Request> {
+        let builder = self.provider.build_request(client, key, target);
+        // set on the request, so the shared clients don't depend on the timeout
+        let builder = match self.timeout {
+            Some(timeout) => builder.timeout(timeout),
+            None => builder,
+        };
+        let mut request = builder.build()?;
+        if self.client.is_some() {
+            let mut headers = self.headers.clone();
+            if let Some(user_agent) = &self.user_agent {
+                headers.push(("User-Agent".to_string(), user_agent.clone()));
+            }
+            let headers = client::header_map(&headers)?;
+            for name in headers.keys() {
+                if !request.headers().contains_key(name) {
+                    for value in headers.get_all(name) {
+                        request.headers_mut().append(name, value.clone());
+                    }
+                }
+            }
+        }
+        for hook in &self.request_hooks {
+            hook(&mut request);
+        }
+        Ok(request)
+    }
+
+    /// Returns the custom client, or the shared client connecting from the configured local address,
+    /// interface and IP version, with the configured DNS resolver
+    fn client(&self) -> Result<Client> {
+        match &self.client {
+            Some(client) => Ok(client.clone()),
+            None => client::shared(client::Binding {
+                local_address: self.local_address.or(self.ip_version.local_address()),
+                interface: self.interface.clone(),
+                user_agent: self.user_agent.clone(),
+                headers: self.headers.clone(),
+                #[cfg(all(feature = "hickory-dns", not(feature = "ureq")))]
+                resolver: self.dns_resolver.clone(),
+                #[cfg(not(feature = "blocking"))]
+                runtime: None,
+            }),
+        }
+    }
+
+    /// Returns the API key to use, from the key provider or the parameters
+    fn api_key(&self) -> Option<String> {
+        match &self.key_provider {
+            Some(key_provider) => key_provider.api_key(),
+            None => self.parameters.as_ref().map(|p| p.api_key.clone()),
+        }
     }
 }
 
-/// Handles the response from reqwest
+/// Hook called with every request before it's sent, see [`LookupService::on_request`]
+type RequestHook = Arc<dyn Fn(&mut Request) + Send + Sync>;
+
+/// Hook called with every parsed response, see [`LookupService::on_response`]
+type ResponseHook = crate::hooks::ResponseHook;
+
+/// Handles the response from the HTTP client
 #[maybe_async::maybe_async]
-pub async fn handle_response(response: reqwest::Result<Response>) -> Result<String> {
+pub async fn handle_response(
+    response: std::result::Result<Response, client::Error>,
+) -> Result<String> {
     match response {
         Ok(response) => match response.status() {
             StatusCode::OK => Ok(response.text().await?),
-            StatusCode::TOO_MANY_REQUESTS => Err(LookupError::TooManyRequests(format!(
-                "Too many requests: {}",
-                response.status()
-            ))),
-            s => Err(LookupError::RequestStatus(format!("Status: {}", s))),
+            status => Err(status_error(status)),
         },
-        Err(e) => Err(LookupError::ReqwestError(e)),
+        Err(e) => Err(e.into()),
     }
 }
 
+/// Returns the error for a response status other than `200 OK`
+pub(crate) fn status_error(status: StatusCode) -> LookupError {
+    warn!("Provider responded with status {}", status);
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            LookupError::InvalidApiKey(format!("Invalid API key: {}", status))
+        }
+        StatusCode::TOO_MANY_REQUESTS => {
+            LookupError::TooManyRequests(format!("Too many requests: {}", status))
+        }
+        s => LookupError::RequestStatus(format!("Status: {}", s)),
+    }
+}
+
+/// Normalizes a calling code of a provider response to the "+1" form, `None` if it's empty
+#[cfg(any(
+    feature = "provider-ipapico",
+    feature = "provider-ipapiio",
+    feature = "provider-ipbase",
+    feature = "provider-ipdata",
+    feature = "provider-ipgeolocation",
+    feature = "provider-ipwhois",
+))]
+pub(crate) fn calling_code(code: &str) -> Option<String> {
+    let code = code.trim().trim_start_matches('+');
+    (!code.is_empty()).then(|| format!("+{}", code))
+}
+
+/// Parses the continent of a provider response from its name, or from its code when the name is unknown
+#[cfg(any(
+    feature = "provider-abstractapi",
+    feature = "provider-freeipapi",
+    feature = "provider-ipapico",
+    feature = "provider-ipapicom",
+    feature = "provider-ipbase",
+    feature = "provider-ipdata",
+    feature = "provider-ipgeolocation",
+    feature = "provider-ipleak",
+    feature = "provider-iplocateio",
+    feature = "provider-ipwhois",
+))]
+pub(crate) fn continent(
+    name: Option<&str>,
+    code: Option<&str>,
+) -> Option<crate::response::Continent> {
+    name.and_then(|name| name.parse().ok())
+        .or_else(|| code.and_then(|code| code.parse().ok()))
+}
+
+/// Normalizes the region code of a provider response to its ISO 3166-2 form, see [`crate::response::region_code`]
+#[cfg(any(
+    feature = "provider-abstractapi",
+    feature = "provider-ifconfig",
+    feature = "provider-ipapico",
+    feature = "provider-ipapicom",
+    feature = "provider-ipapiio",
+    feature = "provider-ipbase",
+    feature = "provider-ipdata",
+    feature = "provider-ipgeolocation",
+    feature = "provider-ipleak",
+    feature = "provider-ipwhois",
+))]
+pub(crate) fn region_code(country_code: Option<&str>, code: Option<&str>) -> Option<String> {
+    crate::response::region_code(country_code?, code?)
+}
+
+/// Splits a comma separated list of a provider response, `None` if it's empty
+#[cfg(any(feature = "provider-ipapico", feature = "provider-ipgeolocation"))]
+pub(crate) fn split_list(list: &str) -> Option<Vec<String>> {
+    let items: Vec<String> = list
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect();
+    (!items.is_empty()).then_some(items)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -339,6 +1258,261 @@ mod tests {
         assert_eq!(provider.get_provider_type(), LookupProvider::IpInfo);
     }
 
+    #[test]
+    fn test_ip_version() {
+        let v4 = "1.1.1.1".parse::<IpAddr>().unwrap();
+        let v6 = "2606:4700:4700::1111".parse::<IpAddr>().unwrap();
+        assert_eq!(IpVersion::Any.local_address(), None);
+        assert!(IpVersion::V4.local_address().unwrap().is_ipv4());
+        assert!(IpVersion::V6.local_address().unwrap().is_ipv6());
+        assert!(IpVersion::Any.matches(&v4) && IpVersion::Any.matches(&v6));
+        assert!(IpVersion::V4.matches(&v4) && !IpVersion::V4.matches(&v6));
+        assert!(IpVersion::V6.matches(&v6) && !IpVersion::V6.matches(&v4));
+
+        let mut service = LookupService::new(LookupProvider::IpInfo, None);
+        assert_eq!(service.get_ip_version(), IpVersion::Any);
+        service.set_ip_version(IpVersion::V4);
+        assert_eq!(service.get_ip_version(), IpVersion::V4);
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_with_client() {
+        let service =
+            LookupService::with_client(Client::new(), LookupProvider::Mock("1.1.1.1".to_string()));
+        assert!(service.client.is_some(), "Client not set");
+        let response = service.lookup(None).await;
+        assert!(response.is_ok(), "Lookup should succeed");
+    }
+
+    #[test]
+    fn test_with_client_headers() {
+        let client = Client::new();
+        let mut service =
+            LookupService::with_client(client.clone(), LookupProvider::Mock("1.1.1.1".to_string()));
+        service.set_user_agent("public-ip-address-test".to_string());
+        service.add_header("X-Gateway".to_string(), "token".to_string());
+        let request = service.build_request(&client, None, None).unwrap();
+        assert_eq!(
+            request.headers()["User-Agent"],
+            "public-ip-address-test",
+            "User agent not sent with a custom client"
+        );
+        assert_eq!(
+            request.headers()["X-Gateway"],
+            "token",
+            "Header not sent with a custom client"
+        );
+    }
+
+    // the ureq backend only binds to unspecified addresses
+    #[cfg(not(feature = "ureq"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_local_address() {
+        let local = "127.0.0.1".parse::<IpAddr>().unwrap();
+        let mut service = LookupService::new(LookupProvider::Mock("1.1.1.1".to_string()), None);
+        assert_eq!(service.get_local_address(), None);
+        service.set_local_address(local);
+        assert_eq!(service.get_local_address(), Some(local));
+        let response = service.lookup(None).await;
+        assert!(response.is_ok(), "Lookup should succeed");
+        service.set_interface("lo".to_string());
+        assert_eq!(service.get_interface(), Some("lo"));
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_headers() {
+        let mut service = LookupService::new(LookupProvider::Mock("1.1.1.1".to_string()), None);
+        service.set_user_agent("public-ip-address-test".to_string());
+        service.add_header("Authorization".to_string(), "Bearer token".to_string());
+        assert_eq!(service.get_user_agent(), Some("public-ip-address-test"));
+        assert_eq!(service.get_headers().len(), 1);
+        let response = service.lookup(None).await;
+        assert!(response.is_ok(), "Lookup should succeed");
+
+        service.add_header("Invalid Header".to_string(), "value".to_string());
+        let response = service.lookup(None).await;
+        assert!(response.is_err(), "Invalid header should fail the lookup");
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_non_global() {
+        let mut service = LookupService::new(LookupProvider::Mock("192.168.1.1".to_string()), None);
+        let response = service.lookup(None).await.unwrap();
+        let is_bogon = response.security.and_then(|security| security.is_bogon);
+        assert_eq!(is_bogon, Some(true), "Private address not flagged");
+
+        service.set_reject_non_global(true);
+        let response = service.lookup(None).await;
+        assert!(
+            matches!(response, Err(LookupError::NonGlobalAddress(_))),
+            "Private address not rejected {:?}",
+            response
+        );
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_keep_raw() {
+        let mut service = LookupService::new(LookupProvider::Mock("1.1.1.1".to_string()), None);
+        let response = service.lookup(None).await;
+        assert_eq!(response.unwrap().raw, None);
+        service.set_keep_raw(true);
+        let response = service.lookup(None).await;
+        assert_eq!(
+            response.unwrap().raw.as_deref(),
+            Some(""),
+            "Raw body not kept"
+        );
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_ip_only() {
+        let mut service = LookupService::new(LookupProvider::IpInfo, None);
+        service.set_ip_only(true);
+        let response = service.lookup(None).await;
+        assert!(
+            matches!(response, Err(LookupError::IpOnlyNotSupported)),
+            "Geolocation provider used {:?}",
+            response
+        );
+
+        let mut service = LookupService::new(LookupProvider::Mock("1.1.1.1".to_string()), None);
+        service.set_ip_only(true);
+        let response = service.lookup(None).await;
+        assert!(response.is_ok(), "IP-only provider rejected {:?}", response);
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_looked_up_at() {
+        let before = SystemTime::now();
+        let service = LookupService::new(LookupProvider::Mock("1.1.1.1".to_string()), None);
+        let response = service.lookup(None).await.unwrap();
+        let looked_up_at = response.looked_up_at.expect("Lookup time not recorded");
+        assert!(looked_up_at >= before, "Lookup time is before the lookup");
+        assert!(response.latency.is_some(), "Latency not recorded");
+        assert!(response.to_string().contains("Looked up at: "));
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_request_hooks() {
+        use std::sync::Mutex;
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut service = LookupService::new(LookupProvider::Mock("1.1.1.1".to_string()), None);
+        service.on_request(|request| {
+            request
+                .headers_mut()
+                .insert("X-Request-Id", "42".parse().unwrap());
+        });
+        let hook_seen = seen.clone();
+        service.on_request(move |request| {
+            let id = request.headers().get("X-Request-Id").cloned();
+            hook_seen
+                .lock()
+                .unwrap()
+                .push((request.url().to_string(), id));
+        });
+        let response = service.lookup(None).await;
+        assert!(response.is_ok(), "Lookup should succeed");
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1, "Hook not called");
+        assert_eq!(seen[0].0, "https://httpbin.org/status/200");
+        assert!(seen[0].1.is_some(), "Hooks should run in order");
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_response_hooks() {
+        let mut service = LookupService::new(LookupProvider::Mock("1.1.1.1".to_string()), None);
+        service.on_response(|response| {
+            response.city = Some("REDACTED".to_string());
+        });
+        service.on_response(|response| {
+            assert_eq!(response.city.as_deref(), Some("REDACTED"));
+            response.asn = Some("AS13335".to_string());
+        });
+        let response = service.lookup(None).await.unwrap();
+        assert_eq!(response.city.as_deref(), Some("REDACTED"));
+        assert_eq!(response.asn.as_deref(), Some("AS13335"));
+    }
+
+    /// Provider answering every request with an error object
+    struct ErrorReply;
+
+    #[maybe_async::maybe_async]
+    impl Provider for ErrorReply {
+        fn get_endpoint(&self, _key: &Option<String>, _target: &Option<IpAddr>) -> String {
+            "https://httpbin.org/status/200".to_string()
+        }
+
+        async fn send_request(&self, _client: &Client, _request: Request) -> Result<String> {
+            Ok(format!(
+                r#"{{"error": "Invalid IP address", "padding": "{}"}}"#,
+                "x".repeat(600)
+            ))
+        }
+
+        fn parse_reply(&self, json: String) -> Result<LookupResponse> {
+            let ip: IpAddr = serde_json::from_str(&json)?;
+            Ok(LookupResponse::new(ip, self.get_type()))
+        }
+
+        fn get_type(&self) -> LookupProvider {
+            LookupProvider::Mock("error".to_string())
+        }
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_provider_object() {
+        let provider: Box<dyn Provider + Send + Sync> = Box::new(ErrorReply);
+        let body = provider
+            .make_request(&Client::new(), None, None)
+            .await
+            .unwrap();
+        assert!(body.contains("Invalid IP address"), "Override not called");
+        assert!(provider.parse_reply(body).is_err());
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_parse_error() {
+        let service = LookupService {
+            provider: Box::new(ErrorReply),
+            ..LookupService::new(LookupProvider::Mock("1.1.1.1".to_string()), None)
+        };
+        let error = service.lookup(None).await.unwrap_err();
+        let LookupError::ParseError {
+            provider,
+            status,
+            body,
+            ..
+        } = error
+        else {
+            panic!("Wrong error {:#?}", error);
+        };
+        assert_eq!(provider, LookupProvider::Mock("error".to_string()));
+        assert_eq!(status, 200);
+        assert!(body.starts_with(r#"{"error": "Invalid IP address""#));
+        assert_eq!(
+            body.chars().count(),
+            error::MAX_BODY_LENGTH + 3,
+            "Body not truncated"
+        );
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_send_request_override() {
+        let mut service = LookupService {
+            provider: Box::new(ErrorReply),
+            timeout: Some(Duration::from_secs(1)),
+            ..LookupService::new(LookupProvider::Mock("1.1.1.1".to_string()), None)
+        };
+        service.on_request(|_| {});
+        let error = service.lookup(None).await.unwrap_err();
+        assert!(
+            matches!(error, LookupError::ParseError { .. }),
+            "Override not called with hooks and a timeout {:#?}",
+            error
+        );
+    }
+
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_make_request() {
         let address = "1.1.1.1".parse::<std::net::IpAddr>().unwrap();
@@ -382,6 +1556,45 @@ mod tests {
         );
     }
 
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_handle_response_invalid_key() {
+        let response = client::get("https://httpbin.org/status/401").await;
+        let body = handle_response(response).await;
+        assert!(body.is_err(), "Response should be an error {:#?}", body);
+        let body = body.unwrap_err();
+        assert_eq!(
+            body.to_string(),
+            "Invalid API key",
+            "Wrong error {:#?}",
+            body
+        );
+    }
+
+    #[test]
+    fn test_key_provider() {
+        let mut service = LookupService::new(
+            LookupProvider::IpData,
+            Some(Parameters::new("static".to_string())),
+        );
+        assert_eq!(service.api_key(), Some("static".to_string()));
+        service.set_key_provider(Arc::new(|| Some("rotated".to_string())));
+        assert_eq!(
+            service.api_key(),
+            Some("rotated".to_string()),
+            "Key provider should take precedence"
+        );
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_provider_not_enabled() {
+        if LookupProvider::IpInfo.is_enabled() {
+            return;
+        }
+        let provider = LookupService::new(LookupProvider::IpInfo, None);
+        let response = provider.lookup(None).await.unwrap_err();
+        assert_eq!(response.to_string(), "Provider not enabled");
+    }
+
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_target_supported() {
         let address = "8.8.8.8".parse::<std::net::IpAddr>().unwrap();
@@ -390,6 +1603,19 @@ mod tests {
         assert!(response.is_ok());
     }
 
+    #[cfg(feature = "provider-ipdata")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_missing_key() {
+        let provider = LookupService::new(LookupProvider::IpData, None);
+        let response = provider.lookup(None).await.unwrap_err();
+        assert_eq!(
+            response.to_string(),
+            "Missing API key",
+            "Keyed provider should not be requested without key"
+        );
+    }
+
+    #[cfg(feature = "provider-myip")]
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_target_not_supported() {
         let address = "1.1.1.1".parse::<std::net::IpAddr>().unwrap();
@@ -411,6 +1637,18 @@ mod tests {
         assert!(provider.is_err(), "Conversion should fail");
     }
 
+    #[test]
+    fn test_all() {
+        let all = LookupProvider::all();
+        if cfg!(feature = "all-providers") {
+            assert_eq!(all.len(), 19, "Provider missing from the list");
+        }
+        for provider in all {
+            let parsed = LookupProvider::from_str(&provider.to_string());
+            assert_eq!(parsed.ok(), Some(provider), "Provider name not parsed");
+        }
+    }
+
     #[test]
     fn test_conversions_with_key() {
         let (provider, parameters) = LookupProvider::from_str_with_params("ipdata abc").unwrap();
@@ -426,5 +1664,13 @@ mod tests {
         let (provider, parameters) = LookupProvider::from_str_with_params("ipdata").unwrap();
         assert_eq!(provider, LookupProvider::IpData, "Conversion failed");
         assert_eq!(parameters, None, "Parameter conversion failed");
+
+        let (provider, parameters) = LookupProvider::from_str_with_params("IpData AbC").unwrap();
+        assert_eq!(provider, LookupProvider::IpData, "Conversion failed");
+        assert_eq!(
+            parameters.map(|parameters| parameters.api_key).as_deref(),
+            Some("AbC"),
+            "Key case not kept"
+        );
     }
 }
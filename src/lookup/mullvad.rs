@@ -3,6 +3,7 @@
 use super::Result;
 use crate::{
     lookup::{LookupProvider, Provider, ProviderResponse},
+    response::Security,
     LookupResponse,
 };
 use serde::{Deserialize, Serialize};
@@ -33,7 +34,10 @@ impl ProviderResponse<MullvadResponse> for MullvadResponse {
         response.latitude = self.latitude;
         response.longitude = self.longitude;
         response.asn_org = self.organization;
-        response.is_proxy = self.mullvad_exit_ip;
+        response.security = Some(Security {
+            is_vpn: self.mullvad_exit_ip,
+            ..Default::default()
+        });
         response
     }
 }
@@ -79,7 +83,10 @@ mod tests {
     #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
     async fn test_request() {
         let service = Box::new(Mullvad);
-        let result = service.get_client(None, None).send().await;
+        let result = service
+            .build_request(&super::super::client::Client::new(), None, None)
+            .send()
+            .await;
         let result = super::super::handle_response(result).await.unwrap();
         assert!(!result.is_empty(), "Result is empty");
         println!("Mullvad: {:#?}", result);
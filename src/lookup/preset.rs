@@ -0,0 +1,106 @@
+//! Curated provider chain presets
+//!
+//! Presets are sensible default provider chains for common needs, so users don't have to
+//! research every service. Providers disabled through their `provider-<name>` feature are
+//! left out of the presets.
+
+use super::LookupProvider;
+use std::fmt;
+
+/// Curated provider chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Preset {
+    /// Lowest latency and most generous rate limits, with little geolocation data
+    Fast,
+    /// Most complete and accurate geolocation data, providers needing an API key first
+    Accurate,
+    /// Providers run by privacy-focused organizations
+    Privacy,
+    /// Every provider that works without an API key
+    Keyless,
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl LookupProvider {
+    /// Returns the providers of a `Preset` chain, in the order they should be tried.
+    ///
+    /// # Example
+    /// ```
+    /// use public_ip_address::lookup::{preset::Preset, LookupProvider, Parameters};
+    ///
+    /// let providers: Vec<(LookupProvider, Option<Parameters>)> = LookupProvider::preset(Preset::Privacy)
+    ///     .into_iter()
+    ///     .map(|provider| (provider, None))
+    ///     .collect();
+    /// ```
+    pub fn preset(preset: Preset) -> Vec<LookupProvider> {
+        let providers = match preset {
+            Preset::Fast => vec![
+                LookupProvider::Ipify,
+                LookupProvider::MyIpCom,
+                LookupProvider::GetJsonIp,
+                LookupProvider::FreeIpApi,
+                LookupProvider::IpWhoIs,
+            ],
+            Preset::Accurate => vec![
+                LookupProvider::IpData,
+                LookupProvider::IpGeolocation,
+                LookupProvider::AbstractApi,
+                LookupProvider::Ip2Location,
+                LookupProvider::IpInfo,
+                LookupProvider::IpWhoIs,
+                LookupProvider::IpApiCom,
+            ],
+            Preset::Privacy => vec![
+                LookupProvider::Mullvad,
+                LookupProvider::IpLeak,
+                LookupProvider::Ipify,
+            ],
+            Preset::Keyless => LookupProvider::all()
+                .into_iter()
+                .filter(|provider| !provider.capabilities().requires_key)
+                .collect(),
+        };
+        providers
+            .into_iter()
+            .filter(LookupProvider::is_enabled)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        for preset in [
+            Preset::Fast,
+            Preset::Accurate,
+            Preset::Privacy,
+            Preset::Keyless,
+        ] {
+            let providers = LookupProvider::preset(preset);
+            if cfg!(feature = "all-providers") {
+                assert!(!providers.is_empty(), "Empty {} preset", preset);
+            }
+            assert!(providers.iter().all(LookupProvider::is_enabled));
+        }
+        assert!(LookupProvider::preset(Preset::Keyless)
+            .iter()
+            .all(|provider| !provider.capabilities().requires_key));
+        if cfg!(feature = "all-providers") {
+            let accurate = LookupProvider::preset(Preset::Accurate);
+            assert!(
+                accurate[0].capabilities().requires_key,
+                "Keyed providers first"
+            );
+        }
+    }
+}
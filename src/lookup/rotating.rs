@@ -0,0 +1,202 @@
+//! Round-robin provider rotation
+//!
+//! The `RotatingLookupService` cycles through a list of providers across successive lookups, spreading
+//! the load over several services to stay under their free-tier quotas.
+//!
+//! ## Example
+//! ```rust
+//! use public_ip_address::lookup::{rotating::RotatingLookupService, LookupProvider};
+//! use std::error::Error;
+//!
+//! #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! #[maybe_async::maybe_async]
+//! async fn main() -> Result<(), Box<dyn Error>> {
+//!     let service = RotatingLookupService::new(vec![
+//!         (LookupProvider::IpInfo, None),
+//!         (LookupProvider::IpWhoIs, None),
+//!     ]);
+//!     // each lookup starts with the next provider in the list
+//!     let first = service.lookup(None).await?;
+//!     let second = service.lookup(None).await?;
+//!     println!("{} {}", first.provider, second.provider);
+//!     Ok(())
+//! }
+//! ```
+
+use super::{error::LookupError, LookupProvider, LookupService, Parameters, Result};
+use crate::logging::{debug, trace, warn};
+use crate::{cache::get_cache_path, LookupResponse};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// Persisted rotation state
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct RotationState {
+    next: usize,
+}
+
+/// Lookup service rotating through a list of providers in round-robin order
+///
+/// Every lookup starts with the next provider in the list. If it fails, the remaining providers are
+/// tried in order until one of them succeeds.
+///
+/// Cloning the service shares the rotation state, so clones can be used from several threads
+/// or tasks while still cycling through the providers together.
+#[derive(Clone, Debug)]
+pub struct RotatingLookupService {
+    providers: Arc<Vec<(LookupProvider, Option<Parameters>)>>,
+    next: Arc<AtomicUsize>,
+    state_file: Option<String>,
+}
+
+impl RotatingLookupService {
+    /// Creates a new `RotatingLookupService` instance starting with the first provider in the list.
+    pub fn new(providers: Vec<(LookupProvider, Option<Parameters>)>) -> Self {
+        RotatingLookupService {
+            providers: Arc::new(providers),
+            next: Arc::new(AtomicUsize::new(0)),
+            state_file: None,
+        }
+    }
+
+    /// Persists the rotation state in a file in the cache directory.
+    ///
+    /// The rotation continues where it left off the last time the state was saved, which keeps
+    /// short-lived processes, like CLI runs, from always starting with the same provider.
+    pub fn with_state_file(mut self, file_name: String) -> Self {
+        let state = fs::read_to_string(get_cache_path(&Some(file_name.clone())))
+            .ok()
+            .and_then(|data| serde_json::from_str::<RotationState>(&data).ok())
+            .unwrap_or_default();
+        trace!("Loaded rotation state {:?}", state);
+        self.next.store(state.next, Ordering::SeqCst);
+        self.state_file = Some(file_name);
+        self
+    }
+
+    /// Returns the providers in the order the next lookup will try them.
+    pub fn next_providers(&self) -> Vec<LookupProvider> {
+        let start = self.next.load(Ordering::SeqCst);
+        self.order(start)
+            .map(|index| self.providers[index].0.clone())
+            .collect()
+    }
+
+    /// Makes a request starting with the next provider in the rotation.
+    ///
+    /// If the provider fails, the remaining providers are tried in order. If all of them fail,
+    /// a `LookupError` containing a list of all the errors received is returned.
+    #[maybe_async::maybe_async]
+    pub async fn lookup(&self, target: Option<IpAddr>) -> Result<LookupResponse> {
+        if self.providers.is_empty() {
+            return Err(LookupError::GenericError("No providers given".to_string()));
+        }
+        let start = self.advance();
+
+        let mut errors = Vec::new();
+        for index in self.order(start) {
+            let (provider, param) = self.providers[index].clone();
+            debug!("Performing rotating lookup with provider {}", &provider);
+            match LookupService::new(provider.clone(), param)
+                .lookup(target)
+                .await
+            {
+                Ok(response) => return Ok(response),
+                Err(e) => {
+                    warn!("Provider failed to perform lookup");
+                    errors.push((provider, e));
+                }
+            }
+        }
+        Err(LookupError::AllProvidersFailed(errors))
+    }
+
+    /// Moves the rotation to the next provider, returning the index to start with.
+    fn advance(&self) -> usize {
+        let start = self.next.fetch_add(1, Ordering::SeqCst) % self.providers.len();
+        self.save_state();
+        start
+    }
+
+    /// Iterates over the provider indices starting at `start`, wrapping around.
+    fn order(&self, start: usize) -> impl Iterator<Item = usize> {
+        let len = self.providers.len();
+        (0..len).map(move |offset| (start + offset) % len)
+    }
+
+    /// Writes the rotation state to disk if a state file is configured.
+    fn save_state(&self) {
+        if let Some(file_name) = &self.state_file {
+            let state = RotationState {
+                next: self.next.load(Ordering::SeqCst) % self.providers.len().max(1),
+            };
+            let result = serde_json::to_string(&state)
+                .map_err(|e| e.to_string())
+                .and_then(|data| {
+                    fs::write(get_cache_path(&Some(file_name.clone())), data)
+                        .map_err(|e| e.to_string())
+                });
+            if let Err(e) = result {
+                warn!("Failed to save rotation state: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn providers() -> Vec<(LookupProvider, Option<Parameters>)> {
+        vec![
+            (LookupProvider::IpInfo, None),
+            (LookupProvider::IpWhoIs, None),
+            (LookupProvider::MyIp, None),
+        ]
+    }
+
+    #[test]
+    fn test_rotation() {
+        let service = RotatingLookupService::new(providers());
+        let shared = service.clone();
+        assert_eq!(service.next_providers()[0], LookupProvider::IpInfo);
+        service.advance();
+        assert_eq!(
+            shared.next_providers(),
+            vec![
+                LookupProvider::IpWhoIs,
+                LookupProvider::MyIp,
+                LookupProvider::IpInfo
+            ],
+            "Rotation state should be shared between clones"
+        );
+        shared.advance();
+        shared.advance();
+        assert_eq!(
+            service.next_providers()[0],
+            LookupProvider::IpInfo,
+            "Rotation should wrap around"
+        );
+    }
+
+    #[test]
+    fn test_rotation_state_file() {
+        let file_name = "rotation_test.state".to_string();
+        let service = RotatingLookupService::new(providers()).with_state_file(file_name.clone());
+        service.advance();
+        let restored = RotatingLookupService::new(providers()).with_state_file(file_name.clone());
+        assert_eq!(
+            restored.next_providers(),
+            service.next_providers(),
+            "Rotation state should be restored"
+        );
+        _ = fs::remove_file(get_cache_path(&Some(file_name)));
+    }
+}
@@ -0,0 +1,118 @@
+//! Periodic lookups
+//!
+//! `LookupService::stream` repeats the lookup at a fixed interval, the building block for
+//! applications reacting to changes of the public IP address. The results go through the
+//! response cache, keyed by the provider and the connection of the service, so the streams of
+//! the same service share their responses.
+//!
+//! Without the `blocking` feature the lookups are a `futures::Stream` driven by a `tokio`
+//! interval, otherwise they are an `Iterator` sleeping between lookups.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use public_ip_address::lookup::{LookupProvider, LookupService};
+//! use std::time::Duration;
+//!
+//! #[cfg(not(feature = "blocking"))]
+//! #[tokio::main]
+//! async fn main() {
+//!     use futures::StreamExt;
+//!
+//!     let service = LookupService::new(LookupProvider::IpInfo, None);
+//!     let mut lookups = Box::pin(service.stream(Duration::from_secs(60)));
+//!     while let Some(response) = lookups.next().await {
+//!         println!("{:?}", response.map(|response| response.ip));
+//!     }
+//! }
+//!
+//! #[cfg(feature = "blocking")]
+//! fn main() {
+//!     let service = LookupService::new(LookupProvider::IpInfo, None);
+//!     for response in service.stream(Duration::from_secs(60)) {
+//!         println!("{:?}", response.map(|response| response.ip));
+//!     }
+//! }
+//! ```
+
+use super::LookupService;
+use crate::logging::{debug, trace, warn};
+use crate::{cache::ResponseCache, error::Result, LookupResponse};
+use std::time::Duration;
+
+impl LookupService {
+    /// Returns a stream of lookups of the current public address, one every `interval`.
+    ///
+    /// The first lookup is made immediately. Cached responses younger than `interval` are
+    /// returned instead of making a new request, and new responses are cached for `interval`.
+    #[maybe_async::async_impl]
+    pub fn stream(
+        self,
+        interval: Duration,
+    ) -> impl futures::Stream<Item = Result<LookupResponse>> + Send {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        futures::stream::unfold((self, ticker), move |(service, mut ticker)| async move {
+            ticker.tick().await;
+            let response = service.cached_lookup(interval).await;
+            Some((response, (service, ticker)))
+        })
+    }
+
+    /// Returns an iterator of lookups of the current public address, one every `interval`.
+    ///
+    /// The first lookup is made immediately. Cached responses younger than `interval` are
+    /// returned instead of making a new request, and new responses are cached for `interval`.
+    #[maybe_async::sync_impl]
+    pub fn stream(self, interval: Duration) -> impl Iterator<Item = Result<LookupResponse>> {
+        let mut first = true;
+        std::iter::from_fn(move || {
+            if !first {
+                std::thread::sleep(interval);
+            }
+            first = false;
+            Some(self.cached_lookup(interval))
+        })
+    }
+
+    /// Looks up the current address, using the cached response of the service if it's younger than `ttl`.
+    #[maybe_async::maybe_async]
+    async fn cached_lookup(&self, ttl: Duration) -> Result<LookupResponse> {
+        let key = self.stream_key();
+        let mut cache = match ResponseCache::load(None) {
+            Ok(cache) => cache,
+            Err(e) => {
+                debug!("No cache loaded, starting a new one: {:?}", e);
+                ResponseCache::default()
+            }
+        };
+        if !cache.restricted_is_expired(&key) {
+            if let Some(response) = cache.restricted_response(&key) {
+                trace!("Using cached value");
+                return Ok(response);
+            }
+        }
+        let response = self.lookup(None).await?;
+        cache.update_restricted(&key, &response, Some(ttl.as_secs()));
+        crate::quota::store(&mut cache);
+        if let Err(e) = cache.save() {
+            warn!("Failed to save cache: {}", e);
+        }
+        Ok(response)
+    }
+
+    /// Returns the key of the cached responses of the streams of the service, made of the provider
+    /// and of the options changing the address it returns
+    fn stream_key(&self) -> String {
+        let mut key = format!("stream+{}+{:?}", self.provider.get_type(), self.ip_version);
+        if self.ip_only {
+            key.push_str("+ip_only");
+        }
+        if let Some(local_address) = self.local_address {
+            key.push_str(&format!("+{}", local_address));
+        }
+        if let Some(interface) = &self.interface {
+            key.push_str(&format!("+{}", interface));
+        }
+        key
+    }
+}
@@ -0,0 +1,213 @@
+//! Lookups through Tor
+//!
+//! With the `tor` feature, the lookup requests can be routed through an embedded
+//! [arti](https://gitlab.torproject.org/tpo/core/arti) Tor client. The providers then see the
+//! address of the Tor exit relay instead of the real address of the host, which is also the address
+//! the lookup returns.
+//!
+//! The requests are made over a new Tor stream each time, so connections are not pooled.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use public_ip_address::lookup::{tor, LookupProvider, LookupService};
+//! use std::error::Error;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn Error>> {
+//!     let mut service = LookupService::new(LookupProvider::IpWhoIs, None);
+//!     service.set_tor_client(tor::bootstrap().await?);
+//!     let result = service.lookup(None).await?;
+//!     println!("Tor exit address: {}", result.ip);
+//!     Ok(())
+//! }
+//! ```
+
+use super::{client::Request as ProviderRequest, error::LookupError, status_error, Result};
+use crate::logging::{debug, trace};
+use http_body_util::{BodyExt, Full};
+use hyper::{body::Bytes, header, Request, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::sync::Arc;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::OnceCell,
+};
+use tokio_rustls::{
+    rustls::{self, pki_types::ServerName},
+    TlsConnector,
+};
+
+pub use arti_client::TorClientConfig;
+
+/// Embedded Tor client routing the lookups
+pub type TorClient = Arc<arti_client::TorClient<tor_rtcompat::PreferredRuntime>>;
+
+/// Tor client shared by the lookups, bootstrapped on first use
+static TOR_CLIENT: OnceCell<TorClient> = OnceCell::const_new();
+
+/// Returns the shared Tor client, bootstrapping it with the default configuration on first use.
+///
+/// Bootstrapping downloads the Tor directory and can take several seconds, later calls return
+/// immediately.
+pub async fn bootstrap() -> Result<TorClient> {
+    TOR_CLIENT
+        .get_or_try_init(|| async {
+            debug!("Bootstrapping Tor client");
+            let client =
+                arti_client::TorClient::create_bootstrapped(TorClientConfig::default()).await?;
+            Ok::<_, LookupError>(client)
+        })
+        .await
+        .cloned()
+}
+
+/// Target of a request sent through Tor
+struct Target {
+    host: String,
+    port: u16,
+    https: bool,
+}
+
+/// Sends the request through Tor with the default headers and returns the response body.
+pub(crate) async fn send(
+    tor: &TorClient,
+    request: ProviderRequest,
+    default_headers: &[(String, String)],
+) -> Result<String> {
+    let (target, request) = into_request(request, default_headers)?;
+    trace!("Connecting to {}:{} through Tor", target.host, target.port);
+    let stream = tor.connect((target.host.as_str(), target.port)).await?;
+    let (status, body) = if target.https {
+        let connector = TlsConnector::from(Arc::new(tls_config()));
+        let server_name = ServerName::try_from(target.host).map_err(generic)?;
+        let stream = connector
+            .connect(server_name, stream)
+            .await
+            .map_err(generic)?;
+        exchange(stream, request).await?
+    } else {
+        exchange(stream, request).await?
+    };
+    match status {
+        StatusCode::OK => String::from_utf8(body.to_vec()).map_err(generic),
+        status => Err(status_error(status)),
+    }
+}
+
+/// Converts the request built by a provider into a request for the Tor stream.
+///
+/// The default headers are added unless the request sets them itself.
+fn into_request(
+    request: ProviderRequest,
+    default_headers: &[(String, String)],
+) -> Result<(Target, Request<Full<Bytes>>)> {
+    let url = request.url();
+    let target = Target {
+        host: url
+            .host_str()
+            .ok_or_else(|| LookupError::GenericError(format!("Missing host in {}", url)))?
+            .to_string(),
+        port: url.port_or_known_default().unwrap_or(443),
+        https: url.scheme() == "https",
+    };
+    let mut builder = Request::builder()
+        .method(request.method())
+        .uri(match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        })
+        .header(header::HOST, &target.host);
+    for (name, value) in request.headers() {
+        builder = builder.header(name, value);
+    }
+    for (name, value) in default_headers {
+        if !request.headers().contains_key(name.as_str()) {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+    }
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(Bytes::copy_from_slice)
+        .unwrap_or_default();
+    let request = builder.body(Full::new(body)).map_err(generic)?;
+    Ok((target, request))
+}
+
+/// Sends the request over the stream and reads the response.
+async fn exchange<S>(stream: S, request: Request<Full<Bytes>>) -> Result<(StatusCode, Bytes)>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(generic)?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            debug!("Tor connection failed: {}", e);
+        }
+    });
+    let response = sender.send_request(request).await.map_err(generic)?;
+    let status = response.status();
+    let body = response
+        .into_body()
+        .collect()
+        .await
+        .map_err(generic)?
+        .to_bytes();
+    Ok((status, body))
+}
+
+/// TLS configuration trusting the Mozilla root certificates
+fn tls_config() -> rustls::ClientConfig {
+    let roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .expect("default protocol versions are supported")
+        .with_root_certificates(roots)
+        .with_no_client_auth()
+}
+
+fn generic<E: std::fmt::Display>(e: E) -> LookupError {
+    LookupError::GenericError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup::client::Client;
+
+    #[test]
+    fn test_into_request() {
+        let request = Client::new()
+            .get("https://ipinfo.io/8.8.8.8/json?token=key")
+            .header("User-Agent", "nil")
+            .build()
+            .unwrap();
+        let headers = vec![
+            ("User-Agent".to_string(), "default".to_string()),
+            ("Authorization".to_string(), "Bearer token".to_string()),
+        ];
+        let (target, request) = into_request(request, &headers).unwrap();
+        assert_eq!(target.host, "ipinfo.io");
+        assert_eq!(target.port, 443);
+        assert!(target.https);
+        assert_eq!(request.uri(), "/8.8.8.8/json?token=key");
+        assert_eq!(request.headers()[header::HOST], "ipinfo.io");
+        assert_eq!(request.headers()[header::USER_AGENT], "nil");
+        assert_eq!(request.headers()[header::AUTHORIZATION], "Bearer token");
+
+        let (target, _) = into_request(
+            Client::new()
+                .get("http://ip-api.com:8080/json")
+                .build()
+                .unwrap(),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(target.port, 8080);
+        assert!(!target.https);
+    }
+}
@@ -0,0 +1,108 @@
+//! # 📈 Metrics
+//!
+//! With the `metrics` feature, the lookups and the cache are measured through the
+//! [`metrics`](https://docs.rs/metrics) facade, so any installed recorder exports them, for example
+//! to Prometheus with `metrics-exporter-prometheus`. Nothing is recorded until a recorder is
+//! installed.
+//!
+//! | Metric | Type | Labels |
+//! |--------|------|--------|
+//! | `public_ip_address_requests_total` | counter | `provider`, `outcome` (`success` or `failure`) |
+//! | `public_ip_address_request_duration_seconds` | histogram | `provider`, `outcome` |
+//! | `public_ip_address_cache_lookups_total` | counter | `result` (`hit` or `miss`) |
+//!
+//! The requests are the lookups made by a `LookupService`, including the ones made by the fallback,
+//! race and enriched lookups. The cache lookups are the ones of the cached lookup functions.
+//!
+//! ## Example
+//! ```rust,ignore
+//! metrics_exporter_prometheus::PrometheusBuilder::new()
+//!     .install()
+//!     .expect("failed to install the Prometheus exporter");
+//! let response = public_ip_address::perform_cached_lookup_with(providers, None, None, false).await?;
+//! ```
+
+use crate::lookup::LookupProvider;
+use std::time::Duration;
+
+/// Counter of the requests made to the providers
+pub const REQUESTS_TOTAL: &str = "public_ip_address_requests_total";
+/// Histogram of the duration of the requests made to the providers, in seconds
+pub const REQUEST_DURATION_SECONDS: &str = "public_ip_address_request_duration_seconds";
+/// Counter of the cache lookups
+pub const CACHE_LOOKUPS_TOTAL: &str = "public_ip_address_cache_lookups_total";
+
+/// Records a request to the provider and its outcome.
+pub(crate) fn record_request(provider: &LookupProvider, success: bool, latency: Duration) {
+    let labels = [
+        ("provider", provider.to_string()),
+        (
+            "outcome",
+            if success { "success" } else { "failure" }.to_string(),
+        ),
+    ];
+    ::metrics::counter!(REQUESTS_TOTAL, &labels).increment(1);
+    ::metrics::histogram!(REQUEST_DURATION_SECONDS, &labels).record(latency.as_secs_f64());
+}
+
+/// Records a hit or a miss of the cache.
+pub(crate) fn record_cache_lookup(hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    ::metrics::counter!(CACHE_LOOKUPS_TOTAL, "result" => result).increment(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    #[test]
+    fn test_record() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        ::metrics::with_local_recorder(&recorder, || {
+            record_request(&LookupProvider::IpWhoIs, true, Duration::from_millis(250));
+            record_request(&LookupProvider::IpWhoIs, false, Duration::from_secs(1));
+            record_request(&LookupProvider::IpWhoIs, true, Duration::from_millis(50));
+            record_cache_lookup(true);
+            record_cache_lookup(false);
+            record_cache_lookup(true);
+        });
+
+        let metrics = snapshotter.snapshot().into_vec();
+        let value = |name: &str, labels: &[(&str, &str)]| {
+            metrics
+                .iter()
+                .find(|(key, _, _, _)| {
+                    let key = key.key();
+                    key.name() == name
+                        && labels.iter().all(|(label, value)| {
+                            key.labels()
+                                .any(|l| l.key() == *label && l.value() == *value)
+                        })
+                })
+                .map(|(_, _, _, value)| value)
+        };
+        let success = [("provider", "IpWhoIs"), ("outcome", "success")];
+        assert_eq!(
+            value(REQUESTS_TOTAL, &success),
+            Some(&DebugValue::Counter(2))
+        );
+        assert_eq!(
+            value(REQUESTS_TOTAL, &[("outcome", "failure")]),
+            Some(&DebugValue::Counter(1))
+        );
+        assert!(matches!(
+            value(REQUEST_DURATION_SECONDS, &success),
+            Some(DebugValue::Histogram(samples)) if samples.len() == 2
+        ));
+        assert_eq!(
+            value(CACHE_LOOKUPS_TOTAL, &[("result", "hit")]),
+            Some(&DebugValue::Counter(2))
+        );
+        assert_eq!(
+            value(CACHE_LOOKUPS_TOTAL, &[("result", "miss")]),
+            Some(&DebugValue::Counter(1))
+        );
+    }
+}
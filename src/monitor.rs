@@ -0,0 +1,386 @@
+//! # 📡 Public address change monitor
+//!
+//! A `Monitor` looks up the current public address periodically from a background component and
+//! reports every change as an [`IpChange`] event. The events are delivered to callbacks registered
+//! with [`Monitor::on_change`], to channels returned by [`Monitor::subscribe`], or to streams returned
+//! by [`Monitor::stream`], which are iterators in the `blocking` build. Callbacks registered with
+//! [`Monitor::on_lookup`] are called after every successful lookup, changed or not.
+//!
+//! The lookups go through the response cache, so the address the host had before the monitor started
+//! is known and a change while the program was not running is reported by the first lookup.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use public_ip_address::{lookup::LookupProvider, monitor::Monitor};
+//! use std::time::Duration;
+//!
+//! #[cfg(not(feature = "blocking"))]
+//! #[tokio::main]
+//! async fn main() {
+//!     use futures::StreamExt;
+//!
+//!     let mut monitor = Monitor::new(vec![(LookupProvider::IpWhoIs, None)], Duration::from_secs(60));
+//!     monitor.on_change(|change| println!("{:?} -> {}", change.old_ip, change.new_ip));
+//!     let mut changes = Box::pin(monitor.stream());
+//!     monitor.start();
+//!     while let Some(change) = changes.next().await {
+//!         println!("Changed by {}", change.provider);
+//!     }
+//! }
+//!
+//! #[cfg(feature = "blocking")]
+//! fn main() {
+//!     let mut monitor = Monitor::new(vec![(LookupProvider::IpWhoIs, None)], Duration::from_secs(60));
+//!     monitor.on_change(|change| println!("{:?} -> {}", change.old_ip, change.new_ip));
+//!     let changes = monitor.stream();
+//!     monitor.start();
+//!     for change in changes {
+//!         println!("Changed by {}", change.provider);
+//!     }
+//! }
+//! ```
+
+use crate::logging::{debug, trace, warn};
+use crate::{
+    background::{BackgroundHandle, ShutdownSignal},
+    cache::ResponseCache,
+    ddns::DdnsUpdater,
+    lookup::{LookupProvider, Parameters},
+    perform_cached_lookup_with,
+    response::LookupResponse,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+/// Change of the public address detected by a [`Monitor`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct IpChange {
+    /// Previous address, `None` if no address was known before
+    pub old_ip: Option<IpAddr>,
+    /// New address
+    pub new_ip: IpAddr,
+    /// Provider the new address was looked up with
+    pub provider: LookupProvider,
+    /// Time the previous address was first seen, if known
+    pub old_since: Option<SystemTime>,
+    /// Time the change was detected
+    pub detected_at: SystemTime,
+    /// Response of the lookup returning the new address
+    pub response: LookupResponse,
+}
+
+/// Receives the changes, returns `false` once it doesn't want more
+type Listener = Arc<Mutex<dyn FnMut(&IpChange) -> bool + Send>>;
+
+/// Receives the responses of the successful lookups
+type LookupListener = Arc<Mutex<dyn FnMut(&LookupResponse) + Send>>;
+
+/// Listeners of the changes and of the lookups
+#[derive(Default)]
+struct ListenerSet {
+    changes: Vec<Listener>,
+    lookups: Vec<LookupListener>,
+}
+
+/// Listeners shared between the monitor and its background component
+type Listeners = Arc<Mutex<ListenerSet>>;
+
+/// Background component reporting the changes of the current public address
+///
+/// The listeners should be registered before the monitor is started, so they don't miss the first
+/// change. Dropping the monitor stops the background component without waiting for it, use
+/// [`Monitor::shutdown`] to stop it cleanly.
+pub struct Monitor {
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    interval: Duration,
+    listeners: Listeners,
+    handle: Option<BackgroundHandle>,
+}
+
+impl std::fmt::Debug for Monitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Monitor")
+            .field("providers", &self.providers)
+            .field("interval", &self.interval)
+            .field("handle", &self.handle)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Monitor {
+    /// Creates a monitor looking up the current address every `interval` with the providers.
+    ///
+    /// The responses are cached for `interval`. Nothing is looked up until the monitor is started.
+    pub fn new(providers: Vec<(LookupProvider, Option<Parameters>)>, interval: Duration) -> Self {
+        Monitor {
+            providers,
+            interval,
+            listeners: Listeners::default(),
+            handle: None,
+        }
+    }
+
+    /// Calls `callback` with every change, from the background component.
+    pub fn on_change<F>(&mut self, mut callback: F) -> &mut Self
+    where
+        F: FnMut(&IpChange) + Send + 'static,
+    {
+        self.listen(Arc::new(Mutex::new(move |change: &IpChange| {
+            callback(change);
+            true
+        })));
+        self
+    }
+
+    /// Calls `callback` with the response of every successful lookup, from the background component.
+    ///
+    /// The callbacks are called after the change listeners, so a change is reported before the
+    /// response that detected it.
+    pub fn on_lookup<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: FnMut(&LookupResponse) + Send + 'static,
+    {
+        self.listeners
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .lookups
+            .push(Arc::new(Mutex::new(callback)));
+        self
+    }
+
+    /// Updates the DNS record of `updater` with every change, see [`DdnsUpdater::attach`].
+    pub fn add_updater<U: DdnsUpdater + 'static>(&mut self, updater: U) -> &mut Self {
+        updater.attach(self);
+        self
+    }
+
+    /// Returns a channel receiving every change.
+    ///
+    /// The channel is unbounded, the changes are dropped once the receiver is dropped.
+    #[maybe_async::async_impl]
+    pub fn subscribe(&mut self) -> tokio::sync::mpsc::UnboundedReceiver<IpChange> {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        self.listen(Arc::new(Mutex::new(move |change: &IpChange| {
+            sender.send(change.clone()).is_ok()
+        })));
+        receiver
+    }
+
+    /// Returns a channel receiving every change.
+    ///
+    /// The channel is unbounded, the changes are dropped once the receiver is dropped.
+    #[maybe_async::sync_impl]
+    pub fn subscribe(&mut self) -> std::sync::mpsc::Receiver<IpChange> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.listen(Arc::new(Mutex::new(move |change: &IpChange| {
+            sender.send(change.clone()).is_ok()
+        })));
+        receiver
+    }
+
+    /// Returns a stream of every change, ending when the monitor stops.
+    #[maybe_async::async_impl]
+    pub fn stream(&mut self) -> impl futures::Stream<Item = IpChange> + Send {
+        futures::stream::unfold(self.subscribe(), |mut receiver| async move {
+            let change = receiver.recv().await?;
+            Some((change, receiver))
+        })
+    }
+
+    /// Returns an iterator of every change, ending when the monitor stops.
+    #[maybe_async::sync_impl]
+    pub fn stream(&mut self) -> impl Iterator<Item = IpChange> {
+        self.subscribe().into_iter()
+    }
+
+    /// Starts monitoring in the background, if the monitor is not started yet.
+    ///
+    /// Without the `blocking` feature the monitor runs as a `tokio` task and must be started from
+    /// within a `tokio` runtime, otherwise it runs on its own thread.
+    #[maybe_async::async_impl]
+    pub fn start(&mut self) -> &Self {
+        if self.handle.is_none() {
+            let (providers, interval) = (self.providers.clone(), self.interval);
+            let listeners = self.listeners.clone();
+            self.handle = Some(BackgroundHandle::spawn(move |signal| async move {
+                watch(providers, interval, listeners, signal).await
+            }));
+        }
+        self
+    }
+
+    /// Starts monitoring in the background, if the monitor is not started yet.
+    ///
+    /// Without the `blocking` feature the monitor runs as a `tokio` task and must be started from
+    /// within a `tokio` runtime, otherwise it runs on its own thread.
+    #[maybe_async::sync_impl]
+    pub fn start(&mut self) -> &Self {
+        if self.handle.is_none() {
+            let (providers, interval) = (self.providers.clone(), self.interval);
+            let listeners = self.listeners.clone();
+            self.handle = Some(BackgroundHandle::spawn(move |signal| {
+                watch(providers, interval, listeners, signal)
+            }));
+        }
+        self
+    }
+
+    /// Checks if the background component has stopped, or was never started.
+    pub fn is_finished(&self) -> bool {
+        self.handle
+            .as_ref()
+            .is_none_or(|handle| handle.is_finished())
+    }
+
+    /// Stops monitoring and waits until the background component finished.
+    ///
+    /// The channels and streams end once the monitor is stopped.
+    #[maybe_async::maybe_async]
+    pub async fn shutdown(self) {
+        if let Some(handle) = self.handle {
+            handle.shutdown().await;
+        }
+    }
+
+    /// Registers a listener of the changes
+    fn listen(&mut self, listener: Listener) {
+        self.listeners
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .changes
+            .push(listener);
+    }
+}
+
+/// Looks up the current address until the shutdown is triggered, reporting the changes.
+#[maybe_async::maybe_async]
+async fn watch(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    interval: Duration,
+    listeners: Listeners,
+    signal: ShutdownSignal,
+) {
+    let mut last = last_known();
+    // the first lookup reuses the cache, later ones are due by the time they run
+    let mut force_refresh = false;
+    loop {
+        match perform_cached_lookup_with(
+            providers.clone(),
+            None,
+            Some(interval.as_secs()),
+            force_refresh,
+        )
+        .await
+        {
+            Ok(response) if last.map(|(ip, _)| ip) != Some(response.ip) => {
+                let change = IpChange {
+                    old_ip: last.map(|(ip, _)| ip),
+                    new_ip: response.ip,
+                    provider: response.provider.clone(),
+                    old_since: last.and_then(|(_, since)| since),
+                    detected_at: SystemTime::now(),
+                    response,
+                };
+                debug!("Public address changed to {}", change.new_ip);
+                last = Some((change.new_ip, Some(change.detected_at)));
+                notify(&listeners, &change);
+                notify_lookup(&listeners, &change.response);
+            }
+            Ok(response) => {
+                trace!("Public address unchanged");
+                notify_lookup(&listeners, &response);
+            }
+            Err(e) => warn!("Failed to look up public address: {:?}", e),
+        }
+        force_refresh = true;
+        if signal.wait(interval).await {
+            break;
+        }
+    }
+}
+
+/// Returns the cached address, and the time it was first seen if it's in the cache history
+fn last_known() -> Option<(IpAddr, Option<SystemTime>)> {
+    let cache = ResponseCache::load(None).ok()?;
+    let ip = cache.current_ip()?;
+    let since = cache
+        .history()
+        .last()
+        .filter(|entry| entry.ip == ip)
+        .map(|entry| entry.time);
+    Some((ip, since))
+}
+
+/// Delivers the change to the listeners, dropping the ones that don't want more
+fn notify(listeners: &Listeners, change: &IpChange) {
+    // the listeners are called without the lock of the set, so they can't block the registrations
+    let changes = listeners
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .changes
+        .clone();
+    let done: Vec<Listener> = changes
+        .into_iter()
+        .filter(|listener| !(*listener.lock().unwrap_or_else(|e| e.into_inner()))(change))
+        .collect();
+    if !done.is_empty() {
+        listeners
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .changes
+            .retain(|listener| !done.iter().any(|done| Arc::ptr_eq(done, listener)));
+    }
+}
+
+/// Delivers the response of a successful lookup to the lookup listeners
+fn notify_lookup(listeners: &Listeners, response: &LookupResponse) {
+    let lookups = listeners
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .lookups
+        .clone();
+    for listener in lookups {
+        (*listener.lock().unwrap_or_else(|e| e.into_inner()))(response);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notify_unlocked() {
+        let ip: IpAddr = "1.1.1.1".parse().unwrap();
+        let provider = LookupProvider::Mock(ip.to_string());
+        let change = IpChange {
+            old_ip: None,
+            new_ip: ip,
+            provider: provider.clone(),
+            old_since: None,
+            detected_at: SystemTime::now(),
+            response: LookupResponse::new(ip, provider),
+        };
+        let listeners = Listeners::default();
+        let registering = listeners.clone();
+        listeners
+            .lock()
+            .unwrap()
+            .changes
+            .push(Arc::new(Mutex::new(move |_: &IpChange| {
+                // registering a listener takes the lock of the set
+                let listener: LookupListener = Arc::new(Mutex::new(|_: &LookupResponse| {}));
+                registering.lock().unwrap().lookups.push(listener);
+                false
+            })));
+        notify(&listeners, &change);
+        notify_lookup(&listeners, &change.response);
+        let set = listeners.lock().unwrap();
+        assert!(set.changes.is_empty(), "Listener not removed");
+        assert_eq!(set.lookups.len(), 1, "Listener not registered");
+    }
+}
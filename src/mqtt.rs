@@ -0,0 +1,178 @@
+//! # 🏠 MQTT publisher
+//!
+//! An `MqttPublisher` publishes the public address changes reported by a [`Monitor`] to an MQTT
+//! broker, for home automation systems like Home Assistant to track the WAN address:
+//!
+//! - the new address as plain text, retained, to the configured topic;
+//! - the [`IpChange`] event as JSON to the `change` subtopic of the configured topic.
+//!
+//! The connection to the broker is kept and re-established from a background component. TLS
+//! connections are available with the `rustls-tls` or `native-tls` features.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use public_ip_address::{lookup::LookupProvider, monitor::Monitor, mqtt::MqttPublisher};
+//! use rumqttc::MqttOptions;
+//! use std::time::Duration;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() {
+//! let mut monitor = Monitor::new(vec![(LookupProvider::IpWhoIs, None)], Duration::from_secs(300));
+//! let options = MqttOptions::new("public-ip-address", "homeassistant.local", 1883);
+//! MqttPublisher::new(options, "home/wan/ip").attach(&mut monitor);
+//! monitor.start();
+//! # }
+//! ```
+//!
+//! [`Monitor`]: crate::monitor::Monitor
+
+use crate::error::Result;
+use crate::logging::{trace, warn};
+use crate::lookup::error::LookupError;
+use crate::monitor::{IpChange, Monitor};
+use rumqttc::{MqttOptions, QoS};
+use std::time::Duration;
+
+#[cfg(not(feature = "blocking"))]
+use rumqttc::{AsyncClient as Client, EventLoop};
+#[cfg(feature = "blocking")]
+use rumqttc::{Client, Connection};
+
+/// Capacity of the queue of messages waiting to be sent to the broker
+const QUEUE_CAPACITY: usize = 16;
+
+/// Delay before connecting again after the connection to the broker failed
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Publishes the public address changes to an MQTT broker
+#[derive(Clone)]
+pub struct MqttPublisher {
+    client: Client,
+    topic: String,
+}
+
+impl std::fmt::Debug for MqttPublisher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MqttPublisher")
+            .field("topic", &self.topic)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MqttPublisher {
+    /// Creates a publisher connecting to the broker of the options, publishing to `topic`.
+    ///
+    /// Without the `blocking` feature the connection runs as a `tokio` task and the publisher must
+    /// be created from within a `tokio` runtime, otherwise it runs on its own thread. The connection
+    /// is closed once the publisher and its clones are dropped.
+    #[maybe_async::async_impl]
+    pub fn new(options: MqttOptions, topic: &str) -> Self {
+        let (client, eventloop) = Client::new(options, QUEUE_CAPACITY);
+        tokio::spawn(drive(eventloop));
+        MqttPublisher {
+            client,
+            topic: topic.to_string(),
+        }
+    }
+
+    /// Creates a publisher connecting to the broker of the options, publishing to `topic`.
+    ///
+    /// Without the `blocking` feature the connection runs as a `tokio` task and the publisher must
+    /// be created from within a `tokio` runtime, otherwise it runs on its own thread. The connection
+    /// is closed once the publisher and its clones are dropped.
+    #[maybe_async::sync_impl]
+    pub fn new(options: MqttOptions, topic: &str) -> Self {
+        let (client, connection) = Client::new(options, QUEUE_CAPACITY);
+        std::thread::spawn(move || drive(connection));
+        MqttPublisher {
+            client,
+            topic: topic.to_string(),
+        }
+    }
+
+    /// Publishes the changes reported by the monitor.
+    pub fn attach(self, monitor: &mut Monitor) {
+        monitor.on_change(move |change| {
+            if let Err(e) = self.publish(change) {
+                warn!("Failed to publish address change: {:?}", e);
+            }
+        });
+    }
+
+    /// Queues the new address and the change event for publishing, without waiting for the broker.
+    pub fn publish(&self, change: &IpChange) -> Result<()> {
+        trace!("Publishing address change to {}", self.topic);
+        self.client.try_publish(
+            self.topic.as_str(),
+            QoS::AtLeastOnce,
+            true,
+            change.new_ip.to_string(),
+        )?;
+        self.client.try_publish(
+            format!("{}/change", self.topic),
+            QoS::AtLeastOnce,
+            false,
+            serde_json::to_vec(change).map_err(LookupError::from)?,
+        )?;
+        Ok(())
+    }
+}
+
+/// Keeps the connection to the broker until the publisher is dropped
+#[cfg(not(feature = "blocking"))]
+async fn drive(mut eventloop: EventLoop) {
+    loop {
+        match eventloop.poll().await {
+            Ok(_) => {}
+            Err(rumqttc::ConnectionError::RequestsDone) => break,
+            Err(e) => {
+                warn!("MQTT connection failed: {:?}", e);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
+
+/// Keeps the connection to the broker until the publisher is dropped
+#[cfg(feature = "blocking")]
+fn drive(mut connection: Connection) {
+    for event in connection.iter() {
+        if let Err(e) = event {
+            warn!("MQTT connection failed: {:?}", e);
+            std::thread::sleep(RECONNECT_DELAY);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lookup::LookupProvider, response::LookupResponse};
+    use std::time::SystemTime;
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_publish() {
+        let response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::Ipify);
+        let change = IpChange {
+            old_ip: None,
+            new_ip: response.ip,
+            provider: response.provider.clone(),
+            old_since: None,
+            detected_at: SystemTime::now(),
+            response,
+        };
+        // nothing listens on the port, the messages are only queued
+        let options = MqttOptions::new("public-ip-address-test", "127.0.0.1", 1);
+        let publisher = MqttPublisher::new(options.clone(), "home/wan/ip");
+        assert!(publisher.publish(&change).is_ok(), "Change not queued");
+        let publisher = MqttPublisher::new(options, "home/wan/#");
+        assert!(
+            matches!(
+                publisher.publish(&change),
+                Err(crate::error::Error::MqttError(_))
+            ),
+            "Wildcard topics should be rejected"
+        );
+    }
+}
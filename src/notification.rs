@@ -0,0 +1,125 @@
+//! # 🔔 Desktop notifications
+//!
+//! A `DesktopNotifier` raises a desktop notification for every public address change reported by
+//! a [`Monitor`], like "Public IP changed to 1.1.1.1 via IpInfo", so VPN users can see their exit
+//! address switch. Notifications are sent through D-Bus on Linux and BSD, the notification center
+//! on macOS and toast notifications on Windows.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use public_ip_address::{lookup::LookupProvider, monitor::Monitor};
+//! use public_ip_address::notification::DesktopNotifier;
+//! use std::time::Duration;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() {
+//! let mut monitor = Monitor::new(vec![(LookupProvider::IpInfo, None)], Duration::from_secs(60));
+//! DesktopNotifier::new().attach(&mut monitor);
+//! monitor.start();
+//! # }
+//! ```
+//!
+//! [`Monitor`]: crate::monitor::Monitor
+
+use crate::error::Result;
+use crate::logging::{trace, warn};
+use crate::monitor::{IpChange, Monitor};
+use notify_rust::Notification;
+
+/// Raises a desktop notification for the public address changes
+#[derive(Debug, Clone)]
+pub struct DesktopNotifier {
+    app_name: String,
+}
+
+impl Default for DesktopNotifier {
+    fn default() -> Self {
+        DesktopNotifier::new()
+    }
+}
+
+impl DesktopNotifier {
+    /// Creates a notifier sending the notifications as this crate.
+    pub fn new() -> Self {
+        DesktopNotifier {
+            app_name: env!("CARGO_PKG_NAME").to_string(),
+        }
+    }
+
+    /// Sets the application name the notifications are sent as.
+    pub fn with_app_name(mut self, app_name: &str) -> Self {
+        self.app_name = app_name.to_string();
+        self
+    }
+
+    /// Raises a notification for the changes reported by the monitor.
+    ///
+    /// The notifications are sent from their own thread, so a slow notification service doesn't
+    /// delay the monitor.
+    pub fn attach(self, monitor: &mut Monitor) {
+        monitor.on_change(move |change| {
+            let notifier = self.clone();
+            let change = change.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = notifier.notify(&change) {
+                    warn!("Failed to show notification: {:?}", e);
+                }
+            });
+        });
+    }
+
+    /// Raises a notification for the change, waiting until it's sent.
+    pub fn notify(&self, change: &IpChange) -> Result<()> {
+        let (summary, body) = message(change);
+        trace!("Showing notification: {}", summary);
+        let mut notification = Notification::new();
+        notification.appname(&self.app_name).summary(&summary);
+        if let Some(body) = body {
+            notification.body(&body);
+        }
+        notification.show()?;
+        Ok(())
+    }
+}
+
+/// Returns the summary and the body of the notification of the change
+fn message(change: &IpChange) -> (String, Option<String>) {
+    let summary = format!(
+        "Public IP changed to {} via {}",
+        change.new_ip, change.provider
+    );
+    let body = change
+        .old_ip
+        .map(|old_ip| format!("Previous address: {}", old_ip));
+    (summary, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lookup::LookupProvider, response::LookupResponse};
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_message() {
+        let response = LookupResponse::new("2.2.2.2".parse().unwrap(), LookupProvider::IpInfo);
+        let mut change = IpChange {
+            old_ip: None,
+            new_ip: response.ip,
+            provider: response.provider.clone(),
+            old_since: None,
+            detected_at: SystemTime::now(),
+            response,
+        };
+        assert_eq!(
+            message(&change),
+            ("Public IP changed to 2.2.2.2 via IpInfo".to_string(), None)
+        );
+        change.old_ip = Some("1.1.1.1".parse().unwrap());
+        assert_eq!(
+            message(&change).1.as_deref(),
+            Some("Previous address: 1.1.1.1")
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! # ⚙️ Lookup options
+//!
+//! The cached and uncached lookups take a `LookupOptions`, grouping the cache time-to-live and
+//! refresh policy, the request timeout and retries, the IP version, the provider selection
+//! strategy, the concurrency limit and the cancellation token instead of positional arguments. Unset options keep the defaults of [`perform_lookup_with`](crate::perform_lookup_with).
+//!
+//! ## Example
+//! ```rust
+//! use public_ip_address::{lookup::LookupProvider, options::LookupOptions};
+//! use std::time::Duration;
+//!
+//! # use std::error::Error;
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() -> Result<(), Box<dyn Error>> {
+//! let providers = vec![(LookupProvider::IpWhoIs, None), (LookupProvider::IpInfo, None)];
+//! let options = LookupOptions::new()
+//!     .ttl(Some(60))
+//!     .timeout(Duration::from_secs(5))
+//!     .retries(1);
+//! match public_ip_address::perform_cached_lookup_with_options(providers, None, &options).await {
+//!     Ok(response) => {
+//!         // Handle successful response
+//!     }
+//!     Err(e) => {
+//!         // Handle error
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    cache::RefreshPolicy,
+    concurrency::ConcurrencyLimit,
+    lookup::{IpVersion, LookupService},
+    strategy::{SelectionStrategy, Sequential},
+    CancellationToken,
+};
+use std::{fmt, sync::Arc, time::Duration};
+
+/// Options of a lookup
+#[derive(Clone)]
+#[non_exhaustive]
+pub struct LookupOptions {
+    /// Seconds before the cached response expires, `None` never expires. Only used by the cached lookups.
+    pub ttl: Option<u64>,
+    /// Bypass the cached response and make a new request. Only used by the cached lookups.
+    pub force_refresh: bool,
+    /// Name of the cache file, `None` uses the default cache. Only used by the cached lookups.
+    pub cache_file: Option<String>,
+    /// Verify the cached current address and refresh its geolocation data with this policy instead
+    /// of the `ttl`, see [`RefreshPolicy`]. Only used by the cached lookups of the unrestricted
+    /// current address.
+    pub refresh_policy: Option<RefreshPolicy>,
+    /// Timeout of each request, `None` waits as long as the HTTP client does.
+    pub timeout: Option<Duration>,
+    /// Number of times a failed provider is retried before the next one is tried.
+    pub retries: u32,
+    /// IP version used to connect to the providers.
+    pub ip_version: IpVersion,
+    /// Only use the providers returning nothing but the address.
+    pub ip_only: bool,
+    /// Fail the lookups returning a non-global address, so the next provider is tried.
+    pub reject_non_global: bool,
+    /// Keep the raw body of the provider responses.
+    pub keep_raw: bool,
+    /// Order in which the providers are tried.
+    pub strategy: Arc<dyn SelectionStrategy>,
+    /// Bound on the requests in flight. Only used by the race and enriched lookups.
+    pub concurrency_limit: ConcurrencyLimit,
+    /// Token aborting the lookup once cancelled. Not used by the enriched lookups.
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl LookupOptions {
+    /// Creates the default options: no cache expiration, no timeout or retries, any IP version and
+    /// the providers tried in the given order.
+    pub fn new() -> Self {
+        LookupOptions {
+            ttl: None,
+            force_refresh: false,
+            cache_file: None,
+            refresh_policy: None,
+            timeout: None,
+            retries: 0,
+            ip_version: IpVersion::Any,
+            ip_only: false,
+            reject_non_global: false,
+            keep_raw: false,
+            strategy: Arc::new(Sequential),
+            concurrency_limit: ConcurrencyLimit::unlimited(),
+            cancellation: None,
+        }
+    }
+
+    /// Sets the seconds before the cached response expires, `None` never expires.
+    pub fn ttl(mut self, ttl: Option<u64>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets whether the cached response is bypassed.
+    pub fn force_refresh(mut self, force_refresh: bool) -> Self {
+        self.force_refresh = force_refresh;
+        self
+    }
+
+    /// Sets the name of the cache file.
+    pub fn cache_file(mut self, cache_file: &str) -> Self {
+        self.cache_file = Some(cache_file.to_string());
+        self
+    }
+
+    /// Sets the policy verifying the cached current address and refreshing its geolocation data.
+    pub fn refresh_policy(mut self, refresh_policy: RefreshPolicy) -> Self {
+        self.refresh_policy = Some(refresh_policy);
+        self
+    }
+
+    /// Sets the timeout of each request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the number of times a failed provider is retried, see [`LookupService::set_retries`](crate::lookup::LookupService::set_retries).
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Sets the IP version used to connect to the providers.
+    pub fn ip_version(mut self, ip_version: IpVersion) -> Self {
+        self.ip_version = ip_version;
+        self
+    }
+
+    /// Sets whether only the providers returning nothing but the address are used.
+    pub fn ip_only(mut self, ip_only: bool) -> Self {
+        self.ip_only = ip_only;
+        self
+    }
+
+    /// Sets whether the lookups returning a non-global address fail, see [`LookupService::set_reject_non_global`](crate::lookup::LookupService::set_reject_non_global).
+    pub fn reject_non_global(mut self, reject_non_global: bool) -> Self {
+        self.reject_non_global = reject_non_global;
+        self
+    }
+
+    /// Sets whether the raw body of the provider responses is kept, see [`LookupService::set_keep_raw`](crate::lookup::LookupService::set_keep_raw).
+    pub fn keep_raw(mut self, keep_raw: bool) -> Self {
+        self.keep_raw = keep_raw;
+        self
+    }
+
+    /// Sets the order in which the providers are tried.
+    pub fn strategy<S: SelectionStrategy + 'static>(mut self, strategy: S) -> Self {
+        self.strategy = Arc::new(strategy);
+        self
+    }
+
+    /// Sets the bound on the requests in flight, see [`ConcurrencyLimit`].
+    pub fn concurrency_limit(mut self, concurrency_limit: ConcurrencyLimit) -> Self {
+        self.concurrency_limit = concurrency_limit;
+        self
+    }
+
+    /// Sets the token aborting the lookup once cancelled.
+    pub fn cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Sets up the service with the IP version, the IP-only mode, the response and request
+    /// settings of the options.
+    pub(crate) fn configure(&self, service: &mut LookupService) {
+        service.set_ip_version(self.ip_version);
+        service.set_ip_only(self.ip_only);
+        service.set_reject_non_global(self.reject_non_global);
+        service.set_keep_raw(self.keep_raw);
+        service.set_retries(self.retries);
+        if let Some(timeout) = self.timeout {
+            service.set_timeout(timeout);
+        }
+    }
+
+    /// Returns the key of the cached current address responses restricted by the IP version or
+    /// the IP-only mode, `None` if the lookup is not restricted.
+    pub(crate) fn restriction(&self) -> Option<String> {
+        match (self.ip_version, self.ip_only) {
+            (IpVersion::Any, false) => None,
+            (ip_version, false) => Some(format!("{:?}", ip_version)),
+            (ip_version, true) => Some(format!("{:?}+ip_only", ip_version)),
+        }
+    }
+}
+
+impl Default for LookupOptions {
+    fn default() -> Self {
+        LookupOptions::new()
+    }
+}
+
+impl fmt::Debug for LookupOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LookupOptions")
+            .field("ttl", &self.ttl)
+            .field("force_refresh", &self.force_refresh)
+            .field("cache_file", &self.cache_file)
+            .field("refresh_policy", &self.refresh_policy)
+            .field("timeout", &self.timeout)
+            .field("retries", &self.retries)
+            .field("ip_version", &self.ip_version)
+            .field("ip_only", &self.ip_only)
+            .field("reject_non_global", &self.reject_non_global)
+            .field("keep_raw", &self.keep_raw)
+            .field("concurrency_limit", &self.concurrency_limit)
+            .field("cancellation", &self.cancellation)
+            .finish_non_exhaustive()
+    }
+}
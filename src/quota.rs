@@ -0,0 +1,221 @@
+//! # ⏳ Provider rate limits
+//!
+//! When a provider answers `429 Too Many Requests` with a `Retry-After` header, or announces an
+//! exhausted quota through rate limit headers like the `X-Rl` and `X-Ttl` headers of ip-api.com,
+//! the time its rate limit resets is recorded. Until then, lookups with the provider fail fast with
+//! a `TooManyRequests` error instead of sending a request, so fallback lookups move on to the next
+//! provider.
+//!
+//! The reset times are kept in the response cache saved by the cached lookups, and restored when a
+//! cache is loaded, so repeated runs of a command line program don't keep hitting a throttled
+//! provider.
+//!
+//! ## Example
+//! ```rust
+//! use public_ip_address::{lookup::LookupProvider, quota};
+//!
+//! if let Some(reset) = quota::rate_limited_until(&LookupProvider::IpApiCom) {
+//!     println!("ip-api.com is rate limited until {:?}", reset);
+//! }
+//! ```
+
+use crate::logging::debug;
+use crate::{
+    cache::ResponseCache,
+    lookup::{
+        client::{header::HeaderMap, StatusCode},
+        LookupProvider,
+    },
+};
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    sync::{LazyLock, Mutex},
+    time::{Duration, SystemTime},
+};
+
+/// Reset times of the rate limited providers, `None` once cleared
+static RATE_LIMITS: LazyLock<Mutex<BTreeMap<String, Option<SystemTime>>>> =
+    LazyLock::new(Mutex::default);
+
+/// Returns the time the rate limit of the provider resets, if it's currently rate limited.
+pub fn rate_limited_until(provider: &LookupProvider) -> Option<SystemTime> {
+    RATE_LIMITS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&provider.to_string())
+        .copied()
+        .flatten()
+        .filter(|reset| *reset > SystemTime::now())
+}
+
+/// Clears the recorded rate limit of the provider, so it's tried again right away.
+///
+/// The rate limit is removed from the cache the next time a cached lookup saves it.
+pub fn clear_rate_limit(provider: &LookupProvider) {
+    RATE_LIMITS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(provider.to_string(), None);
+}
+
+/// Records the rate limit announced by the response headers of the provider, if any.
+pub(crate) fn observe(provider: &LookupProvider, status: StatusCode, headers: &HeaderMap) {
+    if let Some(delay) = reset_delay(status, headers) {
+        debug!("{} rate limited for {:?}", provider, delay);
+        RATE_LIMITS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(provider.to_string(), Some(SystemTime::now() + delay));
+    }
+}
+
+/// Restores the rate limits saved in the cache, the latest reset time wins.
+///
+/// Rate limits cleared with [`clear_rate_limit`] are not restored.
+pub(crate) fn restore(cache: &ResponseCache) {
+    let mut rate_limits = RATE_LIMITS.lock().unwrap_or_else(|e| e.into_inner());
+    for (provider, reset) in &cache.rate_limits {
+        match rate_limits.entry(provider.clone()) {
+            Entry::Vacant(entry) => {
+                entry.insert(Some(*reset));
+            }
+            Entry::Occupied(mut entry) => {
+                if let Some(known) = entry.get_mut() {
+                    *known = (*known).max(*reset);
+                }
+            }
+        }
+    }
+}
+
+/// Stores the current rate limits in the cache, dropping the cleared and expired ones.
+pub(crate) fn store(cache: &mut ResponseCache) {
+    let now = SystemTime::now();
+    let rate_limits = RATE_LIMITS.lock().unwrap_or_else(|e| e.into_inner());
+    for (provider, reset) in rate_limits.iter() {
+        match reset {
+            Some(reset) => cache.rate_limits.insert(provider.clone(), *reset),
+            None => cache.rate_limits.remove(provider),
+        };
+    }
+    cache.rate_limits.retain(|_, reset| *reset > now);
+}
+
+/// Returns the delay until the rate limit resets, from the response status and headers.
+///
+/// Only delays in seconds are supported, `Retry-After` dates are ignored.
+fn reset_delay(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    let seconds = |name: &str| {
+        headers
+            .get(name)?
+            .to_str()
+            .ok()?
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .map(Duration::from_secs)
+    };
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return seconds("retry-after").or_else(|| seconds("x-ttl"));
+    }
+    // ip-api.com announces the requests left in the current window and when it resets
+    if seconds("x-rl") == Some(Duration::ZERO) {
+        return seconds("x-ttl");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup::{error::LookupError, LookupService};
+    use serial_test::serial;
+
+    fn headers(headers: &[(&'static str, &'static str)]) -> HeaderMap {
+        headers
+            .iter()
+            .map(|(name, value)| (name.parse().unwrap(), value.parse().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_reset_delay() {
+        let too_many = StatusCode::TOO_MANY_REQUESTS;
+        let retry_after = headers(&[("Retry-After", "120")]);
+        assert_eq!(
+            reset_delay(too_many, &retry_after),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(reset_delay(StatusCode::OK, &retry_after), None);
+        let date = headers(&[("Retry-After", "Wed, 21 Oct 2015 07:28:00 GMT")]);
+        assert_eq!(
+            reset_delay(too_many, &date),
+            None,
+            "Dates are not supported"
+        );
+
+        let exhausted = headers(&[("X-Rl", "0"), ("X-Ttl", "42")]);
+        assert_eq!(
+            reset_delay(StatusCode::OK, &exhausted),
+            Some(Duration::from_secs(42))
+        );
+        let remaining = headers(&[("X-Rl", "10"), ("X-Ttl", "42")]);
+        assert_eq!(reset_delay(StatusCode::OK, &remaining), None);
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    #[serial]
+    async fn test_rate_limit() {
+        let provider = LookupProvider::Mock("rate-limited".to_string());
+        observe(
+            &provider,
+            StatusCode::TOO_MANY_REQUESTS,
+            &headers(&[("Retry-After", "60")]),
+        );
+        assert!(
+            rate_limited_until(&provider).is_some(),
+            "Limit not recorded"
+        );
+        let mut cache = ResponseCache::new(None);
+        store(&mut cache);
+        assert!(
+            cache.rate_limited_until(&provider).is_some(),
+            "Limit not stored"
+        );
+        let response = LookupService::new(provider.clone(), None)
+            .lookup(None)
+            .await;
+        assert!(
+            matches!(response, Err(LookupError::TooManyRequests(_))),
+            "Rate limited provider should be skipped"
+        );
+        clear_rate_limit(&provider);
+        assert!(rate_limited_until(&provider).is_none(), "Limit not cleared");
+        restore(&cache);
+        assert!(
+            rate_limited_until(&provider).is_none(),
+            "Cleared limit restored"
+        );
+        store(&mut cache);
+        assert!(
+            cache.rate_limited_until(&provider).is_none(),
+            "Cleared limit kept in the cache"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_restore() {
+        let provider = LookupProvider::Mock("restored".to_string());
+        let mut cache = ResponseCache::new(None);
+        let reset = SystemTime::now() + Duration::from_secs(60);
+        cache.record_rate_limit(&provider, Some(reset));
+        restore(&cache);
+        assert_eq!(
+            rate_limited_until(&provider),
+            Some(reset),
+            "Limit not restored"
+        );
+        clear_rate_limit(&provider);
+    }
+}
@@ -0,0 +1,148 @@
+//! # 🔄 Background cache refresh
+//!
+//! A `Refresher` keeps the cached lookup of the current public address warm from a background
+//! component, so the latest response can be read instantly with [`Refresher::current`] instead of
+//! waiting on a provider round trip.
+//!
+//! The refreshed responses are written to the response cache, so cached lookups made elsewhere in
+//! the application share them.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use public_ip_address::lookup::LookupProvider;
+//! use std::time::Duration;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() {
+//! let refresher = public_ip_address::spawn_refresher(
+//!     vec![(LookupProvider::IpInfo, None), (LookupProvider::IpWhoIs, None)],
+//!     Duration::from_secs(300),
+//! );
+//! // in a request handler, never waits on the network
+//! if let Some(response) = refresher.current() {
+//!     println!("{}", response.ip);
+//! }
+//! refresher.shutdown().await;
+//! # }
+//! ```
+
+use crate::logging::{trace, warn};
+use crate::{
+    background::{BackgroundHandle, ShutdownSignal},
+    cache::ResponseCache,
+    lookup::{LookupProvider, Parameters},
+    perform_cached_lookup_with,
+    response::LookupResponse,
+};
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+/// Latest response shared between the refresher and its readers
+type Current = Arc<RwLock<Option<LookupResponse>>>;
+
+/// Background component refreshing the lookup of the current public address
+///
+/// Dropping the refresher stops the background component without waiting for it,
+/// use [`Refresher::shutdown`] to stop it cleanly.
+#[derive(Debug)]
+pub struct Refresher {
+    current: Current,
+    handle: BackgroundHandle,
+}
+
+impl Refresher {
+    /// Starts refreshing the lookup every `ttl` in the background.
+    ///
+    /// The current response is taken from the cache if it's not expired, otherwise it's available
+    /// once the first lookup completes. Failed refreshes keep the previous response and are retried
+    /// after `ttl`.
+    #[maybe_async::async_impl]
+    pub fn spawn(providers: Vec<(LookupProvider, Option<Parameters>)>, ttl: Duration) -> Self {
+        let current = Current::new(RwLock::new(cached()));
+        let component_current = current.clone();
+        let handle = BackgroundHandle::spawn(move |signal| async move {
+            refresh(providers, ttl, component_current, signal).await
+        });
+        Refresher { current, handle }
+    }
+
+    /// Starts refreshing the lookup every `ttl` in the background.
+    ///
+    /// The current response is taken from the cache if it's not expired, otherwise it's available
+    /// once the first lookup completes. Failed refreshes keep the previous response and are retried
+    /// after `ttl`.
+    #[maybe_async::sync_impl]
+    pub fn spawn(providers: Vec<(LookupProvider, Option<Parameters>)>, ttl: Duration) -> Self {
+        let current = Current::new(RwLock::new(cached()));
+        let component_current = current.clone();
+        let handle = BackgroundHandle::spawn(move |signal| {
+            refresh(providers, ttl, component_current, signal)
+        });
+        Refresher { current, handle }
+    }
+
+    /// Returns the latest response, without making any request.
+    ///
+    /// Returns `None` until the first lookup completes.
+    pub fn current(&self) -> Option<LookupResponse> {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Checks if the background component has stopped.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Stops refreshing and waits until the background component finished.
+    #[maybe_async::maybe_async]
+    pub async fn shutdown(self) {
+        self.handle.shutdown().await;
+    }
+}
+
+/// Returns the cached response of the current address if it's not expired.
+fn cached() -> Option<LookupResponse> {
+    let cache = ResponseCache::load(None).ok()?;
+    if cache.current_is_expired() {
+        return None;
+    }
+    cache.current_response()
+}
+
+/// Refreshes the lookup until the shutdown is triggered.
+#[maybe_async::maybe_async]
+async fn refresh(
+    providers: Vec<(LookupProvider, Option<Parameters>)>,
+    ttl: Duration,
+    current: Current,
+    signal: ShutdownSignal,
+) {
+    // the first lookup reuses the cache, later ones are due by the time they run
+    let mut force_refresh = false;
+    loop {
+        match perform_cached_lookup_with(
+            providers.clone(),
+            None,
+            Some(ttl.as_secs()),
+            force_refresh,
+        )
+        .await
+        {
+            Ok(response) => {
+                trace!("Refreshed lookup from {}", response.provider);
+                *current.write().unwrap_or_else(|e| e.into_inner()) = Some(response);
+            }
+            Err(e) => warn!("Failed to refresh lookup: {:?}", e),
+        }
+        force_refresh = true;
+        if signal.wait(ttl).await {
+            break;
+        }
+    }
+}
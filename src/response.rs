@@ -1,8 +1,155 @@
 //! ✉️ Lookup response.
 
-use crate::lookup::LookupProvider;
+use crate::lookup::{error::LookupError, LookupProvider};
 use serde::{Deserialize, Serialize};
-use std::{fmt, net::IpAddr};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    io::IsTerminal,
+    net::IpAddr,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+#[cfg(feature = "iso3166")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "iso3166")]
+pub use isocountry::CountryCode;
+
+#[cfg(feature = "iso3166")]
+static NORMALIZE_COUNTRIES: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether the lookups normalize the country of the responses, see [`LookupResponse::normalize_country`].
+///
+/// Disabled by default: the lookups keep the country of the providers and only fill the missing
+/// country code, see [`LookupResponse::fill_country_code`].
+#[cfg(feature = "iso3166")]
+pub fn set_normalize_countries(normalize: bool) {
+    NORMALIZE_COUNTRIES.store(normalize, Ordering::Relaxed);
+}
+
+/// Returns whether the lookups normalize the country of the responses.
+#[cfg(feature = "iso3166")]
+pub fn normalize_countries() -> bool {
+    NORMALIZE_COUNTRIES.load(Ordering::Relaxed)
+}
+
+/// Returns the ISO 3166-1 country of a name, like "United States", "USA" or "Viet Nam", ignoring the case.
+///
+/// The ISO names, the alpha-2 and alpha-3 codes and common names differing from the ISO names are recognized.
+#[cfg(feature = "iso3166")]
+pub fn country_by_name(name: &str) -> Option<CountryCode> {
+    let name = name.trim().to_lowercase();
+    CountryCode::iter()
+        .find(|country| country.name().to_lowercase() == name)
+        .copied()
+        .or_else(|| {
+            COUNTRY_ALIASES
+                .iter()
+                .find(|(alias, _)| *alias == name)
+                .map(|(_, country)| *country)
+        })
+        .or_else(|| CountryCode::for_alpha2_caseless(&name).ok())
+        .or_else(|| CountryCode::for_alpha3_caseless(&name).ok())
+}
+
+/// Common country names that differ from the ISO 3166-1 names, in lowercase
+#[cfg(feature = "iso3166")]
+const COUNTRY_ALIASES: [(&str, CountryCode); 42] = [
+    ("united states", CountryCode::USA),
+    ("usa", CountryCode::USA),
+    ("uk", CountryCode::GBR),
+    ("united kingdom", CountryCode::GBR),
+    ("great britain", CountryCode::GBR),
+    ("britain", CountryCode::GBR),
+    ("russia", CountryCode::RUS),
+    ("south korea", CountryCode::KOR),
+    ("republic of korea", CountryCode::KOR),
+    ("korea, republic of", CountryCode::KOR),
+    ("north korea", CountryCode::PRK),
+    ("iran", CountryCode::IRN),
+    ("vietnam", CountryCode::VNM),
+    ("czech republic", CountryCode::CZE),
+    ("bolivia", CountryCode::BOL),
+    ("venezuela", CountryCode::VEN),
+    ("tanzania", CountryCode::TZA),
+    ("syria", CountryCode::SYR),
+    ("laos", CountryCode::LAO),
+    ("moldova", CountryCode::MDA),
+    ("macedonia", CountryCode::MKD),
+    ("north macedonia", CountryCode::MKD),
+    ("taiwan", CountryCode::TWN),
+    ("the netherlands", CountryCode::NLD),
+    ("holland", CountryCode::NLD),
+    ("dr congo", CountryCode::COD),
+    ("democratic republic of the congo", CountryCode::COD),
+    ("congo, the democratic republic of the", CountryCode::COD),
+    ("republic of the congo", CountryCode::COG),
+    ("ivory coast", CountryCode::CIV),
+    ("cote d'ivoire", CountryCode::CIV),
+    ("palestine", CountryCode::PSE),
+    ("turkiye", CountryCode::TUR),
+    ("türkiye", CountryCode::TUR),
+    ("the bahamas", CountryCode::BHS),
+    ("micronesia", CountryCode::FSM),
+    ("brunei", CountryCode::BRN),
+    ("cape verde", CountryCode::CPV),
+    ("eswatini", CountryCode::SWZ),
+    ("vatican", CountryCode::VAT),
+    ("vatican city", CountryCode::VAT),
+    ("macau", CountryCode::MAC),
+];
+
+/// Checks if an address is globally routable.
+///
+/// Private, loopback, link-local, shared, documentation, benchmarking, multicast, broadcast and
+/// reserved addresses are not global. IPv6 addresses are global when they are global unicast
+/// addresses outside of the documentation and benchmarking ranges, and IPv4-mapped addresses
+/// are checked as IPv4 addresses.
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::response::is_global_ip;
+///
+/// assert!(is_global_ip(&"1.1.1.1".parse().unwrap()));
+/// assert!(!is_global_ip(&"192.168.1.1".parse().unwrap()));
+/// assert!(!is_global_ip(&"fe80::1".parse().unwrap()));
+/// ```
+pub fn is_global_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            !(a == 0
+                || ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                // shared address space, 100.64.0.0/10
+                || (a == 100 && (b & 0b1100_0000) == 64)
+                // IETF protocol assignments, 192.0.0.0/24
+                || (a == 192 && b == 0 && c == 0)
+                || ip.is_documentation()
+                // benchmarking, 198.18.0.0/15
+                || (a == 198 && (b & 0xfe) == 18)
+                || ip.is_multicast()
+                // reserved, 240.0.0.0/4, including the broadcast address
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_global_ip(&IpAddr::V4(mapped));
+            }
+            let segments = ip.segments();
+            // global unicast, 2000::/3
+            (segments[0] & 0xe000) == 0x2000
+                // documentation, 2001:db8::/32
+                && !(segments[0] == 0x2001 && segments[1] == 0x0db8)
+                // benchmarking, 2001:2::/48
+                && !(segments[0] == 0x2001 && segments[1] == 0x0002 && segments[2] == 0)
+        }
+    }
+}
 
 /// Lookup response containing information like IP, country, city, hostname etc.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -10,14 +157,20 @@ use std::{fmt, net::IpAddr};
 pub struct LookupResponse {
     /// Public IP address.
     pub ip: IpAddr,
-    /// Continent name.
-    pub continent: Option<String>,
+    /// Continent, normalized from the name or the code given by the provider.
+    #[serde(default, deserialize_with = "deserialize_continent")]
+    pub continent: Option<Continent>,
     /// Country name.
     pub country: Option<String>,
     /// Country ISO code.
     pub country_code: Option<String>,
+    /// Is the country a member of the European Union?
+    pub is_eu: Option<bool>,
     /// Region name.
     pub region: Option<String>,
+    /// ISO 3166-2 code of the region, like "US-NY".
+    #[serde(default)]
+    pub region_code: Option<String>,
     /// Postal code.
     pub postal_code: Option<String>,
     /// City name.
@@ -34,13 +187,61 @@ pub struct LookupResponse {
     pub asn_org: Option<String>,
     /// Hostname of the IP address.
     pub hostname: Option<String>,
-    /// Is the IP a proxy or vpn?
-    pub is_proxy: Option<bool>,
+    /// Security flags of the IP address, like VPN, Tor or proxy.
+    pub security: Option<Security>,
+    /// Mobile carrier of the IP address.
+    pub carrier: Option<Carrier>,
+    /// Currency of the country.
+    pub currency: Option<Currency>,
+    /// Languages spoken in the country, as ISO 639 codes with an optional region like "en-US".
+    pub languages: Option<Vec<String>>,
+    /// Emoji of the country flag, derived from the country code when the provider has none.
+    pub flag_emoji: Option<String>,
+    /// URL of an image of the country flag.
+    pub flag_url: Option<String>,
+    /// International calling code of the country, like "+1".
+    pub calling_code: Option<String>,
     /// Provider that was used for the lookup.
     pub provider: LookupProvider,
+    /// Time the provider response was received, kept when the response is cached.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub looked_up_at: Option<SystemTime>,
+    /// Round-trip time of the provider request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<Duration>,
+    /// Time the provider response stops being fresh, from its `Cache-Control` or `Expires` header,
+    /// see [`freshness`](crate::freshness).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fresh_until: Option<SystemTime>,
+    /// Providers of the fields filled from other responses by [`LookupResponse::merge`], by field name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sources: BTreeMap<String, LookupProvider>,
+    /// Raw body of the provider response, kept when enabled with [`LookupService::set_keep_raw`](crate::lookup::LookupService::set_keep_raw).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
 }
 
 impl LookupResponse {
+    /// Creates a builder of a lookup response, for providers implemented outside of this crate.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::LookupResponse};
+    ///
+    /// let response = LookupResponse::builder("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo)
+    ///     .country("Australia")
+    ///     .country_code("AU")
+    ///     .city("Sydney")
+    ///     .build();
+    /// assert_eq!(response.city.as_deref(), Some("Sydney"));
+    /// ```
+    pub fn builder(ip: IpAddr, provider: LookupProvider) -> LookupResponseBuilder {
+        LookupResponseBuilder {
+            response: LookupResponse::new(ip, provider),
+        }
+    }
+
     /// Create a new lookup response.
     pub fn new(ip: IpAddr, provider: LookupProvider) -> Self {
         LookupResponse {
@@ -48,7 +249,9 @@ impl LookupResponse {
             continent: None,
             country: None,
             country_code: None,
+            is_eu: None,
             region: None,
+            region_code: None,
             postal_code: None,
             city: None,
             latitude: None,
@@ -57,62 +260,2395 @@ impl LookupResponse {
             asn: None,
             asn_org: None,
             hostname: None,
-            is_proxy: None,
+            security: None,
+            carrier: None,
+            currency: None,
+            languages: None,
+            flag_emoji: None,
+            flag_url: None,
+            calling_code: None,
             provider,
+            looked_up_at: None,
+            latency: None,
+            fresh_until: None,
+            sources: BTreeMap::new(),
+            raw: None,
         }
     }
-}
 
-impl fmt::Display for LookupResponse {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "IP: {}", self.ip)?;
+    /// Returns the typed ISO 3166-1 code of the country, parsed from the alpha-2 or alpha-3 `country_code`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::{CountryCode, LookupResponse}};
+    ///
+    /// let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+    /// response.country_code = Some("de".to_string());
+    /// let country = response.country_iso().unwrap();
+    /// assert_eq!(country, CountryCode::DEU);
+    /// assert_eq!(country.alpha3(), "DEU");
+    /// assert_eq!(country.numeric_id(), 276);
+    /// ```
+    #[cfg(feature = "iso3166")]
+    pub fn country_iso(&self) -> Option<CountryCode> {
+        let code = self.country_code.as_deref()?.trim();
+        CountryCode::for_alpha2_caseless(code)
+            .or_else(|_| CountryCode::for_alpha3_caseless(code))
+            .ok()
+    }
+
+    /// Normalizes the region code to its ISO 3166-2 form, see [`region_code`].
+    ///
+    /// The code is derived from the region name when the provider has none. The region code is
+    /// kept as it is when it can't be normalized.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::LookupResponse};
+    ///
+    /// let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+    /// response.country_code = Some("US".to_string());
+    /// response.region = Some("New York".to_string());
+    /// response.normalize_region();
+    /// assert_eq!(response.region_code.as_deref(), Some("US-NY"));
+    /// ```
+    pub fn normalize_region(&mut self) {
+        let Some(country_code) = self.country_code.as_deref() else {
+            return;
+        };
+        let code = self
+            .region_code
+            .as_deref()
+            .and_then(|code| region_code(country_code, code))
+            .or_else(|| {
+                self.region
+                    .as_deref()
+                    .and_then(|region| region_code(country_code, region))
+            });
+        if code.is_some() {
+            self.region_code = code;
+        }
+    }
+
+    /// Normalizes the country to the ISO 3166-1 name and alpha-2 code.
+    ///
+    /// The country is identified by the code, or by the name when the code is unknown, see
+    /// [`country_by_name`]. Unrecognized countries are kept as they are. The lookups normalize
+    /// the responses when enabled with [`set_normalize_countries`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::LookupResponse};
+    ///
+    /// let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+    /// response.country = Some("USA".to_string());
+    /// response.normalize_country();
+    /// assert_eq!(response.country.as_deref(), Some("United States of America"));
+    /// assert_eq!(response.country_code.as_deref(), Some("US"));
+    /// ```
+    #[cfg(feature = "iso3166")]
+    pub fn normalize_country(&mut self) {
+        let country = self
+            .country_iso()
+            .or_else(|| self.country.as_deref().and_then(country_by_name));
+        if let Some(country) = country {
+            self.country = Some(country.name().to_string());
+            self.country_code = Some(country.alpha2().to_string());
+        }
+    }
+
+    /// Fills the missing country code with the ISO 3166-1 alpha-2 code of the country name.
+    ///
+    /// Unlike [`normalize_country`](Self::normalize_country), the values of the provider are kept.
+    /// The lookups fill the country code of the responses unless the countries are normalized.
+    #[cfg(feature = "iso3166")]
+    pub fn fill_country_code(&mut self) {
+        if self.country_code.is_some() {
+            return;
+        }
+        if let Some(country) = self.country.as_deref().and_then(country_by_name) {
+            self.country_code = Some(country.alpha2().to_string());
+        }
+    }
+
+    /// Returns the time zone of the IP address, parsed from the IANA name in `time_zone`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::LookupResponse};
+    ///
+    /// let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+    /// response.time_zone = Some("Europe/Berlin".to_string());
+    /// assert_eq!(response.timezone(), Some(chrono_tz::Europe::Berlin));
+    /// ```
+    #[cfg(feature = "chrono")]
+    pub fn timezone(&self) -> Option<chrono_tz::Tz> {
+        self.time_zone.as_deref()?.trim().parse().ok()
+    }
+
+    /// Returns the current local time at the location of the IP address, `None` if the time zone is unknown.
+    #[cfg(feature = "chrono")]
+    pub fn local_time(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        let timezone = self.timezone()?;
+        Some(chrono::Utc::now().with_timezone(&timezone))
+    }
+
+    /// Checks if the IP address is globally routable, see [`is_global_ip`].
+    pub fn is_global(&self) -> bool {
+        is_global_ip(&self.ip)
+    }
+
+    /// Returns the coordinates of the IP address, `None` if they are unknown or out of range.
+    pub fn coordinates(&self) -> Option<Coordinates> {
+        let coordinates = Coordinates::new(self.latitude?, self.longitude?);
+        coordinates.is_valid().then_some(coordinates)
+    }
+
+    /// Returns the geohash of the coordinates of the IP address, see [`Coordinates::geohash`].
+    pub fn geohash(&self, precision: usize) -> Option<String> {
+        self.coordinates()
+            .map(|coordinates| coordinates.geohash(precision))
+    }
+
+    /// Returns the response as a GeoJSON `Feature`.
+    ///
+    /// The geometry is a `Point` at the coordinates of the IP address, or `null` if they are unknown,
+    /// and the other fields of the response are the properties of the feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::LookupResponse};
+    ///
+    /// let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+    /// response.latitude = Some(52.52);
+    /// response.longitude = Some(13.405);
+    /// let feature = response.to_geojson();
+    /// assert_eq!(feature["geometry"]["coordinates"], serde_json::json!([13.405, 52.52]));
+    /// assert_eq!(feature["properties"]["ip"], "1.1.1.1");
+    /// ```
+    pub fn to_geojson(&self) -> serde_json::Value {
+        let geometry = match self.coordinates() {
+            // GeoJSON positions are in longitude, latitude order
+            Some(coordinates) => serde_json::json!({
+                "type": "Point",
+                "coordinates": [coordinates.lon, coordinates.lat],
+            }),
+            None => serde_json::Value::Null,
+        };
+        let mut properties = serde_json::to_value(self).unwrap_or_default();
+        if let Some(properties) = properties.as_object_mut() {
+            properties.remove("latitude");
+            properties.remove("longitude");
+            properties.remove("raw");
+        }
+        serde_json::json!({
+            "type": "Feature",
+            "geometry": geometry,
+            "properties": properties,
+        })
+    }
+
+    /// Returns the names of the CSV columns written by [`LookupResponse::to_csv_record`].
+    #[cfg(feature = "csv")]
+    pub fn csv_header() -> Vec<&'static str> {
+        vec![
+            "ip",
+            "continent",
+            "country",
+            "country_code",
+            "is_eu",
+            "region",
+            "region_code",
+            "postal_code",
+            "city",
+            "latitude",
+            "longitude",
+            "time_zone",
+            "asn",
+            "asn_org",
+            "hostname",
+            "security",
+            "carrier",
+            "currency",
+            "languages",
+            "flag_emoji",
+            "flag_url",
+            "calling_code",
+            "provider",
+            "looked_up_at",
+            "latency_ms",
+        ]
+    }
+
+    /// Returns the fields of the response as a CSV record, in the order of [`LookupResponse::csv_header`].
+    ///
+    /// Unknown fields are empty, the languages are separated by semicolons and the lookup time is in
+    /// seconds since the Unix epoch. The raw body of the provider response is not included.
+    #[cfg(feature = "csv")]
+    pub fn to_csv_record(&self) -> Vec<String> {
+        fn field<T: fmt::Display>(value: &Option<T>) -> String {
+            value.as_ref().map(T::to_string).unwrap_or_default()
+        }
+        vec![
+            self.ip.to_string(),
+            field(&self.continent),
+            field(&self.country),
+            field(&self.country_code),
+            field(&self.is_eu),
+            field(&self.region),
+            field(&self.region_code),
+            field(&self.postal_code),
+            field(&self.city),
+            field(&self.latitude),
+            field(&self.longitude),
+            field(&self.time_zone),
+            field(&self.asn),
+            field(&self.asn_org),
+            field(&self.hostname),
+            field(&self.security),
+            field(&self.carrier),
+            field(&self.currency.as_ref().map(|currency| &currency.code)),
+            field(&self.languages.as_ref().map(|languages| languages.join(";"))),
+            field(&self.flag_emoji),
+            field(&self.flag_url),
+            field(&self.calling_code),
+            self.provider.to_string(),
+            field(&self.looked_up_at.and_then(|time| {
+                time.duration_since(SystemTime::UNIX_EPOCH)
+                    .ok()
+                    .map(|since| since.as_secs())
+            })),
+            field(&self.latency.map(|latency| latency.as_millis())),
+        ]
+    }
+
+    /// Serializes the response to YAML.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::LookupResponse};
+    ///
+    /// let response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+    /// let yaml = response.to_yaml().unwrap();
+    /// assert!(yaml.contains("provider: IpInfo"));
+    /// assert_eq!(LookupResponse::from_yaml(&yaml).unwrap(), response);
+    /// ```
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml(&self) -> Result<String, serde_yaml_ng::Error> {
+        let mut yaml = Vec::new();
+        // the mock provider is written as a map instead of a YAML tag, which can't be read back
+        serde_yaml_ng::with::singleton_map_recursive::serialize(
+            self,
+            &mut serde_yaml_ng::Serializer::new(&mut yaml),
+        )?;
+        Ok(String::from_utf8(yaml).expect("YAML is valid UTF-8"))
+    }
+
+    /// Deserializes a response from YAML written by [`LookupResponse::to_yaml`].
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml: &str) -> Result<Self, serde_yaml_ng::Error> {
+        serde_yaml_ng::from_str(yaml)
+    }
+
+    /// Serializes the response to TOML, the unknown fields are left out.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::LookupResponse};
+    ///
+    /// let response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+    /// let toml = response.to_toml().unwrap();
+    /// assert!(toml.contains(r#"provider = "IpInfo""#));
+    /// assert_eq!(LookupResponse::from_toml(&toml).unwrap(), response);
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string(self)
+    }
+
+    /// Deserializes a response from TOML written by [`LookupResponse::to_toml`].
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml)
+    }
+
+    /// Returns the known fields of the response as a flat map, for templates and environment variables.
+    ///
+    /// The keys are the serialized field names, with the nested fields joined by underscores like
+    /// "security_is_vpn" and "currency_code". Lists are separated by commas, the lookup time is in
+    /// seconds since the Unix epoch and the latency in milliseconds as "latency_ms". The providers of
+    /// the merged fields are under "sources_", and the fields of a raw JSON provider response under
+    /// "raw_", including the ones this crate doesn't model.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::LookupResponse};
+    ///
+    /// let response = LookupResponse::builder("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo)
+    ///     .city("Sydney")
+    ///     .raw(r#"{"anycast": true}"#)
+    ///     .build();
+    /// let map = response.to_map();
+    /// assert_eq!(map["ip"], "1.1.1.1");
+    /// assert_eq!(map["city"], "Sydney");
+    /// assert_eq!(map["provider"], "IpInfo");
+    /// assert_eq!(map["raw_anycast"], "true");
+    /// ```
+    pub fn to_map(&self) -> BTreeMap<String, String> {
+        fn flatten(key: String, value: &serde_json::Value, map: &mut BTreeMap<String, String>) {
+            let text = |value: &serde_json::Value| match value {
+                serde_json::Value::String(text) => text.clone(),
+                value => value.to_string(),
+            };
+            match value {
+                serde_json::Value::Null => {}
+                serde_json::Value::Object(object) => {
+                    for (name, value) in object {
+                        let name = if key.is_empty() {
+                            name.clone()
+                        } else {
+                            format!("{}_{}", key, name)
+                        };
+                        flatten(name, value, map);
+                    }
+                }
+                serde_json::Value::Array(items) => {
+                    let items: Vec<String> = items.iter().map(text).collect();
+                    map.insert(key, items.join(","));
+                }
+                value => {
+                    map.insert(key, text(value));
+                }
+            }
+        }
+
+        let mut map = BTreeMap::new();
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Some(object) = value.as_object_mut() {
+            for metadata in [
+                "provider",
+                "looked_up_at",
+                "latency",
+                "fresh_until",
+                "sources",
+                "raw",
+            ] {
+                object.remove(metadata);
+            }
+        }
+        flatten(String::new(), &value, &mut map);
+        map.insert("provider".to_string(), self.provider.to_string());
+        if let Some(since) = self
+            .looked_up_at
+            .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        {
+            map.insert("looked_up_at".to_string(), since.as_secs().to_string());
+        }
+        if let Some(latency) = self.latency {
+            map.insert("latency_ms".to_string(), latency.as_millis().to_string());
+        }
+        for (field, provider) in &self.sources {
+            map.insert(format!("sources_{}", field), provider.to_string());
+        }
+        if let Some(raw) = &self.raw {
+            match serde_json::from_str(raw) {
+                Ok(value @ serde_json::Value::Object(_)) => {
+                    flatten("raw".to_string(), &value, &mut map)
+                }
+                _ => {
+                    map.insert("raw".to_string(), raw.clone());
+                }
+            }
+        }
+        map
+    }
+
+    /// Returns a single line summary of the response, for status bars and log lines.
+    ///
+    /// The unknown parts are left out.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::LookupResponse};
+    ///
+    /// let mut response = LookupResponse::new("1.2.3.4".parse().unwrap(), LookupProvider::IpInfo);
+    /// response.city = Some("Berlin".to_string());
+    /// response.country_code = Some("DE".to_string());
+    /// response.asn = Some("3320".to_string());
+    /// response.asn_org = Some("Deutsche Telekom".to_string());
+    /// assert_eq!(
+    ///     response.to_compact_string(),
+    ///     "1.2.3.4 — Berlin, DE — AS3320 Deutsche Telekom — via IpInfo"
+    /// );
+    /// ```
+    pub fn to_compact_string(&self) -> String {
+        let mut parts = vec![self.ip.to_string()];
+        let location = self.location().display_short();
+        if !location.is_empty() {
+            parts.push(location);
+        }
+        // some providers give the bare number of the ASN
+        let asn = self.asn.as_ref().map(|asn| {
+            if asn.chars().all(|c| c.is_ascii_digit()) {
+                format!("AS{}", asn)
+            } else {
+                asn.clone()
+            }
+        });
+        match (asn, &self.asn_org) {
+            (Some(asn), Some(asn_org)) => parts.push(format!("{} {}", asn, asn_org)),
+            (Some(asn), None) => parts.push(asn),
+            (None, Some(asn_org)) => parts.push(asn_org.clone()),
+            (None, None) => {}
+        }
+        parts.push(format!("via {}", self.provider));
+        parts.join(" — ")
+    }
+
+    /// Returns the labels and the values of the known fields, in display order.
+    fn fields(&self) -> Vec<(Field, String)> {
+        let mut fields = vec![(Field::Ip, self.ip.to_string())];
         if let Some(continent) = &self.continent {
-            writeln!(f, "Continent: {}", continent)?;
+            fields.push((Field::Continent, continent.to_string()));
         }
-        if let Some(country) = &self.country {
-            write!(f, "Country: {}", country)?;
+        let country = match (&self.country, &self.country_code) {
+            (Some(country), Some(code)) => Some(format!("{} ({})", country, code)),
+            (Some(country), None) => Some(country.clone()),
+            (None, Some(code)) => Some(code.clone()),
+            (None, None) => None,
+        };
+        if let Some(mut country) = country {
+            if let Some(flag_emoji) = &self.flag_emoji {
+                country = format!("{} {}", country, flag_emoji);
+            }
+            fields.push((Field::Country, country));
         }
-        if let Some(country_code) = &self.country_code {
-            writeln!(f, " ({})", country_code)?;
-        } else {
-            writeln!(f)?;
+        if let Some(is_eu) = &self.is_eu {
+            fields.push((Field::IsEu, is_eu.to_string()));
         }
-        if let Some(region) = &self.region {
-            writeln!(f, "Region: {}", region)?;
+        let region = match (&self.region, &self.region_code) {
+            (Some(region), Some(code)) => Some(format!("{} ({})", region, code)),
+            (Some(region), None) => Some(region.clone()),
+            (None, Some(code)) => Some(code.clone()),
+            (None, None) => None,
+        };
+        if let Some(region) = region {
+            fields.push((Field::Region, region));
         }
         if let Some(postal_code) = &self.postal_code {
-            writeln!(f, "Postal code: {}", postal_code)?;
+            fields.push((Field::PostalCode, postal_code.clone()));
         }
         if let Some(city) = &self.city {
-            writeln!(f, "City: {}", city)?;
+            fields.push((Field::City, city.clone()));
         }
-        if let Some(latitude) = &self.latitude {
-            write!(f, "Coordinates: {}", latitude)?;
+        if let (Some(latitude), Some(longitude)) = (&self.latitude, &self.longitude) {
+            fields.push((Field::Coordinates, format!("{}, {}", latitude, longitude)));
         }
-        if let Some(longitude) = &self.longitude {
-            writeln!(f, ", {}", longitude)?;
+        if let Some(time_zone) = &self.time_zone {
+            fields.push((Field::TimeZone, time_zone.clone()));
+        }
+        let organization = match (&self.asn_org, &self.asn) {
+            (Some(asn_org), Some(asn)) => Some(format!("{} ({})", asn_org, asn)),
+            (Some(asn_org), None) => Some(asn_org.clone()),
+            (None, Some(asn)) => Some(asn.clone()),
+            (None, None) => None,
+        };
+        if let Some(organization) = organization {
+            fields.push((Field::Organization, organization));
+        }
+        if let Some(hostname) = &self.hostname {
+            fields.push((Field::Hostname, hostname.clone()));
+        }
+        if let Some(security) = &self.security {
+            fields.push((Field::Security, security.to_string()));
+        }
+        if let Some(carrier) = &self.carrier {
+            fields.push((Field::Carrier, carrier.to_string()));
+        }
+        if let Some(calling_code) = &self.calling_code {
+            fields.push((Field::CallingCode, calling_code.clone()));
+        }
+        if let Some(currency) = &self.currency {
+            fields.push((Field::Currency, currency.to_string()));
+        }
+        if let Some(languages) = &self.languages {
+            fields.push((Field::Languages, languages.join(", ")));
+        }
+        if let Some(looked_up_at) = &self.looked_up_at {
+            fields.push((Field::LookedUpAt, httpdate::fmt_http_date(*looked_up_at)));
+        }
+        let provider = match &self.latency {
+            Some(latency) => format!("{} ({:?})", self.provider, latency),
+            None => self.provider.to_string(),
+        };
+        fields.push((Field::Provider, provider));
+        fields
+    }
+
+    /// Returns the known fields of the response as an aligned, boxed table.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::LookupResponse};
+    ///
+    /// let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+    /// response.city = Some("Berlin".to_string());
+    /// println!("{}", response.to_table());
+    /// ```
+    #[cfg(feature = "table")]
+    pub fn to_table(&self) -> String {
+        let mut table = comfy_table::Table::new();
+        table
+            .load_preset(comfy_table::presets::UTF8_FULL)
+            .apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS);
+        for (field, value) in self.fields() {
+            table.add_row(vec![field.label().to_string(), value]);
+        }
+        table.to_string()
+    }
+
+    /// Returns the multi-line display of the response with colors for terminals, see [`Colored`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::LookupResponse};
+    ///
+    /// let response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+    /// println!("{}", response.colored());
+    /// ```
+    pub fn colored(&self) -> Colored<'_> {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        Colored {
+            response: self,
+            labels: None,
+            color: !no_color && std::io::stdout().is_terminal(),
+        }
+    }
+
+    /// Returns the multi-line display of the response with localized labels, see [`Labels`].
+    pub fn localized<'a>(&'a self, labels: &'a Labels) -> Localized<'a> {
+        Localized {
+            response: self,
+            labels,
+        }
+    }
+
+    /// Returns the location of the IP address as a country > region > city hierarchy.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::LookupResponse};
+    ///
+    /// let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+    /// response.country_code = Some("DE".to_string());
+    /// response.city = Some("Berlin".to_string());
+    /// assert_eq!(response.location().display_short(), "Berlin, DE");
+    /// ```
+    pub fn location(&self) -> Location {
+        let city = self.city.clone().map(|name| City {
+            name,
+            postal_code: self.postal_code.clone(),
+        });
+        let region = if self.region.is_some() || self.region_code.is_some() || city.is_some() {
+            Some(Region {
+                name: self.region.clone(),
+                code: self.region_code.clone(),
+                city,
+            })
         } else {
-            writeln!(f)?;
+            None
+        };
+        let country = if self.country.is_some() || self.country_code.is_some() || region.is_some() {
+            Some(Country {
+                name: self.country.clone(),
+                code: self.country_code.clone(),
+                region,
+            })
+        } else {
+            None
+        };
+        Location {
+            continent: self.continent,
+            country,
         }
-        if let Some(time_zone) = &self.time_zone {
-            writeln!(f, "Time zone: {}", time_zone)?;
+    }
+
+    /// Compares the data of this response with another response.
+    ///
+    /// Only the fields describing the IP address are compared, the provider and the lookup metadata
+    /// like the time and the latency are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::LookupResponse};
+    ///
+    /// let mut old = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+    /// old.country = Some("Germany".to_string());
+    /// let mut new = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpWhoIs);
+    /// new.country = Some("France".to_string());
+    /// let diff = old.diff(&new);
+    /// assert_eq!(diff.fields(), vec!["country"]);
+    /// assert_eq!(diff.to_string(), "country: Germany -> France");
+    /// ```
+    pub fn diff(&self, other: &LookupResponse) -> ResponseDiff {
+        let old = serde_json::to_value(self).unwrap_or_default();
+        let new = serde_json::to_value(other).unwrap_or_default();
+        let changes = DATA_FIELDS
+            .iter()
+            .filter_map(|&field| {
+                let old = old.get(field).filter(|value| !value.is_null()).cloned();
+                let new = new.get(field).filter(|value| !value.is_null()).cloned();
+                (old != new).then(|| FieldChange {
+                    field: field.to_string(),
+                    old,
+                    new,
+                })
+            })
+            .collect();
+        ResponseDiff { changes }
+    }
+
+    /// Fills the missing fields of this response with the values from another response.
+    ///
+    /// Fields that are already set are kept. Coordinates are only taken as a pair,
+    /// so latitude and longitude always come from the same provider.
+    ///
+    /// The provider of every filled field is recorded, see [`LookupResponse::source`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::{lookup::LookupProvider, response::LookupResponse};
+    ///
+    /// let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::Ipify);
+    /// response.country = Some("Australia".to_string());
+    /// let mut other = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+    /// other.country = Some("United States".to_string());
+    /// other.asn = Some("AS13335".to_string());
+    ///
+    /// let merged = response.merge(&other);
+    /// assert_eq!(merged.country.as_deref(), Some("Australia"));
+    /// assert_eq!(merged.asn.as_deref(), Some("AS13335"));
+    /// assert_eq!(merged.source("country"), Some(&LookupProvider::Ipify));
+    /// assert_eq!(merged.source("asn"), Some(&LookupProvider::IpInfo));
+    /// ```
+    pub fn merge(mut self, other: &LookupResponse) -> Self {
+        self.enrich_from(other);
+        self
+    }
+
+    /// Returns the provider that supplied a field, by its serialized name like "country" or "asn".
+    ///
+    /// Fields filled by [`LookupResponse::merge`] come from the provider of the merged response,
+    /// the other known fields from the provider of this response. Returns `None` if the field is unknown.
+    pub fn source(&self, field: &str) -> Option<&LookupProvider> {
+        let value = serde_json::to_value(self).ok()?;
+        match value.get(field) {
+            Some(value) if !value.is_null() => {
+                Some(self.sources.get(field).unwrap_or(&self.provider))
+            }
+            _ => None,
         }
-        if let Some(asn_org) = &self.asn_org {
-            write!(f, "Organization: {}", asn_org)?;
+    }
+
+    /// Fills the missing fields of this response with the values from another response, see [`LookupResponse::merge`].
+    pub(crate) fn enrich_from(&mut self, other: &LookupResponse) {
+        let mut filled = Vec::new();
+        macro_rules! fill {
+            ($($field:ident),+) => {
+                $(
+                    if self.$field.is_none() && other.$field.is_some() {
+                        self.$field.clone_from(&other.$field);
+                        filled.push(stringify!($field));
+                    }
+                )+
+            };
+        }
+        fill!(
+            continent,
+            country,
+            country_code,
+            is_eu,
+            region,
+            region_code,
+            postal_code,
+            city
+        );
+        if self.latitude.is_none() && self.longitude.is_none() {
+            fill!(latitude, longitude);
+        }
+        fill!(time_zone, asn, asn_org, hostname, security, carrier, currency);
+        fill!(languages, flag_emoji, flag_url, calling_code);
+        for field in filled {
+            let source = other.sources.get(field).unwrap_or(&other.provider);
+            self.sources.insert(field.to_string(), source.clone());
+        }
+    }
+}
+
+/// Returns the emoji of the flag of a country from its ISO 3166-1 alpha-2 code.
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::response::flag_emoji;
+///
+/// assert_eq!(flag_emoji("de").as_deref(), Some("🇩🇪"));
+/// assert_eq!(flag_emoji("DEU"), None);
+/// ```
+pub fn flag_emoji(country_code: &str) -> Option<String> {
+    if country_code.len() != 2 || !country_code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    // regional indicator symbols, from 🇦 for A
+    country_code
+        .to_ascii_uppercase()
+        .chars()
+        .map(|c| char::from_u32(0x1F1E6 + (c as u32 - 'A' as u32)))
+        .collect()
+}
+
+/// Returns the ISO 3166-2 code of a region of a country from its ISO 3166-1 alpha-2 code.
+///
+/// The region can be a subdivision code with or without the country prefix, like "NY" or "US-NY",
+/// or the name of a US state or a Canadian province. Returns `None` when the region isn't
+/// recognized or is in another country.
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::response::region_code;
+///
+/// assert_eq!(region_code("US", "NY").as_deref(), Some("US-NY"));
+/// assert_eq!(region_code("us", "us-ny").as_deref(), Some("US-NY"));
+/// assert_eq!(region_code("US", "New York").as_deref(), Some("US-NY"));
+/// assert_eq!(region_code("CA", "US-NY"), None);
+/// ```
+pub fn region_code(country_code: &str, region: &str) -> Option<String> {
+    let country_code = country_code.trim().to_ascii_uppercase();
+    if country_code.len() != 2 || !country_code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let region = region.trim();
+    let is_code = |code: &str| {
+        (1..=3).contains(&code.len())
+            && code
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    };
+    // codes are uppercase, so a short name like "Goa" isn't taken for one
+    let code = match region.to_ascii_uppercase().split_once('-') {
+        Some((prefix, code)) if prefix == country_code && is_code(code) => Some(code.to_string()),
+        Some(_) => return None,
+        None if is_code(region) => Some(region.to_string()),
+        None => SUBDIVISIONS
+            .iter()
+            .find(|(country, _, name)| {
+                *country == country_code && name.eq_ignore_ascii_case(region)
+            })
+            .map(|(_, code, _)| code.to_string()),
+    };
+    code.map(|code| format!("{}-{}", country_code, code))
+}
+
+/// ISO 3166-2 codes and names of the US states and territories and the Canadian provinces
+const SUBDIVISIONS: [(&str, &str, &str); 70] = [
+    ("US", "AL", "Alabama"),
+    ("US", "AK", "Alaska"),
+    ("US", "AZ", "Arizona"),
+    ("US", "AR", "Arkansas"),
+    ("US", "CA", "California"),
+    ("US", "CO", "Colorado"),
+    ("US", "CT", "Connecticut"),
+    ("US", "DE", "Delaware"),
+    ("US", "FL", "Florida"),
+    ("US", "GA", "Georgia"),
+    ("US", "HI", "Hawaii"),
+    ("US", "ID", "Idaho"),
+    ("US", "IL", "Illinois"),
+    ("US", "IN", "Indiana"),
+    ("US", "IA", "Iowa"),
+    ("US", "KS", "Kansas"),
+    ("US", "KY", "Kentucky"),
+    ("US", "LA", "Louisiana"),
+    ("US", "ME", "Maine"),
+    ("US", "MD", "Maryland"),
+    ("US", "MA", "Massachusetts"),
+    ("US", "MI", "Michigan"),
+    ("US", "MN", "Minnesota"),
+    ("US", "MS", "Mississippi"),
+    ("US", "MO", "Missouri"),
+    ("US", "MT", "Montana"),
+    ("US", "NE", "Nebraska"),
+    ("US", "NV", "Nevada"),
+    ("US", "NH", "New Hampshire"),
+    ("US", "NJ", "New Jersey"),
+    ("US", "NM", "New Mexico"),
+    ("US", "NY", "New York"),
+    ("US", "NC", "North Carolina"),
+    ("US", "ND", "North Dakota"),
+    ("US", "OH", "Ohio"),
+    ("US", "OK", "Oklahoma"),
+    ("US", "OR", "Oregon"),
+    ("US", "PA", "Pennsylvania"),
+    ("US", "RI", "Rhode Island"),
+    ("US", "SC", "South Carolina"),
+    ("US", "SD", "South Dakota"),
+    ("US", "TN", "Tennessee"),
+    ("US", "TX", "Texas"),
+    ("US", "UT", "Utah"),
+    ("US", "VT", "Vermont"),
+    ("US", "VA", "Virginia"),
+    ("US", "WA", "Washington"),
+    ("US", "WV", "West Virginia"),
+    ("US", "WI", "Wisconsin"),
+    ("US", "WY", "Wyoming"),
+    ("US", "DC", "District of Columbia"),
+    ("US", "AS", "American Samoa"),
+    ("US", "GU", "Guam"),
+    ("US", "MP", "Northern Mariana Islands"),
+    ("US", "PR", "Puerto Rico"),
+    ("US", "VI", "Virgin Islands"),
+    ("US", "UM", "United States Minor Outlying Islands"),
+    ("CA", "AB", "Alberta"),
+    ("CA", "BC", "British Columbia"),
+    ("CA", "MB", "Manitoba"),
+    ("CA", "NB", "New Brunswick"),
+    ("CA", "NL", "Newfoundland and Labrador"),
+    ("CA", "NS", "Nova Scotia"),
+    ("CA", "NT", "Northwest Territories"),
+    ("CA", "NU", "Nunavut"),
+    ("CA", "ON", "Ontario"),
+    ("CA", "PE", "Prince Edward Island"),
+    ("CA", "QC", "Quebec"),
+    ("CA", "SK", "Saskatchewan"),
+    ("CA", "YT", "Yukon"),
+];
+
+/// Builder of a lookup response, created with [`LookupResponse::builder`]
+///
+/// The fields that aren't set are unknown.
+#[derive(Debug, Clone)]
+pub struct LookupResponseBuilder {
+    response: LookupResponse,
+}
+
+macro_rules! setters {
+    ($($field:ident: $type:ty => $doc:literal),+ $(,)?) => {
+        $(
+            #[doc = $doc]
+            pub fn $field(mut self, $field: impl Into<$type>) -> Self {
+                self.response.$field = Some($field.into());
+                self
+            }
+        )+
+    };
+}
+
+impl LookupResponseBuilder {
+    setters!(
+        continent: Continent => "Sets the continent.",
+        country: String => "Sets the country name.",
+        country_code: String => "Sets the country ISO code.",
+        is_eu: bool => "Sets whether the country is a member of the European Union.",
+        region: String => "Sets the region name.",
+        region_code: String => "Sets the ISO 3166-2 code of the region.",
+        postal_code: String => "Sets the postal code.",
+        city: String => "Sets the city name.",
+        latitude: f64 => "Sets the latitude.",
+        longitude: f64 => "Sets the longitude.",
+        time_zone: String => "Sets the IANA name of the time zone.",
+        asn: String => "Sets the Autonomous System Number.",
+        asn_org: String => "Sets the Autonomous System Organization.",
+        hostname: String => "Sets the hostname.",
+        security: Security => "Sets the security flags.",
+        carrier: Carrier => "Sets the mobile carrier.",
+        currency: Currency => "Sets the currency of the country.",
+        flag_emoji: String => "Sets the emoji of the country flag.",
+        flag_url: String => "Sets the URL of an image of the country flag.",
+        calling_code: String => "Sets the international calling code of the country.",
+        raw: String => "Sets the raw body of the provider response.",
+    );
+
+    /// Sets the languages spoken in the country.
+    pub fn languages<I, S>(mut self, languages: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.response.languages = Some(languages.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Builds the lookup response.
+    pub fn build(self) -> LookupResponse {
+        self.response
+    }
+}
+
+/// Security flags of the IP address reported by the provider
+///
+/// A flag is `None` when the provider doesn't report it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct Security {
+    /// Is the IP a VPN exit?
+    pub is_vpn: Option<bool>,
+    /// Is the IP a Tor exit node?
+    pub is_tor: Option<bool>,
+    /// Is the IP an open or anonymous proxy?
+    pub is_proxy: Option<bool>,
+    /// Does the IP belong to a datacenter or hosting provider?
+    pub is_datacenter: Option<bool>,
+    /// Is the IP a known source of abuse, like spam or attacks?
+    pub is_abuser: Option<bool>,
+    /// Is the IP a bogon, a reserved address that should not be routed?
+    pub is_bogon: Option<bool>,
+}
+
+impl Security {
+    /// Checks if the IP hides the client behind a VPN, Tor or a proxy.
+    ///
+    /// Returns `None` if none of these flags is reported.
+    pub fn is_anonymous(&self) -> Option<bool> {
+        let flags = [self.is_vpn, self.is_tor, self.is_proxy];
+        if flags.contains(&Some(true)) {
+            Some(true)
+        } else {
+            flags.into_iter().flatten().next()
         }
-        if let Some(asn) = &self.asn {
-            writeln!(f, " ({})", asn)?;
+    }
+
+    /// Returns the names of the flags that are set
+    fn flags(&self) -> Vec<&'static str> {
+        [
+            (self.is_vpn, "VPN"),
+            (self.is_tor, "Tor"),
+            (self.is_proxy, "proxy"),
+            (self.is_datacenter, "datacenter"),
+            (self.is_abuser, "abuser"),
+            (self.is_bogon, "bogon"),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| *flag == Some(true))
+        .map(|(_, name)| name)
+        .collect()
+    }
+}
+
+impl fmt::Display for Security {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flags = self.flags();
+        if flags.is_empty() {
+            write!(f, "none")
         } else {
-            writeln!(f)?;
+            write!(f, "{}", flags.join(", "))
         }
-        if let Some(hostname) = &self.hostname {
-            writeln!(f, "Hostname: {}", hostname)?;
+    }
+}
+
+/// Mobile carrier of the IP address
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct Carrier {
+    /// Carrier name, like "T-Mobile".
+    pub name: Option<String>,
+    /// Mobile Country Code, like "310".
+    pub mcc: Option<String>,
+    /// Mobile Network Code, like "160".
+    pub mnc: Option<String>,
+}
+
+impl fmt::Display for Carrier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name.as_deref().unwrap_or("Unknown"))?;
+        if let (Some(mcc), Some(mnc)) = (&self.mcc, &self.mnc) {
+            write!(f, " ({}-{})", mcc, mnc)?;
         }
-        if let Some(proxy) = &self.is_proxy {
-            writeln!(f, "Proxy: {}", proxy)?;
+        Ok(())
+    }
+}
+
+/// Currency of the country of the IP address
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Currency {
+    /// ISO 4217 currency code, like "USD".
+    pub code: String,
+    /// Currency name, like "US Dollar".
+    pub name: Option<String>,
+    /// Currency symbol, like "$".
+    pub symbol: Option<String>,
+}
+
+impl Currency {
+    /// Create a currency from its ISO 4217 code.
+    pub fn new(code: String) -> Self {
+        Currency {
+            code,
+            name: None,
+            symbol: None,
         }
-        write!(f, "Provider: {}", self.provider)?;
+    }
+}
 
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.code)?;
+        if let Some(symbol) = &self.symbol {
+            write!(f, " ({})", symbol)?;
+        }
         Ok(())
     }
 }
+
+/// Returns the responses as a GeoJSON `FeatureCollection`, with a feature per response built by
+/// [`LookupResponse::to_geojson`].
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::{lookup::LookupProvider, response::{self, LookupResponse}};
+///
+/// let responses = vec![
+///     LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo),
+///     LookupResponse::new("8.8.8.8".parse().unwrap(), LookupProvider::IpInfo),
+/// ];
+/// let collection = response::to_geojson_collection(&responses);
+/// assert_eq!(collection["features"].as_array().unwrap().len(), 2);
+/// ```
+pub fn to_geojson_collection<'a, I>(responses: I) -> serde_json::Value
+where
+    I: IntoIterator<Item = &'a LookupResponse>,
+{
+    let features: Vec<_> = responses
+        .into_iter()
+        .map(LookupResponse::to_geojson)
+        .collect();
+    serde_json::json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+/// Writes the responses as CSV, with a header row followed by a record per response.
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::{lookup::LookupProvider, response::{self, LookupResponse}};
+///
+/// let responses = vec![LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo)];
+/// let mut output = Vec::new();
+/// response::write_csv(&mut output, &responses).unwrap();
+/// let output = String::from_utf8(output).unwrap();
+/// assert!(output.starts_with("ip,continent,country,"));
+/// assert_eq!(output.lines().count(), 2);
+/// ```
+#[cfg(feature = "csv")]
+pub fn write_csv<W: std::io::Write>(writer: W, responses: &[LookupResponse]) -> csv::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(LookupResponse::csv_header())?;
+    for response in responses {
+        writer.write_record(response.to_csv_record())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Mean radius of the Earth in kilometers
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// Maximum number of characters of a geohash, about 4 cm precise
+pub const MAX_GEOHASH_PRECISION: usize = 12;
+
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Geographic coordinates of a lookup response, in decimal degrees
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::response::Coordinates;
+///
+/// let berlin = Coordinates::new(52.52, 13.405);
+/// let paris = Coordinates::new(48.8566, 2.3522);
+/// assert!(berlin.is_valid());
+/// assert_eq!(berlin.distance_km(&paris).round(), 877.0);
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct Coordinates {
+    /// Latitude, from -90 to 90.
+    pub lat: f64,
+    /// Longitude, from -180 to 180.
+    pub lon: f64,
+}
+
+impl Coordinates {
+    /// Creates new coordinates from a latitude and a longitude.
+    pub fn new(lat: f64, lon: f64) -> Self {
+        Coordinates { lat, lon }
+    }
+
+    /// Checks if the latitude and the longitude are finite and within their range.
+    pub fn is_valid(&self) -> bool {
+        self.lat.is_finite()
+            && self.lon.is_finite()
+            && (-90.0..=90.0).contains(&self.lat)
+            && (-180.0..=180.0).contains(&self.lon)
+    }
+
+    /// Returns the great-circle distance to other coordinates in kilometers, using the haversine formula.
+    pub fn distance_km(&self, other: &Coordinates) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.lon - self.lon).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+    }
+
+    /// Returns the geohash of the coordinates with `precision` characters, between 1 and [`MAX_GEOHASH_PRECISION`].
+    ///
+    /// Nearby coordinates share a prefix, so a short geohash can be used to group the lookups by area:
+    /// 4 characters cover about 40 km, 6 characters about 1 km.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use public_ip_address::response::Coordinates;
+    ///
+    /// let coordinates = Coordinates::new(57.64911, 10.40744);
+    /// assert_eq!(coordinates.geohash(11), "u4pruydqqvj");
+    /// ```
+    pub fn geohash(&self, precision: usize) -> String {
+        let precision = precision.clamp(1, MAX_GEOHASH_PRECISION);
+        let mut lat_range = (-90.0, 90.0);
+        let mut lon_range = (-180.0, 180.0);
+        let mut hash = String::with_capacity(precision);
+        let mut even = true;
+        while hash.len() < precision {
+            let mut index = 0;
+            for _ in 0..5 {
+                // bits alternate between the longitude and the latitude, starting with the longitude
+                let (range, value) = if even {
+                    (&mut lon_range, self.lon)
+                } else {
+                    (&mut lat_range, self.lat)
+                };
+                let middle = (range.0 + range.1) / 2.0;
+                index <<= 1;
+                if value >= middle {
+                    index |= 1;
+                    range.0 = middle;
+                } else {
+                    range.1 = middle;
+                }
+                even = !even;
+            }
+            hash.push(GEOHASH_ALPHABET[index] as char);
+        }
+        hash
+    }
+}
+
+impl fmt::Display for Coordinates {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, {}", self.lat, self.lon)
+    }
+}
+
+/// Colored multi-line display of a lookup response, created with [`LookupResponse::colored`]
+///
+/// The labels are dimmed, the IP address is highlighted and the security flags are shown in red.
+/// The colors are left out when the standard output is not a terminal or when the `NO_COLOR`
+/// environment variable is set, unless they are forced with [`Colored::color`].
+#[derive(Debug, Clone, Copy)]
+pub struct Colored<'a> {
+    response: &'a LookupResponse,
+    labels: Option<&'a Labels>,
+    color: bool,
+}
+
+impl<'a> Colored<'a> {
+    /// Enables or disables the colors, regardless of the terminal and the environment.
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Uses localized labels for the fields.
+    pub fn labels(mut self, labels: &'a Labels) -> Self {
+        self.labels = Some(labels);
+        self
+    }
+}
+
+impl fmt::Display for Colored<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_fields(f, self.response, self.labels, self.color)
+    }
+}
+
+/// Multi-line display of a lookup response with localized labels, created with [`LookupResponse::localized`]
+#[derive(Debug, Clone, Copy)]
+pub struct Localized<'a> {
+    response: &'a LookupResponse,
+    labels: &'a Labels,
+}
+
+impl fmt::Display for Localized<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_fields(f, self.response, Some(self.labels), false)
+    }
+}
+
+/// Writes the known fields of a response, one per line
+fn write_fields(
+    f: &mut fmt::Formatter,
+    response: &LookupResponse,
+    labels: Option<&Labels>,
+    color: bool,
+) -> fmt::Result {
+    const DIM: &str = "\x1b[2m";
+    const HIGHLIGHT: &str = "\x1b[1;36m";
+    const WARNING: &str = "\x1b[1;31m";
+    const RESET: &str = "\x1b[0m";
+
+    let flagged = response
+        .security
+        .as_ref()
+        .is_some_and(|security| !security.flags().is_empty());
+    let lines: Vec<String> = response
+        .fields()
+        .into_iter()
+        .map(|(field, value)| {
+            let label = labels.map_or(field.label(), |labels| labels.label(field));
+            if !color {
+                return format!("{}: {}", label, value);
+            }
+            let style = match field {
+                Field::Ip => Some(HIGHLIGHT),
+                Field::Security if flagged => Some(WARNING),
+                _ => None,
+            };
+            match style {
+                Some(style) => format!("{DIM}{}:{RESET} {style}{}{RESET}", label, value),
+                None => format!("{DIM}{}:{RESET} {}", label, value),
+            }
+        })
+        .collect();
+    write!(f, "{}", lines.join("\n"))
+}
+
+/// Serialized names of the fields describing the IP address, in declaration order
+const DATA_FIELDS: [&str; 22] = [
+    "ip",
+    "continent",
+    "country",
+    "country_code",
+    "is_eu",
+    "region",
+    "region_code",
+    "postal_code",
+    "city",
+    "latitude",
+    "longitude",
+    "time_zone",
+    "asn",
+    "asn_org",
+    "hostname",
+    "security",
+    "carrier",
+    "currency",
+    "languages",
+    "flag_emoji",
+    "flag_url",
+    "calling_code",
+];
+
+/// Differences between two lookup responses, created with [`LookupResponse::diff`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[non_exhaustive]
+pub struct ResponseDiff {
+    /// Changed fields, in declaration order
+    pub changes: Vec<FieldChange>,
+}
+
+impl ResponseDiff {
+    /// Checks if the responses have the same data.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns the names of the changed fields.
+    pub fn fields(&self) -> Vec<&str> {
+        self.changes
+            .iter()
+            .map(|change| change.field.as_str())
+            .collect()
+    }
+
+    /// Returns the change of a field, by its serialized name like "country" or "asn".
+    pub fn get(&self, field: &str) -> Option<&FieldChange> {
+        self.changes.iter().find(|change| change.field == field)
+    }
+}
+
+impl fmt::Display for ResponseDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines: Vec<String> = self.changes.iter().map(ToString::to_string).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Change of a field between two lookup responses
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct FieldChange {
+    /// Serialized name of the field, like "country" or "asn"
+    pub field: String,
+    /// Value in the first response, `None` if it was unknown
+    pub old: Option<serde_json::Value>,
+    /// Value in the second response, `None` if it's unknown
+    pub new: Option<serde_json::Value>,
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn value(value: &Option<serde_json::Value>) -> String {
+            match value {
+                Some(serde_json::Value::String(value)) => value.clone(),
+                Some(value) => value.to_string(),
+                None => "none".to_string(),
+            }
+        }
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.field,
+            value(&self.old),
+            value(&self.new)
+        )
+    }
+}
+
+/// Field of a lookup response in its human-readable output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Field {
+    /// IP address
+    Ip,
+    /// Continent
+    Continent,
+    /// Country name and code, with the flag
+    Country,
+    /// European Union membership
+    IsEu,
+    /// Region
+    Region,
+    /// Postal code
+    PostalCode,
+    /// City
+    City,
+    /// Latitude and longitude
+    Coordinates,
+    /// Time zone
+    TimeZone,
+    /// Organization and ASN
+    Organization,
+    /// Hostname
+    Hostname,
+    /// Security flags
+    Security,
+    /// Mobile carrier
+    Carrier,
+    /// Calling code
+    CallingCode,
+    /// Currency
+    Currency,
+    /// Languages
+    Languages,
+    /// Time of the lookup
+    LookedUpAt,
+    /// Provider and latency
+    Provider,
+}
+
+impl Field {
+    /// Every field, in display order
+    pub const ALL: [Field; 18] = [
+        Field::Ip,
+        Field::Continent,
+        Field::Country,
+        Field::IsEu,
+        Field::Region,
+        Field::PostalCode,
+        Field::City,
+        Field::Coordinates,
+        Field::TimeZone,
+        Field::Organization,
+        Field::Hostname,
+        Field::Security,
+        Field::Carrier,
+        Field::CallingCode,
+        Field::Currency,
+        Field::Languages,
+        Field::LookedUpAt,
+        Field::Provider,
+    ];
+
+    /// Returns the English label of the field.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Field::Ip => "IP",
+            Field::Continent => "Continent",
+            Field::Country => "Country",
+            Field::IsEu => "EU member",
+            Field::Region => "Region",
+            Field::PostalCode => "Postal code",
+            Field::City => "City",
+            Field::Coordinates => "Coordinates",
+            Field::TimeZone => "Time zone",
+            Field::Organization => "Organization",
+            Field::Hostname => "Hostname",
+            Field::Security => "Security",
+            Field::Carrier => "Carrier",
+            Field::CallingCode => "Calling code",
+            Field::Currency => "Currency",
+            Field::Languages => "Languages",
+            Field::LookedUpAt => "Looked up at",
+            Field::Provider => "Provider",
+        }
+    }
+}
+
+/// Labels of the fields in the human-readable output of a lookup response
+///
+/// Created for a locale with [`Labels::for_locale`], with built-in German, French and Spanish labels,
+/// and customized with [`Labels::set`]. The fields without a label use the English one.
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::{lookup::LookupProvider, response::{Field, Labels, LookupResponse}};
+///
+/// let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+/// response.country = Some("Deutschland".to_string());
+/// let labels = Labels::for_locale("de-DE").set(Field::Ip, "IP-Adresse");
+/// let output = response.localized(&labels).to_string();
+/// assert!(output.starts_with("IP-Adresse: 1.1.1.1\nLand: Deutschland"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Labels {
+    labels: HashMap<Field, String>,
+}
+
+impl Labels {
+    /// Creates labels using the English label of every field.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the labels of a locale, like "de", "fr-FR" or "es_ES.UTF-8".
+    ///
+    /// Only the language of the locale is used, unsupported languages get the English labels.
+    pub fn for_locale(locale: &str) -> Self {
+        let language = locale
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let translations = match language.as_str() {
+            "de" => GERMAN_LABELS,
+            "fr" => FRENCH_LABELS,
+            "es" => SPANISH_LABELS,
+            _ => return Self::default(),
+        };
+        Labels {
+            labels: Field::ALL
+                .into_iter()
+                .zip(translations)
+                .map(|(field, label)| (field, label.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Sets the label of a field.
+    pub fn set<L: Into<String>>(mut self, field: Field, label: L) -> Self {
+        self.labels.insert(field, label.into());
+        self
+    }
+
+    /// Returns the label of a field.
+    pub fn label(&self, field: Field) -> &str {
+        self.labels
+            .get(&field)
+            .map_or(field.label(), String::as_str)
+    }
+}
+
+/// German labels, in the order of [`Field::ALL`]
+const GERMAN_LABELS: [&str; 18] = [
+    "IP",
+    "Kontinent",
+    "Land",
+    "EU-Mitglied",
+    "Region",
+    "Postleitzahl",
+    "Stadt",
+    "Koordinaten",
+    "Zeitzone",
+    "Organisation",
+    "Hostname",
+    "Sicherheit",
+    "Mobilfunkanbieter",
+    "Ländervorwahl",
+    "Währung",
+    "Sprachen",
+    "Abgefragt am",
+    "Anbieter",
+];
+
+/// French labels, in the order of [`Field::ALL`]
+const FRENCH_LABELS: [&str; 18] = [
+    "IP",
+    "Continent",
+    "Pays",
+    "Membre de l'UE",
+    "Région",
+    "Code postal",
+    "Ville",
+    "Coordonnées",
+    "Fuseau horaire",
+    "Organisation",
+    "Nom d'hôte",
+    "Sécurité",
+    "Opérateur",
+    "Indicatif téléphonique",
+    "Devise",
+    "Langues",
+    "Consulté le",
+    "Fournisseur",
+];
+
+/// Spanish labels, in the order of [`Field::ALL`]
+const SPANISH_LABELS: [&str; 18] = [
+    "IP",
+    "Continente",
+    "País",
+    "Miembro de la UE",
+    "Región",
+    "Código postal",
+    "Ciudad",
+    "Coordenadas",
+    "Zona horaria",
+    "Organización",
+    "Nombre de host",
+    "Seguridad",
+    "Operador",
+    "Prefijo telefónico",
+    "Moneda",
+    "Idiomas",
+    "Consultado el",
+    "Proveedor",
+];
+
+/// Continent of a lookup response
+///
+/// Parsed from the name, like "North America", or the two-letter code, like "NA", ignoring the case.
+/// Serialized as the name.
+///
+/// # Example
+///
+/// ```rust
+/// use public_ip_address::response::Continent;
+///
+/// let continent: Continent = "na".parse().unwrap();
+/// assert_eq!(continent, Continent::NorthAmerica);
+/// assert_eq!(continent.code(), "NA");
+/// assert_eq!(continent.to_string(), "North America");
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(try_from = "String", into = "String")]
+#[non_exhaustive]
+pub enum Continent {
+    /// Africa, "AF"
+    Africa,
+    /// Antarctica, "AN"
+    Antarctica,
+    /// Asia, "AS"
+    Asia,
+    /// Europe, "EU"
+    Europe,
+    /// North America, "NA"
+    NorthAmerica,
+    /// Oceania, "OC"
+    Oceania,
+    /// South America, "SA"
+    SouthAmerica,
+}
+
+impl Continent {
+    /// Returns the two-letter code of the continent, like "NA".
+    pub fn code(&self) -> &'static str {
+        match self {
+            Continent::Africa => "AF",
+            Continent::Antarctica => "AN",
+            Continent::Asia => "AS",
+            Continent::Europe => "EU",
+            Continent::NorthAmerica => "NA",
+            Continent::Oceania => "OC",
+            Continent::SouthAmerica => "SA",
+        }
+    }
+
+    /// Returns the name of the continent, like "North America".
+    pub fn name(&self) -> &'static str {
+        match self {
+            Continent::Africa => "Africa",
+            Continent::Antarctica => "Antarctica",
+            Continent::Asia => "Asia",
+            Continent::Europe => "Europe",
+            Continent::NorthAmerica => "North America",
+            Continent::Oceania => "Oceania",
+            Continent::SouthAmerica => "South America",
+        }
+    }
+}
+
+impl FromStr for Continent {
+    type Err = LookupError;
+    /// Parse a continent name or code into a `Continent`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s
+            .trim()
+            .to_lowercase()
+            .replace(['_', '-'], " ")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        match name.as_str() {
+            "af" | "africa" => Ok(Continent::Africa),
+            "an" | "antarctica" => Ok(Continent::Antarctica),
+            "as" | "asia" => Ok(Continent::Asia),
+            "eu" | "europe" => Ok(Continent::Europe),
+            "na" | "north america" => Ok(Continent::NorthAmerica),
+            "oc" | "oceania" | "australia" => Ok(Continent::Oceania),
+            "sa" | "south america" => Ok(Continent::SouthAmerica),
+            _ => Err(LookupError::GenericError(format!(
+                "Continent not found: {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl TryFrom<String> for Continent {
+    type Error = LookupError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Continent> for String {
+    fn from(continent: Continent) -> Self {
+        continent.name().to_string()
+    }
+}
+
+impl fmt::Display for Continent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Deserializes an optional continent, unknown continents are `None` so they don't fail the whole response
+fn deserialize_continent<'de, D>(deserializer: D) -> Result<Option<Continent>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let continent = Option::<String>::deserialize(deserializer)?;
+    Ok(continent.and_then(|continent| continent.parse().ok()))
+}
+
+/// Location hierarchy of a lookup response
+///
+/// A level is only present when it or one of its sub-levels is known,
+/// so a known city is always reachable through `country` and `region`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[non_exhaustive]
+pub struct Location {
+    /// Continent of the location.
+    #[serde(default, deserialize_with = "deserialize_continent")]
+    pub continent: Option<Continent>,
+    /// Country of the location.
+    pub country: Option<Country>,
+}
+
+/// Country level of a `Location`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[non_exhaustive]
+pub struct Country {
+    /// Country name.
+    pub name: Option<String>,
+    /// Country ISO code.
+    pub code: Option<String>,
+    /// Region within the country.
+    pub region: Option<Region>,
+}
+
+/// Region level of a `Location`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[non_exhaustive]
+pub struct Region {
+    /// Region name.
+    pub name: Option<String>,
+    /// ISO 3166-2 code of the region.
+    #[serde(default)]
+    pub code: Option<String>,
+    /// City within the region.
+    pub city: Option<City>,
+}
+
+/// City level of a `Location`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct City {
+    /// City name.
+    pub name: String,
+    /// Postal code.
+    pub postal_code: Option<String>,
+}
+
+impl Location {
+    /// Returns the region of the location.
+    pub fn region(&self) -> Option<&Region> {
+        self.country.as_ref()?.region.as_ref()
+    }
+
+    /// Returns the city of the location.
+    pub fn city(&self) -> Option<&City> {
+        self.region()?.city.as_ref()
+    }
+
+    /// Checks if nothing is known about the location.
+    pub fn is_empty(&self) -> bool {
+        self.continent.is_none() && self.country.is_none()
+    }
+
+    /// Returns a short description of the location, like "Berlin, DE".
+    ///
+    /// The most precise place is combined with the country code, or the country name
+    /// when the code is unknown. Returns an empty string if nothing is known.
+    pub fn display_short(&self) -> String {
+        let place = self
+            .city()
+            .map(|city| city.name.as_str())
+            .or(self.region().and_then(|region| region.name.as_deref()));
+        let country = self
+            .country
+            .as_ref()
+            .and_then(|country| country.code.as_deref().or(country.name.as_deref()));
+        match (place, country) {
+            (Some(place), Some(country)) => format!("{}, {}", place, country),
+            (Some(place), None) => place.to_string(),
+            (None, Some(country)) => country.to_string(),
+            (None, None) => self
+                .continent
+                .map(|continent| continent.to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns a full description of the location, like "Berlin, Land Berlin, Germany (DE), Europe".
+    ///
+    /// Returns an empty string if nothing is known.
+    pub fn display_long(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(city) = self.city() {
+            parts.push(city.name.clone());
+        }
+        if let Some(name) = self.region().and_then(|region| region.name.as_ref()) {
+            parts.push(name.clone());
+        }
+        if let Some(country) = &self.country {
+            match (&country.name, &country.code) {
+                (Some(name), Some(code)) => parts.push(format!("{} ({})", name, code)),
+                (Some(name), None) => parts.push(name.clone()),
+                (None, Some(code)) => parts.push(code.clone()),
+                (None, None) => {}
+            }
+        }
+        if let Some(continent) = &self.continent {
+            parts.push(continent.to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_long())
+    }
+}
+
+impl fmt::Display for LookupResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_fields(f, self, None, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enrich_from() {
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::Ipify);
+        response.country = Some("Germany".to_string());
+        let mut other = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        other.country = Some("United States".to_string());
+        other.asn = Some("AS13335".to_string());
+        other.latitude = Some(1.0);
+        other.longitude = Some(2.0);
+
+        response.enrich_from(&other);
+        assert_eq!(
+            response.country,
+            Some("Germany".to_string()),
+            "Set field overwritten"
+        );
+        assert_eq!(
+            response.asn,
+            Some("AS13335".to_string()),
+            "Missing field not filled"
+        );
+        assert_eq!(response.latitude, Some(1.0), "Coordinates not filled");
+        assert_eq!(response.longitude, Some(2.0), "Coordinates not filled");
+        assert_eq!(response.provider, LookupProvider::Ipify, "Provider changed");
+        assert_eq!(response.source("longitude"), Some(&LookupProvider::IpInfo));
+
+        let mut third = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpWhoIs);
+        third.city = Some("Sydney".to_string());
+        let merged = third.merge(&response);
+        assert_eq!(merged.source("city"), Some(&LookupProvider::IpWhoIs));
+        assert_eq!(merged.source("country"), Some(&LookupProvider::Ipify));
+        assert_eq!(
+            merged.source("asn"),
+            Some(&LookupProvider::IpInfo),
+            "Provenance of a merged response not kept"
+        );
+        assert_eq!(merged.source("hostname"), None);
+    }
+
+    #[test]
+    fn test_security() {
+        let mut security = Security::default();
+        assert_eq!(security.is_anonymous(), None);
+        assert_eq!(security.to_string(), "none");
+        security.is_proxy = Some(false);
+        assert_eq!(security.is_anonymous(), Some(false));
+        security.is_tor = Some(true);
+        security.is_datacenter = Some(true);
+        assert_eq!(security.is_anonymous(), Some(true));
+        assert_eq!(security.to_string(), "Tor, datacenter");
+    }
+
+    #[test]
+    fn test_flag_emoji() {
+        assert_eq!(flag_emoji("US").as_deref(), Some("🇺🇸"));
+        assert_eq!(flag_emoji("gb").as_deref(), Some("🇬🇧"));
+        assert_eq!(flag_emoji(""), None);
+        assert_eq!(flag_emoji("1A"), None);
+    }
+
+    #[test]
+    fn test_coordinates() {
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        assert_eq!(response.coordinates(), None);
+        response.latitude = Some(40.7128);
+        response.longitude = Some(-74.006);
+        let new_york = response.coordinates().unwrap();
+        let london = Coordinates::new(51.5074, -0.1278);
+        assert_eq!(new_york.distance_km(&london).round(), 5570.0);
+        assert_eq!(london.distance_km(&london), 0.0);
+
+        response.latitude = Some(91.0);
+        assert_eq!(response.coordinates(), None, "Latitude out of range");
+        assert!(!Coordinates::new(0.0, f64::NAN).is_valid());
+    }
+
+    #[test]
+    fn test_geojson() {
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        response.country = Some("Germany".to_string());
+        response.raw = Some("{}".to_string());
+        let feature = response.to_geojson();
+        assert_eq!(feature["type"], "Feature");
+        assert!(
+            feature["geometry"].is_null(),
+            "Geometry without coordinates"
+        );
+        assert_eq!(feature["properties"]["country"], "Germany");
+        assert!(
+            feature["properties"].get("raw").is_none(),
+            "Raw body in properties"
+        );
+
+        response.latitude = Some(52.52);
+        response.longitude = Some(13.405);
+        let collection = to_geojson_collection([&response]);
+        assert_eq!(collection["type"], "FeatureCollection");
+        let feature = &collection["features"][0];
+        assert_eq!(feature["geometry"]["type"], "Point");
+        assert_eq!(feature["geometry"]["coordinates"][0], 13.405);
+        assert!(feature["properties"].get("latitude").is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "csv")]
+    fn test_csv() {
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        response.city = Some("Berlin, Mitte".to_string());
+        response.languages = Some(vec!["de".to_string(), "en".to_string()]);
+        response.latency = Some(Duration::from_millis(120));
+        let record = response.to_csv_record();
+        assert_eq!(record.len(), LookupResponse::csv_header().len());
+        assert_eq!(record[18], "de;en");
+        assert_eq!(record[24], "120");
+
+        let mut output = Vec::new();
+        write_csv(&mut output, &[response]).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let row = output.lines().nth(1).unwrap();
+        assert!(
+            row.starts_with("1.1.1.1,,,,,,,,\"Berlin, Mitte\","),
+            "{}",
+            row
+        );
+    }
+
+    /// Response with every kind of field set, to check the serialization round trips
+    #[cfg(any(feature = "yaml", feature = "toml"))]
+    fn full_response() -> LookupResponse {
+        let mut response = LookupResponse::new(
+            "2001:db8::1".parse().unwrap(),
+            LookupProvider::Mock("2001:db8::1".to_string()),
+        );
+        response.continent = Some(Continent::Europe);
+        response.country = Some("Germany".to_string());
+        response.latitude = Some(52.52);
+        response.longitude = Some(13.405);
+        response.security = Some(Security {
+            is_vpn: Some(true),
+            ..Default::default()
+        });
+        response.currency = Some(Currency::new("EUR".to_string()));
+        response.languages = Some(vec!["de".to_string()]);
+        response.looked_up_at = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        response.latency = Some(Duration::from_millis(120));
+        response
+    }
+
+    #[test]
+    #[cfg(feature = "yaml")]
+    fn test_yaml() {
+        let response = full_response();
+        let yaml = response.to_yaml().unwrap();
+        assert_eq!(LookupResponse::from_yaml(&yaml).unwrap(), response);
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn test_toml() {
+        let response = full_response();
+        let toml = response.to_toml().unwrap();
+        assert_eq!(LookupResponse::from_toml(&toml).unwrap(), response);
+    }
+
+    #[test]
+    fn test_to_map() {
+        let mut response =
+            LookupResponse::builder("1.1.1.1".parse().unwrap(), LookupProvider::IpWhoIs)
+                .country_code("DE")
+                .security(Security {
+                    is_vpn: Some(true),
+                    ..Default::default()
+                })
+                .currency(Currency::new("EUR".to_string()))
+                .languages(["de", "en"])
+                .raw("1.1.1.1")
+                .build();
+        response.latency = Some(Duration::from_millis(120));
+        response
+            .sources
+            .insert("country_code".to_string(), LookupProvider::IpInfo);
+
+        let map = response.to_map();
+        assert_eq!(map["country_code"], "DE");
+        assert_eq!(map["security_is_vpn"], "true");
+        assert_eq!(map["currency_code"], "EUR");
+        assert_eq!(map["languages"], "de,en");
+        assert_eq!(map["latency_ms"], "120");
+        assert_eq!(map["sources_country_code"], "IpInfo");
+        assert_eq!(map["raw"], "1.1.1.1", "Raw text kept as it is");
+        assert!(!map.contains_key("city"), "Unknown field in the map");
+        assert!(!map.contains_key("latency"));
+    }
+
+    #[test]
+    fn test_compact_string() {
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpWhoIs);
+        assert_eq!(response.to_compact_string(), "1.1.1.1 — via IpWhoIs");
+        response.country = Some("Australia".to_string());
+        response.asn_org = Some("Cloudflare".to_string());
+        assert_eq!(
+            response.to_compact_string(),
+            "1.1.1.1 — Australia — Cloudflare — via IpWhoIs"
+        );
+        response.asn = Some("AS13335".to_string());
+        assert!(response
+            .to_compact_string()
+            .contains("— AS13335 Cloudflare —"));
+    }
+
+    #[test]
+    fn test_is_global_ip() {
+        let global = ["1.1.1.1", "8.8.8.8", "2606:4700::1111", "::ffff:1.1.1.1"];
+        for ip in global {
+            assert!(
+                is_global_ip(&ip.parse().unwrap()),
+                "{} should be global",
+                ip
+            );
+        }
+        let non_global = [
+            "0.0.0.0",
+            "10.1.2.3",
+            "172.16.0.1",
+            "127.0.0.1",
+            "169.254.1.1",
+            "100.64.0.1",
+            "192.0.0.8",
+            "192.0.2.1",
+            "198.18.0.1",
+            "224.0.0.1",
+            "255.255.255.255",
+            "::",
+            "::1",
+            "fc00::1",
+            "fe80::1",
+            "ff02::1",
+            "2001:db8::1",
+            "::ffff:192.168.1.1",
+        ];
+        for ip in non_global {
+            assert!(
+                !is_global_ip(&ip.parse().unwrap()),
+                "{} should not be global",
+                ip
+            );
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut old = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        old.asn = Some("AS13335".to_string());
+        old.latency = Some(Duration::from_millis(10));
+        let mut new = old.clone();
+        new.provider = LookupProvider::IpWhoIs;
+        new.latency = Some(Duration::from_millis(20));
+        assert!(old.diff(&new).is_empty(), "Metadata should be ignored");
+
+        new.ip = "2.2.2.2".parse().unwrap();
+        new.asn = None;
+        new.continent = Some(Continent::Europe);
+        let diff = old.diff(&new);
+        assert_eq!(diff.fields(), vec!["ip", "continent", "asn"]);
+        let asn = diff.get("asn").unwrap();
+        assert_eq!(asn.old, Some(serde_json::json!("AS13335")));
+        assert_eq!(asn.new, None);
+        assert_eq!(asn.to_string(), "asn: AS13335 -> none");
+    }
+
+    #[test]
+    fn test_diff_fields() {
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        response.looked_up_at = Some(SystemTime::now());
+        response.latency = Some(Duration::from_millis(10));
+        response.fresh_until = Some(SystemTime::now());
+        response.raw = Some(String::new());
+        let value = serde_json::to_value(&response).unwrap();
+        let metadata = [
+            "provider",
+            "looked_up_at",
+            "latency",
+            "fresh_until",
+            "sources",
+            "raw",
+        ];
+        for key in value.as_object().unwrap().keys() {
+            assert!(
+                DATA_FIELDS.contains(&key.as_str()) || metadata.contains(&key.as_str()),
+                "Field {} missing from the diff",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        assert_eq!(response.to_string(), "IP: 1.1.1.1\nProvider: IpInfo");
+        response.country_code = Some("AU".to_string());
+        response.longitude = Some(1.0);
+        response.asn = Some("AS13335".to_string());
+        assert_eq!(
+            response.to_string(),
+            "IP: 1.1.1.1\nCountry: AU\nOrganization: AS13335\nProvider: IpInfo",
+            "Partial fields should not break the lines"
+        );
+    }
+
+    #[test]
+    fn test_colored() {
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        response.security = Some(Security {
+            is_proxy: Some(true),
+            ..Default::default()
+        });
+        let plain = response.colored().color(false).to_string();
+        assert_eq!(plain, response.to_string());
+
+        let colored = response.colored().color(true).to_string();
+        assert!(colored.starts_with("\x1b[2mIP:\x1b[0m \x1b[1;36m1.1.1.1\x1b[0m"));
+        assert!(colored.contains("\x1b[1;31mproxy\x1b[0m"), "{:?}", colored);
+    }
+
+    #[test]
+    fn test_labels() {
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        response.city = Some("Paris".to_string());
+        let labels = Labels::for_locale("fr_FR.UTF-8");
+        assert_eq!(labels.label(Field::City), "Ville");
+        assert_eq!(
+            response.localized(&labels).to_string(),
+            "IP: 1.1.1.1\nVille: Paris\nFournisseur: IpInfo"
+        );
+        let colored = response.colored().labels(&labels).color(true).to_string();
+        assert!(colored.contains("\x1b[2mVille:\x1b[0m Paris"));
+
+        let labels = Labels::for_locale("xx").set(Field::City, "Town");
+        assert_eq!(
+            labels.label(Field::Provider),
+            "Provider",
+            "English fallback"
+        );
+        assert!(response
+            .localized(&labels)
+            .to_string()
+            .contains("Town: Paris"));
+    }
+
+    #[test]
+    #[cfg(feature = "table")]
+    fn test_table() {
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        response.city = Some("Berlin".to_string());
+        let table = response.to_table();
+        // a row per field between the borders
+        assert_eq!(table.lines().count(), 7);
+        assert!(table
+            .lines()
+            .any(|line| line.contains("City") && line.contains("Berlin")));
+    }
+
+    #[test]
+    fn test_geohash() {
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        assert_eq!(response.geohash(5), None);
+        response.latitude = Some(42.6);
+        response.longitude = Some(-5.6);
+        assert_eq!(response.geohash(5).as_deref(), Some("ezs42"));
+        assert_eq!(
+            response.geohash(0).as_deref(),
+            Some("e"),
+            "Precision below 1"
+        );
+        assert_eq!(
+            response.geohash(20).map(|hash| hash.len()),
+            Some(MAX_GEOHASH_PRECISION)
+        );
+    }
+
+    #[test]
+    fn test_continent() {
+        assert_eq!(
+            "North America".parse::<Continent>().ok(),
+            Some(Continent::NorthAmerica)
+        );
+        assert_eq!(
+            "south_america".parse::<Continent>().ok(),
+            Some(Continent::SouthAmerica)
+        );
+        assert_eq!(" eu ".parse::<Continent>().ok(), Some(Continent::Europe));
+        assert!("Atlantis".parse::<Continent>().is_err());
+
+        let json = serde_json::to_string(&Continent::Oceania).unwrap();
+        assert_eq!(json, r#""Oceania""#);
+        let continent: Continent = serde_json::from_str(r#""AS""#).unwrap();
+        assert_eq!(continent, Continent::Asia);
+
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        response.continent = Some(Continent::Asia);
+        let json = serde_json::to_string(&response)
+            .unwrap()
+            .replace("Asia", "Atlantis");
+        let parsed: LookupResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.continent, None, "Unknown continent not ignored");
+    }
+
+    #[test]
+    fn test_region_code() {
+        assert_eq!(region_code("US", "NY").as_deref(), Some("US-NY"));
+        assert_eq!(region_code("US", " us-ny ").as_deref(), Some("US-NY"));
+        assert_eq!(region_code("US", "new york").as_deref(), Some("US-NY"));
+        assert_eq!(region_code("CA", "Quebec").as_deref(), Some("CA-QC"));
+        assert_eq!(region_code("SE", "E").as_deref(), Some("SE-E"));
+        assert_eq!(
+            region_code("IN", "Goa"),
+            None,
+            "Short name taken for a code"
+        );
+        assert_eq!(
+            region_code("CA", "US-NY"),
+            None,
+            "Region of another country"
+        );
+        assert_eq!(region_code("USA", "NY"), None);
+
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        response.region = Some("Massachusetts".to_string());
+        response.normalize_region();
+        assert_eq!(response.region_code, None, "Unknown country");
+
+        response.country_code = Some("US".to_string());
+        response.normalize_region();
+        assert_eq!(response.region_code.as_deref(), Some("US-MA"));
+
+        response.region = Some("Bavaria".to_string());
+        response.country_code = Some("DE".to_string());
+        response.region_code = Some("BY".to_string());
+        response.normalize_region();
+        assert_eq!(response.region_code.as_deref(), Some("DE-BY"));
+    }
+
+    #[test]
+    #[cfg(feature = "iso3166")]
+    fn test_normalize_country() {
+        let names = ["United States", "united states of america", "USA", "us"];
+        for name in names {
+            assert_eq!(country_by_name(name), Some(CountryCode::USA), "{}", name);
+        }
+        assert_eq!(country_by_name("Vietnam"), Some(CountryCode::VNM));
+        assert_eq!(country_by_name("Atlantis"), None);
+        assert_eq!(country_by_name("Korea"), None, "Ambiguous");
+
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        response.country = Some("Germany".to_string());
+        response.country_code = Some("GBR".to_string());
+        response.normalize_country();
+        assert_eq!(response.country_code.as_deref(), Some("GB"), "Code wins");
+        assert_eq!(
+            response.country.as_deref(),
+            Some("United Kingdom of Great Britain and Northern Ireland")
+        );
+
+        response.country = Some("Atlantis".to_string());
+        response.country_code = None;
+        response.normalize_country();
+        assert_eq!(
+            response.country.as_deref(),
+            Some("Atlantis"),
+            "Unknown kept"
+        );
+        assert_eq!(response.country_code, None);
+
+        response.country = Some("Vietnam".to_string());
+        response.fill_country_code();
+        assert_eq!(response.country.as_deref(), Some("Vietnam"), "Country kept");
+        assert_eq!(response.country_code.as_deref(), Some("VN"));
+        response.country = Some("Germany".to_string());
+        response.fill_country_code();
+        assert_eq!(response.country_code.as_deref(), Some("VN"), "Code kept");
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn test_timezone() {
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        assert_eq!(response.timezone(), None);
+        assert_eq!(response.local_time(), None);
+        response.time_zone = Some("America/Los_Angeles".to_string());
+        assert_eq!(response.timezone(), Some(chrono_tz::America::Los_Angeles));
+        let local_time = response.local_time().unwrap();
+        assert_eq!(local_time.timezone(), chrono_tz::America::Los_Angeles);
+        response.time_zone = Some("Nowhere/Special".to_string());
+        assert_eq!(response.timezone(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "iso3166")]
+    fn test_country_iso() {
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        assert_eq!(response.country_iso(), None);
+        response.country_code = Some("US".to_string());
+        assert_eq!(response.country_iso(), Some(CountryCode::USA));
+        response.country_code = Some("gbr".to_string());
+        assert_eq!(response.country_iso().map(|c| c.alpha2()), Some("GB"));
+        response.country_code = Some("XX".to_string());
+        assert_eq!(response.country_iso(), None);
+    }
+
+    #[test]
+    fn test_location() {
+        let mut response = LookupResponse::new("1.1.1.1".parse().unwrap(), LookupProvider::IpInfo);
+        assert!(response.location().is_empty(), "Location should be empty");
+        assert_eq!(response.location().display_short(), "");
+
+        response.city = Some("Berlin".to_string());
+        let location = response.location();
+        assert_eq!(
+            location.city().map(|city| city.name.as_str()),
+            Some("Berlin"),
+            "City should be nested in country and region"
+        );
+        assert_eq!(location.display_short(), "Berlin");
+
+        response.continent = Some(Continent::Europe);
+        response.country = Some("Germany".to_string());
+        response.country_code = Some("DE".to_string());
+        response.region = Some("Land Berlin".to_string());
+        let location = response.location();
+        assert_eq!(location.display_short(), "Berlin, DE");
+        assert_eq!(
+            location.display_long(),
+            "Berlin, Land Berlin, Germany (DE), Europe"
+        );
+    }
+}
@@ -0,0 +1,346 @@
+//! Single-flight deduplication of concurrent calls
+//!
+//! When several callers ask for the same key at the same time, only the first one, the leader,
+//! does the work. The others wait for the leader and receive a copy of its result. This keeps a
+//! burst of cache misses, like a web server handling many requests at once, from sending one
+//! lookup request per caller.
+//!
+//! If the leader is dropped before finishing, one of the waiting callers takes over.
+//!
+//! A failed call returns a [`LookupError::SharedFailed`] error to every caller when other callers
+//! were waiting for it, so the error keeps its kind for all of them.
+
+use crate::{
+    error::{Error, Result},
+    lookup::error::LookupError,
+};
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+/// Result shared with the waiting callers, errors are shared behind an `Arc`
+type Outcome<T> = std::result::Result<T, Arc<Error>>;
+
+/// Group of calls deduplicated by key
+pub(crate) struct SingleFlight<K, T> {
+    calls: Mutex<HashMap<K, Call<T>>>,
+}
+
+/// Role of a caller in a call
+enum Role<T> {
+    Leader(Sender<T>),
+    Follower(Call<T>),
+}
+
+impl<K: Hash + Eq + Clone, T: Clone> SingleFlight<K, T> {
+    /// Creates a new group without calls in flight.
+    pub(crate) fn new() -> Self {
+        SingleFlight {
+            calls: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Joins the call in flight for the key, or starts a new one.
+    fn join(&self, key: &K) -> Role<T> {
+        let mut calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+        match calls.get(key) {
+            Some(call) => Role::Follower(call.clone()),
+            None => {
+                let (sender, call) = channel();
+                calls.insert(key.clone(), call);
+                Role::Leader(sender)
+            }
+        }
+    }
+
+    /// Removes the call for the key once the leader is done, so no caller joins it anymore.
+    fn leave(&self, key: &K) {
+        let call = self
+            .calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key);
+        drop(call);
+    }
+
+    /// Runs `work` unless a call for the same key is already in flight, in which case its result
+    /// is awaited instead.
+    #[maybe_async::async_impl]
+    pub(crate) async fn run<F, Fut>(&self, key: K, work: F) -> Result<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let sender = loop {
+            match self.join(&key) {
+                Role::Leader(sender) => break sender,
+                Role::Follower(mut call) => {
+                    if let Ok(outcome) = call.wait_for(Option::is_some).await {
+                        return shared(outcome.clone());
+                    }
+                    crate::logging::trace!("Call abandoned, retrying");
+                }
+            }
+        };
+        let mut guard = Guard {
+            group: self,
+            key: Some(key),
+        };
+        let result = work().await;
+        guard.finish();
+        if sender.receiver_count() == 0 {
+            return result;
+        }
+        let (result, outcome) = share(result);
+        sender.send_replace(Some(outcome));
+        result
+    }
+
+    /// Runs `work` unless a call for the same key is already in flight, in which case its result
+    /// is awaited instead.
+    #[maybe_async::sync_impl]
+    pub(crate) fn run<F>(&self, key: K, work: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        let sender = loop {
+            match self.join(&key) {
+                Role::Leader(sender) => break sender,
+                Role::Follower(call) => {
+                    let (slot, done) = &*call;
+                    let slot = slot.lock().unwrap_or_else(|e| e.into_inner());
+                    let slot = done
+                        .wait_while(slot, |slot| matches!(slot, Slot::Pending))
+                        .unwrap_or_else(|e| e.into_inner());
+                    if let Slot::Done(outcome) = &*slot {
+                        return shared(Some(outcome.clone()));
+                    }
+                    crate::logging::trace!("Call abandoned, retrying");
+                }
+            }
+        };
+        let mut guard = Guard {
+            group: self,
+            key: Some(key),
+        };
+        let result = work();
+        guard.finish();
+        // the call is only referenced by the sender once no caller waits for it
+        if Arc::strong_count(&sender.0) == 1 {
+            return result;
+        }
+        let (result, outcome) = share(result);
+        sender.send(outcome);
+        result
+    }
+}
+
+/// Splits the leader's result into its own result and the outcome shared with the waiting
+/// callers, the error being shared by both.
+fn share<T: Clone>(result: Result<T>) -> (Result<T>, Outcome<T>) {
+    match result {
+        Ok(value) => (Ok(value.clone()), Ok(value)),
+        Err(e) => {
+            let e = Arc::new(e);
+            (shared(Some(Err(e.clone()))), Err(e))
+        }
+    }
+}
+
+/// Converts a shared outcome back into a result.
+fn shared<T>(outcome: Option<Outcome<T>>) -> Result<T> {
+    match outcome {
+        Some(Ok(value)) => Ok(value),
+        Some(Err(e)) => Err(Error::LookupError(LookupError::SharedFailed(e))),
+        None => Err(Error::LookupError(LookupError::GenericError(
+            "Call abandoned".to_string(),
+        ))),
+    }
+}
+
+/// Removes the call from the group when the leader finishes or is dropped
+struct Guard<'a, K: Hash + Eq + Clone, T: Clone> {
+    group: &'a SingleFlight<K, T>,
+    key: Option<K>,
+}
+
+impl<K: Hash + Eq + Clone, T: Clone> Guard<'_, K, T> {
+    /// Removes the call from the group, the callers already waiting for it keep waiting
+    fn finish(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.group.leave(&key);
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, T: Clone> Drop for Guard<'_, K, T> {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(not(feature = "blocking"))]
+type Call<T> = tokio::sync::watch::Receiver<Option<Outcome<T>>>;
+
+#[cfg(not(feature = "blocking"))]
+type Sender<T> = tokio::sync::watch::Sender<Option<Outcome<T>>>;
+
+#[cfg(not(feature = "blocking"))]
+fn channel<T>() -> (Sender<T>, Call<T>) {
+    tokio::sync::watch::channel(None)
+}
+
+/// State of a call
+#[cfg(feature = "blocking")]
+enum Slot<T> {
+    Pending,
+    Done(Outcome<T>),
+    Abandoned,
+}
+
+#[cfg(feature = "blocking")]
+type Call<T> = std::sync::Arc<(Mutex<Slot<T>>, std::sync::Condvar)>;
+
+/// Sending half of a call, abandons the call if dropped without sending
+#[cfg(feature = "blocking")]
+struct Sender<T>(Call<T>);
+
+#[cfg(feature = "blocking")]
+fn channel<T>() -> (Sender<T>, Call<T>) {
+    let call = Call::new((Mutex::new(Slot::Pending), std::sync::Condvar::new()));
+    (Sender(call.clone()), call)
+}
+
+#[cfg(feature = "blocking")]
+impl<T> Sender<T> {
+    fn send(&self, outcome: Outcome<T>) {
+        self.complete(Slot::Done(outcome));
+    }
+
+    fn complete(&self, state: Slot<T>) {
+        let (slot, done) = &*self.0;
+        let mut slot = slot.lock().unwrap_or_else(|e| e.into_inner());
+        if matches!(*slot, Slot::Pending) {
+            *slot = state;
+        }
+        done.notify_all();
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.complete(Slot::Abandoned);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_single_flight() {
+        let group = SingleFlight::new();
+        let calls = AtomicUsize::new(0);
+        let work = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(42)
+        };
+        let results = futures::future::join_all((0..5).map(|_| group.run("key", work))).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1, "Work should run once");
+        assert!(results.iter().all(|result| matches!(result, Ok(42))));
+        assert!(group.calls.lock().unwrap().is_empty(), "Call not removed");
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_single_flight() {
+        let group = SingleFlight::new();
+        let calls = AtomicUsize::new(0);
+        let work = || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(50));
+            Ok(42)
+        };
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..5)
+                .map(|_| scope.spawn(|| group.run("key", work)))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 1, "Work should run once");
+        assert!(results.iter().all(|result| matches!(result, Ok(42))));
+        assert!(group.calls.lock().unwrap().is_empty(), "Call not removed");
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn test_sequential_calls() {
+        let group = SingleFlight::new();
+        let first = group.run("key", || async { Ok(1) }).await;
+        let second = group
+            .run("key", || async {
+                Err(Error::LookupError(LookupError::GenericError(
+                    "failed".to_string(),
+                )))
+            })
+            .await;
+        assert!(matches!(first, Ok(1)));
+        assert!(
+            matches!(
+                second,
+                Err(Error::LookupError(LookupError::GenericError(_)))
+            ),
+            "Finished calls should not be shared"
+        );
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn test_shared_error() {
+        let group = SingleFlight::<_, ()>::new();
+        let work = || async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Err(Error::LookupError(LookupError::TooManyRequests(
+                "Too many requests".to_string(),
+            )))
+        };
+        let results = futures::future::join_all((0..3).map(|_| group.run("key", work))).await;
+        for result in results {
+            let Err(e) = result else {
+                panic!("Error not shared");
+            };
+            assert!(e.is_retryable(), "Error kind lost: {:?}", e);
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_shared_error() {
+        let group = SingleFlight::<_, ()>::new();
+        let work = || {
+            std::thread::sleep(Duration::from_millis(50));
+            Err(Error::LookupError(LookupError::TooManyRequests(
+                "Too many requests".to_string(),
+            )))
+        };
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..3)
+                .map(|_| scope.spawn(|| group.run("key", work)))
+                .collect();
+            for handle in handles {
+                let Err(e) = handle.join().unwrap() else {
+                    panic!("Error not shared");
+                };
+                assert!(e.is_retryable(), "Error kind lost: {:?}", e);
+            }
+        });
+    }
+}
@@ -0,0 +1,253 @@
+//! # 🧭 Provider selection strategies
+//!
+//! This module contains the `SelectionStrategy` trait consulted by the fallback machinery
+//! to decide in which order the providers are tried, and the built-in strategies.
+//!
+//! ## Example
+//! ```rust
+//! use public_ip_address::lookup::LookupProvider;
+//! use public_ip_address::options::LookupOptions;
+//! use public_ip_address::strategy::Random;
+//!
+//! # use std::error::Error;
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() -> Result<(), Box<dyn Error>> {
+//! let providers = vec![
+//!     (LookupProvider::IpWhoIs, None),
+//!     (LookupProvider::IpInfo, None),
+//! ];
+//! let options = LookupOptions::new().strategy(Random);
+//! match public_ip_address::perform_lookup_with_options(providers, None, &options).await {
+//!     Ok(response) => {
+//!         // Handle successful response
+//!     }
+//!     Err(e) => {
+//!         // Handle error
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Custom policies are implemented through the `SelectionStrategy` trait.
+//! ```rust
+//! use public_ip_address::lookup::LookupProvider;
+//! use public_ip_address::strategy::SelectionStrategy;
+//!
+//! /// Tries the providers in reverse order
+//! struct Reverse;
+//!
+//! impl SelectionStrategy for Reverse {
+//!     fn order(&self, providers: &[LookupProvider]) -> Vec<usize> {
+//!         (0..providers.len()).rev().collect()
+//!     }
+//! }
+//! ```
+
+use crate::cache::{ProviderStats, ResponseCache};
+use crate::lookup::LookupProvider;
+use std::{collections::BTreeMap, time::Duration};
+
+/// Strategy deciding in which order providers are tried
+pub trait SelectionStrategy: Send + Sync {
+    /// Returns the order in which the given providers should be tried, as indexes into `providers`.
+    ///
+    /// Providers left out of the returned order are not tried, invalid and repeated indexes are ignored.
+    fn order(&self, providers: &[LookupProvider]) -> Vec<usize>;
+}
+
+/// Tries the providers in the order they were given
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sequential;
+
+impl SelectionStrategy for Sequential {
+    fn order(&self, providers: &[LookupProvider]) -> Vec<usize> {
+        (0..providers.len()).collect()
+    }
+}
+
+/// Tries the providers in a random order, spreading the load between them
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Random;
+
+impl SelectionStrategy for Random {
+    fn order(&self, providers: &[LookupProvider]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..providers.len()).collect();
+        fastrand::shuffle(&mut order);
+        order
+    }
+}
+
+/// Tries the providers with the lowest known latency first
+///
+/// Providers without a known latency are tried last, in the order they were given.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyBased {
+    latencies: Vec<(LookupProvider, Duration)>,
+}
+
+impl LatencyBased {
+    /// Creates a new strategy from a list of known provider latencies.
+    pub fn new(latencies: Vec<(LookupProvider, Duration)>) -> Self {
+        LatencyBased { latencies }
+    }
+
+    /// Returns the known latency for the provider.
+    pub fn latency(&self, provider: &LookupProvider) -> Option<Duration> {
+        self.latencies
+            .iter()
+            .find(|(known, _)| known == provider)
+            .map(|(_, latency)| *latency)
+    }
+}
+
+impl SelectionStrategy for LatencyBased {
+    fn order(&self, providers: &[LookupProvider]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..providers.len()).collect();
+        // stable sort keeps the given order for equal and unknown latencies
+        order.sort_by_key(|&i| self.latency(&providers[i]).unwrap_or(Duration::MAX));
+        order
+    }
+}
+
+/// Tries the providers in a random order biased by their weight
+///
+/// Providers with a higher weight are more likely to be tried first. Providers with
+/// a weight of `0` are emergency fallbacks, only tried after all the others, in the
+/// order they were given. Providers without a weight get a weight of `1`.
+#[derive(Debug, Clone, Default)]
+pub struct Weighted {
+    weights: Vec<u8>,
+}
+
+impl Weighted {
+    /// Creates a new strategy from a list of weights, one for each provider in the same order.
+    pub fn new(weights: Vec<u8>) -> Self {
+        Weighted { weights }
+    }
+
+    /// Returns the weight of the provider at the given index.
+    pub fn weight(&self, index: usize) -> u8 {
+        self.weights.get(index).copied().unwrap_or(1)
+    }
+}
+
+impl SelectionStrategy for Weighted {
+    fn order(&self, providers: &[LookupProvider]) -> Vec<usize> {
+        // weighted random sampling: each provider gets a key of u^(1/weight), the highest keys go first
+        let mut keyed: Vec<(usize, f64)> = (0..providers.len())
+            .filter(|&i| self.weight(i) > 0)
+            .map(|i| (i, fastrand::f64().powf(1.0 / f64::from(self.weight(i)))))
+            .collect();
+        keyed.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let fallbacks = (0..providers.len()).filter(|&i| self.weight(i) == 0);
+        keyed.into_iter().map(|(i, _)| i).chain(fallbacks).collect()
+    }
+}
+
+/// Tries the providers with the best recorded performance first
+///
+/// Providers are ordered by their [`ProviderStats::score`], combining their success rate
+/// and average latency. Providers without recorded lookups get a neutral score.
+/// The statistics are recorded by [`perform_adaptive_lookup_with`](crate::perform_adaptive_lookup_with).
+#[derive(Debug, Clone, Default)]
+pub struct Adaptive {
+    stats: BTreeMap<String, ProviderStats>,
+}
+
+impl Adaptive {
+    /// Creates a new strategy from the recorded provider statistics.
+    pub fn new(stats: BTreeMap<String, ProviderStats>) -> Self {
+        Adaptive { stats }
+    }
+
+    /// Creates a new strategy from the provider statistics recorded in the cache.
+    pub fn from_cache(cache: &ResponseCache) -> Self {
+        Self::new(cache.provider_stats.clone())
+    }
+
+    /// Returns the score of the provider.
+    pub fn score(&self, provider: &LookupProvider) -> f64 {
+        self.stats
+            .get(&provider.to_string())
+            .copied()
+            .unwrap_or_default()
+            .score()
+    }
+}
+
+impl SelectionStrategy for Adaptive {
+    fn order(&self, providers: &[LookupProvider]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..providers.len()).collect();
+        order.sort_by(|&a, &b| {
+            self.score(&providers[b])
+                .total_cmp(&self.score(&providers[a]))
+        });
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn providers() -> Vec<LookupProvider> {
+        vec![
+            LookupProvider::IpInfo,
+            LookupProvider::IpWhoIs,
+            LookupProvider::MyIp,
+            LookupProvider::FreeIpApi,
+        ]
+    }
+
+    #[test]
+    fn test_sequential() {
+        assert_eq!(Sequential.order(&providers()), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_random() {
+        let mut order = Random.order(&providers());
+        order.sort();
+        assert_eq!(
+            order,
+            vec![0, 1, 2, 3],
+            "Every provider should be tried once"
+        );
+    }
+
+    #[test]
+    fn test_latency_based() {
+        let strategy = LatencyBased::new(vec![
+            (LookupProvider::MyIp, Duration::from_millis(300)),
+            (LookupProvider::IpWhoIs, Duration::from_millis(100)),
+        ]);
+        assert_eq!(strategy.order(&providers()), vec![1, 2, 0, 3]);
+    }
+
+    #[test]
+    fn test_weighted() {
+        let strategy = Weighted::new(vec![0, 255, 1]);
+        let mut first_tried = [0; 4];
+        for _ in 0..100 {
+            let order = strategy.order(&providers());
+            assert_eq!(order.len(), 4, "Every provider should be tried once");
+            assert_eq!(order[3], 0, "Zero weight provider should be tried last");
+            first_tried[order[0]] += 1;
+        }
+        assert!(
+            first_tried[1] > first_tried[2],
+            "Higher weight provider should be tried first more often"
+        );
+    }
+
+    #[test]
+    fn test_adaptive() {
+        let mut cache = ResponseCache::default();
+        cache.record_provider(&LookupProvider::IpInfo, false, Duration::from_secs(1));
+        cache.record_provider(&LookupProvider::MyIp, true, Duration::from_millis(50));
+        let strategy = Adaptive::from_cache(&cache);
+        assert_eq!(strategy.order(&providers()), vec![2, 1, 3, 0]);
+    }
+}
@@ -0,0 +1,107 @@
+//! # 📡 Error telemetry
+//!
+//! This module provides a global hook observing the failures of the lookup providers, so applications
+//! can count them in their own metrics system.
+//!
+//! The observer is called for every failed request made by a `LookupService`, including the requests
+//! made by the fallback, race and enriched lookups.
+//!
+//! ## Example
+//! ```rust
+//! use public_ip_address::telemetry;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! static FAILURES: AtomicUsize = AtomicUsize::new(0);
+//!
+//! telemetry::set_error_observer(|event| {
+//!     FAILURES.fetch_add(1, Ordering::Relaxed);
+//!     eprintln!("{} failed after {:?}: {}", event.provider, event.latency, event.error);
+//! });
+//! ```
+
+use crate::lookup::{error::LookupError, LookupProvider};
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+/// Failed request reported to the error observer
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ErrorEvent<'a> {
+    /// Provider that failed
+    pub provider: &'a LookupProvider,
+    /// Error returned by the provider
+    pub error: &'a LookupError,
+    /// Time spent on the failed request
+    pub latency: Duration,
+}
+
+type ErrorObserver = Arc<dyn Fn(&ErrorEvent) + Send + Sync>;
+
+static ERROR_OBSERVER: RwLock<Option<ErrorObserver>> = RwLock::new(None);
+
+/// Sets the global error observer, replacing the previous one.
+///
+/// The observer is called synchronously from the lookup, so it should return quickly.
+pub fn set_error_observer<F>(observer: F)
+where
+    F: Fn(&ErrorEvent) + Send + Sync + 'static,
+{
+    *ERROR_OBSERVER.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(observer));
+}
+
+/// Removes the global error observer.
+pub fn clear_error_observer() {
+    *ERROR_OBSERVER.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Reports a failed request to the global error observer, if any.
+pub(crate) fn report_error(provider: &LookupProvider, error: &LookupError, latency: Duration) {
+    // clone the observer so it's not called while holding the lock
+    let observer = ERROR_OBSERVER
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if let Some(observer) = observer {
+        observer(&ErrorEvent {
+            provider,
+            error,
+            latency,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_error_observer() {
+        static FAILURES: AtomicUsize = AtomicUsize::new(0);
+        // other tests may report errors concurrently, only count this one
+        set_error_observer(|event| {
+            if matches!(event.error, LookupError::TooManyRequests(quota) if quota == "telemetry test")
+            {
+                FAILURES.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        report_error(
+            &LookupProvider::IpData,
+            &LookupError::TooManyRequests("telemetry test".to_string()),
+            Duration::from_millis(10),
+        );
+        clear_error_observer();
+        report_error(
+            &LookupProvider::IpData,
+            &LookupError::TooManyRequests("telemetry test".to_string()),
+            Duration::ZERO,
+        );
+        assert_eq!(
+            FAILURES.load(Ordering::SeqCst),
+            1,
+            "Observer not called once"
+        );
+    }
+}
@@ -3,6 +3,16 @@ use public_ip_address::{cache::ResponseCache, lookup::LookupProvider};
 use serial_test::serial;
 use std::net::IpAddr;
 
+#[maybe_async::async_impl]
+async fn sleep(duration: std::time::Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+#[maybe_async::sync_impl]
+fn sleep(duration: std::time::Duration) {
+    std::thread::sleep(duration);
+}
+
 fn clear_cache() {
     _ = ResponseCache::default().delete();
 }
@@ -173,3 +183,697 @@ async fn test_perform_lookup_cached_expired() {
     );
     clear_cache();
 }
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_perform_race_lookup() {
+    let response = perform_race_lookup_with(
+        vec![
+            (LookupProvider::Mock("1.1.1.1".to_string()), None),
+            (LookupProvider::Mock("1.1.1.1".to_string()), None),
+        ],
+        None,
+    )
+    .await;
+    assert_eq!(
+        response.unwrap().ip,
+        ip("1.1.1.1"),
+        "IP address not matching"
+    );
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_perform_race_lookup_empty() {
+    let response = perform_race_lookup_with(vec![], None).await;
+    assert!(response.is_err(), "Empty provider list should fail");
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+#[serial]
+async fn test_perform_lookup_cached_policy() {
+    clear_cache();
+    let options =
+        options::LookupOptions::new().refresh_policy(cache::RefreshPolicy::new(None, Some(60)));
+    let response = perform_cached_lookup_with_options(
+        vec![(LookupProvider::Mock("31.1.1.1".to_string()), None)],
+        None,
+        &options,
+    )
+    .await;
+    assert_eq!(
+        response.unwrap().ip,
+        ip("31.1.1.1"),
+        "IP address not matching"
+    );
+    let response = perform_cached_lookup_with_options(
+        vec![(LookupProvider::Mock("32.2.2.2".to_string()), None)],
+        None,
+        &options,
+    )
+    .await;
+    assert_eq!(
+        response.unwrap().ip,
+        ip("31.1.1.1"),
+        "Cached value should be used while the IP and geolocation are fresh"
+    );
+    clear_cache();
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_perform_enriched_lookup() {
+    let response = perform_enriched_lookup_with(
+        vec![
+            (LookupProvider::Mock("1.1.1.1".to_string()), None),
+            (LookupProvider::Mock("2.2.2.2".to_string()), None),
+        ],
+        None,
+    )
+    .await;
+    let response = response.unwrap();
+    assert_eq!(response.ip, ip("1.1.1.1"), "IP address not matching");
+    assert_eq!(
+        response.provider,
+        LookupProvider::Mock("1.1.1.1".to_string()),
+        "Provider with the highest priority should be kept"
+    );
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_perform_lookup_with_strategy() {
+    let options = options::LookupOptions::new().strategy(strategy::LatencyBased::new(vec![(
+        LookupProvider::Mock("2.2.2.2".to_string()),
+        std::time::Duration::from_millis(10),
+    )]));
+    let response = perform_lookup_with_options(
+        vec![
+            (LookupProvider::Mock("1.1.1.1".to_string()), None),
+            (LookupProvider::Mock("2.2.2.2".to_string()), None),
+        ],
+        None,
+        &options,
+    )
+    .await;
+    assert!(response.is_ok(), "Lookup should succeed");
+    assert_eq!(
+        response.unwrap().ip,
+        ip("2.2.2.2"),
+        "Fastest provider should be tried first"
+    );
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+#[serial]
+async fn test_perform_adaptive_lookup() {
+    clear_cache();
+    let provider = LookupProvider::Mock("1.1.1.1".to_string());
+    let response = perform_adaptive_lookup_with(vec![(provider.clone(), None)], None).await;
+    assert!(response.is_ok(), "Lookup should succeed");
+    let cache = ResponseCache::load(None).unwrap();
+    let stats = cache.provider_stats(&provider).unwrap();
+    assert_eq!(stats.successes, 1, "Successful lookup not recorded");
+    clear_cache();
+}
+
+#[cfg(not(feature = "blocking"))]
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn test_perform_adaptive_lookup_concurrent() {
+    clear_cache();
+    let provider = LookupProvider::Mock("1.1.1.1".to_string());
+    let lookups = (0..32).map(|_| {
+        let providers = vec![(provider.clone(), None)];
+        tokio::spawn(async move { perform_adaptive_lookup_with(providers, None).await })
+    });
+    for lookup in futures::future::join_all(lookups).await {
+        assert!(lookup.unwrap().is_ok(), "Lookup should succeed");
+    }
+    let cache = ResponseCache::load(None).unwrap();
+    let stats = cache.provider_stats(&provider).unwrap();
+    assert_eq!(stats.successes, 32, "Concurrent lookups not all recorded");
+    clear_cache();
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+#[serial]
+fn test_perform_adaptive_lookup_concurrent() {
+    clear_cache();
+    let provider = LookupProvider::Mock("1.1.1.1".to_string());
+    std::thread::scope(|scope| {
+        let lookups: Vec<_> = (0..32)
+            .map(|_| {
+                scope.spawn(|| perform_adaptive_lookup_with(vec![(provider.clone(), None)], None))
+            })
+            .collect();
+        for lookup in lookups {
+            assert!(lookup.join().unwrap().is_ok(), "Lookup should succeed");
+        }
+    });
+    let cache = ResponseCache::load(None).unwrap();
+    let stats = cache.provider_stats(&provider).unwrap();
+    assert_eq!(stats.successes, 32, "Concurrent lookups not all recorded");
+    clear_cache();
+}
+
+#[cfg(not(feature = "blocking"))]
+#[tokio::test]
+#[serial]
+async fn test_lookup_stream() {
+    use futures::StreamExt;
+    use public_ip_address::lookup::LookupService;
+    use std::time::Duration;
+
+    clear_cache();
+    let service = LookupService::new(LookupProvider::Mock("41.1.1.1".to_string()), None);
+    let mut lookups = Box::pin(service.stream(Duration::from_secs(60)));
+    let first = lookups.next().await.unwrap().unwrap();
+    assert_eq!(first.ip, ip("41.1.1.1"));
+    let service = LookupService::new(LookupProvider::Mock("41.1.1.1".to_string()), None);
+    let mut lookups = Box::pin(service.stream(Duration::from_secs(60)));
+    let response = lookups.next().await.unwrap();
+    assert_eq!(
+        response.unwrap().looked_up_at,
+        first.looked_up_at,
+        "Cached value should be used"
+    );
+
+    let service = LookupService::new(LookupProvider::Mock("42.2.2.2".to_string()), None);
+    let mut lookups = Box::pin(service.stream(Duration::from_secs(60)));
+    let response = lookups.next().await.unwrap();
+    assert_eq!(
+        response.unwrap().ip,
+        ip("42.2.2.2"),
+        "Cached value of another provider should not be used"
+    );
+    let cache = ResponseCache::load(None).unwrap();
+    assert_eq!(cache.current_response(), None, "Current address cached");
+    clear_cache();
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+#[serial]
+fn test_lookup_stream() {
+    use public_ip_address::lookup::LookupService;
+    use std::time::Duration;
+
+    clear_cache();
+    let service = LookupService::new(LookupProvider::Mock("41.1.1.1".to_string()), None);
+    let first = service.stream(Duration::from_secs(60)).next();
+    let first = first.unwrap().unwrap();
+    assert_eq!(first.ip, ip("41.1.1.1"));
+    let service = LookupService::new(LookupProvider::Mock("41.1.1.1".to_string()), None);
+    let response = service.stream(Duration::from_secs(60)).next().unwrap();
+    assert_eq!(
+        response.unwrap().looked_up_at,
+        first.looked_up_at,
+        "Cached value should be used"
+    );
+
+    let service = LookupService::new(LookupProvider::Mock("42.2.2.2".to_string()), None);
+    let response = service.stream(Duration::from_secs(60)).next().unwrap();
+    assert_eq!(
+        response.unwrap().ip,
+        ip("42.2.2.2"),
+        "Cached value of another provider should not be used"
+    );
+    let cache = ResponseCache::load(None).unwrap();
+    assert_eq!(cache.current_response(), None, "Current address cached");
+    clear_cache();
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_perform_lookup_cancelled() {
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let options = options::LookupOptions::new().cancellation(cancel);
+    let providers = vec![(LookupProvider::Mock("1.1.1.1".to_string()), None)];
+    let response = perform_lookup_with_options(providers.clone(), None, &options).await;
+    assert!(
+        matches!(
+            response,
+            Err(error::Error::LookupError(
+                lookup::error::LookupError::Cancelled
+            ))
+        ),
+        "Cancelled lookup should fail"
+    );
+    let response = perform_race_lookup_with_options(providers, None, &options).await;
+    assert!(response.is_err(), "Cancelled race should fail");
+
+    let options = options::LookupOptions::new().cancellation(CancellationToken::new());
+    let response = perform_lookup_with_options(
+        vec![(LookupProvider::Mock("1.1.1.1".to_string()), None)],
+        None,
+        &options,
+    )
+    .await;
+    assert!(response.is_ok(), "Lookup should succeed");
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+#[serial]
+async fn test_spawn_refresher() {
+    use std::time::Duration;
+
+    clear_cache();
+    let refresher = spawn_refresher(
+        vec![(LookupProvider::Mock("1.1.1.1".to_string()), None)],
+        Duration::from_secs(60),
+    );
+    let mut current = refresher.current();
+    for _ in 0..100 {
+        if current.is_some() {
+            break;
+        }
+        sleep(Duration::from_millis(10)).await;
+        current = refresher.current();
+    }
+    let current = current.expect("Refresher should provide a response");
+    assert_eq!(current.ip, "1.1.1.1".parse::<IpAddr>().unwrap());
+    assert!(!refresher.is_finished(), "Refresher should be running");
+    refresher.shutdown().await;
+    clear_cache();
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+#[serial]
+async fn test_monitor() {
+    use public_ip_address::{monitor::Monitor, response::LookupResponse};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    clear_cache();
+    let mut cache = ResponseCache::default();
+    let old = LookupResponse::new(ip("51.1.1.1"), LookupProvider::Mock("51.1.1.1".to_string()));
+    cache.update_current(&old, Some(0));
+    cache.save().unwrap();
+
+    let mut monitor = Monitor::new(
+        vec![(LookupProvider::Mock("52.2.2.2".to_string()), None)],
+        Duration::from_secs(60),
+    );
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let callback_changes = changes.clone();
+    monitor.on_change(move |change| callback_changes.lock().unwrap().push(change.clone()));
+    #[cfg_attr(feature = "blocking", allow(unused_mut))]
+    let mut receiver = monitor.subscribe();
+    monitor.start();
+    for _ in 0..100 {
+        if !changes.lock().unwrap().is_empty() {
+            break;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+    let change = changes.lock().unwrap().pop().expect("Change not reported");
+    assert_eq!(change.old_ip, Some(ip("51.1.1.1")));
+    assert_eq!(change.new_ip, ip("52.2.2.2"));
+    assert!(change.old_since.is_some(), "Old address time unknown");
+    assert_eq!(receiver.try_recv().ok(), Some(change), "Change not sent");
+    assert!(!monitor.is_finished(), "Monitor should be running");
+    monitor.shutdown().await;
+    clear_cache();
+}
+
+/// Updater recording the addresses it's updated with
+struct RecordingUpdater(std::sync::Arc<std::sync::Mutex<Vec<IpAddr>>>);
+
+#[maybe_async::maybe_async]
+impl ddns::DdnsUpdater for RecordingUpdater {
+    async fn update(&self, ip: IpAddr) -> ddns::Result<ddns::UpdateOutcome> {
+        self.0.lock().unwrap().push(ip);
+        Ok(ddns::UpdateOutcome::Updated)
+    }
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+#[serial]
+async fn test_monitor_updater() {
+    use public_ip_address::monitor::Monitor;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    clear_cache();
+    let mut monitor = Monitor::new(
+        vec![(LookupProvider::Mock("53.3.3.3".to_string()), None)],
+        Duration::from_secs(60),
+    );
+    let updates = Arc::new(Mutex::new(Vec::new()));
+    monitor.add_updater(RecordingUpdater(updates.clone()));
+    monitor.start();
+    for _ in 0..100 {
+        if !updates.lock().unwrap().is_empty() {
+            break;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(*updates.lock().unwrap(), vec![ip("53.3.3.3")]);
+    monitor.shutdown().await;
+    clear_cache();
+}
+
+/// Updater failing with a retryable error before recording the addresses
+struct FlakyUpdater(std::sync::Arc<std::sync::Mutex<Vec<IpAddr>>>);
+
+#[maybe_async::maybe_async]
+impl ddns::DdnsUpdater for FlakyUpdater {
+    async fn update(&self, ip: IpAddr) -> ddns::Result<ddns::UpdateOutcome> {
+        let mut updates = self.0.lock().unwrap();
+        updates.push(ip);
+        if updates.len() == 1 {
+            return Err(error::DdnsError::Refused(
+                ddns::dyndns2::ReturnCode::ServerError,
+            ));
+        }
+        Ok(ddns::UpdateOutcome::Updated)
+    }
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+#[serial]
+async fn test_monitor_updater_retry() {
+    use public_ip_address::monitor::Monitor;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    clear_cache();
+    let mut monitor = Monitor::new(
+        vec![(LookupProvider::Mock("54.4.4.4".to_string()), None)],
+        Duration::from_millis(20),
+    );
+    let updates = Arc::new(Mutex::new(Vec::new()));
+    monitor.add_updater(FlakyUpdater(updates.clone()));
+    monitor.start();
+    for _ in 0..100 {
+        if updates.lock().unwrap().len() >= 2 {
+            break;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+    sleep(Duration::from_millis(60)).await;
+    assert_eq!(
+        *updates.lock().unwrap(),
+        vec![ip("54.4.4.4"), ip("54.4.4.4")],
+        "Failed update not retried once"
+    );
+    monitor.shutdown().await;
+    clear_cache();
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_perform_lookup_for() {
+    let ip = "8.8.8.8".parse::<IpAddr>().unwrap();
+    let response = perform_lookup_for(
+        ip,
+        vec![
+            (LookupProvider::Ipify, None),
+            (LookupProvider::Mock("1.1.1.1".to_string()), None),
+        ],
+    )
+    .await;
+    assert!(response.is_ok(), "Lookup should succeed");
+    assert_eq!(
+        response.unwrap().provider,
+        LookupProvider::Mock("1.1.1.1".to_string()),
+        "Provider without target lookup should be skipped"
+    );
+
+    let response = perform_lookup_for(ip, vec![(LookupProvider::Ipify, None)]).await;
+    assert!(
+        matches!(
+            response,
+            Err(error::Error::LookupError(
+                lookup::error::LookupError::TargetNotSupported
+            ))
+        ),
+        "No provider supports target lookup"
+    );
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_perform_lookup_with_ip_version() {
+    let options = options::LookupOptions::new().ip_version(lookup::IpVersion::V4);
+    let response = perform_lookup_with_options(
+        vec![(LookupProvider::Mock("1.1.1.1".to_string()), None)],
+        None,
+        &options,
+    )
+    .await;
+    assert!(response.is_ok(), "Lookup should succeed");
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_perform_lookup_reject_non_global() {
+    let providers = vec![
+        (LookupProvider::Mock("192.168.1.1".to_string()), None),
+        (LookupProvider::Mock("1.1.1.1".to_string()), None),
+    ];
+    let options = options::LookupOptions::new().reject_non_global(true);
+    let response = perform_lookup_with_options(providers, None, &options)
+        .await
+        .unwrap();
+    assert_eq!(
+        response.ip,
+        ip("1.1.1.1"),
+        "Non-global address not rejected"
+    );
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_perform_lookup_all_failed() {
+    // keyed providers fail without making a request
+    let response = perform_lookup_with(
+        vec![
+            (LookupProvider::IpData, None),
+            (LookupProvider::AbstractApi, None),
+        ],
+        None,
+    )
+    .await;
+    let Err(error::Error::LookupError(lookup::error::LookupError::AllProvidersFailed(errors))) =
+        response
+    else {
+        panic!("Every provider should fail: {:?}", response);
+    };
+    let providers: Vec<_> = errors.into_iter().map(|(provider, _)| provider).collect();
+    assert_eq!(
+        providers,
+        vec![LookupProvider::IpData, LookupProvider::AbstractApi],
+        "Errors not reported in order"
+    );
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+#[serial]
+async fn test_perform_stale_while_revalidate_lookup() {
+    clear_cache();
+    let response = perform_stale_while_revalidate_lookup_with(
+        vec![(LookupProvider::Mock("1.1.1.1".to_string()), None)],
+        Some(1),
+        60,
+    )
+    .await;
+    assert_eq!(response.unwrap().ip, ip("1.1.1.1"), "Empty cache");
+    sleep(std::time::Duration::from_secs(1)).await;
+
+    let response = perform_stale_while_revalidate_lookup_with(
+        vec![(LookupProvider::Mock("2.2.2.2".to_string()), None)],
+        Some(1),
+        60,
+    )
+    .await;
+    assert_eq!(
+        response.unwrap().ip,
+        ip("1.1.1.1"),
+        "Stale value should be returned"
+    );
+    // wait for the background refresh
+    sleep(std::time::Duration::from_millis(200)).await;
+    let cache = ResponseCache::load(None).unwrap();
+    assert_eq!(
+        cache.current_ip(),
+        Some(ip("2.2.2.2")),
+        "Cache not revalidated"
+    );
+
+    sleep(std::time::Duration::from_secs(1)).await;
+    let response = perform_stale_while_revalidate_lookup_with(
+        vec![(LookupProvider::Mock("3.3.3.3".to_string()), None)],
+        Some(1),
+        0,
+    )
+    .await;
+    assert_eq!(
+        response.unwrap().ip,
+        ip("3.3.3.3"),
+        "Too stale value should not be returned"
+    );
+    clear_cache();
+}
+
+#[test]
+fn test_response_builder() {
+    let response = response::LookupResponse::builder(
+        ip("1.1.1.1"),
+        LookupProvider::Mock("1.1.1.1".to_string()),
+    )
+    .country("Germany")
+    .country_code("DE")
+    .latitude(52.52)
+    .longitude(13.405)
+    .languages(["de"])
+    .build();
+    assert_eq!(response.country_code.as_deref(), Some("DE"));
+    assert_eq!(response.latitude, Some(52.52));
+    assert_eq!(response.languages, Some(vec!["de".to_string()]));
+    assert_eq!(response.city, None);
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_perform_ip_only_lookup() {
+    let options = options::LookupOptions::new().ip_only(true);
+    let response = perform_lookup_with_options(
+        vec![
+            (LookupProvider::IpInfo, None),
+            (LookupProvider::Mock("1.1.1.1".to_string()), None),
+        ],
+        None,
+        &options,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        response.provider,
+        LookupProvider::Mock("1.1.1.1".to_string()),
+        "Geolocation provider used"
+    );
+    assert_eq!(response.country, None);
+}
+
+#[cfg(not(feature = "blocking"))]
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn test_perform_cached_lookup_concurrent_targets() {
+    let options = options::LookupOptions::new().cache_file("concurrent-test.cache");
+    _ = ResponseCache::new(options.cache_file.clone()).delete();
+    let targets: Vec<IpAddr> = (1..=16).map(|i| ip(&format!("71.1.1.{}", i))).collect();
+    let lookups = targets.iter().map(|&target| {
+        let options = options.clone();
+        tokio::spawn(async move {
+            let providers = vec![(LookupProvider::Mock("1.1.1.1".to_string()), None)];
+            perform_cached_lookup_with_options(providers, Some(target), &options).await
+        })
+    });
+    for lookup in futures::future::join_all(lookups).await {
+        assert!(lookup.unwrap().is_ok(), "Lookup should succeed");
+    }
+    let cache = ResponseCache::load(options.cache_file.clone()).unwrap();
+    for target in &targets {
+        assert!(
+            cache.target_response(target).is_some(),
+            "{} not cached",
+            target
+        );
+    }
+    _ = cache.delete();
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+#[serial]
+fn test_perform_cached_lookup_concurrent_targets() {
+    let options = options::LookupOptions::new().cache_file("concurrent-test.cache");
+    _ = ResponseCache::new(options.cache_file.clone()).delete();
+    let targets: Vec<IpAddr> = (1..=16).map(|i| ip(&format!("71.1.1.{}", i))).collect();
+    std::thread::scope(|scope| {
+        let lookups: Vec<_> = targets
+            .iter()
+            .map(|&target| {
+                let options = &options;
+                scope.spawn(move || {
+                    let providers = vec![(LookupProvider::Mock("1.1.1.1".to_string()), None)];
+                    perform_cached_lookup_with_options(providers, Some(target), options)
+                })
+            })
+            .collect();
+        for lookup in lookups {
+            assert!(lookup.join().unwrap().is_ok(), "Lookup should succeed");
+        }
+    });
+    let cache = ResponseCache::load(options.cache_file.clone()).unwrap();
+    for target in &targets {
+        assert!(
+            cache.target_response(target).is_some(),
+            "{} not cached",
+            target
+        );
+    }
+    _ = cache.delete();
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+#[serial]
+async fn test_perform_cached_lookup_with_options() {
+    let options = options::LookupOptions::new()
+        .ttl(Some(60))
+        .cache_file("options-test.cache")
+        .retries(1);
+    _ = ResponseCache::new(options.cache_file.clone()).delete();
+    let response = perform_cached_lookup_with_options(
+        vec![(LookupProvider::Mock("61.1.1.1".to_string()), None)],
+        None,
+        &options,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.ip, ip("61.1.1.1"));
+
+    let response = perform_cached_lookup_with_options(
+        vec![(LookupProvider::Mock("62.2.2.2".to_string()), None)],
+        None,
+        &options,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.ip, ip("61.1.1.1"), "Cached value not returned");
+    if let Some(current) = ResponseCache::load(None)
+        .ok()
+        .and_then(|cache| cache.current_address)
+    {
+        assert_ne!(current.response.ip, ip("61.1.1.1"), "Default cache used");
+    }
+
+    let options = options.force_refresh(true);
+    let response = perform_cached_lookup_with_options(
+        vec![(LookupProvider::Mock("62.2.2.2".to_string()), None)],
+        None,
+        &options,
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.ip, ip("62.2.2.2"), "Refresh not forced");
+
+    let mock = |address: &str| vec![(LookupProvider::Mock(address.to_string()), None)];
+    let ip_only = options.clone().force_refresh(false).ip_only(true);
+    let response = perform_cached_lookup_with_options(mock("63.3.3.3"), None, &ip_only)
+        .await
+        .unwrap();
+    assert_eq!(
+        response.ip,
+        ip("63.3.3.3"),
+        "Full response used for IP only"
+    );
+    let response = perform_cached_lookup_with_options(mock("64.4.4.4"), None, &ip_only)
+        .await
+        .unwrap();
+    assert_eq!(response.ip, ip("63.3.3.3"), "IP only response not cached");
+    let options = options.force_refresh(false);
+    let response = perform_cached_lookup_with_options(mock("64.4.4.4"), None, &options)
+        .await
+        .unwrap();
+    assert_eq!(
+        response.ip,
+        ip("62.2.2.2"),
+        "IP only response used for full"
+    );
+    _ = ResponseCache::new(options.cache_file.clone()).delete();
+}